@@ -1,17 +1,159 @@
 use std::env;
 use std::fs;
-use std::fs::File;
-use std::io::{BufReader, BufRead};
+use std::path::Path;
 
 pub mod parser;
 pub mod code;
+pub mod symbol_table;
+pub mod error;
+pub mod disassembler;
+pub mod listing;
+pub mod preprocessor;
+pub mod output;
+pub mod optimizer;
+pub mod lint;
+pub mod source_map;
+
+use parser::{CommandType, Parser};
+use symbol_table::{SymbolKind, SymbolTable};
+pub use error::{AsmError, Diagnostic, Span};
+
+// 変数は RAM の 16 番地から順に割り当てる
+const VARIABLE_BASE_ADDRESS: u16 = 16;
+// A命令で指定できる最大値 (15bit)
+const MAX_ADDRESS: u16 = 0x7fff;
+// ROM は 32K ワード
+const ROM_SIZE: usize = 0x8000;
 
 pub fn main() {
-    let program_path = &env::args().nth(1);
+    // --listing を付けるとリスティングを標準出力に出す
+    // --extended を付けると .macro / .include が使える
+    // --format=ihex などで出力形式を変える (既定は hack)
+    // --optimize を付けると覗き穴最適化をかけてからエンコードする
+    // --lint を付けるとアセンブルの前に怪しいところを警告する
+    // --map を付けると ROM アドレスから元の行を引ける .map ファイルも書く
+    // .map を --lookup=N と一緒に渡すと、ROM の N 番地の命令が元のどの行かを出す
+    let args: Vec<String> = env::args().skip(1).collect();
+    let show_listing = args.iter().any(|a| a == "--listing");
+    let extended = args.iter().any(|a| a == "--extended");
+    let optimize = args.iter().any(|a| a == "--optimize");
+    let show_lints = args.iter().any(|a| a == "--lint");
+    let write_map = args.iter().any(|a| a == "--map");
+    let format = match args.iter().find_map(|a| a.strip_prefix("--format=")) {
+        Some(name) => match output::Format::from_name(name) {
+            Some(format) => format,
+            None => {
+                eprintln!("unknown format: {}", name);
+                std::process::exit(1)
+            }
+        },
+        None => output::Format::Hack
+    };
+    let lookup = match args.iter().find_map(|a| a.strip_prefix("--lookup=")) {
+        Some(address) => match address.parse::<u16>() {
+            Ok(address) => Some(address),
+            Err(e) => {
+                eprintln!("--lookup={}: {}", address, e);
+                std::process::exit(1)
+            }
+        },
+        None => None
+    };
+    let program_path = &args.iter().find(|a| !a.starts_with("--")).cloned();
     match program_path {
         Some(path) => {
-            let f = fs::File::open(path).unwrap();
-            parser::Parser::new(f);
+            let source = match fs::read_to_string(path) {
+                Ok(source) => source,
+                Err(e) => {
+                    eprintln!("{}: {}", path, e);
+                    std::process::exit(1)
+                }
+            };
+            // .hack を渡されたら逆アセンブルして標準出力に出す
+            if path.ends_with(".hack") {
+                match disassembler::from_hack(&source) {
+                    Ok(words) => println!("{}", disassembler::disassemble(&words, true)),
+                    Err(e) => {
+                        eprintln!("{}: {}", path, e);
+                        std::process::exit(1)
+                    }
+                }
+                return
+            }
+            if path.ends_with(".map") {
+                let map = match source_map::SourceMap::parse(&source) {
+                    Some(map) => map,
+                    None => {
+                        eprintln!("{}: not a source map", path);
+                        std::process::exit(1)
+                    }
+                };
+                let address = match lookup {
+                    Some(address) => address,
+                    None => {
+                        eprintln!("{}: give the ROM address with --lookup=N", path);
+                        std::process::exit(1)
+                    }
+                };
+                match map.lookup(address) {
+                    Some(entry) => println!("{}", entry),
+                    None => {
+                        eprintln!("{}: no instruction at ROM address {}", path, address);
+                        std::process::exit(1)
+                    }
+                }
+                return
+            }
+            // .include のパスはアセンブル対象のファイルがあるディレクトリから辿る
+            let base_dir = Path::new(path).parent().unwrap_or_else(|| Path::new("")).to_path_buf();
+            let loader = |name: &str| fs::read_to_string(base_dir.join(name)).ok();
+            let mut parser = if extended {
+                Parser::new_extended(path, &source, &loader)
+            } else {
+                Parser::new(&source)
+            };
+            if show_lints {
+                let warnings = lint::lint(&mut parser);
+                for warning in &warnings {
+                    let file = &parser.files()[warning.span.file];
+                    let name = if file.name.is_empty() { path } else { &file.name };
+                    eprintln!("{}", warning.render(name, &file.text));
+                }
+                eprintln!("{} warning(s)", warnings.len());
+            }
+            if optimize {
+                parser.optimize();
+            }
+            match assemble_parser(&mut parser) {
+                Ok(assembly) => {
+                    if show_listing {
+                        print!("{}", listing::listing(&source, &assembly));
+                    }
+                    let output_path = Path::new(path).with_extension(format.extension());
+                    let name = Path::new(path).file_stem().and_then(|s| s.to_str()).unwrap_or("program");
+                    if let Err(e) = fs::write(&output_path, output::encode(&assembly.words, format, name)) {
+                        eprintln!("{}: {}", output_path.display(), e);
+                        std::process::exit(1)
+                    }
+                    if write_map {
+                        let map_path = Path::new(path).with_extension("map");
+                        let map = source_map::SourceMap::from_parser(&mut parser, path);
+                        if let Err(e) = fs::write(&map_path, map.to_text()) {
+                            eprintln!("{}: {}", map_path.display(), e);
+                            std::process::exit(1)
+                        }
+                    }
+                },
+                Err(diagnostics) => {
+                    for diagnostic in &diagnostics {
+                        let file = &parser.files()[diagnostic.span.file];
+                        let name = if file.name.is_empty() { path } else { &file.name };
+                        eprintln!("{}", diagnostic.render(name, &file.text));
+                    }
+                    eprintln!("{} error(s)", diagnostics.len());
+                    std::process::exit(1)
+                }
+            }
         },
         None => {
             println!("{}", "ファイルパスを指定してね");
@@ -19,3 +161,486 @@ pub fn main() {
         }
     }
 }
+
+// 1パス目: (LABEL) を ROM アドレスに結びつける
+fn resolve_labels(parser: &mut Parser, symbol_table: &mut SymbolTable, diagnostics: &mut Vec<Diagnostic>) {
+    let mut rom_address: usize = 0;
+    let mut overflow: Option<Span> = None;
+    while parser.has_more_commands() {
+        match parser.command_type() {
+            CommandType::A_COMMAND | CommandType::C_COMMAND => {
+                if rom_address == ROM_SIZE {
+                    overflow = Some(parser.command_span());
+                }
+                rom_address += 1;
+            },
+            CommandType::L_COMMAND => {
+                match parser.symbol() {
+                    Ok(symbol) if symbol_table.contains(symbol) => {
+                        diagnostics.push(Diagnostic {
+                            error: AsmError::DuplicateLabel(symbol.to_string()),
+                            span: parser.command_span()
+                        });
+                    },
+                    Ok(symbol) => {
+                        // ROM_SIZE を超えた分は最後にまとめてエラーにする
+                        symbol_table.add_entry(symbol, rom_address.min(ROM_SIZE) as u16, SymbolKind::Label);
+                    },
+                    Err(error) => {
+                        diagnostics.push(Diagnostic { error, span: parser.command_span() });
+                    }
+                }
+            }
+        }
+        parser.advance();
+    }
+    if let Some(span) = overflow {
+        diagnostics.push(Diagnostic { error: AsmError::ProgramTooLarge(rom_address), span });
+    }
+    parser.reset();
+}
+
+// アセンブリのソースを機械語に変換する。エラーがあれば最初の1つを返す
+// 組み込んで使うための API なので、このバイナリからは呼ばない
+#[allow(dead_code)]
+pub fn assemble(source: &str) -> Result<Vec<u16>, AsmError> {
+    assemble_with_diagnostics(source).map_err(|mut diagnostics| diagnostics.remove(0).error)
+}
+
+// アセンブリのソースを機械語に変換する。エラーがあっても最後まで読んで全部返す
+#[allow(dead_code)]
+pub fn assemble_with_diagnostics(source: &str) -> Result<Vec<u16>, Vec<Diagnostic>> {
+    assemble_program(source).map(|assembly| assembly.words)
+}
+
+// アセンブルした結果。機械語と、それぞれがどこから来たか、解決したシンボル
+#[derive(Debug)]
+pub struct Assembly {
+    pub words: Vec<u16>,
+    pub origins: Vec<Origin>,
+    pub symbol_table: SymbolTable
+}
+
+// 機械語 1 ワードの出どころ
+#[derive(Debug, Clone, PartialEq)]
+pub struct Origin {
+    // 実際に書かれている場所 (マクロ本体や .include 先のこともある)
+    pub span: Span,
+    // 大元のファイルの何行目から展開されたか
+    pub source_line: usize,
+    // 空白とコメントを除いたコマンド
+    pub command: String
+}
+
+// ソースだけからアセンブルする。assemble と同じく組み込み用
+#[allow(dead_code)]
+pub fn assemble_program(source: &str) -> Result<Assembly, Vec<Diagnostic>> {
+    assemble_parser(&mut Parser::new(source))
+}
+
+pub fn assemble_parser(parser: &mut Parser) -> Result<Assembly, Vec<Diagnostic>> {
+    let mut symbol_table = SymbolTable::new();
+    let mut diagnostics: Vec<Diagnostic> = parser.take_diagnostics();
+    resolve_labels(parser, &mut symbol_table, &mut diagnostics);
+
+    // 2パス目: 機械語に変換する。未知のシンボルは変数として RAM を割り当てる
+    let mut ram_address: u16 = VARIABLE_BASE_ADDRESS;
+    let mut words: Vec<u16> = Vec::new();
+    let mut origins: Vec<Origin> = Vec::new();
+    while parser.has_more_commands() {
+        match parser.command_type() {
+            CommandType::A_COMMAND => {
+                origins.push(Origin {
+                    span: parser.command_span(),
+                    source_line: parser.origin_line(),
+                    command: parser.current_command().to_string()
+                });
+                let address = match parser.symbol() {
+                    Ok(symbol) if parser::is_number(symbol) => {
+                        match symbol.parse::<u16>() {
+                            Ok(address) if address <= MAX_ADDRESS => Some(address),
+                            _ => {
+                                diagnostics.push(Diagnostic {
+                                    error: AsmError::AddressOutOfRange(parser.current_command().to_string()),
+                                    span: parser.symbol_span()
+                                });
+                                None
+                            }
+                        }
+                    },
+                    Ok(symbol) => match symbol_table.get_address(symbol) {
+                        Some(address) => Some(address),
+                        None if ram_address > MAX_ADDRESS => {
+                            diagnostics.push(Diagnostic {
+                                error: AsmError::AddressOutOfRange(parser.current_command().to_string()),
+                                span: parser.symbol_span()
+                            });
+                            None
+                        },
+                        None => {
+                            symbol_table.add_entry(symbol, ram_address, SymbolKind::Variable);
+                            ram_address += 1;
+                            Some(ram_address - 1)
+                        }
+                    },
+                    Err(error) => {
+                        diagnostics.push(Diagnostic { error, span: parser.command_span() });
+                        None
+                    }
+                };
+                words.push(address.unwrap_or(0));
+            },
+            CommandType::C_COMMAND => {
+                origins.push(Origin {
+                    span: parser.command_span(),
+                    source_line: parser.origin_line(),
+                    command: parser.current_command().to_string()
+                });
+                let command = parser.current_command().to_string();
+                let dest = code::dest(parser.dest());
+                let comp = code::comp(parser.comp());
+                let jump = code::jump(parser.jump());
+                if dest.is_none() {
+                    diagnostics.push(Diagnostic {
+                        error: AsmError::InvalidDest(command.clone()),
+                        span: parser.dest_span()
+                    });
+                }
+                if comp.is_none() {
+                    diagnostics.push(Diagnostic {
+                        error: AsmError::InvalidComp(command.clone()),
+                        span: parser.comp_span()
+                    });
+                }
+                if jump.is_none() {
+                    diagnostics.push(Diagnostic {
+                        error: AsmError::InvalidJump(command),
+                        span: parser.jump_span()
+                    });
+                }
+                words.push(code::c_instruction(dest.unwrap_or(0), comp.unwrap_or(0), jump.unwrap_or(0)));
+            },
+            CommandType::L_COMMAND => {}
+        }
+        parser.advance();
+    }
+
+    if diagnostics.is_empty() {
+        Ok(Assembly { words, origins, symbol_table })
+    } else {
+        // 1パス目と2パス目のエラーをファイルの上から順に並べる
+        diagnostics.sort_by_key(|d| (d.span.file, d.span.line, d.span.column));
+        Err(diagnostics)
+    }
+}
+
+// nand2tetris の .hack 形式 (1行1命令の 0/1 文字列)
+pub fn to_hack(words: &[u16]) -> String {
+    words
+        .iter()
+        .map(|w| format!("{:016b}", w))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assemble_str(source: &str) -> String {
+        to_hack(&assemble(source).unwrap())
+    }
+
+    #[test]
+    fn add_test() {
+        assert_eq!(
+            include_str!("../06/dist/Add.hack"),
+            assemble_str(include_str!("../06/test/add/Add.asm"))
+        );
+    }
+
+    #[test]
+    fn max_l_test() {
+        assert_eq!(
+            include_str!("../06/dist/MaxL.hack"),
+            assemble_str(include_str!("../06/test/max/MaxL.asm"))
+        );
+    }
+
+    #[test]
+    fn rect_l_test() {
+        assert_eq!(
+            include_str!("../06/dist/RectL.hack"),
+            assemble_str(include_str!("../06/test/rect/RectL.asm"))
+        );
+    }
+
+    #[test]
+    fn pong_l_test() {
+        assert_eq!(
+            include_str!("../06/dist/PongL.hack"),
+            assemble_str(include_str!("../06/test/pong/PongL.asm"))
+        );
+    }
+
+    #[test]
+    fn max_test() {
+        assert_eq!(
+            include_str!("../06/dist/Max.hack"),
+            assemble_str(include_str!("../06/test/max/Max.asm"))
+        );
+    }
+
+    #[test]
+    fn rect_test() {
+        assert_eq!(
+            include_str!("../06/dist/Rect.hack"),
+            assemble_str(include_str!("../06/test/rect/Rect.asm"))
+        );
+    }
+
+    #[test]
+    fn pong_test() {
+        assert_eq!(
+            include_str!("../06/dist/Pong.hack"),
+            assemble_str(include_str!("../06/test/pong/Pong.asm"))
+        );
+    }
+
+    #[test]
+    fn symbol_equivalence_test() {
+        // シンボル付きとシンボル無しで同じ機械語になること
+        assert_eq!(
+            assemble_str(include_str!("../06/test/max/Max.asm")),
+            assemble_str(include_str!("../06/test/max/MaxL.asm"))
+        );
+        assert_eq!(
+            assemble_str(include_str!("../06/test/rect/Rect.asm")),
+            assemble_str(include_str!("../06/test/rect/RectL.asm"))
+        );
+        assert_eq!(
+            assemble_str(include_str!("../06/test/pong/Pong.asm")),
+            assemble_str(include_str!("../06/test/pong/PongL.asm"))
+        );
+    }
+
+    #[test]
+    fn empty_source_test() {
+        assert_eq!(Ok(vec![]), assemble(""));
+        assert_eq!(Ok(vec![]), assemble("// nothing here\n\n"));
+        assert_eq!(Ok(vec![]), assemble("(END)\n"));
+    }
+
+    #[test]
+    fn error_test() {
+        assert_eq!(Err(AsmError::InvalidComp("abababab".to_string())), assemble("abababab"));
+        assert_eq!(Err(AsmError::InvalidDest("X=D".to_string())), assemble("X=D"));
+        assert_eq!(Err(AsmError::InvalidJump("0;JJJ".to_string())), assemble("0;JJJ"));
+        assert_eq!(Err(AsmError::InvalidDest("0;JMP=D".to_string())), assemble("0;JMP=D"));
+        assert_eq!(Err(AsmError::InvalidComp("あ".to_string())), assemble("あ"));
+        assert_eq!(Err(AsmError::InvalidSymbol("@".to_string())), assemble("@"));
+        assert_eq!(Err(AsmError::InvalidLabel("(LOOP".to_string())), assemble("(LOOP\n@LOOP"));
+        assert_eq!(Err(AsmError::AddressOutOfRange("@70000".to_string())), assemble("@70000"));
+        assert_eq!(Err(AsmError::AddressOutOfRange("@32768".to_string())), assemble("@32768"));
+        assert_eq!(Ok(vec![32767]), assemble("@32767"));
+    }
+
+    #[test]
+    fn diagnostics_test() {
+        let source = "@1\n(LOOP\n  D=D+X   // typo\n@70000\n(END)\n(END)\nAMX=0;JXX\n";
+        let diagnostics = assemble_with_diagnostics(source).unwrap_err();
+        assert_eq!(
+            vec![
+                Diagnostic {
+                    error: AsmError::InvalidLabel("(LOOP".to_string()),
+                    span: Span { file: 0, line: 2, column: 1, length: 5 }
+                },
+                Diagnostic {
+                    error: AsmError::InvalidComp("D=D+X".to_string()),
+                    span: Span { file: 0, line: 3, column: 5, length: 3 }
+                },
+                Diagnostic {
+                    error: AsmError::AddressOutOfRange("@70000".to_string()),
+                    span: Span { file: 0, line: 4, column: 2, length: 5 }
+                },
+                Diagnostic {
+                    error: AsmError::DuplicateLabel("END".to_string()),
+                    span: Span { file: 0, line: 6, column: 1, length: 5 }
+                },
+                Diagnostic {
+                    error: AsmError::InvalidDest("AMX=0;JXX".to_string()),
+                    span: Span { file: 0, line: 7, column: 1, length: 3 }
+                },
+                Diagnostic {
+                    error: AsmError::InvalidJump("AMX=0;JXX".to_string()),
+                    span: Span { file: 0, line: 7, column: 7, length: 3 }
+                },
+            ],
+            diagnostics
+        );
+        assert_eq!(
+            "error: invalid comp mnemonic: D=D+X\n --> Prog.asm:3:5\n  |\n3 |   D=D+X   // typo\n  |     ^^^\n",
+            diagnostics[1].render("Prog.asm", source)
+        );
+    }
+
+    #[test]
+    fn extended_test() {
+        let loader = |name: &str| match name {
+            "stack.asm" => Some(concat!(
+                ".macro PUSH_CONST value\n",
+                "    @\\value\n",
+                "    D=A\n",
+                "    @SP\n",
+                "    A=M\n",
+                "    M=D\n",
+                "    @SP\n",
+                "    M=M+1\n",
+                ".endm\n",
+            ).to_string()),
+            _ => None
+        };
+        let source = ".include \"stack.asm\"\n@256\nD=A\n@SP\nM=D\nPUSH_CONST 7\nPUSH_CONST 8\n";
+        let expanded = "@256\nD=A\n@SP\nM=D\n@7\nD=A\n@SP\nA=M\nM=D\n@SP\nM=M+1\n@8\nD=A\n@SP\nA=M\nM=D\n@SP\nM=M+1\n";
+        let assembly = assemble_parser(&mut Parser::new_extended("main.asm", source, &loader)).unwrap();
+        assert_eq!(assemble(expanded).unwrap(), assembly.words);
+        assert_eq!(
+            vec![2, 3, 4, 5, 6, 6, 6, 6, 6, 6, 6, 7, 7, 7, 7, 7, 7, 7],
+            assembly.origins.iter().map(|o| o.source_line).collect::<Vec<_>>()
+        );
+
+        let diagnostics = assemble_parser(&mut Parser::new_extended("main.asm", "PUSH_CONST 7\n.include \"x.asm\"\n", &loader)).unwrap_err();
+        assert_eq!(
+            vec![
+                AsmError::InvalidComp("PUSH_CONST7".to_string()),
+                AsmError::IncludeNotFound("x.asm".to_string()),
+            ],
+            diagnostics.into_iter().map(|d| d.error).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn program_too_large_test() {
+        let source = "D=0\n".repeat(ROM_SIZE + 1);
+        assert_eq!(Err(AsmError::ProgramTooLarge(ROM_SIZE + 1)), assemble(&source));
+        let source = "D=0\n".repeat(ROM_SIZE);
+        assert_eq!(ROM_SIZE, assemble(&source).unwrap().len());
+    }
+
+    #[test]
+    fn optimize_test() {
+        let mut parser = Parser::new(include_str!("../06/test/pong/Pong.asm"));
+        parser.optimize();
+        let optimized = assemble_parser(&mut parser).unwrap();
+        assert!(optimized.words.len() < assemble_program(include_str!("../06/test/pong/Pong.asm")).unwrap().words.len());
+        // 共通ルーチンへの @95 / 0;JMP はラベルに置き換わっている
+        assert!(optimized.symbol_table.contains("ROM.95"));
+
+        // PongL は戻り番地を数値で持っているので何も変えない
+        let mut parser = Parser::new(include_str!("../06/test/pong/PongL.asm"));
+        parser.optimize();
+        assert_eq!(include_str!("../06/dist/PongL.hack"), to_hack(&assemble_parser(&mut parser).unwrap().words));
+    }
+
+    // Hack の CPU を cycles 命令分動かす。ROM の外に出たら止める
+    fn execute(words: &[u16], ram: &mut [i16], cycles: usize) {
+        let (mut a, mut d, mut pc): (i16, i16, usize) = (0, 0, 0);
+        for _ in 0..cycles {
+            let word = match words.get(pc) {
+                Some(word) => *word,
+                None => break
+            };
+            pc += 1;
+            if word & 0x8000 == 0 {
+                a = word as i16;
+                continue
+            }
+            let bit = |i: u16| (word >> i) & 1 == 1;
+            let mut x = d;
+            let mut y = if bit(12) { ram[a as u16 as usize] } else { a };
+            // comp の 6 ビットはそのまま ALU の zx nx zy ny f no
+            if bit(11) { x = 0; }
+            if bit(10) { x = !x; }
+            if bit(9) { y = 0; }
+            if bit(8) { y = !y; }
+            let mut out = if bit(7) { x.wrapping_add(y) } else { x & y };
+            if bit(6) { out = !out; }
+            let address = a as u16 as usize;
+            if bit(5) { a = out; }
+            if bit(4) { d = out; }
+            if bit(3) { ram[address] = out; }
+            if (bit(2) && out < 0) || (bit(1) && out == 0) || (bit(0) && out > 0) {
+                pc = address;
+            }
+        }
+    }
+
+    // .tst の set RAM[n] v と repeat の回数を使って動かし、output-list の RAM を読む
+    fn run_tst(words: &[u16], script: &str) -> Vec<i16> {
+        let mut ram = vec![0i16; 0x8000];
+        let mut cycles = 0;
+        let mut cells = Vec::new();
+        let tokens: Vec<&str> = script
+            .lines()
+            .map(|l| l.split("//").next().unwrap())
+            .flat_map(|l| l.split(|c: char| c.is_whitespace() || c == ',' || c == ';'))
+            .filter(|t| !t.is_empty())
+            .collect();
+        let cell = |t: &str| t.strip_prefix("RAM[").and_then(|t| t.split(']').next()).map(|n| n.parse::<usize>().unwrap());
+        let mut i = 0;
+        while i < tokens.len() {
+            match tokens[i] {
+                "set" => {
+                    ram[cell(tokens[i + 1]).unwrap()] = tokens[i + 2].parse().unwrap();
+                    i += 3;
+                    continue
+                },
+                "repeat" => cycles += tokens[i + 1].parse::<usize>().unwrap(),
+                t => cells.extend(cell(t))
+            }
+            i += 1;
+        }
+        execute(words, &mut ram, cycles);
+        cells.iter().map(|c| ram[*c]).collect()
+    }
+
+    // .cmp の値の行を全部つなげたもの
+    fn cmp_values(cmp: &str) -> Vec<i16> {
+        cmp.lines()
+            .skip(1)
+            .step_by(2)
+            .flat_map(|l| l.split('|').map(|v| v.trim()).filter(|v| !v.is_empty()).map(|v| v.parse().unwrap()).collect::<Vec<i16>>())
+            .collect()
+    }
+
+    // 07 / 08 の VM 変換結果を、最適化の前後で同じ .tst で動かして同じ RAM になるか
+    #[test]
+    fn optimize_execution_test() {
+        let programs = [
+            (include_str!("../07/test/StackArithmetic/SimpleAdd/SimpleAdd.asm"), include_str!("../07/test/StackArithmetic/SimpleAdd/SimpleAdd.tst"), include_str!("../07/test/StackArithmetic/SimpleAdd/SimpleAdd.cmp")),
+            (include_str!("../07/test/StackArithmetic/StackTest/StackTest.asm"), include_str!("../07/test/StackArithmetic/StackTest/StackTest.tst"), include_str!("../07/test/StackArithmetic/StackTest/StackTest.cmp")),
+            (include_str!("../07/test/MemoryAccess/BasicTest/BasicTest.asm"), include_str!("../07/test/MemoryAccess/BasicTest/BasicTest.tst"), include_str!("../07/test/MemoryAccess/BasicTest/BasicTest.cmp")),
+            (include_str!("../07/test/MemoryAccess/PointerTest/PointerTest.asm"), include_str!("../07/test/MemoryAccess/PointerTest/PointerTest.tst"), include_str!("../07/test/MemoryAccess/PointerTest/PointerTest.cmp")),
+            (include_str!("../07/test/MemoryAccess/StaticTest/StaticTest.asm"), include_str!("../07/test/MemoryAccess/StaticTest/StaticTest.tst"), include_str!("../07/test/MemoryAccess/StaticTest/StaticTest.cmp")),
+            (include_str!("../08/test/ProgramFlow/BasicLoop/BasicLoop.asm"), include_str!("../08/test/ProgramFlow/BasicLoop/BasicLoop.tst"), include_str!("../08/test/ProgramFlow/BasicLoop/BasicLoop.cmp")),
+            (include_str!("../08/test/ProgramFlow/FibonacciSeries/FibonacciSeries.asm"), include_str!("../08/test/ProgramFlow/FibonacciSeries/FibonacciSeries.tst"), include_str!("../08/test/ProgramFlow/FibonacciSeries/FibonacciSeries.cmp")),
+            (include_str!("../08/test/FunctionCalls/SimpleFunction/SimpleFunction.asm"), include_str!("../08/test/FunctionCalls/SimpleFunction/SimpleFunction.tst"), include_str!("../08/test/FunctionCalls/SimpleFunction/SimpleFunction.cmp")),
+            (include_str!("../08/test/FunctionCalls/NestedCall/NestedCall.asm"), include_str!("../08/test/FunctionCalls/NestedCall/NestedCall.tst"), include_str!("../08/test/FunctionCalls/NestedCall/NestedCall.cmp")),
+            (include_str!("../08/test/FunctionCalls/FibonacciElement/FibonacciElement.asm"), include_str!("../08/test/FunctionCalls/FibonacciElement/FibonacciElement.tst"), include_str!("../08/test/FunctionCalls/FibonacciElement/FibonacciElement.cmp"))
+            // StaticsTest.asm は VM 変換器が @/StaticsTest.asm.0 のような読めないシンボルを出しているので入れない
+        ];
+        let mut shrunk = 0;
+        for (asm, tst, cmp) in programs.iter() {
+            let plain = assemble(asm).unwrap();
+            let mut parser = Parser::new(asm);
+            parser.optimize();
+            let optimized = assemble_parser(&mut parser).unwrap().words;
+            let expected = run_tst(&plain, tst);
+            // 最適化しない方は nand2tetris の .cmp と同じ
+            assert_eq!(cmp_values(cmp), expected);
+            assert_eq!(expected, run_tst(&optimized, tst));
+            shrunk += (optimized.len() < plain.len()) as usize;
+        }
+        assert!(shrunk > 0);
+    }
+}
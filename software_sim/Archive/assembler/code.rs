@@ -0,0 +1,177 @@
+// dest=comp;jump の各ニーモニックを機械語のビット列に変換する
+// 知らないニーモニックは None を返す
+
+pub fn dest(mnemonic: Option<&str>) -> Option<u16> {
+    let mnemonic = match mnemonic {
+        Some(m) => m,
+        None => return Some(0b000)
+    };
+    if mnemonic.is_empty() {
+        return None
+    }
+
+    // d1: A, d2: D, d3: M の順。"MD" も "DM" も同じ意味になる
+    let mut bits: u16 = 0;
+    for c in mnemonic.chars() {
+        let bit = match c {
+            'A' => 0b100,
+            'D' => 0b010,
+            'M' => 0b001,
+            _ => return None
+        };
+        if bits & bit != 0 {
+            return None
+        }
+        bits |= bit;
+    }
+    Some(bits)
+}
+
+// 先頭のビットが a。a=0 なら A、a=1 なら M を使う
+const COMP_TABLE: [(&str, u16); 28] = [
+    // a=0
+    ("0",   0b0_101010),
+    ("1",   0b0_111111),
+    ("-1",  0b0_111010),
+    ("D",   0b0_001100),
+    ("A",   0b0_110000),
+    ("!D",  0b0_001101),
+    ("!A",  0b0_110001),
+    ("-D",  0b0_001111),
+    ("-A",  0b0_110011),
+    ("D+1", 0b0_011111),
+    ("A+1", 0b0_110111),
+    ("D-1", 0b0_001110),
+    ("A-1", 0b0_110010),
+    ("D+A", 0b0_000010),
+    ("D-A", 0b0_010011),
+    ("A-D", 0b0_000111),
+    ("D&A", 0b0_000000),
+    ("D|A", 0b0_010101),
+    // a=1
+    ("M",   0b1_110000),
+    ("!M",  0b1_110001),
+    ("-M",  0b1_110011),
+    ("M+1", 0b1_110111),
+    ("M-1", 0b1_110010),
+    ("D+M", 0b1_000010),
+    ("D-M", 0b1_010011),
+    ("M-D", 0b1_000111),
+    ("D&M", 0b1_000000),
+    ("D|M", 0b1_010101),
+];
+
+const JUMP_TABLE: [&str; 8] = ["", "JGT", "JEQ", "JGE", "JLT", "JNE", "JLE", "JMP"];
+
+pub fn comp(mnemonic: &str) -> Option<u16> {
+    COMP_TABLE
+        .iter()
+        .find(|(m, _)| *m == mnemonic)
+        .map(|(_, bits)| *bits)
+}
+
+pub fn jump(mnemonic: Option<&str>) -> Option<u16> {
+    match mnemonic {
+        None => Some(0b000),
+        Some("") => None,
+        Some(m) => JUMP_TABLE.iter().position(|j| *j == m).map(|bits| bits as u16)
+    }
+}
+
+// 111a cccc ccdd djjj
+pub fn c_instruction(dest: u16, comp: u16, jump: u16) -> u16 {
+    0b111 << 13 | comp << 6 | dest << 3 | jump
+}
+
+// ここから下は逆変換 (ビット列 -> ニーモニック)
+
+pub fn dest_mnemonic(bits: u16) -> Option<String> {
+    if bits & 0b111 == 0 {
+        return None
+    }
+    let mut mnemonic = String::new();
+    if bits & 0b100 != 0 { mnemonic.push('A'); }
+    if bits & 0b001 != 0 { mnemonic.push('M'); }
+    if bits & 0b010 != 0 { mnemonic.push('D'); }
+    Some(mnemonic)
+}
+
+pub fn comp_mnemonic(bits: u16) -> Option<&'static str> {
+    COMP_TABLE
+        .iter()
+        .find(|(_, b)| *b == bits & 0b1111111)
+        .map(|(m, _)| *m)
+}
+
+pub fn jump_mnemonic(bits: u16) -> Option<&'static str> {
+    match bits & 0b111 {
+        0 => None,
+        b => Some(JUMP_TABLE[b as usize])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn dest_test() {
+        assert_eq!(Some(0b000), dest(None));
+        assert_eq!(Some(0b001), dest(Some("M")));
+        assert_eq!(Some(0b010), dest(Some("D")));
+        assert_eq!(Some(0b011), dest(Some("MD")));
+        assert_eq!(Some(0b011), dest(Some("DM")));
+        assert_eq!(Some(0b100), dest(Some("A")));
+        assert_eq!(Some(0b101), dest(Some("AM")));
+        assert_eq!(Some(0b110), dest(Some("AD")));
+        assert_eq!(Some(0b111), dest(Some("AMD")));
+        assert_eq!(None, dest(Some("")));
+        assert_eq!(None, dest(Some("X")));
+        assert_eq!(None, dest(Some("MM")));
+    }
+
+    #[test]
+    fn comp_test() {
+        assert_eq!(Some(0b0101010), comp("0"));
+        assert_eq!(Some(0b0000111), comp("A-D"));
+        assert_eq!(Some(0b1110000), comp("M"));
+        assert_eq!(Some(0b1010101), comp("D|M"));
+        assert_eq!(None, comp("M+D"));
+        assert_eq!(None, comp("2"));
+        assert_eq!(None, comp(""));
+    }
+
+    #[test]
+    fn jump_test() {
+        assert_eq!(Some(0b000), jump(None));
+        assert_eq!(Some(0b001), jump(Some("JGT")));
+        assert_eq!(Some(0b111), jump(Some("JMP")));
+        assert_eq!(None, jump(Some("JXX")));
+        assert_eq!(None, jump(Some("")));
+    }
+
+    #[test]
+    fn mnemonic_round_trip_test() {
+        for (m, bits) in COMP_TABLE.iter() {
+            assert_eq!(Some(*m), comp_mnemonic(*bits));
+            assert_eq!(Some(*bits), comp(m));
+        }
+        for bits in 0..8 {
+            assert_eq!(Some(bits), dest(dest_mnemonic(bits).as_deref()));
+            assert_eq!(Some(bits), jump(jump_mnemonic(bits)));
+        }
+        assert_eq!(Some("AMD".to_string()), dest_mnemonic(0b111));
+        assert_eq!(Some("MD".to_string()), dest_mnemonic(0b011));
+        assert_eq!(None, comp_mnemonic(0b1_111111));
+    }
+
+    #[test]
+    fn c_instruction_test() {
+        // D=A
+        assert_eq!(0b1110110000010000, c_instruction(0b010, 0b0110000, 0b000));
+        // 0;JMP
+        assert_eq!(0b1110101010000111, c_instruction(0b000, 0b0101010, 0b111));
+        // AMD=M-1;JNE
+        assert_eq!(0b1111110010111101, c_instruction(0b111, 0b1110010, 0b101));
+    }
+}
@@ -0,0 +1,166 @@
+use std::collections::BTreeSet;
+
+use super::code;
+use super::error::AsmError;
+
+// 機械語 1 ワードを解釈した結果
+#[derive(Debug, Clone, PartialEq)]
+pub enum Instruction {
+    A(u16),
+    C {
+        dest: Option<String>,
+        comp: &'static str,
+        jump: Option<&'static str>
+    },
+    // C命令として解釈できないワード (11x 以外の上位ビットや未定義の comp)
+    Invalid(u16)
+}
+
+pub fn decode(word: u16) -> Instruction {
+    if word & 0x8000 == 0 {
+        return Instruction::A(word);
+    }
+    if word >> 13 != 0b111 {
+        return Instruction::Invalid(word);
+    }
+    match code::comp_mnemonic(word >> 6) {
+        Some(comp) => Instruction::C {
+            dest: code::dest_mnemonic(word >> 3),
+            comp,
+            jump: code::jump_mnemonic(word)
+        },
+        None => Instruction::Invalid(word)
+    }
+}
+
+// .hack 形式 (1行1命令の 0/1 文字列) を読む
+pub fn from_hack(text: &str) -> Result<Vec<u16>, AsmError> {
+    text.lines()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty())
+        .map(|l| {
+            if l.len() != 16 || !l.chars().all(|c| c == '0' || c == '1') {
+                return Err(AsmError::InvalidMachineCode(l.to_string()));
+            }
+            u16::from_str_radix(l, 2).map_err(|_| AsmError::InvalidMachineCode(l.to_string()))
+        })
+        .collect()
+}
+
+// ジャンプ先の ROM アドレス。@n の直後にジャンプする C命令があれば n をジャンプ先とみなす
+pub fn jump_targets(words: &[u16]) -> BTreeSet<u16> {
+    let mut targets = BTreeSet::new();
+    for pair in words.windows(2) {
+        if let (Instruction::A(address), Instruction::C { jump: Some(_), .. }) = (decode(pair[0]), decode(pair[1])) {
+            if address as usize <= words.len() {
+                targets.insert(address);
+            }
+        }
+    }
+    targets
+}
+
+fn label_name(address: u16) -> String {
+    format!("L{}", address)
+}
+
+// 機械語をアセンブリに戻す。labels が true ならジャンプ先に (Lnnn) ラベルを復元する
+pub fn disassemble(words: &[u16], labels: bool) -> String {
+    let targets = if labels { jump_targets(words) } else { BTreeSet::new() };
+    let mut lines: Vec<String> = Vec::new();
+
+    for (address, word) in words.iter().enumerate() {
+        if targets.contains(&(address as u16)) {
+            lines.push(format!("({})", label_name(address as u16)));
+        }
+        let next_jumps = matches!(words.get(address + 1).map(|w| decode(*w)), Some(Instruction::C { jump: Some(_), .. }));
+        let line = match decode(*word) {
+            Instruction::A(value) if next_jumps && targets.contains(&value) => format!("@{}", label_name(value)),
+            Instruction::A(value) => format!("@{}", value),
+            Instruction::C { dest, comp, jump } => {
+                let mut line = String::new();
+                if let Some(dest) = dest {
+                    line.push_str(&dest);
+                    line.push('=');
+                }
+                line.push_str(comp);
+                if let Some(jump) = jump {
+                    line.push(';');
+                    line.push_str(jump);
+                }
+                line
+            },
+            Instruction::Invalid(word) => format!("// invalid instruction: {:016b}", word)
+        };
+        lines.push(line);
+    }
+    // プログラムの末尾 (= words.len()) へのジャンプ
+    if targets.contains(&(words.len() as u16)) {
+        lines.push(format!("({})", label_name(words.len() as u16)));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use super::super::assemble;
+
+    #[test]
+    fn decode_test() {
+        assert_eq!(Instruction::A(21), decode(0b0000000000010101));
+        assert_eq!(
+            Instruction::C { dest: Some("D".to_string()), comp: "A", jump: None },
+            decode(0b1110110000010000)
+        );
+        assert_eq!(
+            Instruction::C { dest: None, comp: "0", jump: Some("JMP") },
+            decode(0b1110101010000111)
+        );
+        assert_eq!(
+            Instruction::C { dest: Some("AMD".to_string()), comp: "M-1", jump: Some("JNE") },
+            decode(0b1111110010111101)
+        );
+        // 上位ビットが 111 でない
+        assert_eq!(Instruction::Invalid(0b1000110000010000), decode(0b1000110000010000));
+        // 未定義の comp
+        assert_eq!(Instruction::Invalid(0b1111111111000000), decode(0b1111111111000000));
+    }
+
+    #[test]
+    fn from_hack_test() {
+        assert_eq!(Ok(vec![2, 0b1110110000010000]), from_hack("0000000000000010\n1110110000010000\n"));
+        assert_eq!(Err(AsmError::InvalidMachineCode("0102".to_string())), from_hack("0102"));
+    }
+
+    #[test]
+    fn disassemble_test() {
+        let words = from_hack(include_str!("../../06/dist/Add.hack")).unwrap();
+        assert_eq!("@2\nD=A\n@3\nD=D+A\n@0\nM=D", disassemble(&words, true));
+    }
+
+    #[test]
+    fn label_test() {
+        let words = from_hack(include_str!("../../06/dist/Max.hack")).unwrap();
+        let asm = disassemble(&words, true);
+        assert!(asm.contains("(L10)\n@0\nD=M\n"));
+        assert!(asm.contains("@L10\nD;JGT\n"));
+        assert!(asm.ends_with("(L14)\n@L14\n0;JMP"));
+        // 変数や定数の @n はラベルにしない
+        assert!(asm.starts_with("@0\nD=M\n@1\nD=D-M\n"));
+    }
+
+    #[test]
+    fn round_trip_test() {
+        // 逆アセンブルしたものをもう一度アセンブルすると同じ機械語になること
+        for hack in [
+            include_str!("../../06/dist/Max.hack"),
+            include_str!("../../06/dist/Rect.hack"),
+            include_str!("../../06/dist/Pong.hack"),
+        ].iter() {
+            let words = from_hack(hack).unwrap();
+            assert_eq!(Ok(words.clone()), assemble(&disassemble(&words, false)));
+            assert_eq!(Ok(words.clone()), assemble(&disassemble(&words, true)));
+        }
+    }
+}
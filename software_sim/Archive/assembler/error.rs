@@ -0,0 +1,126 @@
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AsmError {
+    // dest=comp;jump のどこかが知らないニーモニック
+    InvalidDest(String),
+    InvalidComp(String),
+    InvalidJump(String),
+    // @xxx の xxx がシンボルとしても数値としても読めない
+    InvalidSymbol(String),
+    // (LABEL) の形になっていない
+    InvalidLabel(String),
+    // 同じラベルが2回以上宣言されている
+    DuplicateLabel(String),
+    // A命令に 15bit を超える値を指定した
+    AddressOutOfRange(String),
+    // ROM (32K ワード) に収まらない
+    ProgramTooLarge(usize),
+    // .hack の行が 16 桁の 0/1 になっていない
+    InvalidMachineCode(String),
+    // 以下は拡張構文 (.macro / .include) のエラー
+    UnknownDirective(String),
+    InvalidMacro(String),
+    UnterminatedMacro(String),
+    MacroArgumentCount(String),
+    MacroRecursion(String),
+    IncludeNotFound(String),
+    IncludeCycle(String),
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AsmError::InvalidDest(s) => write!(f, "invalid dest mnemonic: {}", s),
+            AsmError::InvalidComp(s) => write!(f, "invalid comp mnemonic: {}", s),
+            AsmError::InvalidJump(s) => write!(f, "invalid jump mnemonic: {}", s),
+            AsmError::InvalidSymbol(s) => write!(f, "invalid symbol: {}", s),
+            AsmError::InvalidLabel(s) => write!(f, "invalid label declaration: {}", s),
+            AsmError::DuplicateLabel(s) => write!(f, "duplicate label: {}", s),
+            AsmError::AddressOutOfRange(s) => write!(f, "address out of range (0..=32767): {}", s),
+            AsmError::ProgramTooLarge(n) => write!(f, "program too large for ROM: {} instructions", n),
+            AsmError::InvalidMachineCode(s) => write!(f, "invalid machine code: {}", s),
+            AsmError::UnknownDirective(s) => write!(f, "unknown directive: {}", s),
+            AsmError::InvalidMacro(s) => write!(f, "invalid macro definition: {}", s),
+            AsmError::UnterminatedMacro(s) => write!(f, "macro {} is missing .endm", s),
+            AsmError::MacroArgumentCount(s) => write!(f, "wrong number of macro arguments: {}", s),
+            AsmError::MacroRecursion(s) => write!(f, "macro expands itself recursively: {}", s),
+            AsmError::IncludeNotFound(s) => write!(f, "cannot include file: {}", s),
+            AsmError::IncludeCycle(s) => write!(f, "include cycle: {}", s),
+        }
+    }
+}
+
+impl Error for AsmError {}
+
+// 元のソース上の位置。line, column は 1 始まり
+// file は読み込んだファイルの番号 (0 がアセンブル対象、1 以降は .include したもの)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Span {
+    pub file: usize,
+    pub line: usize,
+    pub column: usize,
+    pub length: usize
+}
+
+// エラーとその発生位置
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub error: AsmError,
+    pub span: Span
+}
+
+impl Diagnostic {
+    // rustc 風に該当行を表示して下線を引く
+    //
+    // error: invalid comp mnemonic: D=D+X
+    //  --> Prog.asm:3:3
+    //   |
+    // 3 | D=D+X
+    //   |   ^^^
+    pub fn render(&self, file_name: &str, source: &str) -> String {
+        render_span("error", &self.error.to_string(), self.span, file_name, source)
+    }
+}
+
+// Diagnostic::render の本体。level は "error" や "warning"
+pub fn render_span(level: &str, message: &str, span: Span, file_name: &str, source: &str) -> String {
+    let line_text = source
+        .lines()
+        .nth(span.line.saturating_sub(1))
+        .unwrap_or("");
+    let gutter = " ".repeat(span.line.to_string().len());
+    let mut underline: String = line_text
+        .chars()
+        .take(span.column.saturating_sub(1))
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+    underline.push_str(&"^".repeat(span.length.max(1)));
+
+    format!(
+        "{}: {}\n{}--> {}:{}:{}\n{} |\n{} | {}\n{} | {}\n",
+        level, message,
+        gutter, file_name, span.line, span.column,
+        gutter,
+        span.line, line_text,
+        gutter, underline
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn render_test() {
+        let diagnostic = Diagnostic {
+            error: AsmError::InvalidComp("D=D+X".to_string()),
+            span: Span { file: 0, line: 2, column: 6, length: 3 }
+        };
+        assert_eq!(
+            "error: invalid comp mnemonic: D=D+X\n --> Prog.asm:2:6\n  |\n2 | \tD = D+X // add\n  | \t    ^^^\n",
+            diagnostic.render("Prog.asm", "@1\n\tD = D+X // add\n")
+        );
+    }
+}
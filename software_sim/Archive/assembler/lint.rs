@@ -0,0 +1,221 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use super::error::{render_span, Span};
+use super::parser::{self, CommandType, Parser};
+use super::symbol_table::SymbolTable;
+
+// 画面 (SCREEN) とキーボード (KBD) より上は RAM が無い
+const KBD_ADDRESS: u16 = 0x6000;
+
+// アセンブルはできるが、たぶん間違っているもの
+#[derive(Debug, Clone, PartialEq)]
+pub enum Lint {
+    // D=M;JGT のように M とジャンプを同時に使っているのに、A を直前の @ で入れていない
+    // A は RAM の番地とジャンプ先を兼ねるので、前に残っていた A でどちらも決まってしまう
+    MemoryWithJump(String),
+    // 定義したが @ で一度も参照していないラベル
+    UnusedLabel(String),
+    // @loop と (LOOP) のように大文字小文字だけ違う変数 (新しい RAM が割り当てられる)
+    CaseMismatch { variable: String, label: String },
+    // 24576 より上の番地への書き込み
+    WriteAboveKeyboard(String),
+    // 最後が 0;JMP で終わっていないので ROM の先まで実行してしまう
+    FallsOffEnd
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Lint::MemoryWithJump(s) => write!(f, "M and jump use an A that the preceding @ did not set: {}", s),
+            Lint::UnusedLabel(s) => write!(f, "label is never referenced: {}", s),
+            Lint::CaseMismatch { variable, label } => write!(f, "variable {} differs only in case from label {}", variable, label),
+            Lint::WriteAboveKeyboard(s) => write!(f, "write above the keyboard address (24576): {}", s),
+            Lint::FallsOffEnd => write!(f, "program can fall off the end; finish with an infinite loop such as (END) @END 0;JMP"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warning {
+    pub lint: Lint,
+    pub span: Span
+}
+
+impl Warning {
+    pub fn render(&self, file_name: &str, source: &str) -> String {
+        render_span("warning", &self.lint.to_string(), self.span, file_name, source)
+    }
+}
+
+// parser のコマンドを一通り調べて、ソースの上から順に警告を返す
+// 文法エラーのあるコマンドは飛ばす (エラーはアセンブル時に出る)
+pub fn lint(parser: &mut Parser) -> Vec<Warning> {
+    let predefined = SymbolTable::new();
+    let mut warnings: Vec<Warning> = Vec::new();
+    // ラベル -> 宣言した位置
+    let mut labels: HashMap<String, Span> = HashMap::new();
+    let mut label_order: Vec<String> = Vec::new();
+    // @ で参照したシンボル (最初に出てきた位置)
+    let mut references: Vec<(String, Span)> = Vec::new();
+    // 直前の @ で A に入った値 (数値か定義済みシンボルのときだけ分かる)
+    let mut a: Option<u16> = None;
+    // 1 つ前のコマンドが @ なら true
+    let mut loaded = false;
+    let mut last: Option<(CommandType, Option<String>, Span)> = None;
+
+    parser.reset();
+    while parser.has_more_commands() {
+        match parser.command_type() {
+            CommandType::A_COMMAND => {
+                a = match parser.symbol() {
+                    Ok(symbol) if parser::is_number(symbol) => symbol.parse::<u16>().ok(),
+                    Ok(symbol) => {
+                        if !references.iter().any(|(s, _)| s == symbol) {
+                            references.push((symbol.to_string(), parser.symbol_span()));
+                        }
+                        predefined.get_address(symbol)
+                    },
+                    Err(_) => None
+                };
+                loaded = true;
+                last = Some((CommandType::A_COMMAND, None, parser.command_span()));
+            },
+            CommandType::C_COMMAND => {
+                let dest = parser.dest().unwrap_or("");
+                let comp = parser.comp();
+                let jump = parser.jump();
+                if jump.is_some() && !loaded && (dest.contains('M') || comp.contains('M')) {
+                    warnings.push(Warning {
+                        lint: Lint::MemoryWithJump(parser.current_command().to_string()),
+                        span: parser.command_span()
+                    });
+                }
+                if dest.contains('M') && a.is_some_and(|address| address > KBD_ADDRESS) {
+                    warnings.push(Warning {
+                        lint: Lint::WriteAboveKeyboard(parser.current_command().to_string()),
+                        span: parser.command_span()
+                    });
+                }
+                if dest.contains('A') {
+                    a = None;
+                }
+                loaded = false;
+                last = Some((CommandType::C_COMMAND, jump.map(|j| j.to_string()), parser.command_span()));
+            },
+            CommandType::L_COMMAND => {
+                if let Ok(label) = parser.symbol() {
+                    if !labels.contains_key(label) {
+                        labels.insert(label.to_string(), parser.command_span());
+                        label_order.push(label.to_string());
+                    }
+                }
+                // どこから飛んでくるか分からない
+                a = None;
+                loaded = false;
+            }
+        }
+        parser.advance();
+    }
+    parser.reset();
+
+    let referenced: HashSet<&str> = references.iter().map(|(s, _)| s.as_str()).collect();
+    for label in &label_order {
+        if !referenced.contains(label.as_str()) {
+            warnings.push(Warning { lint: Lint::UnusedLabel(label.clone()), span: labels[label] });
+        }
+    }
+    for (symbol, span) in &references {
+        if labels.contains_key(symbol) || predefined.contains(symbol) {
+            continue
+        }
+        if let Some(label) = label_order.iter().find(|l| l.eq_ignore_ascii_case(symbol)) {
+            warnings.push(Warning {
+                lint: Lint::CaseMismatch { variable: symbol.clone(), label: label.clone() },
+                span: *span
+            });
+        }
+    }
+    match last {
+        Some((CommandType::C_COMMAND, Some(jump), _)) if jump == "JMP" => {},
+        Some((_, _, span)) => warnings.push(Warning { lint: Lint::FallsOffEnd, span }),
+        None => {}
+    }
+
+    warnings.sort_by_key(|w| (w.span.file, w.span.line, w.span.column));
+    warnings
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn lint_str(source: &str) -> Vec<Lint> {
+        lint(&mut Parser::new(source)).into_iter().map(|w| w.lint).collect()
+    }
+
+    #[test]
+    fn clean_test() {
+        assert_eq!(Vec::<Lint>::new(), lint_str(include_str!("../../06/test/max/Max.asm")));
+        assert_eq!(Vec::<Lint>::new(), lint_str(include_str!("../../06/test/rect/Rect.asm")));
+    }
+
+    #[test]
+    fn memory_with_jump_test() {
+        // 2 つ前の @i のまま、ラベルに飛んできた後
+        assert_eq!(
+            vec![Lint::MemoryWithJump("M=M-1;JGT".to_string()), Lint::MemoryWithJump("D=M;JEQ".to_string())],
+            lint_str("@i\nD=M\nM=M-1;JGT\n(LOOP)\nD=M;JEQ\n@LOOP\n0;JMP\n")
+        );
+        // 直前の @ で入れた A なら警告しない
+        assert_eq!(Vec::<Lint>::new(), lint_str("@LOOP\n(LOOP)\n@i\nD=M;JGT\n@LOOP\n0;JMP\n"));
+    }
+
+    #[test]
+    fn unused_label_test() {
+        let warnings = lint(&mut Parser::new("(START)\n@0\nD=A\n(END)\n@END\n0;JMP\n"));
+        assert_eq!(
+            vec![Warning { lint: Lint::UnusedLabel("START".to_string()), span: Span { file: 0, line: 1, column: 1, length: 7 } }],
+            warnings
+        );
+    }
+
+    #[test]
+    fn case_mismatch_test() {
+        assert_eq!(
+            vec![Lint::CaseMismatch { variable: "loop".to_string(), label: "LOOP".to_string() }],
+            lint_str("(LOOP)\n@loop\n0;JMP\n@LOOP\n0;JMP\n")
+        );
+        // 定義済みシンボルは対象外
+        assert_eq!(Vec::<Lint>::new(), lint_str("(kbd)\n@KBD\nD=M\n@kbd\n0;JMP\n"));
+    }
+
+    #[test]
+    fn write_above_keyboard_test() {
+        assert_eq!(
+            vec![Lint::WriteAboveKeyboard("M=0".to_string())],
+            lint_str("@24576\nD=M\n@24577\nM=0\n@KBD\nM=0\n(END)\n@END\n0;JMP\n")
+        );
+        // A を計算で変えたら分からない
+        assert_eq!(Vec::<Lint>::new(), lint_str("@24577\nA=D\nM=0\n(END)\n@END\n0;JMP\n"));
+    }
+
+    #[test]
+    fn falls_off_end_test() {
+        let warnings = lint(&mut Parser::new("@1\nD=A\n@R0\nM=D\n"));
+        assert_eq!(
+            vec![Warning { lint: Lint::FallsOffEnd, span: Span { file: 0, line: 4, column: 1, length: 3 } }],
+            warnings
+        );
+        assert_eq!(Vec::<Lint>::new(), lint_str(""));
+    }
+
+    #[test]
+    fn render_test() {
+        let warning = Warning { lint: Lint::UnusedLabel("X".to_string()), span: Span { file: 0, line: 1, column: 1, length: 3 } };
+        assert_eq!(
+            "warning: label is never referenced: X\n --> a.asm:1:1\n  |\n1 | (X)\n  | ^^^\n",
+            warning.render("a.asm", "(X)\n")
+        );
+    }
+}
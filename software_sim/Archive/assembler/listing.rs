@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+
+use super::Assembly;
+use super::symbol_table::SymbolKind;
+
+// ソースの各行に ROM アドレスと機械語 (2進/16進) を並べ、最後にシンボル表を付ける
+//
+//  ROM  Binary            Hex   Line  Source
+//     0 0000000000000010  0002     8  @2
+//                                  9  (LOOP)
+pub fn listing(source: &str, assembly: &Assembly) -> String {
+    // 行番号 -> その行から生成された ROM アドレス (マクロなら複数)
+    let mut addresses: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (address, origin) in assembly.origins.iter().enumerate() {
+        addresses.entry(origin.source_line).or_default().push(address);
+    }
+
+    let mut out = String::new();
+    out.push_str(" ROM  Binary            Hex   Line  Source\n");
+    for (i, text) in source.lines().enumerate() {
+        let line = i + 1;
+        match addresses.get(&line).map(|a| a.as_slice()) {
+            Some([address]) if assembly.origins[*address].span.file == 0 && assembly.origins[*address].span.line == line => {
+                let word = assembly.words[*address];
+                out.push_str(&format!("{:>5} {:016b}  {:04X}  {:>4}  {}\n", address, word, word, line, text));
+            },
+            expanded => {
+                let row = format!("{:>5} {:16}  {:4}  {:>4}  {}", "", "", "", line, text);
+                out.push_str(row.trim_end());
+                out.push('\n');
+                // マクロや .include で展開された分は "+" を付けて続ける
+                for address in expanded.unwrap_or(&[]) {
+                    let word = assembly.words[*address];
+                    let command = &assembly.origins[*address].command;
+                    out.push_str(&format!("{:>5} {:016b}  {:04X}  {:>4}  + {}\n", address, word, word, "", command));
+                }
+            }
+        }
+    }
+
+    out.push_str("\nLabels (ROM)\n");
+    for (symbol, address) in assembly.symbol_table.entries(SymbolKind::Label) {
+        out.push_str(&format!("{:>5}  {}\n", address, symbol));
+    }
+    out.push_str("\nVariables (RAM)\n");
+    for (symbol, address) in assembly.symbol_table.entries(SymbolKind::Variable) {
+        out.push_str(&format!("{:>5}  {}\n", address, symbol));
+    }
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use super::super::{assemble_parser, assemble_program};
+    use super::super::parser::Parser;
+
+    #[test]
+    fn listing_test() {
+        let source = "// count down\n@10\nD=A\n(LOOP)\n  @i   // counter\nM=D\nD=D-1\n@LOOP\nD;JGT\n";
+        let assembly = assemble_program(source).unwrap();
+        assert_eq!(
+            concat!(
+                " ROM  Binary            Hex   Line  Source\n",
+                "                                 1  // count down\n",
+                "    0 0000000000001010  000A     2  @10\n",
+                "    1 1110110000010000  EC10     3  D=A\n",
+                "                                 4  (LOOP)\n",
+                "    2 0000000000010000  0010     5    @i   // counter\n",
+                "    3 1110001100001000  E308     6  M=D\n",
+                "    4 1110001110010000  E390     7  D=D-1\n",
+                "    5 0000000000000010  0002     8  @LOOP\n",
+                "    6 1110001100000001  E301     9  D;JGT\n",
+                "\n",
+                "Labels (ROM)\n",
+                "    2  LOOP\n",
+                "\n",
+                "Variables (RAM)\n",
+                "   16  i\n",
+            ),
+            listing(source, &assembly)
+        );
+    }
+
+    #[test]
+    fn expanded_listing_test() {
+        let loader = |_: &str| None;
+        let source = ".macro INC addr\n@\\addr\nM=M+1\n.endm\nINC R1\n";
+        let assembly = assemble_parser(&mut Parser::new_extended("main.asm", source, &loader)).unwrap();
+        assert_eq!(
+            concat!(
+                " ROM  Binary            Hex   Line  Source\n",
+                "                                 1  .macro INC addr\n",
+                "                                 2  @\\addr\n",
+                "                                 3  M=M+1\n",
+                "                                 4  .endm\n",
+                "                                 5  INC R1\n",
+                "    0 0000000000000001  0001        + @R1\n",
+                "    1 1111110111001000  FDC8        + M=M+1\n",
+                "\n",
+                "Labels (ROM)\n",
+                "\n",
+                "Variables (RAM)\n",
+            ),
+            listing(source, &assembly)
+        );
+    }
+}
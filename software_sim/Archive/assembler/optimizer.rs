@@ -0,0 +1,396 @@
+use std::collections::{HashMap, HashSet};
+
+use super::parser::is_number;
+use super::MAX_ADDRESS;
+
+// 覗き穴最適化。ラベルはシンボルのまま扱い、アドレスは最適化した後の 1パス目で決まるので
+// 命令を消したり書き換えたりしてもラベルのアドレスはずれない
+//
+// - A が既に x のときの @x を消す
+// - @a / D=A / @b / D=D+A (D=D-A) を @(a+b) / D=A にまとめる
+// - 無条件ジャンプの後ろ、次のラベルまでの到達しない命令を消す
+// - ジャンプ先が @L2 / 0;JMP だけのラベル L1 なら、@L1 を直接 @L2 にする
+//
+// VM 変換したコード (Pong など) は共通ルーチンへ @95 / 0;JMP のように番地で飛ぶので、
+// 最初にそういう飛び先にラベル (ROM.95) を付けてから最適化する。
+// ジャンプ直前の @n 以外の数値 (@5 / D=A など) はデータとみなすが、A=M / 0;JMP のような
+// 計算したジャンプがあって、無条件ジャンプの直後 (ラベルなし、@n / 0;JMP の飛び先でもない) の
+// 番地を数値で持っているなら、戻り番地を数値で書いている (PongL など) ので最適化しない
+
+#[derive(Debug, Clone, PartialEq)]
+enum Item {
+    Label(String),
+    A(String),
+    // 省略された dest / jump は空文字列
+    C { dest: String, comp: String, jump: String }
+}
+
+impl Item {
+    fn parse(command: &str) -> Item {
+        if let Some(symbol) = command.strip_prefix('@') {
+            return Item::A(symbol.to_string())
+        }
+        if let Some(label) = command.strip_prefix('(') {
+            return Item::Label(label.trim_end_matches(')').to_string())
+        }
+        let (dest, rest) = match command.find('=') {
+            Some(i) => (&command[..i], &command[i + 1..]),
+            None => ("", command)
+        };
+        let (comp, jump) = match rest.find(';') {
+            Some(i) => (&rest[..i], &rest[i + 1..]),
+            None => (rest, "")
+        };
+        Item::C { dest: dest.to_string(), comp: comp.to_string(), jump: jump.to_string() }
+    }
+
+    fn to_command(&self) -> String {
+        match self {
+            Item::Label(label) => format!("({})", label),
+            Item::A(symbol) => format!("@{}", symbol),
+            Item::C { dest, comp, jump } => {
+                let mut command = String::new();
+                if !dest.is_empty() {
+                    command.push_str(dest);
+                    command.push('=');
+                }
+                command.push_str(comp);
+                if !jump.is_empty() {
+                    command.push(';');
+                    command.push_str(jump);
+                }
+                command
+            }
+        }
+    }
+}
+
+// 最適化後のコマンドと、それが元の何番目のコマンドから来たかを返す
+pub fn optimize(commands: &[&str]) -> Vec<(usize, String)> {
+    let mut items: Vec<(usize, Item)> = commands.iter().map(|c| Item::parse(c)).enumerate().collect();
+    if !pin_numeric_targets(&mut items) {
+        // 番地で飛んでいる先をラベルに置き換えられないなら、命令を動かせないので何もしない
+        return commands.iter().enumerate().map(|(i, c)| (i, c.to_string())).collect()
+    }
+    // どれかが変化しなくなるまで繰り返す (1回の変化で命令は減るか書き換わるだけなので必ず止まる)
+    loop {
+        let mut changed = thread_jumps(&mut items);
+        changed |= remove_unreachable(&mut items);
+        changed |= remove_reloads(&mut items);
+        changed |= fold_constants(&mut items);
+        if !changed {
+            break
+        }
+    }
+    items.into_iter().map(|(index, item)| (index, item.to_command())).collect()
+}
+
+// @n / (ジャンプ) の n を ROM.n というラベルにして、元の n 番地の命令の前に (ROM.n) を置く
+fn pin_numeric_targets(items: &mut Vec<(usize, Item)>) -> bool {
+    let labels: HashSet<String> = items
+        .iter()
+        .filter_map(|(_, item)| match item {
+            Item::Label(label) => Some(label.clone()),
+            _ => None
+        })
+        .collect();
+    // 元の ROM アドレス -> items の添字
+    let positions: Vec<usize> = (0..items.len()).filter(|i| !matches!(items[*i].1, Item::Label(_))).collect();
+
+    let mut targets: Vec<usize> = Vec::new();
+    for i in 0..items.len().saturating_sub(1) {
+        let address = match &items[i].1 {
+            Item::A(symbol) if is_number(symbol) => match symbol.parse::<usize>() {
+                Ok(address) => address,
+                Err(_) => continue
+            },
+            _ => continue
+        };
+        let (dest, comp, jump) = match &items[i + 1].1 {
+            Item::C { dest, comp, jump } if !jump.is_empty() => (dest, comp, jump),
+            _ => continue
+        };
+        // ジャンプ以外で A の値 (= n) を使うなら置き換えられない
+        let a_used = comp.contains('A') || comp.contains('M') || dest.contains('M')
+            || (jump != "JMP" && !dest.contains('A') && !a_is_dead_after(items, i + 2));
+        let label = format!("ROM.{}", address);
+        if address >= positions.len() || a_used || labels.contains(&label) {
+            return false
+        }
+        items[i].1 = Item::A(label);
+        targets.push(address);
+    }
+
+    // 計算したジャンプ (直前が @ でないジャンプ) の飛び先が数値で書かれていないか
+    let computed_jump = (0..items.len()).any(|i| match &items[i].1 {
+        Item::C { jump, .. } if !jump.is_empty() => i == 0 || !matches!(items[i - 1].1, Item::A(_)),
+        _ => false
+    });
+    if computed_jump {
+        for (_, item) in items.iter() {
+            let address = match item {
+                Item::A(symbol) if is_number(symbol) => symbol.parse::<usize>().unwrap_or(0),
+                _ => continue
+            };
+            let after_jump = !targets.contains(&address) && address > 0 && address < positions.len() && matches!(
+                &items[positions[address] - 1].1,
+                Item::C { jump, .. } if jump == "JMP"
+            );
+            if after_jump {
+                return false
+            }
+        }
+    }
+
+    targets.sort_unstable();
+    targets.dedup();
+    // 後ろから挿入すれば positions の添字がずれない
+    for address in targets.into_iter().rev() {
+        let position = positions[address];
+        let origin = items[position].0;
+        items.insert(position, (origin, Item::Label(format!("ROM.{}", address))));
+    }
+    true
+}
+
+// start 以降、A の今の値が読まれる前に上書きされる (かプログラムが終わる) なら true
+// ラベルに着いたら、そこへ飛んできた側の事情は分からないので false
+fn a_is_dead_after(items: &[(usize, Item)], start: usize) -> bool {
+    for (_, item) in &items[start..] {
+        match item {
+            Item::A(_) => return true,
+            Item::Label(_) => return false,
+            Item::C { dest, comp, jump } => {
+                if comp.contains('A') || comp.contains('M') || dest.contains('M') || !jump.is_empty() {
+                    return false
+                }
+                if dest.contains('A') {
+                    return true
+                }
+            }
+        }
+    }
+    true
+}
+
+fn thread_jumps(items: &mut [(usize, Item)]) -> bool {
+    // (L1) の直後が @L2 / 0;JMP なら L1 -> L2
+    let mut trampolines: HashMap<String, String> = HashMap::new();
+    for i in 0..items.len() {
+        if let Item::Label(label) = &items[i].1 {
+            let mut j = i + 1;
+            while j < items.len() && matches!(items[j].1, Item::Label(_)) {
+                j += 1;
+            }
+            if let (Some((_, Item::A(target))), Some((_, Item::C { dest, jump, .. }))) = (items.get(j), items.get(j + 1)) {
+                if !is_number(target) && target != label && dest.is_empty() && jump == "JMP" {
+                    trampolines.insert(label.clone(), target.clone());
+                }
+            }
+        }
+    }
+
+    let mut changed = false;
+    for i in 0..items.len().saturating_sub(1) {
+        let label = match &items[i].1 {
+            Item::A(symbol) if trampolines.contains_key(symbol) => symbol.clone(),
+            _ => continue
+        };
+        // ジャンプ以外で A (と M) を使うなら書き換えられない
+        let rewritable = match &items[i + 1].1 {
+            Item::C { dest, comp, jump } => {
+                !jump.is_empty()
+                    && !comp.contains('A') && !comp.contains('M') && !dest.contains('M')
+                    && (jump == "JMP" || dest.contains('A') || a_is_dead_after(items, i + 2))
+            },
+            _ => false
+        };
+        if !rewritable {
+            continue
+        }
+        // L1 -> L2 -> L3 ... と辿る。ぐるっと回っている (無限ループ) ならそのままにする
+        let mut visited: HashSet<&str> = HashSet::new();
+        let mut target = label.as_str();
+        visited.insert(target);
+        let mut cyclic = false;
+        while let Some(next) = trampolines.get(target) {
+            if !visited.insert(next) {
+                cyclic = true;
+                break
+            }
+            target = next;
+        }
+        if !cyclic && target != label {
+            items[i].1 = Item::A(target.to_string());
+            changed = true;
+        }
+    }
+    changed
+}
+
+fn remove_unreachable(items: &mut Vec<(usize, Item)>) -> bool {
+    let before = items.len();
+    let mut reachable = true;
+    items.retain(|(_, item)| match item {
+        Item::Label(_) => {
+            reachable = true;
+            true
+        },
+        Item::C { jump, .. } if reachable && jump == "JMP" => {
+            reachable = false;
+            true
+        },
+        _ => reachable
+    });
+    items.len() != before
+}
+
+fn remove_reloads(items: &mut Vec<(usize, Item)>) -> bool {
+    let before = items.len();
+    let mut a: Option<String> = None;
+    items.retain(|(_, item)| match item {
+        Item::Label(_) => {
+            a = None;
+            true
+        },
+        Item::A(symbol) => {
+            if a.as_deref() == Some(symbol.as_str()) {
+                false
+            } else {
+                a = Some(symbol.clone());
+                true
+            }
+        },
+        Item::C { dest, .. } => {
+            if dest.contains('A') {
+                a = None;
+            }
+            true
+        }
+    });
+    items.len() != before
+}
+
+fn fold_constants(items: &mut Vec<(usize, Item)>) -> bool {
+    let mut changed = false;
+    let mut i = 0;
+    while i + 4 <= items.len() {
+        if let Some(value) = constant_chain(&items[i..i + 4]) {
+            if a_is_dead_after(items, i + 4) {
+                items[i].1 = Item::A(value.to_string());
+                items.drain(i + 2..i + 4);
+                changed = true;
+                // まとめた結果がさらに次の @n / D=D+A とつながるかもしれない
+                continue
+            }
+        }
+        i += 1;
+    }
+    changed
+}
+
+// @a / D=A / @b / D=D+A (D=D-A) の計算結果。@ で書ける範囲に収まるときだけ
+fn constant_chain(window: &[(usize, Item)]) -> Option<u16> {
+    let number = |item: &Item| match item {
+        Item::A(symbol) if is_number(symbol) => symbol.parse::<i32>().ok(),
+        _ => None
+    };
+    let is_c = |item: &Item, d: &str, c: &[&str]| match item {
+        Item::C { dest, comp, jump } => dest == d && c.contains(&comp.as_str()) && jump.is_empty(),
+        _ => false
+    };
+    let a = number(&window[0].1)?;
+    if !is_c(&window[1].1, "D", &["A"]) {
+        return None
+    }
+    let b = number(&window[2].1)?;
+    let value = if is_c(&window[3].1, "D", &["D+A", "A+D"]) {
+        a + b
+    } else if is_c(&window[3].1, "D", &["D-A"]) {
+        a - b
+    } else {
+        return None
+    };
+    if (0..=MAX_ADDRESS as i32).contains(&value) {
+        Some(value as u16)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn optimize_str(source: &str) -> Vec<String> {
+        let commands: Vec<&str> = source.split_whitespace().collect();
+        optimize(&commands).into_iter().map(|(_, c)| c).collect()
+    }
+
+    #[test]
+    fn reload_test() {
+        assert_eq!(
+            vec!["@i", "M=1", "D=M", "(LOOP)", "@i", "M=D"],
+            optimize_str("@i M=1 @i D=M (LOOP) @i M=D")
+        );
+        // A を書き換えたら読み直しは必要
+        assert_eq!(vec!["@i", "AM=M-1", "@i", "D=M"], optimize_str("@i AM=M-1 @i D=M"));
+    }
+
+    #[test]
+    fn fold_test() {
+        assert_eq!(vec!["@5", "D=A", "@R0", "M=D"], optimize_str("@2 D=A @3 D=D+A @R0 M=D"));
+        assert_eq!(vec!["@3", "D=A", "@R0", "M=D"], optimize_str("@10 D=A @4 D=D-A @3 D=D-A @R0 M=D"));
+        // 後ろで A (= 3) を使うのでまとめられない
+        assert_eq!(vec!["@2", "D=A", "@3", "D=D+A", "M=D"], optimize_str("@2 D=A @3 D=D+A M=D"));
+        // 負になるものは @ で書けない
+        assert_eq!(vec!["@2", "D=A", "@3", "D=D-A", "@R0"], optimize_str("@2 D=A @3 D=D-A @R0"));
+    }
+
+    #[test]
+    fn unreachable_test() {
+        assert_eq!(
+            vec!["@END", "0;JMP", "(END)", "@END", "0;JMP"],
+            optimize_str("@END 0;JMP @R0 M=0 (END) @END 0;JMP D=0")
+        );
+    }
+
+    #[test]
+    fn thread_jumps_test() {
+        assert_eq!(
+            vec!["@C", "D;JGT", "0;JMP", "(A)", "@C", "0;JMP", "(B)", "@C", "0;JMP", "(C)", "@C", "0;JMP"],
+            optimize_str("@A D;JGT @B 0;JMP (A) @B 0;JMP (B) @C 0;JMP (C) @C 0;JMP")
+        );
+        // 条件ジャンプの後ろで A を使うなら書き換えない
+        assert_eq!(
+            vec!["@A", "D;JGT", "M=0", "(A)", "@B", "0;JMP", "(B)"],
+            optimize_str("@A D;JGT M=0 (A) @B 0;JMP (B)")
+        );
+        // 飛び先がぐるっと回っている
+        assert_eq!(
+            vec!["(A)", "@B", "0;JMP", "(B)", "@A", "0;JMP"],
+            optimize_str("(A) @B 0;JMP (B) @A 0;JMP")
+        );
+    }
+
+    #[test]
+    fn numeric_target_test() {
+        assert_eq!(
+            vec!["@ROM.3", "0;JMP", "(ROM.3)", "D=0", "@R1", "M=D"],
+            optimize_str("@3 0;JMP M=1 D=0 @R1 M=D")
+        );
+        // 飛ばなかったときに A (= 2) を使っているので、番地を動かせない
+        assert_eq!(vec!["@2", "D;JGT", "M=0", "@i", "@i"], optimize_str("@2 D;JGT M=0 @i @i"));
+        // 戻り番地 (4) を数値で渡して、A=M / 0;JMP で戻ってくる
+        assert_eq!(
+            vec!["@4", "D=A", "@6", "0;JMP", "@i", "@i", "A=D", "0;JMP"],
+            optimize_str("@4 D=A @6 0;JMP @i @i A=D 0;JMP")
+        );
+    }
+
+    #[test]
+    fn origin_test() {
+        let optimized = optimize(&["@i", "M=1", "@i", "D=M"]);
+        assert_eq!(vec![(0, "@i".to_string()), (1, "M=1".to_string()), (3, "D=M".to_string())], optimized);
+    }
+}
@@ -0,0 +1,205 @@
+use super::to_hack;
+
+// アセンブル結果の書き出し形式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    // nand2tetris の .hack (1行1命令の 0/1 文字列)
+    Hack,
+    // 1ワード 2バイトのバイナリ
+    LittleEndian,
+    BigEndian,
+    // Intel HEX (バイトアドレス、ワードはビッグエンディアン)
+    IntelHex,
+    // Logisim の ROM/RAM に読み込める "v2.0 raw" 形式
+    Logisim,
+    // そのままソースに貼れる配列リテラル
+    RustArray,
+    CArray
+}
+
+impl Format {
+    pub fn from_name(name: &str) -> Option<Format> {
+        match name {
+            "hack" => Some(Format::Hack),
+            "le" | "bin" => Some(Format::LittleEndian),
+            "be" => Some(Format::BigEndian),
+            "ihex" | "hex" => Some(Format::IntelHex),
+            "logisim" => Some(Format::Logisim),
+            "rust" | "rs" => Some(Format::RustArray),
+            "c" => Some(Format::CArray),
+            _ => None
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Hack => "hack",
+            Format::LittleEndian => "le.bin",
+            Format::BigEndian => "be.bin",
+            Format::IntelHex => "hex",
+            Format::Logisim => "logisim",
+            Format::RustArray => "rs",
+            Format::CArray => "h"
+        }
+    }
+}
+
+// name は配列リテラルの変数名に使う (それ以外の形式では無視)
+pub fn encode(words: &[u16], format: Format, name: &str) -> Vec<u8> {
+    match format {
+        Format::Hack => to_hack(words).into_bytes(),
+        Format::LittleEndian => words.iter().flat_map(|w| w.to_le_bytes().to_vec()).collect(),
+        Format::BigEndian => words.iter().flat_map(|w| w.to_be_bytes().to_vec()).collect(),
+        Format::IntelHex => to_intel_hex(words).into_bytes(),
+        Format::Logisim => to_logisim(words).into_bytes(),
+        Format::RustArray => to_rust_array(words, name).into_bytes(),
+        Format::CArray => to_c_array(words, name).into_bytes()
+    }
+}
+
+pub fn to_intel_hex(words: &[u16]) -> String {
+    // 1レコード 16 バイト (8 ワード)
+    let mut out = String::new();
+    for (i, chunk) in words.chunks(8).enumerate() {
+        let address = (i * 16) as u16;
+        let mut record: Vec<u8> = vec![(chunk.len() * 2) as u8, (address >> 8) as u8, address as u8, 0x00];
+        for word in chunk {
+            record.extend_from_slice(&word.to_be_bytes());
+        }
+        out.push_str(&intel_hex_record(&record));
+    }
+    // End Of File
+    out.push_str(&intel_hex_record(&[0x00, 0x00, 0x00, 0x01]));
+    out
+}
+
+fn intel_hex_record(record: &[u8]) -> String {
+    let sum: u8 = record.iter().fold(0u8, |acc, b| acc.wrapping_add(*b));
+    let checksum = sum.wrapping_neg();
+    let body: String = record.iter().map(|b| format!("{:02X}", b)).collect();
+    format!(":{}{:02X}\n", body, checksum)
+}
+
+pub fn to_logisim(words: &[u16]) -> String {
+    // 同じ値が続くところは "個数*値" にまとめる
+    let mut values: Vec<String> = Vec::new();
+    let mut i = 0;
+    while i < words.len() {
+        let mut run = 1;
+        while i + run < words.len() && words[i + run] == words[i] {
+            run += 1;
+        }
+        if run >= 4 {
+            values.push(format!("{}*{:x}", run, words[i]));
+        } else {
+            for _ in 0..run {
+                values.push(format!("{:x}", words[i]));
+            }
+        }
+        i += run;
+    }
+
+    let mut out = String::from("v2.0 raw\n");
+    for line in values.chunks(8) {
+        out.push_str(&line.join(" "));
+        out.push('\n');
+    }
+    out
+}
+
+fn array_body(words: &[u16]) -> String {
+    words
+        .chunks(8)
+        .map(|line| {
+            let values: Vec<String> = line.iter().map(|w| format!("0x{:04x},", w)).collect();
+            format!("    {}\n", values.join(" "))
+        })
+        .collect()
+}
+
+// ファイル名をそのまま変数名にできるとは限らないので、英数字と _ 以外は _ にし、数字で始まるなら _ を付ける
+fn identifier(name: &str) -> String {
+    let name: String = name.chars().map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' }).collect();
+    match name.chars().next() {
+        Some(c) if c.is_ascii_digit() => format!("_{}", name),
+        Some(_) => name,
+        None => "_".to_string()
+    }
+}
+
+pub fn to_rust_array(words: &[u16], name: &str) -> String {
+    format!(
+        "pub const {}: [u16; {}] = [\n{}];\n",
+        identifier(name).to_uppercase(), words.len(), array_body(words)
+    )
+}
+
+pub fn to_c_array(words: &[u16], name: &str) -> String {
+    format!(
+        "#include <stdint.h>\n\nconst uint16_t {}[{}] = {{\n{}}};\n",
+        identifier(name).to_lowercase(), words.len(), array_body(words)
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Add.hack と同じ内容
+    const ADD: [u16; 6] = [0x0002, 0xec10, 0x0003, 0xe090, 0x0000, 0xe308];
+
+    #[test]
+    fn from_name_test() {
+        assert_eq!(Some(Format::IntelHex), Format::from_name("ihex"));
+        assert_eq!(Some(Format::LittleEndian), Format::from_name("le"));
+        assert_eq!(None, Format::from_name("elf"));
+    }
+
+    #[test]
+    fn hack_test() {
+        assert_eq!(include_str!("../../06/dist/Add.hack").as_bytes(), &encode(&ADD, Format::Hack, "add")[..]);
+    }
+
+    #[test]
+    fn binary_test() {
+        assert_eq!(vec![0x02, 0x00, 0x10, 0xec], encode(&ADD[..2], Format::LittleEndian, "add"));
+        assert_eq!(vec![0x00, 0x02, 0xec, 0x10], encode(&ADD[..2], Format::BigEndian, "add"));
+    }
+
+    #[test]
+    fn intel_hex_test() {
+        assert_eq!(
+            ":0C0000000002EC100003E0900000E30898\n:00000001FF\n",
+            to_intel_hex(&ADD)
+        );
+        let words: Vec<u16> = (0..9).collect();
+        assert_eq!(
+            ":1000000000000001000200030004000500060007D4\n:020010000008E6\n:00000001FF\n",
+            to_intel_hex(&words)
+        );
+    }
+
+    #[test]
+    fn logisim_test() {
+        assert_eq!("v2.0 raw\n2 ec10 3 e090 0 e308\n", to_logisim(&ADD));
+        assert_eq!(
+            "v2.0 raw\n1 1 1 5*0 2 3 4 5\n6 7 8\n",
+            to_logisim(&[1, 1, 1, 0, 0, 0, 0, 0, 2, 3, 4, 5, 6, 7, 8])
+        );
+    }
+
+    #[test]
+    fn array_test() {
+        assert_eq!(
+            "pub const ADD: [u16; 6] = [\n    0x0002, 0xec10, 0x0003, 0xe090, 0x0000, 0xe308,\n];\n",
+            to_rust_array(&ADD, "add")
+        );
+        assert_eq!(
+            "#include <stdint.h>\n\nconst uint16_t add[6] = {\n    0x0002, 0xec10, 0x0003, 0xe090, 0x0000, 0xe308,\n};\n",
+            to_c_array(&ADD, "Add")
+        );
+        assert!(to_rust_array(&ADD, "my-prog").starts_with("pub const MY_PROG: [u16; 6]"));
+        assert!(to_c_array(&ADD, "2nd.v1").contains("const uint16_t _2nd_v1[6]"));
+        assert!(to_c_array(&ADD, "").contains("const uint16_t _[6]"));
+    }
+}
@@ -1,43 +1,133 @@
-use std::fs::File;
-use std::io::{BufReader, BufRead};
-use std::convert::TryInto;
+use std::ops::Range;
 
-#[derive(PartialEq)]
-enum CommandType {
+use super::error::{AsmError, Diagnostic, Span};
+use super::optimizer;
+use super::preprocessor::{Preprocessor, SourceFile, SourceLine};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CommandType {
     A_COMMAND,
     C_COMMAND,
     L_COMMAND
 }
 
+// コメントと空白を取り除いたコマンドと、元のファイル上の位置
+struct Instruction {
+    command: String,
+    file: usize,
+    line: usize,
+    // command の各バイトが元の行の何文字目 (1始まり) にあったか
+    columns: Vec<usize>,
+    // マクロや .include を展開した場合は、大元のファイルで展開した行
+    origin_line: usize
+}
+
 pub struct Parser {
-    instructions: Vec<String>,
+    instructions: Vec<Instruction>,
     line_counter: usize,
-    current_command: String
+    files: Vec<SourceFile>,
+    diagnostics: Vec<Diagnostic>
 }
 
 impl Parser {
-    pub fn new(f: File) -> Parser {
-        let reader = BufReader::new(f);
-        let lines: Vec<String> = reader
+    pub fn new(source: &str) -> Parser {
+        let lines: Vec<SourceLine> = source
             .lines()
-            .map(|l| l.unwrap())
-            .map(|l| match l.find("//") {
-                Some(v) => l[0..v].to_string(),
-                None => l,
-            })
-            .filter(|l| !l.is_empty())
-            .map(|l| l.trim().to_owned())
+            .enumerate()
+            .map(|(i, l)| SourceLine { text: l.to_string(), file: 0, line: i + 1, origin_line: i + 1 })
             .collect();
-        let first_cmd = lines[0].to_string();
+        let files = vec![SourceFile { name: String::new(), text: source.to_string() }];
+        Parser::from_lines(lines, files, Vec::new())
+    }
+
+    // 拡張構文 (.macro / .endm / .include) を使えるモード
+    // .include "xxx" は loader("xxx") で読み込む。展開で見つかったエラーは take_diagnostics で受け取る
+    pub fn new_extended(name: &str, source: &str, loader: &dyn Fn(&str) -> Option<String>) -> Parser {
+        let mut preprocessor = Preprocessor::new(loader);
+        preprocessor.run(name, source);
+        Parser::from_lines(preprocessor.lines, preprocessor.files, preprocessor.diagnostics)
+    }
+
+    fn from_lines(lines: Vec<SourceLine>, files: Vec<SourceFile>, diagnostics: Vec<Diagnostic>) -> Parser {
+        let mut instructions: Vec<Instruction> = Vec::new();
+        for l in lines {
+            let code = match l.text.find("//") {
+                Some(v) => &l.text[0..v],
+                None => &l.text,
+            };
+            // "D = M" のように空白が混ざっていても読めるように全部消す
+            // 消した後も元の列番号が分かるように覚えておく
+            let mut command = String::new();
+            let mut columns: Vec<usize> = Vec::new();
+            for (column, c) in code.chars().enumerate() {
+                if c.is_whitespace() {
+                    continue;
+                }
+                command.push(c);
+                for _ in 0..c.len_utf8() {
+                    columns.push(column + 1);
+                }
+            }
+            if !command.is_empty() {
+                instructions.push(Instruction {
+                    command,
+                    file: l.file,
+                    line: l.line,
+                    columns,
+                    origin_line: l.origin_line
+                });
+            }
+        }
 
         Parser {
-            instructions: lines,
+            instructions,
             line_counter: 0,
-            current_command: first_cmd
+            files,
+            diagnostics
+        }
+    }
+
+    // 読み込んだファイル。Span の file はこの添字
+    pub fn files(&self) -> &[SourceFile] {
+        &self.files
+    }
+
+    // 拡張構文の展開中に見つかったエラー
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.diagnostics)
+    }
+
+    // 先頭のコマンドに戻る (2パス目用)
+    pub fn reset(&mut self) {
+        self.line_counter = 0;
+    }
+
+    // 覗き穴最適化をかける (エンコードする前に呼ぶ)
+    // 書き換えたコマンドの位置は、元になったコマンドの先頭を指すようにする
+    pub fn optimize(&mut self) {
+        let commands: Vec<&str> = self.instructions.iter().map(|i| i.command.as_str()).collect();
+        let optimized = optimizer::optimize(&commands);
+        let mut instructions: Vec<Instruction> = Vec::new();
+        for (index, command) in optimized {
+            let original = &self.instructions[index];
+            let columns = if command == original.command {
+                original.columns.clone()
+            } else {
+                vec![original.columns[0]; command.len()]
+            };
+            instructions.push(Instruction {
+                command,
+                file: original.file,
+                line: original.line,
+                columns,
+                origin_line: original.origin_line
+            });
         }
+        self.instructions = instructions;
+        self.reset();
     }
 
-    pub fn has_more_commands(&mut self) -> bool {
+    pub fn has_more_commands(&self) -> bool {
         self.instructions.len() > self.line_counter
     }
 
@@ -45,62 +135,318 @@ impl Parser {
         if !self.has_more_commands() {
             return
         }
-        self.line_counter += self.line_counter;
+        self.line_counter += 1;
+    }
 
-        let command = &self.current_command;
-        if command.is_empty() {
-            return
+    pub fn command_type(&self) -> CommandType {
+        let command = self.current_command();
+        if command.starts_with('@') {
+            CommandType::A_COMMAND
+        } else if command.starts_with('(') {
+            CommandType::L_COMMAND
+        } else {
+            CommandType::C_COMMAND
         }
     }
 
-    pub fn command_type(&mut self) -> CommandType {
-        match &self.current_command[0..1] {
-            "@" => CommandType::A_COMMAND,
-            "(" => CommandType::L_COMMAND,
-            _   => CommandType::C_COMMAND
+    // @xxx と (xxx) の xxx 部分。数値の場合もそのまま返す
+    pub fn symbol(&self) -> Result<&str, AsmError> {
+        let command = self.current_command();
+        match self.command_type() {
+            CommandType::A_COMMAND => {
+                let symbol = &command[1..];
+                if is_number(symbol) || is_symbol(symbol) {
+                    Ok(symbol)
+                } else {
+                    Err(AsmError::InvalidSymbol(command.to_string()))
+                }
+            },
+            CommandType::L_COMMAND => {
+                let label = command[1..].strip_suffix(')');
+                match label {
+                    Some(label) if is_symbol(label) => Ok(label),
+                    _ => Err(AsmError::InvalidLabel(command.to_string()))
+                }
+            },
+            CommandType::C_COMMAND => Err(AsmError::InvalidSymbol(command.to_string()))
         }
     }
 
-    pub fn symbol(&mut self) -> String {
-        let str = match self.command_type() {
-            CommandType::A_COMMAND => &self.current_command[1..],
-            CommandType::L_COMMAND => {
-                let start: usize = self.current_command.find("(").unwrap();
-                let end: usize = self.current_command.find(")").unwrap();
-                &self.current_command[start..end]
-            }
-            CommandType::C_COMMAND => ""
-        };
-        str.to_string()
+    // dest=comp;jump のうち dest 部分。省略されていれば (C命令以外でも) None
+    pub fn dest(&self) -> Option<&str> {
+        self.dest_range().map(|r| &self.current_command()[r])
     }
 
-    pub fn dest(&mut self) -> &str {
-        if self.command_type() != CommandType::C_COMMAND { panic!("abababababababababa"); }
+    // dest=comp;jump のうち comp 部分。C命令以外では空文字
+    pub fn comp(&self) -> &str {
+        match self.comp_range() {
+            Some(r) => &self.current_command()[r],
+            None => ""
+        }
+    }
 
-        let is_have_char = match self.current_command.find(";") {
-            Some(size) => true,
-            None => false
-        };
+    // dest=comp;jump のうち jump 部分。省略されていれば (C命令以外でも) None
+    pub fn jump(&self) -> Option<&str> {
+        self.jump_range().map(|r| &self.current_command()[r])
+    }
 
-        if is_have_char {
-            let tmp: Vec<&str> = self.current_command.split("=").collect();
-            tmp[0]
-        } else {
-            ""
+    // 今のコマンドの文字列 (エラー表示用)
+    pub fn current_command(&self) -> &str {
+        match self.instructions.get(self.line_counter) {
+            Some(instruction) => &instruction.command,
+            None => ""
+        }
+    }
+
+    // 今のコマンドが元のファイルの何行目 (1始まり) にあったか
+    #[allow(dead_code)]
+    pub fn line_number(&self) -> usize {
+        match self.instructions.get(self.line_counter) {
+            Some(instruction) => instruction.line,
+            None => 0
+        }
+    }
+
+    // マクロや .include の場合は、大元のファイルで展開した行
+    pub fn origin_line(&self) -> usize {
+        match self.instructions.get(self.line_counter) {
+            Some(instruction) => instruction.origin_line,
+            None => 0
+        }
+    }
+
+    pub fn command_span(&self) -> Span {
+        self.span(0..self.current_command().len())
+    }
+
+    pub fn symbol_span(&self) -> Span {
+        let command = self.current_command();
+        match self.command_type() {
+            CommandType::A_COMMAND => self.span(1..command.len()),
+            _ => self.command_span()
+        }
+    }
+
+    pub fn dest_span(&self) -> Span {
+        match self.dest_range() {
+            Some(r) => self.span(r),
+            None => self.command_span()
+        }
+    }
+
+    pub fn comp_span(&self) -> Span {
+        match self.comp_range() {
+            Some(r) => self.span(r),
+            None => self.command_span()
+        }
+    }
+
+    pub fn jump_span(&self) -> Span {
+        match self.jump_range() {
+            Some(r) => self.span(r),
+            None => self.command_span()
+        }
+    }
+
+    fn dest_range(&self) -> Option<Range<usize>> {
+        if self.command_type() != CommandType::C_COMMAND {
+            return None
         }
+        self.current_command().find('=').map(|size| 0..size)
     }
 
-    pub fn comp(&mut self) {
+    fn comp_range(&self) -> Option<Range<usize>> {
         if self.command_type() != CommandType::C_COMMAND {
-            panic!("abababababababababa");
+            return None
         }
-        // TODO implement
+        let command = self.current_command();
+        let start = match command.find('=') {
+            Some(size) => size + 1,
+            None => 0
+        };
+        let end = match command.find(';') {
+            Some(size) => size,
+            None => command.len()
+        };
+        if start > end {
+            return None
+        }
+        Some(start..end)
     }
 
-    pub fn jump(&mut self) {
+    fn jump_range(&self) -> Option<Range<usize>> {
         if self.command_type() != CommandType::C_COMMAND {
-            panic!("abababababababababa");
+            return None
+        }
+        let command = self.current_command();
+        command.find(';').map(|size| size + 1..command.len())
+    }
+
+    // 空白を消したコマンド上のバイト範囲を、元の行の列範囲に戻す
+    fn span(&self, range: Range<usize>) -> Span {
+        let instruction = match self.instructions.get(self.line_counter) {
+            Some(instruction) => instruction,
+            None => return Span { file: 0, line: 0, column: 0, length: 0 }
+        };
+        let columns = &instruction.columns;
+        if range.start >= range.end {
+            // "D=" の comp のように中身が空なら直後の 1 文字を指す
+            let column = match range.start.checked_sub(1).and_then(|i| columns.get(i)) {
+                Some(c) => c + 1,
+                None => columns.first().copied().unwrap_or(1)
+            };
+            return Span { file: instruction.file, line: instruction.line, column, length: 1 }
+        }
+        let start = columns[range.start];
+        let end = columns[range.end - 1];
+        Span { file: instruction.file, line: instruction.line, column: start, length: end - start + 1 }
+    }
+}
+
+pub fn is_number(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_ascii_digit())
+}
+
+// 英数字と _ . $ : からなり、数字で始まらないもの
+pub fn is_symbol(s: &str) -> bool {
+    match s.chars().next() {
+        Some(c) if !c.is_ascii_digit() => {
+            s.chars().all(|c| c.is_ascii_alphanumeric() || "_.$:".contains(c))
+        },
+        _ => false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn advance_test() {
+        let mut parser = Parser::new(include_str!("../../06/test/add/Add.asm"));
+        let mut commands: Vec<CommandType> = Vec::new();
+        while parser.has_more_commands() {
+            commands.push(parser.command_type());
+            parser.advance();
+        }
+        assert_eq!(
+            vec![
+                CommandType::A_COMMAND, CommandType::C_COMMAND,
+                CommandType::A_COMMAND, CommandType::C_COMMAND,
+                CommandType::A_COMMAND, CommandType::C_COMMAND,
+            ],
+            commands
+        );
+        assert!(!parser.has_more_commands());
+    }
+
+    #[test]
+    fn empty_source_test() {
+        let mut parser = Parser::new("// only a comment\n\n   \n");
+        assert!(!parser.has_more_commands());
+        parser.advance();
+        parser.reset();
+        assert!(!parser.has_more_commands());
+    }
+
+    #[test]
+    fn symbol_test() {
+        let mut parser = Parser::new("@R0\n(LOOP) // label\n@100\n");
+        assert_eq!(Ok("R0"), parser.symbol());
+        parser.advance();
+        assert_eq!(CommandType::L_COMMAND, parser.command_type());
+        assert_eq!(Ok("LOOP"), parser.symbol());
+        parser.advance();
+        assert_eq!(Ok("100"), parser.symbol());
+    }
+
+    #[test]
+    fn invalid_symbol_test() {
+        let mut parser = Parser::new("@\n@1abc\n(LOOP\n)(\n()\nD=M\n");
+        assert_eq!(Err(AsmError::InvalidSymbol("@".to_string())), parser.symbol());
+        parser.advance();
+        assert_eq!(Err(AsmError::InvalidSymbol("@1abc".to_string())), parser.symbol());
+        parser.advance();
+        assert_eq!(Err(AsmError::InvalidLabel("(LOOP".to_string())), parser.symbol());
+        parser.advance();
+        assert_eq!(Err(AsmError::InvalidSymbol(")(".to_string())), parser.symbol());
+        parser.advance();
+        assert_eq!(Err(AsmError::InvalidLabel("()".to_string())), parser.symbol());
+        parser.advance();
+        assert_eq!(Err(AsmError::InvalidSymbol("D=M".to_string())), parser.symbol());
+    }
+
+    #[test]
+    fn c_command_test() {
+        let mut parser = Parser::new("D=M\nD;JGT\nAM=M-1\n  0 ; JMP   // spaces\nMD=D+1;JEQ\n");
+        assert_eq!((Some("D"), "M", None), (parser.dest(), parser.comp(), parser.jump()));
+        parser.advance();
+        assert_eq!((None, "D", Some("JGT")), (parser.dest(), parser.comp(), parser.jump()));
+        parser.advance();
+        assert_eq!((Some("AM"), "M-1", None), (parser.dest(), parser.comp(), parser.jump()));
+        parser.advance();
+        assert_eq!((None, "0", Some("JMP")), (parser.dest(), parser.comp(), parser.jump()));
+        parser.advance();
+        assert_eq!((Some("MD"), "D+1", Some("JEQ")), (parser.dest(), parser.comp(), parser.jump()));
+    }
+
+    #[test]
+    fn malformed_c_command_test() {
+        let mut parser = Parser::new("abababab\n0;JMP=D\nあ\n@1\n");
+        assert_eq!((None, "abababab", None), (parser.dest(), parser.comp(), parser.jump()));
+        parser.advance();
+        assert_eq!((Some("0;JMP"), "", Some("JMP=D")), (parser.dest(), parser.comp(), parser.jump()));
+        parser.advance();
+        assert_eq!((None, "あ", None), (parser.dest(), parser.comp(), parser.jump()));
+        parser.advance();
+        assert_eq!((None, "", None), (parser.dest(), parser.comp(), parser.jump()));
+    }
+
+    #[test]
+    fn span_test() {
+        let mut parser = Parser::new("// header\n\n   AM = M - 1 ; JNE // comment\n@LOOP\nD=\n");
+        assert_eq!(3, parser.line_number());
+        assert_eq!(Span { file: 0, line: 3, column: 4, length: 16 }, parser.command_span());
+        assert_eq!(Span { file: 0, line: 3, column: 4, length: 2 }, parser.dest_span());
+        assert_eq!(Span { file: 0, line: 3, column: 9, length: 5 }, parser.comp_span());
+        assert_eq!(Span { file: 0, line: 3, column: 17, length: 3 }, parser.jump_span());
+        parser.advance();
+        assert_eq!(Span { file: 0, line: 4, column: 2, length: 4 }, parser.symbol_span());
+        parser.advance();
+        assert_eq!(Span { file: 0, line: 5, column: 3, length: 1 }, parser.comp_span());
+    }
+
+    #[test]
+    fn extended_test() {
+        let loader = |name: &str| match name {
+            "push.asm" => Some(".macro PUSH_D\n@SP\nA=M\nM=D\n@SP\nM=M+1\n.endm\n".to_string()),
+            _ => None
+        };
+        let source = ".include \"push.asm\"\n@7\nD=A\nPUSH_D\n";
+        let mut parser = Parser::new_extended("main.asm", source, &loader);
+        assert!(parser.take_diagnostics().is_empty());
+        let mut commands: Vec<(String, usize, usize, usize)> = Vec::new();
+        while parser.has_more_commands() {
+            commands.push((
+                parser.current_command().to_string(),
+                parser.command_span().file,
+                parser.line_number(),
+                parser.origin_line()
+            ));
+            parser.advance();
         }
-        // TODO implement
+        assert_eq!(
+            vec![
+                ("@7".to_string(), 0, 2, 2),
+                ("D=A".to_string(), 0, 3, 3),
+                ("@SP".to_string(), 1, 2, 4),
+                ("A=M".to_string(), 1, 3, 4),
+                ("M=D".to_string(), 1, 4, 4),
+                ("@SP".to_string(), 1, 5, 4),
+                ("M=M+1".to_string(), 1, 6, 4),
+            ],
+            commands
+        );
+        assert_eq!("push.asm", parser.files()[1].name);
     }
 }
@@ -0,0 +1,387 @@
+use std::collections::HashMap;
+
+use super::error::{AsmError, Diagnostic, Span};
+use super::parser::is_symbol;
+
+// マクロ展開がこれより深くなったら再帰しているとみなす
+const MAX_EXPANSION_DEPTH: usize = 32;
+
+// 読み込んだファイル。files[0] がアセンブル対象のファイル
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceFile {
+    pub name: String,
+    pub text: String
+}
+
+// 展開後の 1 行と、それがどこから来たか
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceLine {
+    pub text: String,
+    // 実際にその文字列が書かれているファイルと行 (マクロ本体やインクルード先)
+    pub file: usize,
+    pub line: usize,
+    // 大元のファイル (files[0]) の何行目から展開されたか
+    pub origin_line: usize
+}
+
+struct Macro {
+    params: Vec<String>,
+    // (ファイル, 行, 本文)
+    body: Vec<(usize, usize, String)>,
+    // 本体の中で宣言されているラベル。展開のたびに名前を変える
+    locals: Vec<String>
+}
+
+// .macro / .endm / .include を展開してただのアセンブリの行にする
+//
+// .macro PUSH_CONST value     // 引数は \value で参照する
+//     @\value
+//     D=A
+//     @SP
+//     ...
+// .endm
+//
+// .include "runtime.asm"     // loader で読み込む。循環していたらエラー
+// PUSH_CONST 7
+pub struct Preprocessor<'a> {
+    loader: &'a dyn Fn(&str) -> Option<String>,
+    macros: HashMap<String, Macro>,
+    include_stack: Vec<String>,
+    expansion_counter: usize,
+    pub files: Vec<SourceFile>,
+    pub lines: Vec<SourceLine>,
+    pub diagnostics: Vec<Diagnostic>
+}
+
+impl<'a> Preprocessor<'a> {
+    pub fn new(loader: &'a dyn Fn(&str) -> Option<String>) -> Preprocessor<'a> {
+        Preprocessor {
+            loader,
+            macros: HashMap::new(),
+            include_stack: Vec::new(),
+            expansion_counter: 0,
+            files: Vec::new(),
+            lines: Vec::new(),
+            diagnostics: Vec::new()
+        }
+    }
+
+    pub fn run(&mut self, name: &str, source: &str) {
+        self.include_stack.push(name.to_string());
+        self.process_file(name, source, None);
+        self.include_stack.pop();
+    }
+
+    fn process_file(&mut self, name: &str, source: &str, origin: Option<usize>) {
+        let file = self.files.len();
+        self.files.push(SourceFile { name: name.to_string(), text: source.to_string() });
+
+        // 定義中のマクロ (名前, 定義行, 中身)
+        let mut defining: Option<(String, usize, Macro)> = None;
+        for (i, raw) in source.lines().enumerate() {
+            let line = i + 1;
+            let code = strip_comment(raw);
+            let trimmed = code.trim();
+            let directive = trimmed.split_whitespace().next().unwrap_or("");
+
+            if let Some((_, _, ref mut definition)) = defining {
+                match directive {
+                    ".endm" => {
+                        let (macro_name, _, definition) = defining.take().unwrap();
+                        self.macros.insert(macro_name, definition);
+                    },
+                    ".macro" => {
+                        self.error(AsmError::InvalidMacro(trimmed.to_string()), file, line, raw);
+                    },
+                    _ => {
+                        if let Some(label) = trimmed.strip_prefix('(').and_then(|l| l.strip_suffix(')')) {
+                            definition.locals.push(label.trim().to_string());
+                        }
+                        definition.body.push((file, line, code.to_string()));
+                    }
+                }
+                continue;
+            }
+
+            match directive {
+                ".macro" => {
+                    match parse_macro_header(trimmed) {
+                        Some((macro_name, params)) => {
+                            defining = Some((macro_name, line, Macro { params, body: Vec::new(), locals: Vec::new() }));
+                        },
+                        None => self.error(AsmError::InvalidMacro(trimmed.to_string()), file, line, raw)
+                    }
+                },
+                ".endm" => self.error(AsmError::InvalidMacro(trimmed.to_string()), file, line, raw),
+                _ => self.process_line(file, line, code, origin.unwrap_or(line), 0)
+            }
+        }
+
+        if let Some((macro_name, line, _)) = defining {
+            let raw = source.lines().nth(line - 1).unwrap_or("");
+            self.error(AsmError::UnterminatedMacro(macro_name), file, line, raw);
+        }
+    }
+
+    fn process_line(&mut self, file: usize, line: usize, code: &str, origin_line: usize, depth: usize) {
+        let trimmed = code.trim();
+        let mut tokens = trimmed.splitn(2, char::is_whitespace);
+        let head = tokens.next().unwrap_or("");
+        let rest = tokens.next().unwrap_or("").trim();
+        let source = SourceLine { text: code.to_string(), file, line, origin_line };
+
+        if head == ".include" {
+            self.include(&source, rest);
+        } else if head.starts_with('.') {
+            self.error(AsmError::UnknownDirective(trimmed.to_string()), file, line, code);
+        } else if self.macros.contains_key(head) {
+            self.expand(&source, head, rest, depth);
+        } else {
+            self.lines.push(source);
+        }
+    }
+
+    // source は .include の行
+    fn include(&mut self, source: &SourceLine, argument: &str) {
+        let (file, line, code) = (source.file, source.line, source.text.as_str());
+        let name = match argument.strip_prefix('"').and_then(|a| a.strip_suffix('"')) {
+            Some(name) if !name.is_empty() => name.to_string(),
+            _ => {
+                self.error(AsmError::UnknownDirective(code.trim().to_string()), file, line, code);
+                return
+            }
+        };
+        if self.include_stack.contains(&name) {
+            let cycle = format!("{} -> {}", self.include_stack.join(" -> "), name);
+            self.error(AsmError::IncludeCycle(cycle), file, line, code);
+            return
+        }
+        match (self.loader)(&name) {
+            Some(text) => {
+                self.include_stack.push(name.clone());
+                self.process_file(&name, &text, Some(source.origin_line));
+                self.include_stack.pop();
+            },
+            None => self.error(AsmError::IncludeNotFound(name), file, line, code)
+        }
+    }
+
+    // source はマクロを呼んでいる行
+    fn expand(&mut self, source: &SourceLine, name: &str, argument: &str, depth: usize) {
+        let (file, line, code) = (source.file, source.line, source.text.as_str());
+        if depth >= MAX_EXPANSION_DEPTH {
+            self.error(AsmError::MacroRecursion(name.to_string()), file, line, code);
+            return
+        }
+        let args: Vec<String> = if argument.is_empty() {
+            Vec::new()
+        } else {
+            argument.split(',').map(|a| a.trim().to_string()).collect()
+        };
+
+        self.expansion_counter += 1;
+        let counter = self.expansion_counter;
+        let definition = &self.macros[name];
+        if args.len() != definition.params.len() {
+            let message = format!("{} expects {} argument(s), got {}", name, definition.params.len(), args.len());
+            self.error(AsmError::MacroArgumentCount(message), file, line, code);
+            return
+        }
+
+        let body: Vec<(usize, usize, String)> = definition.body
+            .iter()
+            .map(|(body_file, body_line, text)| {
+                let mut text = text.to_string();
+                // 長い名前から置換しないと \ab が \a に食われる
+                let mut params: Vec<(&String, &String)> = definition.params.iter().zip(args.iter()).collect();
+                params.sort_by_key(|(param, _)| std::cmp::Reverse(param.len()));
+                for (param, arg) in params {
+                    text = text.replace(&format!("\\{}", param), arg);
+                }
+                (*body_file, *body_line, rename_local(&text, &definition.locals, name, counter))
+            })
+            .collect();
+
+        for (body_file, body_line, text) in body {
+            self.process_line(body_file, body_line, &text, source.origin_line, depth + 1);
+        }
+    }
+
+    fn error(&mut self, error: AsmError, file: usize, line: usize, raw: &str) {
+        let code = strip_comment(raw);
+        let start = code.len() - code.trim_start().len();
+        let column = code[..start].chars().count() + 1;
+        let length = code.trim().chars().count().max(1);
+        self.diagnostics.push(Diagnostic { error, span: Span { file, line, column, length } });
+    }
+}
+
+fn strip_comment(raw: &str) -> &str {
+    match raw.find("//") {
+        Some(v) => &raw[0..v],
+        None => raw
+    }
+}
+
+// ".macro NAME a, b" -> ("NAME", ["a", "b"])
+fn parse_macro_header(trimmed: &str) -> Option<(String, Vec<String>)> {
+    let rest = trimmed.strip_prefix(".macro")?.trim();
+    let mut tokens = rest.splitn(2, char::is_whitespace);
+    let name = tokens.next()?;
+    if !is_symbol(name) {
+        return None
+    }
+    let params: Vec<String> = match tokens.next().map(|p| p.trim()) {
+        Some(p) if !p.is_empty() => p.split(',').map(|p| p.trim().to_string()).collect(),
+        _ => Vec::new()
+    };
+    if params.iter().any(|p| !is_symbol(p)) {
+        return None
+    }
+    Some((name.to_string(), params))
+}
+
+// マクロ内のラベル (LOOP) と @LOOP を NAME.n.LOOP に置き換える
+fn rename_local(text: &str, locals: &[String], name: &str, counter: usize) -> String {
+    let trimmed = text.trim();
+    let renamed = |label: &str| format!("{}.{}.{}", name, counter, label);
+    if let Some(label) = trimmed.strip_prefix('(').and_then(|l| l.strip_suffix(')')) {
+        if locals.iter().any(|l| l == label.trim()) {
+            return format!("({})", renamed(label.trim()));
+        }
+    }
+    if let Some(symbol) = trimmed.strip_prefix('@') {
+        if locals.iter().any(|l| l == symbol.trim()) {
+            return format!("@{}", renamed(symbol.trim()));
+        }
+    }
+    text.to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn no_files(_: &str) -> Option<String> {
+        None
+    }
+
+    fn texts(preprocessor: &Preprocessor) -> Vec<String> {
+        preprocessor.lines.iter().map(|l| l.text.trim().to_string()).collect()
+    }
+
+    #[test]
+    fn macro_test() {
+        let mut preprocessor = Preprocessor::new(&no_files);
+        preprocessor.run("main.asm", concat!(
+            ".macro PUSH_CONST value\n",
+            "    @\\value\n",
+            "    D=A\n",
+            ".endm\n",
+            "PUSH_CONST 7\n",
+            "PUSH_CONST SCREEN   // comment\n",
+        ));
+        assert_eq!(vec!["@7", "D=A", "@SCREEN", "D=A"], texts(&preprocessor));
+        // 展開された行は大元の呼び出し行を覚えている
+        assert_eq!(
+            vec![(2, 5), (3, 5), (2, 6), (3, 6)],
+            preprocessor.lines.iter().map(|l| (l.line, l.origin_line)).collect::<Vec<_>>()
+        );
+        assert!(preprocessor.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn local_label_test() {
+        let mut preprocessor = Preprocessor::new(&no_files);
+        preprocessor.run("main.asm", concat!(
+            ".macro WAIT\n",
+            "(LOOP)\n",
+            "@LOOP\n",
+            "0;JMP\n",
+            "@END\n",
+            ".endm\n",
+            "WAIT\n",
+            "WAIT\n",
+        ));
+        assert_eq!(
+            vec![
+                "(WAIT.1.LOOP)", "@WAIT.1.LOOP", "0;JMP", "@END",
+                "(WAIT.2.LOOP)", "@WAIT.2.LOOP", "0;JMP", "@END",
+            ],
+            texts(&preprocessor)
+        );
+    }
+
+    #[test]
+    fn nested_macro_test() {
+        let mut preprocessor = Preprocessor::new(&no_files);
+        preprocessor.run("main.asm", concat!(
+            ".macro SET addr, value\n",
+            "@\\value\n",
+            "D=A\n",
+            "@\\addr\n",
+            "M=D\n",
+            ".endm\n",
+            ".macro CLEAR addr\n",
+            "SET \\addr, 0\n",
+            ".endm\n",
+            "CLEAR R5\n",
+        ));
+        assert_eq!(vec!["@0", "D=A", "@R5", "M=D"], texts(&preprocessor));
+    }
+
+    #[test]
+    fn include_test() {
+        let loader = |name: &str| match name {
+            "lib.asm" => Some(".macro INC addr\n@\\addr\nM=M+1\n.endm\n@100\n".to_string()),
+            _ => None
+        };
+        let mut preprocessor = Preprocessor::new(&loader);
+        preprocessor.run("main.asm", "@1\n.include \"lib.asm\"\nINC R0\n");
+        assert_eq!(vec!["@1", "@100", "@R0", "M=M+1"], texts(&preprocessor));
+        assert_eq!(vec!["main.asm", "lib.asm"], preprocessor.files.iter().map(|f| f.name.as_str()).collect::<Vec<_>>());
+        assert_eq!(
+            vec![(0, 1, 1), (1, 5, 2), (1, 2, 3), (1, 3, 3)],
+            preprocessor.lines.iter().map(|l| (l.file, l.line, l.origin_line)).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn error_test() {
+        let loader = |name: &str| match name {
+            "a.asm" => Some(".include \"b.asm\"\n".to_string()),
+            "b.asm" => Some(".include \"a.asm\"\n".to_string()),
+            _ => None
+        };
+        let mut preprocessor = Preprocessor::new(&loader);
+        preprocessor.run("main.asm", concat!(
+            ".include \"a.asm\"\n",
+            ".include \"missing.asm\"\n",
+            ".macro ONE x\n",
+            "@\\x\n",
+            ".endm\n",
+            "ONE\n",
+            ".macro LOOP\n",
+            "LOOP\n",
+            ".endm\n",
+            "LOOP\n",
+            ".org 100\n",
+            ".endm\n",
+            ".macro OPEN\n",
+        ));
+        assert_eq!(
+            vec![
+                AsmError::IncludeCycle("main.asm -> a.asm -> b.asm -> a.asm".to_string()),
+                AsmError::IncludeNotFound("missing.asm".to_string()),
+                AsmError::MacroArgumentCount("ONE expects 1 argument(s), got 0".to_string()),
+                AsmError::MacroRecursion("LOOP".to_string()),
+                AsmError::UnknownDirective(".org 100".to_string()),
+                AsmError::InvalidMacro(".endm".to_string()),
+                AsmError::UnterminatedMacro("OPEN".to_string()),
+            ],
+            preprocessor.diagnostics.iter().map(|d| d.error.clone()).collect::<Vec<_>>()
+        );
+        assert_eq!(Span { file: 2, line: 1, column: 1, length: 16 }, preprocessor.diagnostics[0].span);
+        assert_eq!(Span { file: 0, line: 13, column: 1, length: 11 }, preprocessor.diagnostics[6].span);
+    }
+}
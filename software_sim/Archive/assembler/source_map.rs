@@ -0,0 +1,146 @@
+use std::fmt;
+
+use super::parser::{CommandType, Parser};
+
+// ROM アドレスから元のソースの位置を引くための表 (.map ファイル)
+//
+// # rom	file	line	label
+// 0	Max.asm	8
+// 10	Max.asm	19	OUTPUT_FIRST
+//
+// タブ区切りで、label はその命令を含む直前のラベル (無ければ空)
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceMap {
+    pub entries: Vec<SourceMapEntry>
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceMapEntry {
+    pub address: u16,
+    pub file: String,
+    pub line: usize,
+    pub label: Option<String>
+}
+
+// Max.asm:19 (OUTPUT_FIRST) の形。ラベルが無ければ括弧は付けない
+impl fmt::Display for SourceMapEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.label {
+            Some(label) => write!(f, "{}:{} ({})", self.file, self.line, label),
+            None => write!(f, "{}:{}", self.file, self.line)
+        }
+    }
+}
+
+const HEADER: &str = "# rom\tfile\tline\tlabel";
+
+impl SourceMap {
+    // アセンブルし終わった parser から作る (最適化した場合も、した後の parser を渡す)
+    // 名前の無いファイル (Parser::new で作ったもの) は default_name にする
+    pub fn from_parser(parser: &mut Parser, default_name: &str) -> SourceMap {
+        let mut entries: Vec<SourceMapEntry> = Vec::new();
+        let mut label: Option<String> = None;
+        parser.reset();
+        while parser.has_more_commands() {
+            match parser.command_type() {
+                CommandType::A_COMMAND | CommandType::C_COMMAND => {
+                    let span = parser.command_span();
+                    let file = &parser.files()[span.file].name;
+                    entries.push(SourceMapEntry {
+                        address: entries.len() as u16,
+                        file: if file.is_empty() { default_name.to_string() } else { file.clone() },
+                        line: span.line,
+                        label: label.clone()
+                    });
+                },
+                CommandType::L_COMMAND => {
+                    label = parser.symbol().ok().map(|s| s.to_string());
+                }
+            }
+            parser.advance();
+        }
+        parser.reset();
+        SourceMap { entries }
+    }
+
+    // ROM の address 番地の命令がどこから来たか
+    pub fn lookup(&self, address: u16) -> Option<&SourceMapEntry> {
+        self.entries.get(address as usize)
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut out = String::from(HEADER);
+        out.push('\n');
+        for entry in &self.entries {
+            let row = format!(
+                "{}\t{}\t{}\t{}",
+                entry.address, entry.file, entry.line, entry.label.as_deref().unwrap_or("")
+            );
+            out.push_str(row.trim_end());
+            out.push('\n');
+        }
+        out
+    }
+
+    // to_text で書いたものを読み戻す。読めない行があれば None
+    pub fn parse(text: &str) -> Option<SourceMap> {
+        let mut entries: Vec<SourceMapEntry> = Vec::new();
+        for row in text.lines().filter(|l| !l.starts_with('#') && !l.is_empty()) {
+            let mut columns = row.split('\t');
+            let address = columns.next()?.parse::<u16>().ok()?;
+            let file = columns.next()?.to_string();
+            let line = columns.next()?.parse::<usize>().ok()?;
+            let label = columns.next().filter(|l| !l.is_empty()).map(|l| l.to_string());
+            if address as usize != entries.len() {
+                return None
+            }
+            entries.push(SourceMapEntry { address, file, line, label });
+        }
+        Some(SourceMap { entries })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn from_parser_test() {
+        let mut parser = Parser::new(include_str!("../../06/test/max/Max.asm"));
+        let map = SourceMap::from_parser(&mut parser, "Max.asm");
+        assert_eq!(16, map.entries.len());
+        assert_eq!(
+            Some(&SourceMapEntry { address: 0, file: "Max.asm".to_string(), line: 8, label: None }),
+            map.lookup(0)
+        );
+        assert_eq!(
+            Some(&SourceMapEntry { address: 10, file: "Max.asm".to_string(), line: 19, label: Some("OUTPUT_FIRST".to_string()) }),
+            map.lookup(10)
+        );
+        assert_eq!(Some("INFINITE_LOOP"), map.lookup(15).and_then(|e| e.label.as_deref()));
+        assert_eq!(None, map.lookup(16));
+    }
+
+    #[test]
+    fn extended_test() {
+        let loader = |name: &str| if name == "lib.asm" { Some("@R1\nM=0\n".to_string()) } else { None };
+        let mut parser = Parser::new_extended("main.asm", "(START)\n.include \"lib.asm\"\n@START\n0;JMP\n", &loader);
+        let map = SourceMap::from_parser(&mut parser, "");
+        assert_eq!(
+            "# rom\tfile\tline\tlabel\n0\tlib.asm\t1\tSTART\n1\tlib.asm\t2\tSTART\n2\tmain.asm\t3\tSTART\n3\tmain.asm\t4\tSTART\n",
+            map.to_text()
+        );
+    }
+
+    #[test]
+    fn parse_test() {
+        let mut parser = Parser::new(include_str!("../../06/test/rect/Rect.asm"));
+        let map = SourceMap::from_parser(&mut parser, "Rect.asm");
+        assert_eq!(Some(map.clone()), SourceMap::parse(&map.to_text()));
+        assert_eq!(None, SourceMap::parse("0\ta.asm\tx\n"));
+        assert_eq!(None, SourceMap::parse("1\ta.asm\t1\n"));
+        let map = SourceMap::parse("# rom\tfile\tline\tlabel\n0\tRect.asm\t9\n1\tRect.asm\t12\tLOOP\n").unwrap();
+        assert_eq!("Rect.asm:9", map.lookup(0).unwrap().to_string());
+        assert_eq!("Rect.asm:12 (LOOP)", map.lookup(1).unwrap().to_string());
+    }
+}
@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SymbolKind {
+    // SP, R0, SCREEN など最初から決まっているもの
+    Predefined,
+    // (LABEL) で宣言された ROM アドレス
+    Label,
+    // @xxx で初めて出てきた RAM アドレス
+    Variable
+}
+
+#[derive(Debug)]
+pub struct SymbolTable {
+    table: HashMap<String, (u16, SymbolKind)>
+}
+
+impl SymbolTable {
+    // 定義済みシンボルを登録した状態で作る
+    pub fn new() -> SymbolTable {
+        let mut symbol_table = SymbolTable {
+            table: HashMap::new()
+        };
+        symbol_table.add_entry("SP", 0x0000, SymbolKind::Predefined);
+        symbol_table.add_entry("LCL", 0x0001, SymbolKind::Predefined);
+        symbol_table.add_entry("ARG", 0x0002, SymbolKind::Predefined);
+        symbol_table.add_entry("THIS", 0x0003, SymbolKind::Predefined);
+        symbol_table.add_entry("THAT", 0x0004, SymbolKind::Predefined);
+        for i in 0..16 {
+            symbol_table.add_entry(&format!("R{}", i), i, SymbolKind::Predefined);
+        }
+        symbol_table.add_entry("SCREEN", 0x4000, SymbolKind::Predefined);
+        symbol_table.add_entry("KBD", 0x6000, SymbolKind::Predefined);
+        symbol_table
+    }
+
+    pub fn add_entry(&mut self, symbol: &str, address: u16, kind: SymbolKind) {
+        self.table.insert(symbol.to_string(), (address, kind));
+    }
+
+    pub fn contains(&self, symbol: &str) -> bool {
+        self.table.contains_key(symbol)
+    }
+
+    pub fn get_address(&self, symbol: &str) -> Option<u16> {
+        self.table.get(symbol).map(|(address, _)| *address)
+    }
+
+    #[cfg(test)]
+    pub fn get_kind(&self, symbol: &str) -> Option<SymbolKind> {
+        self.table.get(symbol).map(|(_, kind)| *kind)
+    }
+
+    // 指定した種類のシンボルをアドレス順 (同じなら名前順) で返す
+    pub fn entries(&self, kind: SymbolKind) -> Vec<(&str, u16)> {
+        let mut entries: Vec<(&str, u16)> = self.table
+            .iter()
+            .filter(|(_, (_, k))| *k == kind)
+            .map(|(symbol, (address, _))| (symbol.as_str(), *address))
+            .collect();
+        entries.sort_by(|a, b| (a.1, a.0).cmp(&(b.1, b.0)));
+        entries
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn predefined_symbol_test() {
+        let table = SymbolTable::new();
+        assert_eq!(Some(0), table.get_address("SP"));
+        assert_eq!(Some(1), table.get_address("LCL"));
+        assert_eq!(Some(2), table.get_address("ARG"));
+        assert_eq!(Some(3), table.get_address("THIS"));
+        assert_eq!(Some(4), table.get_address("THAT"));
+        assert_eq!(Some(0), table.get_address("R0"));
+        assert_eq!(Some(15), table.get_address("R15"));
+        assert_eq!(Some(16384), table.get_address("SCREEN"));
+        assert_eq!(Some(24576), table.get_address("KBD"));
+        assert_eq!(None, table.get_address("R16"));
+    }
+
+    #[test]
+    fn add_entry_test() {
+        let mut table = SymbolTable::new();
+        assert!(!table.contains("LOOP"));
+        table.add_entry("LOOP", 10, SymbolKind::Label);
+        assert!(table.contains("LOOP"));
+        assert_eq!(Some(10), table.get_address("LOOP"));
+        assert_eq!(Some(SymbolKind::Label), table.get_kind("LOOP"));
+        assert_eq!(Some(SymbolKind::Predefined), table.get_kind("SP"));
+    }
+
+    #[test]
+    fn entries_test() {
+        let mut table = SymbolTable::new();
+        table.add_entry("END", 20, SymbolKind::Label);
+        table.add_entry("LOOP", 4, SymbolKind::Label);
+        table.add_entry("i", 16, SymbolKind::Variable);
+        assert_eq!(vec![("LOOP", 4), ("END", 20)], table.entries(SymbolKind::Label));
+        assert_eq!(vec![("i", 16)], table.entries(SymbolKind::Variable));
+        assert_eq!(
+            vec![("ARG", 2), ("R2", 2)],
+            table.entries(SymbolKind::Predefined).into_iter().filter(|(_, a)| *a == 2).collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,175 @@
+use crate::bool_arithmetic;
+use crate::signal::{Bit, Bus, Signal};
+
+// 64 本の独立した信号を u64 の各桁に詰めたもの (ビットスライス)
+// 桁 k が k 番目のテストベクタ。nand 1 回で 64 通りを同時に計算できる
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lanes(pub u64);
+
+pub const LANES: usize = 64;
+
+impl Signal for Lanes {
+    fn nand(self, other: Lanes) -> Lanes {
+        Lanes(!(self.0 & other.0))
+    }
+
+    fn constant(bit: Bit) -> Lanes {
+        Lanes(if bit.is_one() { !0 } else { 0 })
+    }
+}
+
+impl Lanes {
+    pub fn lane(self, k: usize) -> Bit {
+        Bit::from((self.0 >> k) & 1 == 1)
+    }
+}
+
+// values[k] を k 番目のレーンに入れる (64 個まで。足りないレーンは 0)
+pub fn pack<const N: usize>(values: &[u64]) -> Bus<N, Lanes> {
+    assert!(values.len() <= LANES, "at most 64 lanes");
+    Bus::from_fn(|i| {
+        Lanes(values.iter().enumerate().fold(0, |acc, (k, v)| acc | (((v >> i) & 1) << k)))
+    })
+}
+
+// pack の逆。k 番目のレーンの値を返す
+pub fn unpack<const N: usize>(bus: &Bus<N, Lanes>) -> [u64; LANES] {
+    let mut result = [0u64; LANES];
+    for i in 0..N {
+        for (k, value) in result.iter_mut().enumerate() {
+            *value |= ((bus[i].0 >> k) & 1) << i;
+        }
+    }
+    result
+}
+
+// 再現できるように種から作る乱数 (xorshift64)
+pub struct XorShift(u64);
+
+impl XorShift {
+    pub fn new(seed: u64) -> XorShift {
+        XorShift(if seed == 0 { 0x9e3779b97f4a7c15 } else { seed })
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+}
+
+// 全レーンに同じ値を入れる
+pub fn splat<const N: usize>(value: u64) -> Bus<N, Lanes> {
+    Bus::from_fn(|i| Lanes::constant(Bit::from((value >> i) & 1 == 1)))
+}
+
+// レーン k に (start + k) の下 16 桁を入れたもの
+fn consecutive(start: u64) -> Bus<16, Lanes> {
+    let values: [u64; LANES] = std::array::from_fn(|k| (start + k as u64) & 0xffff);
+    pack(&values)
+}
+
+// adder_16bit を a の範囲と全ての b (65536 通り) で調べ、i16 の足し算と違う最初の (a, b, 出力) を返す
+// a_range に 0..=0xffff を渡せば 2^32 通り全部になる
+pub fn check_adder<R: IntoIterator<Item = u16>>(a_range: R) -> Option<(u16, u16, u16)> {
+    // b は 64 個ずつ連続した値を流すので、答えも連続した 64 個になる。どちらも先に全部作っておく
+    let table: Vec<Bus<16, Lanes>> = (0..0x10000).map(consecutive).collect();
+    for a in a_range {
+        let a_bus: Bus<16, Lanes> = splat(a as u64);
+        for base in (0..0x10000u64).step_by(LANES) {
+            let out = bool_arithmetic::adder_16bit(&a_bus, &table[base as usize]);
+            if out == table[(a as usize + base as usize) & 0xffff] {
+                continue
+            }
+            for (k, value) in unpack(&out).iter().enumerate() {
+                let b = (base + k as u64) as u16;
+                if *value as u16 != (a as i16).wrapping_add(b as i16) as u16 {
+                    return Some((a, b, *value as u16))
+                }
+            }
+        }
+    }
+    None
+}
+
+// ALU の仕様を普通の Rust で書いたもの。(out, zr, ng)
+pub fn alu_reference(x: i16, y: i16, control: u8) -> (i16, bool, bool) {
+    // control は上の桁から zx nx zy ny f no
+    let bit = |n: u8| (control >> (5 - n)) & 1 == 1;
+    let mut x = if bit(0) { 0 } else { x };
+    if bit(1) { x = !x; }
+    let mut y = if bit(2) { 0 } else { y };
+    if bit(3) { y = !y; }
+    let mut out = if bit(4) { x.wrapping_add(y) } else { x & y };
+    if bit(5) { out = !out; }
+    (out, out == 0, out < 0)
+}
+
+// ランダムな x, y を samples 組作り、それぞれ制御ビット 64 通り全部を 1 回で調べる
+// 違ったら最初の (x, y, control) を返す
+pub fn check_alu(samples: usize, seed: u64) -> Option<(i16, i16, u8)> {
+    let mut rng = XorShift::new(seed);
+    // レーン k の制御ビットは k そのもの
+    let control = |n: u32| Lanes((0..LANES as u64).fold(0, |acc, k| acc | (((k >> (5 - n)) & 1) << k)));
+    for _ in 0..samples {
+        let r = rng.next_u64();
+        let (x, y) = (r as i16, (r >> 16) as i16);
+        let (out, zr, ng) = bool_arithmetic::alu(
+            &splat(x as u16 as u64),
+            &splat(y as u16 as u64),
+            control(0), control(1), control(2), control(3), control(4), control(5)
+        );
+        for (k, value) in unpack(&out).iter().enumerate() {
+            let (expect, expect_zr, expect_ng) = alu_reference(x, y, k as u8);
+            if *value as u16 as i16 != expect || zr.lane(k).is_one() != expect_zr || ng.lane(k).is_one() != expect_ng {
+                return Some((x, y, k as u8))
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bool_logic;
+    use crate::signal::{I, O};
+
+    #[test]
+    fn lanes_test() {
+        let a = Lanes(0b1100);
+        let b = Lanes(0b1010);
+        assert_eq!(0b1000, bool_logic::and(a, b).0 & 0b1111);
+        assert_eq!(0b0110, bool_logic::xor(a, b).0 & 0b1111);
+        assert_eq!(0b1110, bool_logic::or(a, b).0 & 0b1111);
+        assert_eq!(Lanes(!0), Lanes::constant(I));
+        assert_eq!(Lanes(0), Lanes::constant(O));
+        assert_eq!(I, a.lane(3));
+        assert_eq!(O, a.lane(0));
+    }
+
+    #[test]
+    fn pack_test() {
+        let values: Vec<u64> = (0..64).map(|k| k * 1000).collect();
+        let bus: Bus<16, Lanes> = pack(&values);
+        assert_eq!(values[..], unpack(&bus)[..]);
+        assert_eq!([0u64; 64], unpack(&pack::<8>(&[])));
+    }
+
+    #[test]
+    fn adder_test() {
+        // 2^32 通りはテストには重いので、端と途中の a だけ
+        assert_eq!(None, check_adder(vec![0, 1, 0x7fff, 0x8000, 0xffff, 12345]));
+    }
+
+    #[test]
+    fn alu_test() {
+        assert_eq!((0, true, false), alu_reference(5, 7, 0b101010));
+        assert_eq!((-1, false, true), alu_reference(5, 7, 0b111010));
+        assert_eq!((12, false, false), alu_reference(5, 7, 0b000010));
+        assert_eq!(None, check_alu(200, 1));
+    }
+}
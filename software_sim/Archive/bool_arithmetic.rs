@@ -1,67 +1,65 @@
 use crate::bool_logic;
-use std::collections::HashMap;
+use crate::signal::{Bit, Bus, Part, Signal, O, I};
 
-fn half_adder(a: u8, b: u8) -> HashMap<&'static str, u8> {
-    let mut result = HashMap::new();
-    let carry: u8 = bool_logic::and(a, b);
-    let sum: u8 = bool_logic::xor(a, b);
+// 加算器の出力。前は HashMap で返していたが、ゲートを何億回も回すと遅いので構造体にした
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AdderOut<S> {
+    pub sum: S,
+    pub carry: S
+}
 
-    result.insert("carry", carry);
-    result.insert("sum", sum);
-    result
+pub fn half_adder<S: Signal>(a: S, b: S) -> AdderOut<S> {
+    let _part = Part::<S>::enter("HalfAdder");
+    let carry: S = bool_logic::and(a, b);
+    let sum: S = bool_logic::xor(a, b);
+
+    AdderOut { sum, carry }
 }
 
-fn full_adder(a: u8, b: u8, c: u8) -> HashMap<&'static str, u8> {
-    let mut result = HashMap::new();
-    let ha_first: HashMap<&'static str, u8> = half_adder(a, b);
-    let ha_second: HashMap<&'static str, u8> = half_adder(c, ha_first["sum"]);
-    let carry: u8 = bool_logic::or(ha_second["carry"], ha_first["carry"]);
-    let sum: u8 = ha_second["sum"];
+pub fn full_adder<S: Signal>(a: S, b: S, c: S) -> AdderOut<S> {
+    let _part = Part::<S>::enter("FullAdder");
+    let ha_first: AdderOut<S> = half_adder(a, b);
+    let ha_second: AdderOut<S> = half_adder(c, ha_first.sum);
+    let carry: S = bool_logic::or(ha_second.carry, ha_first.carry);
+    let sum: S = ha_second.sum;
 
-    result.insert("carry", carry);
-    result.insert("sum", sum);
-    result
+    AdderOut { sum, carry }
 }
 
-pub fn adder_16bit(a_arr: &[u8; 16], b_arr: &[u8; 16]) -> [u8; 16] {
+pub fn adder_16bit<S: Signal>(a_arr: &Bus<16, S>, b_arr: &Bus<16, S>) -> Bus<16, S> {
+    let _part = Part::<S>::enter("Add16");
     // without overflow check
-    let mut result: [u8; 16] = [0; 16];
-    let mut carry: u8 = 0;
-
-    for a in 0..a_arr.len() {
-        for b in 0..b_arr.len() {
-            if a == b {
-                let fa = full_adder(a_arr[a_arr.len() - 1 - a], b_arr[b_arr.len() - 1 - b], carry);
-                result[result.len() - 1 - a] = fa["sum"];
-                carry = fa["carry"];
-            }
-        }
+    // 下の桁 (a_arr[0]) から順に桁上がりを渡していく
+    let mut result: Bus<16, S> = Bus::constant(O);
+    let mut carry: S = S::constant(O);
+
+    for i in 0..16 {
+        let fa = full_adder(a_arr[i], b_arr[i], carry);
+        result[i] = fa.sum;
+        carry = fa.carry;
     }
     result
 }
 
-pub fn incrementer(a_arr: &[u8; 16]) -> [u8; 16] {
-    const INCREMENTER: [u8; 16] = [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1];
-
+pub fn incrementer<S: Signal>(a_arr: &Bus<16, S>) -> Bus<16, S> {
+    let _part = Part::<S>::enter("Inc16");
     // without overflow check
-    let mut result: [u8; 16] = [0; 16];
-    let mut carry: u8 = 0;
-
-    for a in 0..a_arr.len() {
-        for b in 0..INCREMENTER.len() {
-            if a == b {
-                let fa = full_adder(a_arr[a_arr.len() - 1 - a], INCREMENTER[INCREMENTER.len() - 1 - b], carry);
-                result[result.len() - 1 - a] = fa["sum"];
-                carry = fa["carry"];
-            }
-        }
+    let mut result: Bus<16, S> = Bus::constant(O);
+    let mut carry: S = S::constant(O);
+    let incrementer: Bus<16, S> = Bus::from_fn(|i| S::constant(if i == 0 { I } else { O }));
+
+    for i in 0..16 {
+        let fa = full_adder(a_arr[i], incrementer[i], carry);
+        result[i] = fa.sum;
+        carry = fa.carry;
     }
     result
 }
 
 #[warn(dead_code)]
-fn alu(x_arr: &[u8; 16], y_arr: &[u8; 16], zx: u8, nx: u8, zy: u8, ny: u8, f: u8, no: u8)
-    -> ([u8; 16], u8, u8) {
+pub fn alu<S: Signal>(x_arr: &Bus<16, S>, y_arr: &Bus<16, S>, zx: S, nx: S, zy: S, ny: S, f: S, no: S)
+    -> (Bus<16, S>, S, S) {
+    let _part = Part::<S>::enter("ALU");
     // ググりまくってもよくわからん。。。
 
     // Arithmetic and Logic Unit
@@ -73,528 +71,169 @@ fn alu(x_arr: &[u8; 16], y_arr: &[u8; 16], zx: u8, nx: u8, zy: u8, ny: u8, f: u8
     // no: 出力outを反転する
 
     // if zx then x = 0
-    let out_zx: [u8; 16] = bool_logic::mux_16bit(x_arr, &[0; 16], zx);
+    let out_zx: Bus<16, S> = bool_logic::mux_16bit(x_arr, &Bus::constant(O), zx);
 
     // if nx then x = !x
-    let not_nx: [u8; 16] = bool_logic::not_16bit(&out_zx);
-    let out_nx: [u8; 16] = bool_logic::mux_16bit(&out_zx, &not_nx, nx);
+    let not_nx: Bus<16, S> = bool_logic::not_16bit(&out_zx);
+    let out_nx: Bus<16, S> = bool_logic::mux_16bit(&out_zx, &not_nx, nx);
 
     // if zy then y = 0
-    let out_zy: [u8; 16] = bool_logic::mux_16bit(y_arr, &[0; 16], zy);
+    let out_zy: Bus<16, S> = bool_logic::mux_16bit(y_arr, &Bus::constant(O), zy);
 
     // if ny then y = !y
-    let not_ny: [u8; 16] = bool_logic::not_16bit(&out_zy);
-    let out_ny: [u8; 16] = bool_logic::mux_16bit(&out_zy, &not_ny, ny);
+    let not_ny: Bus<16, S> = bool_logic::not_16bit(&out_zy);
+    let out_ny: Bus<16, S> = bool_logic::mux_16bit(&out_zy, &not_ny, ny);
 
     // if f then out = x + y
     //      else out = x & y
-    let x_plus_y: [u8; 16] = adder_16bit(&out_nx, &out_ny);
-    let x_and_y: [u8; 16] = bool_logic::and_16bit(&out_nx, &out_ny);
-    let f_xy: [u8; 16] = bool_logic::mux_16bit(&x_and_y, &x_plus_y, f);
+    let x_plus_y: Bus<16, S> = adder_16bit(&out_nx, &out_ny);
+    let x_and_y: Bus<16, S> = bool_logic::and_16bit(&out_nx, &out_ny);
+    let f_xy: Bus<16, S> = bool_logic::mux_16bit(&x_and_y, &x_plus_y, f);
 
     // if no then out = !out
-    let not_f_xy: [u8; 16] = bool_logic::not_16bit(&f_xy);
-    let out: [u8; 16] = bool_logic::mux_16bit(&f_xy, &not_f_xy, no);
+    let not_f_xy: Bus<16, S> = bool_logic::not_16bit(&f_xy);
+    let out: Bus<16, S> = bool_logic::mux_16bit(&f_xy, &not_f_xy, no);
 
-    let mut ret0: [u8; 8] = [0; 8];
-    let mut ret1: [u8; 8] = [0; 8];
-    let retsign: u8 = out[0];
-    for i in 0..8 { ret0[i] = out[i]; }
-    for i in 8..16 { ret1[i - 8] = out[i]; }
+    // 上の 8 桁と下の 8 桁に分けて Or8Way に通す
+    let mut ret0: [S; 8] = [S::constant(O); 8];
+    let mut ret1: [S; 8] = [S::constant(O); 8];
+    let retsign: S = out[15];
+    for i in 0..8 { ret0[i] = out[i + 8]; }
+    for i in 0..8 { ret1[i] = out[i]; }
 
     let ret0is0 = bool_logic::or_8way(&ret0);
     let ret1is0 = bool_logic::or_8way(&ret1);
 
     // if out = 0 then zr = 1 else zr = 0
-    let inverse_zr: u8 = bool_logic::or(ret0is0, ret1is0);
-    let zr: u8 = bool_logic::not(inverse_zr);
+    let inverse_zr: S = bool_logic::or(ret0is0, ret1is0);
+    let zr: S = bool_logic::not(inverse_zr);
 
     // if out < 0 then ng = 1 else ng = 0
-    let ng: u8 = bool_logic::and(retsign, 1);
+    let ng: S = bool_logic::and(retsign, S::constant(I));
 
     (out, zr, ng)
 }
 
+// 手で書いたテストのベクタ。下の #[test] のほか、vectors を通して fault と verilog でも流す
+// (a, b, sum, carry)
+pub const HALF_ADDER_VECTORS: &[(Bit, Bit, Bit, Bit)] = &[
+    (I, I, O, I),
+    (I, O, I, O),
+    (O, I, I, O),
+    (O, O, O, O),
+];
+
+// (a, b, c, sum, carry)
+pub const FULL_ADDER_VECTORS: &[(Bit, Bit, Bit, Bit, Bit)] = &[
+    (O, O, O, O, O),
+    (O, O, I, I, O),
+    (O, I, O, I, O),
+    (O, I, I, O, I),
+    (I, O, O, I, O),
+    (I, O, I, O, I),
+    (I, I, O, O, I),
+    (I, I, I, I, I),
+];
+
+// (a, b, out)
+pub const ADD16_VECTORS: &[(u16, u16, u16)] = &[
+    (0b0000000000000000, 0b0000000000000000, 0b0000000000000000),
+    (0b0000000000000000, 0b1111111111111111, 0b1111111111111111),
+    (0b1111111111111111, 0b1111111111111111, 0b1111111111111110),
+    (0b1010101010101010, 0b0101010101010101, 0b1111111111111111),
+    (0b0011110011000011, 0b0000111111110000, 0b0100110010110011),
+    (0b0001001000110100, 0b1001100001110110, 0b1010101010101010),
+];
+
+// (in, out)
+pub const INC16_VECTORS: &[(u16, u16)] = &[
+    (0b0000000000000000, 0b0000000000000001),
+    (0b1111111111111111, 0b0000000000000000),
+    (0b0000000000000101, 0b0000000000000110),
+    (0b1111111111111011, 0b1111111111111100),
+];
+
+// (x, y, [zx, nx, zy, ny, f, no], out, zr, ng)
+pub type AluVector = (u16, u16, [Bit; 6], u16, Bit, Bit);
+pub const ALU_VECTORS: &[AluVector] = &[
+    (0b0000000000000000, 0b1111111111111111, [I, O, I, O, I, O], 0b0000000000000000, I, O),
+    (0b0000000000000000, 0b1111111111111111, [I, I, I, I, I, I], 0b0000000000000001, O, O),
+    (0b0000000000000000, 0b1111111111111111, [I, I, I, O, I, O], 0b1111111111111111, O, I),
+    (0b0000000000000000, 0b1111111111111111, [O, O, I, I, O, O], 0b0000000000000000, I, O),
+    (0b0000000000000000, 0b1111111111111111, [I, I, O, O, O, O], 0b1111111111111111, O, I),
+    (0b0000000000000000, 0b1111111111111111, [O, O, I, I, O, I], 0b1111111111111111, O, I),
+    (0b0000000000000000, 0b1111111111111111, [I, I, O, O, O, I], 0b0000000000000000, I, O),
+    (0b0000000000000000, 0b1111111111111111, [O, O, I, I, I, I], 0b0000000000000000, I, O),
+    (0b0000000000000000, 0b1111111111111111, [I, I, O, O, I, I], 0b0000000000000001, O, O),
+    (0b0000000000000000, 0b1111111111111111, [O, I, I, I, I, I], 0b0000000000000001, O, O),
+    (0b0000000000000000, 0b1111111111111111, [I, I, O, I, I, I], 0b0000000000000000, I, O),
+    (0b0000000000000000, 0b1111111111111111, [O, O, I, I, I, O], 0b1111111111111111, O, I),
+    (0b0000000000000000, 0b1111111111111111, [I, I, O, O, I, O], 0b1111111111111110, O, I),
+    (0b0000000000000000, 0b1111111111111111, [O, O, O, O, I, O], 0b1111111111111111, O, I),
+    (0b0000000000000000, 0b1111111111111111, [O, I, O, O, I, I], 0b0000000000000001, O, O),
+    (0b0000000000000000, 0b1111111111111111, [O, O, O, I, I, I], 0b1111111111111111, O, I),
+    (0b0000000000000000, 0b1111111111111111, [O, O, O, O, O, O], 0b0000000000000000, I, O),
+    (0b0000000000000000, 0b1111111111111111, [O, I, O, I, O, I], 0b1111111111111111, O, I),
+    (0b0000000000010001, 0b0000000000000011, [I, O, I, O, I, O], 0b0000000000000000, I, O),
+    (0b0000000000010001, 0b0000000000000011, [I, I, I, I, I, I], 0b0000000000000001, O, O),
+    (0b0000000000010001, 0b0000000000000011, [I, I, I, O, I, O], 0b1111111111111111, O, I),
+    (0b0000000000010001, 0b0000000000000011, [O, O, I, I, O, O], 0b0000000000010001, O, O),
+    (0b0000000000010001, 0b0000000000000011, [I, I, O, O, O, O], 0b0000000000000011, O, O),
+    (0b0000000000010001, 0b0000000000000011, [O, O, I, I, O, I], 0b1111111111101110, O, I),
+    (0b0000000000010001, 0b0000000000000011, [I, I, O, O, O, I], 0b1111111111111100, O, I),
+    (0b0000000000010001, 0b0000000000000011, [O, O, I, I, I, I], 0b1111111111101111, O, I),
+    (0b0000000000010001, 0b0000000000000011, [I, I, O, O, I, I], 0b1111111111111101, O, I),
+    (0b0000000000010001, 0b0000000000000011, [O, I, I, I, I, I], 0b0000000000010010, O, O),
+    (0b0000000000010001, 0b0000000000000011, [I, I, O, I, I, I], 0b0000000000000100, O, O),
+    (0b0000000000010001, 0b0000000000000011, [O, O, I, I, I, O], 0b0000000000010000, O, O),
+    (0b0000000000010001, 0b0000000000000011, [I, I, O, O, I, O], 0b0000000000000010, O, O),
+    (0b0000000000010001, 0b0000000000000011, [O, O, O, O, I, O], 0b0000000000010100, O, O),
+    (0b0000000000010001, 0b0000000000000011, [O, I, O, O, I, I], 0b0000000000001110, O, O),
+    (0b0000000000010001, 0b0000000000000011, [O, O, O, I, I, I], 0b1111111111110010, O, I),
+    (0b0000000000010001, 0b0000000000000011, [O, O, O, O, O, O], 0b0000000000000001, O, O),
+    (0b0000000000010001, 0b0000000000000011, [O, I, O, I, O, I], 0b0000000000010011, O, O),
+];
+
 #[cfg(test)]
 mod test {
-    use std::convert::{TryFrom};
     use super::*;
-
-    fn converter_16bit_to_array<'a>(input: &'a str) -> [u8; 16] {
-        let mut output: [u8; 16] = [0; 16];
-        for i in 0..input.len() {
-            output[i] = u8::try_from(
-                input.chars().nth(i).unwrap().to_digit(2).unwrap()
-            ).unwrap();
-        }
-        output
-    }
-
-    #[test]
-    fn converter_16bit_to_array_test() {
-        assert_eq!([1; 16], converter_16bit_to_array("1111111111111111"));
-        assert_eq!([0; 16], converter_16bit_to_array("0000000000000000"));
-        assert_eq!([1,0,0,1,0,0,1,0,0,1,0,0,1,0,0,1], converter_16bit_to_array("1001001001001001"));
-    }
+    use crate::signal::Word16;
 
     #[test]
     fn half_adder_test() {
-        assert_eq!(1, half_adder(1, 1)["carry"]);
-        assert_eq!(0, half_adder(1, 0)["carry"]);
-        assert_eq!(0, half_adder(0, 1)["carry"]);
-        assert_eq!(0, half_adder(0, 0)["carry"]);
-        assert_eq!(0, half_adder(1, 1)["sum"]);
-        assert_eq!(1, half_adder(1, 0)["sum"]);
-        assert_eq!(1, half_adder(0, 1)["sum"]);
-        assert_eq!(0, half_adder(0, 0)["sum"]);
+        for (a, b, sum, carry) in HALF_ADDER_VECTORS {
+            assert_eq!(AdderOut { sum: *sum, carry: *carry }, half_adder(*a, *b));
+        }
     }
 
     #[test]
     fn full_adder_test() {
-        assert_eq!(0, full_adder(0, 0, 0)["carry"]);
-        assert_eq!(0, full_adder(0, 0, 1)["carry"]);
-        assert_eq!(0, full_adder(0, 1, 0)["carry"]);
-        assert_eq!(1, full_adder(0, 1, 1)["carry"]);
-        assert_eq!(0, full_adder(1, 0, 0)["carry"]);
-        assert_eq!(1, full_adder(1, 0, 1)["carry"]);
-        assert_eq!(1, full_adder(1, 1, 0)["carry"]);
-        assert_eq!(1, full_adder(1, 1, 1)["carry"]);
-
-        assert_eq!(0, full_adder(0, 0, 0)["sum"]);
-        assert_eq!(1, full_adder(0, 0, 1)["sum"]);
-        assert_eq!(1, full_adder(0, 1, 0)["sum"]);
-        assert_eq!(0, full_adder(0, 1, 1)["sum"]);
-        assert_eq!(1, full_adder(1, 0, 0)["sum"]);
-        assert_eq!(0, full_adder(1, 0, 1)["sum"]);
-        assert_eq!(0, full_adder(1, 1, 0)["sum"]);
-        assert_eq!(1, full_adder(1, 1, 1)["sum"]);
+        for (a, b, c, sum, carry) in FULL_ADDER_VECTORS {
+            assert_eq!(AdderOut { sum: *sum, carry: *carry }, full_adder(*a, *b, *c));
+        }
     }
 
     #[test]
     fn adder_16bit_test() {
-        assert_eq!(
-            converter_16bit_to_array("0000000000000000"),
-            adder_16bit(
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000")
-            )
-        );
-        assert_eq!(
-            converter_16bit_to_array("1111111111111111"),
-            adder_16bit(
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("1111111111111111")
-            )
-        );
-        assert_eq!(
-            converter_16bit_to_array("1111111111111110"),
-            adder_16bit(
-                &converter_16bit_to_array("1111111111111111"),
-                &converter_16bit_to_array("1111111111111111")
-            )
-        );
-        assert_eq!(
-            converter_16bit_to_array("1111111111111111"),
-            adder_16bit(
-                &converter_16bit_to_array("1010101010101010"),
-                &converter_16bit_to_array("0101010101010101")
-            )
-        );
-        assert_eq!(
-            converter_16bit_to_array("0100110010110011"),
-            adder_16bit(
-                &converter_16bit_to_array("0011110011000011"),
-                &converter_16bit_to_array("0000111111110000")
-            )
-        );
-        assert_eq!(
-            converter_16bit_to_array("1010101010101010"),
-            adder_16bit(
-                &converter_16bit_to_array("0001001000110100"),
-                &converter_16bit_to_array("1001100001110110")
-            )
-        );
+        for (a, b, out) in ADD16_VECTORS {
+            assert_eq!(Word16::from(*out), adder_16bit(&Word16::from(*a), &Word16::from(*b)));
+        }
     }
 
     #[test]
     fn incrementer_test() {
-        assert_eq!(
-            converter_16bit_to_array("0000000000000001"),
-            incrementer(&converter_16bit_to_array("0000000000000000"))
-        );
-        assert_eq!(
-            converter_16bit_to_array("0000000000000000"),
-            incrementer(&converter_16bit_to_array("1111111111111111"))
-        );
-        assert_eq!(
-            converter_16bit_to_array("0000000000000110"),
-            incrementer(&converter_16bit_to_array("0000000000000101"))
-        );
-        assert_eq!(
-            converter_16bit_to_array("1111111111111100"),
-            incrementer(&converter_16bit_to_array("1111111111111011"))
-        );
+        for (input, out) in INC16_VECTORS {
+            assert_eq!(Word16::from(*out), incrementer(&Word16::from(*input)));
+        }
     }
 
     #[test]
     fn aul_test() {
-        let result = alu(
-            &converter_16bit_to_array("0000000000000000"),
-            &converter_16bit_to_array("1111111111111111"),
-            1, 0, 1, 0, 1, 0,
-        );
-        assert_eq!(converter_16bit_to_array("0000000000000000"), result.0);
-        assert_eq!(1, result.1);
-        assert_eq!(0, result.2);
-
-
-        let result = alu(
-            &converter_16bit_to_array("0000000000000000"),
-            &converter_16bit_to_array("1111111111111111"),
-            1, 1, 1, 1, 1, 1,
-        );
-        assert_eq!(converter_16bit_to_array("0000000000000001"), result.0);
-        assert_eq!(0, result.1);
-        assert_eq!(0, result.2);
-
-
-        let result = alu(
-            &converter_16bit_to_array("0000000000000000"),
-            &converter_16bit_to_array("1111111111111111"),
-            1, 1, 1, 0, 1, 0,
-        );
-        assert_eq!(converter_16bit_to_array("1111111111111111"), result.0);
-        assert_eq!(0, result.1);
-        assert_eq!(1, result.2);
-
-
-        let result = alu(
-            &converter_16bit_to_array("0000000000000000"),
-            &converter_16bit_to_array("1111111111111111"),
-            0, 0, 1, 1, 0, 0,
-        );
-        assert_eq!(converter_16bit_to_array("0000000000000000"), result.0);
-        assert_eq!(1, result.1);
-        assert_eq!(0, result.2);
-
-
-        let result = alu(
-            &converter_16bit_to_array("0000000000000000"),
-            &converter_16bit_to_array("1111111111111111"),
-            1, 1, 0, 0, 0, 0,
-        );
-        assert_eq!(converter_16bit_to_array("1111111111111111"), result.0);
-        assert_eq!(0, result.1);
-        assert_eq!(1, result.2);
-
-
-        let result = alu(
-            &converter_16bit_to_array("0000000000000000"),
-            &converter_16bit_to_array("1111111111111111"),
-            0, 0, 1, 1, 0, 1,
-        );
-        assert_eq!(converter_16bit_to_array("1111111111111111"), result.0);
-        assert_eq!(0, result.1);
-        assert_eq!(1, result.2);
-
-
-        let result = alu(
-            &converter_16bit_to_array("0000000000000000"),
-            &converter_16bit_to_array("1111111111111111"),
-            1, 1, 0, 0, 0, 1,
-        );
-        assert_eq!(converter_16bit_to_array("0000000000000000"), result.0);
-        assert_eq!(1, result.1);
-        assert_eq!(0, result.2);
-
-
-        let result = alu(
-            &converter_16bit_to_array("0000000000000000"),
-            &converter_16bit_to_array("1111111111111111"),
-            0, 0, 1, 1, 1, 1,
-        );
-        assert_eq!(converter_16bit_to_array("0000000000000000"), result.0);
-        assert_eq!(1, result.1);
-        assert_eq!(0, result.2);
-
-
-        let result = alu(
-            &converter_16bit_to_array("0000000000000000"),
-            &converter_16bit_to_array("1111111111111111"),
-            1, 1, 0, 0, 1, 1,
-        );
-        assert_eq!(converter_16bit_to_array("0000000000000001"), result.0);
-        assert_eq!(0, result.1);
-        assert_eq!(0, result.2);
-
-
-        let result = alu(
-            &converter_16bit_to_array("0000000000000000"),
-            &converter_16bit_to_array("1111111111111111"),
-            0, 1, 1, 1, 1, 1,
-        );
-        assert_eq!(converter_16bit_to_array("0000000000000001"), result.0);
-        assert_eq!(0, result.1);
-        assert_eq!(0, result.2);
-
-
-        let result = alu(
-            &converter_16bit_to_array("0000000000000000"),
-            &converter_16bit_to_array("1111111111111111"),
-            1, 1, 0, 1, 1, 1,
-        );
-        assert_eq!(converter_16bit_to_array("0000000000000000"), result.0);
-        assert_eq!(1, result.1);
-        assert_eq!(0, result.2);
-
-
-        let result = alu(
-            &converter_16bit_to_array("0000000000000000"),
-            &converter_16bit_to_array("1111111111111111"),
-            0, 0, 1, 1, 1, 0,
-        );
-        assert_eq!(converter_16bit_to_array("1111111111111111"), result.0);
-        assert_eq!(0, result.1);
-        assert_eq!(1, result.2);
-
-
-        let result = alu(
-            &converter_16bit_to_array("0000000000000000"),
-            &converter_16bit_to_array("1111111111111111"),
-            1, 1, 0, 0, 1, 0,
-        );
-        assert_eq!(converter_16bit_to_array("1111111111111110"), result.0);
-        assert_eq!(0, result.1);
-        assert_eq!(1, result.2);
-
-
-        let result = alu(
-            &converter_16bit_to_array("0000000000000000"),
-            &converter_16bit_to_array("1111111111111111"),
-            0, 0, 0, 0, 1, 0,
-        );
-        assert_eq!(converter_16bit_to_array("1111111111111111"), result.0);
-        assert_eq!(0, result.1);
-        assert_eq!(1, result.2);
-
-
-        let result = alu(
-            &converter_16bit_to_array("0000000000000000"),
-            &converter_16bit_to_array("1111111111111111"),
-            0, 1, 0, 0, 1, 1,
-        );
-        assert_eq!(converter_16bit_to_array("0000000000000001"), result.0);
-        assert_eq!(0, result.1);
-        assert_eq!(0, result.2);
-
-
-        let result = alu(
-            &converter_16bit_to_array("0000000000000000"),
-            &converter_16bit_to_array("1111111111111111"),
-            0, 0, 0, 1, 1, 1,
-        );
-        assert_eq!(converter_16bit_to_array("1111111111111111"), result.0);
-        assert_eq!(0, result.1);
-        assert_eq!(1, result.2);
-
-
-        let result = alu(
-            &converter_16bit_to_array("0000000000000000"),
-            &converter_16bit_to_array("1111111111111111"),
-            0, 0, 0, 0, 0, 0,
-        );
-        assert_eq!(converter_16bit_to_array("0000000000000000"), result.0);
-        assert_eq!(1, result.1);
-        assert_eq!(0, result.2);
-
-
-        let result = alu(
-            &converter_16bit_to_array("0000000000000000"),
-            &converter_16bit_to_array("1111111111111111"),
-            0, 1, 0, 1, 0, 1,
-        );
-        assert_eq!(converter_16bit_to_array("1111111111111111"), result.0);
-        assert_eq!(0, result.1);
-        assert_eq!(1, result.2);
-
-
-        let result = alu(
-            &converter_16bit_to_array("0000000000010001"),
-            &converter_16bit_to_array("0000000000000011"),
-            1, 0, 1, 0, 1, 0,
-        );
-        assert_eq!(converter_16bit_to_array("0000000000000000"), result.0);
-        assert_eq!(1, result.1);
-        assert_eq!(0, result.2);
-
-
-        let result = alu(
-            &converter_16bit_to_array("0000000000010001"),
-            &converter_16bit_to_array("0000000000000011"),
-            1, 1, 1, 1, 1, 1,
-        );
-        assert_eq!(converter_16bit_to_array("0000000000000001"), result.0);
-        assert_eq!(0, result.1);
-        assert_eq!(0, result.2);
-
-
-        let result = alu(
-            &converter_16bit_to_array("0000000000010001"),
-            &converter_16bit_to_array("0000000000000011"),
-            1, 1, 1, 0, 1, 0,
-        );
-        assert_eq!(converter_16bit_to_array("1111111111111111"), result.0);
-        assert_eq!(0, result.1);
-        assert_eq!(1, result.2);
-
-
-        let result = alu(
-            &converter_16bit_to_array("0000000000010001"),
-            &converter_16bit_to_array("0000000000000011"),
-            0, 0, 1, 1, 0, 0,
-        );
-        assert_eq!(converter_16bit_to_array("0000000000010001"), result.0);
-        assert_eq!(0, result.1);
-        assert_eq!(0, result.2);
-
-
-        let result = alu(
-            &converter_16bit_to_array("0000000000010001"),
-            &converter_16bit_to_array("0000000000000011"),
-            1, 1, 0, 0, 0, 0,
-        );
-        assert_eq!(converter_16bit_to_array("0000000000000011"), result.0);
-        assert_eq!(0, result.1);
-        assert_eq!(0, result.2);
-
-
-        let result = alu(
-            &converter_16bit_to_array("0000000000010001"),
-            &converter_16bit_to_array("0000000000000011"),
-            0, 0, 1, 1, 0, 1,
-        );
-        assert_eq!(converter_16bit_to_array("1111111111101110"), result.0);
-        assert_eq!(0, result.1);
-        assert_eq!(1, result.2);
-
-
-        let result = alu(
-            &converter_16bit_to_array("0000000000010001"),
-            &converter_16bit_to_array("0000000000000011"),
-            1, 1, 0, 0, 0, 1,
-        );
-        assert_eq!(converter_16bit_to_array("1111111111111100"), result.0);
-        assert_eq!(0, result.1);
-        assert_eq!(1, result.2);
-
-
-        let result = alu(
-            &converter_16bit_to_array("0000000000010001"),
-            &converter_16bit_to_array("0000000000000011"),
-            0, 0, 1, 1, 1, 1,
-        );
-        assert_eq!(converter_16bit_to_array("1111111111101111"), result.0);
-        assert_eq!(0, result.1);
-        assert_eq!(1, result.2);
-
-
-        let result = alu(
-            &converter_16bit_to_array("0000000000010001"),
-            &converter_16bit_to_array("0000000000000011"),
-            1, 1, 0, 0, 1, 1,
-        );
-        assert_eq!(converter_16bit_to_array("1111111111111101"), result.0);
-        assert_eq!(0, result.1);
-        assert_eq!(1, result.2);
-
-
-        let result = alu(
-            &converter_16bit_to_array("0000000000010001"),
-            &converter_16bit_to_array("0000000000000011"),
-            0, 1, 1, 1, 1, 1,
-        );
-        assert_eq!(converter_16bit_to_array("0000000000010010"), result.0);
-        assert_eq!(0, result.1);
-        assert_eq!(0, result.2);
-
-
-        let result = alu(
-            &converter_16bit_to_array("0000000000010001"),
-            &converter_16bit_to_array("0000000000000011"),
-            1, 1, 0, 1, 1, 1,
-        );
-        assert_eq!(converter_16bit_to_array("0000000000000100"), result.0);
-        assert_eq!(0, result.1);
-        assert_eq!(0, result.2);
-
-
-        let result = alu(
-            &converter_16bit_to_array("0000000000010001"),
-            &converter_16bit_to_array("0000000000000011"),
-            0, 0, 1, 1, 1, 0,
-        );
-        assert_eq!(converter_16bit_to_array("0000000000010000"), result.0);
-        assert_eq!(0, result.1);
-        assert_eq!(0, result.2);
-
-
-        let result = alu(
-            &converter_16bit_to_array("0000000000010001"),
-            &converter_16bit_to_array("0000000000000011"),
-            1, 1, 0, 0, 1, 0,
-        );
-        assert_eq!(converter_16bit_to_array("0000000000000010"), result.0);
-        assert_eq!(0, result.1);
-        assert_eq!(0, result.2);
-
-
-        let result = alu(
-            &converter_16bit_to_array("0000000000010001"),
-            &converter_16bit_to_array("0000000000000011"),
-            0, 0, 0, 0, 1, 0,
-        );
-        assert_eq!(converter_16bit_to_array("0000000000010100"), result.0);
-        assert_eq!(0, result.1);
-        assert_eq!(0, result.2);
-
-
-        let result = alu(
-            &converter_16bit_to_array("0000000000010001"),
-            &converter_16bit_to_array("0000000000000011"),
-            0, 1, 0, 0, 1, 1,
-        );
-        assert_eq!(converter_16bit_to_array("0000000000001110"), result.0);
-        assert_eq!(0, result.1);
-        assert_eq!(0, result.2);
-
-
-        let result = alu(
-            &converter_16bit_to_array("0000000000010001"),
-            &converter_16bit_to_array("0000000000000011"),
-            0, 0, 0, 1, 1, 1,
-        );
-        assert_eq!(converter_16bit_to_array("1111111111110010"), result.0);
-        assert_eq!(0, result.1);
-        assert_eq!(1, result.2);
-
-
-        let result = alu(
-            &converter_16bit_to_array("0000000000010001"),
-            &converter_16bit_to_array("0000000000000011"),
-            0, 0, 0, 0, 0, 0,
-        );
-        assert_eq!(converter_16bit_to_array("0000000000000001"), result.0);
-        assert_eq!(0, result.1);
-        assert_eq!(0, result.2);
-
-
-        let result = alu(
-            &converter_16bit_to_array("0000000000010001"),
-            &converter_16bit_to_array("0000000000000011"),
-            0, 1, 0, 1, 0, 1,
-        );
-        assert_eq!(converter_16bit_to_array("0000000000010011"), result.0);
-        assert_eq!(0, result.1);
-        assert_eq!(0, result.2);
+        for (x, y, [zx, nx, zy, ny, f, no], out, zr, ng) in ALU_VECTORS {
+            let result = alu(&Word16::from(*x), &Word16::from(*y), *zx, *nx, *zy, *ny, *f, *no);
+            assert_eq!(Word16::from(*out), result.0);
+            assert_eq!(*zr, result.1);
+            assert_eq!(*ng, result.2);
+        }
     }
 }
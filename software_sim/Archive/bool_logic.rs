@@ -1,36 +1,41 @@
-pub fn nand(x: u8, y: u8) -> u8 {
-    // 全ての基本
-    if x == 1 && y == 1 {
-        0
-    } else {
-        1
-    }
+use crate::signal::{Bus, Part, Signal, O};
+
+// ゲートは全て Signal について書いてある。普段は Bit を流すが、計測用の信号も流せる (instrument.rs)
+
+pub fn nand<S: Signal>(x: S, y: S) -> S {
+    // 全ての基本 (真理値表は signal.rs の Bit にある)
+    x.nand(y)
 }
 
-pub fn not(x: u8) -> u8 {
+pub fn not<S: Signal>(x: S) -> S {
+    let _part = Part::<S>::enter("Not");
     // Nandに同じ値をいれるとNotになるよ
     nand(x, x)
 }
 
-pub fn and(x: u8, y: u8) -> u8 {
+pub fn and<S: Signal>(x: S, y: S) -> S {
+    let _part = Part::<S>::enter("And");
     // Nandの結果をNotすればOk
     not(nand(x, y))
     // nand(nand(x, y), nand(x, y))
 }
 
-pub fn or(x: u8, y: u8) -> u8 {
+pub fn or<S: Signal>(x: S, y: S) -> S {
+    let _part = Part::<S>::enter("Or");
     // 入力した値をNotで入替えてNandに通せばOk
     nand(not(x), not(y))
     // nand(nand(x, x), nand(y, y))
 }
 
-pub fn nor(x: u8, y: u8) -> u8 {
+pub fn nor<S: Signal>(x: S, y: S) -> S {
+    let _part = Part::<S>::enter("Nor");
     // OrをNotすればOk
     not(or(x, y))
     // nand(nand(nand(x, x), nand(y, y)), nand(nand(x, x), nand(y, y)))
 }
 
-pub fn xor(x: u8, y: u8) -> u8 {
+pub fn xor<S: Signal>(x: S, y: S) -> S {
+    let _part = Part::<S>::enter("Xor");
     // むっず。。。これは調べながら。
     nand(
         nand(x, nand(x, y)),
@@ -38,7 +43,8 @@ pub fn xor(x: u8, y: u8) -> u8 {
     )
 }
 
-pub fn mux(x: u8, y: u8, sel: u8) -> u8 {
+pub fn mux<S: Signal>(x: S, y: S, sel: S) -> S {
+    let _part = Part::<S>::enter("Mux");
     // これもググってくれ。。。
     let selector = not(sel);
     let a = and(x, selector);
@@ -50,8 +56,9 @@ pub fn mux(x: u8, y: u8, sel: u8) -> u8 {
     // nand(nand(a, a), nand(b, b))
 }
 
-pub fn dmux(input: u8, sel: u8) -> [u8; 2] {
-    let mut result: [u8; 2] = [0; 2];
+pub fn dmux<S: Signal>(input: S, sel: S) -> [S; 2] {
+    let _part = Part::<S>::enter("DMux");
+    let mut result: [S; 2] = [S::constant(O); 2];
     result[0] = and(input, not(sel));
     result[1] = and(input, sel);
     // result[0] = nand(
@@ -65,326 +72,332 @@ pub fn dmux(input: u8, sel: u8) -> [u8; 2] {
     result
 }
 
-pub fn not_16bit(x_arr: &[u8; 16]) -> [u8; 16] {
-    let mut result: [u8; 16] = [0; 16];
-    for i in 0..16 {
+// ここから下のバス用のゲートは、幅 N の版を作って 16bit などはそれを呼ぶだけにしている
+
+pub fn not_nbit<const N: usize, S: Signal>(x_arr: &Bus<N, S>) -> Bus<N, S> {
+    let mut result: Bus<N, S> = Bus::constant(O);
+    for i in 0..N {
         result[i] = not(x_arr[i]);
     }
     result
 }
 
-pub fn and_16bit(x_arr: &[u8; 16], y_arr: &[u8; 16]) -> [u8; 16] {
-    let mut result: [u8; 16] = [0; 16];
-    for i in 0..16 {
+pub fn and_nbit<const N: usize, S: Signal>(x_arr: &Bus<N, S>, y_arr: &Bus<N, S>) -> Bus<N, S> {
+    let mut result: Bus<N, S> = Bus::constant(O);
+    for i in 0..N {
         result[i] = and(x_arr[i], y_arr[i]);
         // result[i] = nand(nand(x_arr[i], y_arr[i]), nand(x_arr[i], y_arr[i]));
     }
     result
 }
 
-pub fn or_8way(x_arr: &[u8; 8]) -> u8 {
-    let mut result: u8 = 0;
-    for x in 0..x_arr.len() {
-        result = or(result, x_arr[x]);
+pub fn or_nbit<const N: usize, S: Signal>(x_arr: &Bus<N, S>, y_arr: &Bus<N, S>) -> Bus<N, S> {
+    let mut result: Bus<N, S> = Bus::constant(O);
+    for i in 0..N {
+        result[i] = or(x_arr[i], y_arr[i]);
     }
     result
 }
 
-pub fn or_16bit(x_arr: &[u8; 16], y_arr: &[u8; 16]) -> [u8; 16] {
-    let mut result: [u8; 16] = [0; 16];
-    for i in 0..16 {
-        result[i] = or(x_arr[i], y_arr[i]);
+pub fn mux_nbit<const N: usize, S: Signal>(a_arr: &Bus<N, S>, b_arr: &Bus<N, S>, sel: S) -> Bus<N, S> {
+    let mut result: Bus<N, S> = Bus::constant(O);
+    for i in 0..N {
+        result[i] = mux(a_arr[i], b_arr[i], sel);
     }
     result
 }
 
-pub fn mux_4way_16bit(a_arr: &[u8; 16], b_arr: &[u8; 16], c_arr: &[u8; 16], d_arr: &[u8; 16], sel: &[u8; 2]) -> [u8; 16] {
-    let mut result: [u8; 16] = [0; 16];
-    let mut ab: [u8; 16] = [0; 16];
-    let mut cd: [u8; 16] = [0; 16];
-    for i in 0..16 {
-        ab[i] = mux(a_arr[i], b_arr[i], sel[1]);
-        cd[i] = mux(c_arr[i], d_arr[i], sel[1]);
-        result[i] = mux(ab[i], cd[i], sel[0]);
+pub fn or_nway<const N: usize, S: Signal>(x_arr: &[S; N]) -> S {
+    let mut result: S = S::constant(O);
+    for x in 0..x_arr.len() {
+        result = or(result, x_arr[x]);
     }
     result
 }
 
-pub fn mux_8way_16bit(
-    a_arr: &[u8; 16],
-    b_arr: &[u8; 16],
-    c_arr: &[u8; 16],
-    d_arr: &[u8; 16],
-    e_arr: &[u8; 16],
-    f_arr: &[u8; 16],
-    g_arr: &[u8; 16],
-    h_arr: &[u8; 16],
-    sel: &[u8; 3]) -> [u8; 16] {
-    let mut result: [u8; 16] = [0; 16];
-    let mut ab: [u8; 16] = [0; 16];
-    let mut cd: [u8; 16] = [0; 16];
-    let mut ef: [u8; 16] = [0; 16];
-    let mut gh: [u8; 16] = [0; 16];
-    let mut abcd: [u8; 16] = [0; 16];
-    let mut efgh: [u8; 16] = [0; 16];
-
-    for i in 0..16 {
-        ab[i] = mux(a_arr[i], b_arr[i], sel[2]);
-        cd[i] = mux(c_arr[i], d_arr[i], sel[2]);
-        ef[i] = mux(e_arr[i], f_arr[i], sel[2]);
-        gh[i] = mux(g_arr[i], h_arr[i], sel[2]);
-
-        abcd[i] = mux(ab[i], cd[i], sel[1]);
-        efgh[i] = mux(ef[i], gh[i], sel[1]);
-
-        result[i] = mux(abcd[i], efgh[i], sel[0]);
+// 2^k 本の入力から sel で 1 本選ぶ。sel は書いたとおりの順 (sel[0] が上の桁)
+// 2-way の mux を木にする: 最後の sel で隣どうしを選び、残りの sel で半分になったものを選ぶ
+pub fn mux_kway<const N: usize, S: Signal>(inputs: &[Bus<N, S>], sel: &[S]) -> Bus<N, S> {
+    assert_eq!(1 << sel.len(), inputs.len(), "mux_kway needs 2^sel inputs");
+    match sel.split_last() {
+        None => inputs[0],
+        Some((last, upper)) => {
+            let halves: Vec<Bus<N, S>> = inputs
+                .chunks(2)
+                .map(|pair| mux_nbit(&pair[0], &pair[1], *last))
+                .collect();
+            mux_kway(&halves, upper)
+        }
     }
-    result
 }
 
-pub fn mux_16bit(a_arr: &[u8; 16], b_arr: &[u8; 16], sel: u8) -> [u8; 16] {
-    let mut result: [u8; 16] = [0; 16];
-    for i in 0..16 {
-        result[i] = mux(a_arr[i], b_arr[i], sel);
+// input を sel の指す 2^k 本のうち 1 本に出す。sel の並びは mux_kway と同じ
+pub fn dmux_kway<S: Signal>(input: S, sel: &[S]) -> Vec<S> {
+    match sel.split_last() {
+        None => vec![input],
+        Some((last, upper)) => {
+            let [even, odd] = dmux(input, *last);
+            let even = dmux_kway(even, upper);
+            let odd = dmux_kway(odd, upper);
+            even.into_iter().zip(odd).flat_map(|(e, o)| vec![e, o]).collect()
+        }
     }
-    result
 }
 
-pub fn dmux_4way(input: u8, sel: &[u8; 2]) -> [u8; 4] {
-    let mut result: [u8; 4] = [0; 4];
-    let temp: [u8; 2] = dmux(input, sel[1]);
-    let ac: [u8; 2] = dmux(temp[0], sel[0]);
-    let bd: [u8; 2] = dmux(temp[1], sel[0]);
-    result[0] = ac[0];
-    result[1] = bd[0];
-    result[2] = ac[1];
-    result[3] = bd[1];
-    result
+pub fn not_16bit<S: Signal>(x_arr: &Bus<16, S>) -> Bus<16, S> {
+    let _part = Part::<S>::enter("Not16");
+    not_nbit(x_arr)
 }
 
-pub fn dmux_8way(input: u8, sel: &[u8; 3]) -> [u8; 8] {
-    let mut result: [u8; 8] = [0; 8];
-    let temp: [u8; 2] = dmux(input, sel[2]);
-    let aceg: [u8; 2] = dmux(temp[0], sel[1]);
-    let bdfh: [u8; 2] = dmux(temp[1], sel[1]);
-    let ae: [u8; 2] = dmux(aceg[0], sel[0]);
-    let bf: [u8; 2] = dmux(bdfh[0], sel[0]);
-    let cg: [u8; 2] = dmux(aceg[1], sel[0]);
-    let dh: [u8; 2] = dmux(bdfh[1], sel[0]);
-
-    result[0] = ae[0];
-    result[1] = bf[0];
-    result[2] = cg[0];
-    result[3] = dh[0];
-    result[4] = ae[1];
-    result[5] = bf[1];
-    result[6] = cg[1];
-    result[7] = dh[1];
-    result
+pub fn and_16bit<S: Signal>(x_arr: &Bus<16, S>, y_arr: &Bus<16, S>) -> Bus<16, S> {
+    let _part = Part::<S>::enter("And16");
+    and_nbit(x_arr, y_arr)
+}
+
+pub fn or_8way<S: Signal>(x_arr: &[S; 8]) -> S {
+    let _part = Part::<S>::enter("Or8Way");
+    or_nway(x_arr)
+}
+
+pub fn or_16bit<S: Signal>(x_arr: &Bus<16, S>, y_arr: &Bus<16, S>) -> Bus<16, S> {
+    let _part = Part::<S>::enter("Or16");
+    or_nbit(x_arr, y_arr)
+}
+
+// sel は書いたとおりの順 (sel[0] が上の桁)。[O, I] なら b を選ぶ
+pub fn mux_4way_16bit<S: Signal>(
+    a_arr: &Bus<16, S>,
+    b_arr: &Bus<16, S>,
+    c_arr: &Bus<16, S>,
+    d_arr: &Bus<16, S>,
+    sel: &[S; 2]) -> Bus<16, S> {
+    let _part = Part::<S>::enter("Mux4Way16");
+    mux_kway(&[*a_arr, *b_arr, *c_arr, *d_arr], sel)
+}
+
+pub fn mux_8way_16bit<S: Signal>(
+    a_arr: &Bus<16, S>,
+    b_arr: &Bus<16, S>,
+    c_arr: &Bus<16, S>,
+    d_arr: &Bus<16, S>,
+    e_arr: &Bus<16, S>,
+    f_arr: &Bus<16, S>,
+    g_arr: &Bus<16, S>,
+    h_arr: &Bus<16, S>,
+    sel: &[S; 3]) -> Bus<16, S> {
+    let _part = Part::<S>::enter("Mux8Way16");
+    mux_kway(&[*a_arr, *b_arr, *c_arr, *d_arr, *e_arr, *f_arr, *g_arr, *h_arr], sel)
+}
+
+pub fn mux_16bit<S: Signal>(a_arr: &Bus<16, S>, b_arr: &Bus<16, S>, sel: S) -> Bus<16, S> {
+    let _part = Part::<S>::enter("Mux16");
+    mux_nbit(a_arr, b_arr, sel)
+}
+
+pub fn dmux_4way<S: Signal>(input: S, sel: &[S; 2]) -> [S; 4] {
+    let _part = Part::<S>::enter("DMux4Way");
+    let result = dmux_kway(input, sel);
+    [result[0], result[1], result[2], result[3]]
+}
+
+pub fn dmux_8way<S: Signal>(input: S, sel: &[S; 3]) -> [S; 8] {
+    let _part = Part::<S>::enter("DMux8Way");
+    let result = dmux_kway(input, sel);
+    [result[0], result[1], result[2], result[3], result[4], result[5], result[6], result[7]]
 }
 
 #[cfg(test)]
 mod test {
-    use std::convert::{TryFrom};
     use super::*;
-
-    fn converter_16bit_to_array(input: &str) -> [u8; 16] {
-        let mut output: [u8; 16] = [0; 16];
-        for i in 0..input.len() {
-            output[i] = u8::try_from(
-                input.chars().nth(i).unwrap().to_digit(2).unwrap()
-            ).unwrap();
-        }
-        output
-    }
+    use crate::signal::{I, Word16};
 
     #[test]
     fn nand_test() {
-        assert_eq!(0, nand(1,  1));
-        assert_eq!(1, nand(1,  0));
-        assert_eq!(1, nand(0, 1));
-        assert_eq!(1, nand(0, 0));
+        assert_eq!(O, nand(I,  I));
+        assert_eq!(I, nand(I,  O));
+        assert_eq!(I, nand(O, I));
+        assert_eq!(I, nand(O, O));
     }
 
     #[test]
     fn not_test() {
-        assert_eq!(0, not(1));
-        assert_eq!(1, not(0));
+        assert_eq!(O, not(I));
+        assert_eq!(I, not(O));
     }
 
     #[test]
     fn and_test() {
-        assert_eq!(1, and(1,  1));
-        assert_eq!(0, and(1,  0));
-        assert_eq!(0, and(0, 1));
-        assert_eq!(0, and(0, 0));
+        assert_eq!(I, and(I,  I));
+        assert_eq!(O, and(I,  O));
+        assert_eq!(O, and(O, I));
+        assert_eq!(O, and(O, O));
     }
 
     #[test]
     fn or_test() {
-        assert_eq!(1, or(1, 1));
-        assert_eq!(1, or(1, 0));
-        assert_eq!(1, or(0, 1));
-        assert_eq!(0, or(0, 0));
+        assert_eq!(I, or(I, I));
+        assert_eq!(I, or(I, O));
+        assert_eq!(I, or(O, I));
+        assert_eq!(O, or(O, O));
     }
 
     #[test]
     fn nor_test() {
-        assert_eq!(0, nor(1, 1));
-        assert_eq!(0, nor(1, 0));
-        assert_eq!(0, nor(0, 1));
-        assert_eq!(1, nor(0, 0));
+        assert_eq!(O, nor(I, I));
+        assert_eq!(O, nor(I, O));
+        assert_eq!(O, nor(O, I));
+        assert_eq!(I, nor(O, O));
     }
 
     #[test]
     fn xor_test() {
-        assert_eq!(0, xor(1, 1));
-        assert_eq!(1, xor(1, 0));
-        assert_eq!(1, xor(0, 1));
-        assert_eq!(0, xor(0, 0));
+        assert_eq!(O, xor(I, I));
+        assert_eq!(I, xor(I, O));
+        assert_eq!(I, xor(O, I));
+        assert_eq!(O, xor(O, O));
     }
 
     #[test]
     fn mux_test() {
-        assert_eq!(0, mux(0, 0, 0));
-        assert_eq!(0, mux(0, 1, 0));
-        assert_eq!(1, mux(1, 0, 0));
-        assert_eq!(1, mux(1, 1, 0));
-        assert_eq!(0, mux(0, 0, 1));
-        assert_eq!(1, mux(0, 1, 1));
-        assert_eq!(0, mux(1, 0, 1));
-        assert_eq!(1, mux(1, 1, 1));
+        assert_eq!(O, mux(O, O, O));
+        assert_eq!(O, mux(O, I, O));
+        assert_eq!(I, mux(I, O, O));
+        assert_eq!(I, mux(I, I, O));
+        assert_eq!(O, mux(O, O, I));
+        assert_eq!(I, mux(O, I, I));
+        assert_eq!(O, mux(I, O, I));
+        assert_eq!(I, mux(I, I, I));
     }
 
     #[test]
     fn dmux_test() {
-        assert_eq!([0, 0], dmux(0, 0));
-        assert_eq!([0, 0], dmux(0, 1));
-        assert_eq!([1, 0], dmux(1, 0));
-        assert_eq!([0, 1], dmux(1, 1));
+        assert_eq!([O, O], dmux(O, O));
+        assert_eq!([O, O], dmux(O, I));
+        assert_eq!([I, O], dmux(I, O));
+        assert_eq!([O, I], dmux(I, I));
     }
 
     #[test]
     fn not_16bit_test() {
         assert_eq!(
-            converter_16bit_to_array("1111111111111111"),
-            not_16bit(&converter_16bit_to_array("0000000000000000"))
+            Word16::from(0b1111111111111111u16),
+            not_16bit(&Word16::from(0b0000000000000000u16))
         );
         assert_eq!(
-            converter_16bit_to_array("0000000000000000"),
-            not_16bit(&converter_16bit_to_array("1111111111111111"))
+            Word16::from(0b0000000000000000u16),
+            not_16bit(&Word16::from(0b1111111111111111u16))
         );
         assert_eq!(
-            converter_16bit_to_array("0101010101010101"),
-            not_16bit(&converter_16bit_to_array("1010101010101010"))
+            Word16::from(0b0101010101010101u16),
+            not_16bit(&Word16::from(0b1010101010101010u16))
         );
         assert_eq!(
-            converter_16bit_to_array("1100001100111100"),
-            not_16bit(&converter_16bit_to_array("0011110011000011"))
+            Word16::from(0b1100001100111100u16),
+            not_16bit(&Word16::from(0b0011110011000011u16))
         );
         assert_eq!(
-            converter_16bit_to_array("1110110111001011"),
-            not_16bit(&converter_16bit_to_array("0001001000110100"))
+            Word16::from(0b1110110111001011u16),
+            not_16bit(&Word16::from(0b0001001000110100u16))
         );
     }
 
     #[test]
     fn and_16bit_test() {
         assert_eq!(
-            converter_16bit_to_array("0000000000000000"),
+            Word16::from(0b0000000000000000u16),
             and_16bit(
-            &converter_16bit_to_array("0000000000000000"),
-            &converter_16bit_to_array("0000000000000000")
+            &Word16::from(0b0000000000000000u16),
+            &Word16::from(0b0000000000000000u16)
             )
         );
         assert_eq!(
-            converter_16bit_to_array("0000000000000000"),
+            Word16::from(0b0000000000000000u16),
             and_16bit(
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("1111111111111111")
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b1111111111111111u16)
             )
         );
         assert_eq!(
-            converter_16bit_to_array("1111111111111111"),
+            Word16::from(0b1111111111111111u16),
             and_16bit(
-                &converter_16bit_to_array("1111111111111111"),
-                &converter_16bit_to_array("1111111111111111")
+                &Word16::from(0b1111111111111111u16),
+                &Word16::from(0b1111111111111111u16)
             )
         );
         assert_eq!(
-            converter_16bit_to_array("0000000000000000"),
+            Word16::from(0b0000000000000000u16),
             and_16bit(
-                &converter_16bit_to_array("1010101010101010"),
-                &converter_16bit_to_array("0101010101010101")
+                &Word16::from(0b1010101010101010u16),
+                &Word16::from(0b0101010101010101u16)
             )
         );
         assert_eq!(
-            converter_16bit_to_array("0000110011000000"),
+            Word16::from(0b0000110011000000u16),
             and_16bit(
-                &converter_16bit_to_array("0011110011000011"),
-                &converter_16bit_to_array("0000111111110000")
+                &Word16::from(0b0011110011000011u16),
+                &Word16::from(0b0000111111110000u16)
             )
         );
         assert_eq!(
-            converter_16bit_to_array("0001000000110100"),
+            Word16::from(0b0001000000110100u16),
             and_16bit(
-                &converter_16bit_to_array("0001001000110100"),
-                &converter_16bit_to_array("1001100001110110")
+                &Word16::from(0b0001001000110100u16),
+                &Word16::from(0b1001100001110110u16)
             )
         );
     }
 
     #[test]
     fn or_8way_test() {
-        assert_eq!(0, or_8way(&[0, 0, 0, 0, 0, 0, 0, 0]));
-        assert_eq!(1, or_8way(&[1, 1, 1, 1, 1, 1, 1, 1]));
-        assert_eq!(1, or_8way(&[0, 0, 0, 1, 0, 0, 0, 0]));
-        assert_eq!(1, or_8way(&[0, 0, 0, 0, 0, 0, 0, 1]));
-        assert_eq!(1, or_8way(&[0, 0, 1, 0, 0, 1, 1, 0]));
+        assert_eq!(O, or_8way(&[O, O, O, O, O, O, O, O]));
+        assert_eq!(I, or_8way(&[I, I, I, I, I, I, I, I]));
+        assert_eq!(I, or_8way(&[O, O, O, I, O, O, O, O]));
+        assert_eq!(I, or_8way(&[O, O, O, O, O, O, O, I]));
+        assert_eq!(I, or_8way(&[O, O, I, O, O, I, I, O]));
     }
 
     #[test]
     fn or_16bit_test() {
         assert_eq!(
-            converter_16bit_to_array("0000000000000000"),
+            Word16::from(0b0000000000000000u16),
             or_16bit(
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000")
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16)
             )
         );
         assert_eq!(
-            converter_16bit_to_array("1111111111111111"),
+            Word16::from(0b1111111111111111u16),
             or_16bit(
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("1111111111111111")
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b1111111111111111u16)
             )
         );
         assert_eq!(
-            converter_16bit_to_array("1111111111111111"),
+            Word16::from(0b1111111111111111u16),
             or_16bit(
-                &converter_16bit_to_array("1111111111111111"),
-                &converter_16bit_to_array("1111111111111111")
+                &Word16::from(0b1111111111111111u16),
+                &Word16::from(0b1111111111111111u16)
             )
         );
         assert_eq!(
-            converter_16bit_to_array("1111111111111111"),
+            Word16::from(0b1111111111111111u16),
             or_16bit(
-                &converter_16bit_to_array("1010101010101010"),
-                &converter_16bit_to_array("0101010101010101")
+                &Word16::from(0b1010101010101010u16),
+                &Word16::from(0b0101010101010101u16)
             )
         );
         assert_eq!(
-            converter_16bit_to_array("0011111111110011"),
+            Word16::from(0b0011111111110011u16),
             or_16bit(
-                &converter_16bit_to_array("0011110011000011"),
-                &converter_16bit_to_array("0000111111110000")
+                &Word16::from(0b0011110011000011u16),
+                &Word16::from(0b0000111111110000u16)
             )
         );
         assert_eq!(
-            converter_16bit_to_array("1001101001110110"),
+            Word16::from(0b1001101001110110u16),
             or_16bit(
-                &converter_16bit_to_array("0001001000110100"),
-                &converter_16bit_to_array("1001100001110110")
+                &Word16::from(0b0001001000110100u16),
+                &Word16::from(0b1001100001110110u16)
             )
         );
     }
@@ -392,83 +405,83 @@ mod test {
     #[test]
     fn mux_4way_16bit_test() {
         assert_eq!(
-            converter_16bit_to_array("0000000000000000"),
+            Word16::from(0b0000000000000000u16),
             mux_4way_16bit(
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &[0, 0]
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &[O, O]
             )
         );
         assert_eq!(
-            converter_16bit_to_array("0000000000000000"),
+            Word16::from(0b0000000000000000u16),
             mux_4way_16bit(
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &[0, 1]
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &[O, I]
             )
         );
         assert_eq!(
-            converter_16bit_to_array("0000000000000000"),
+            Word16::from(0b0000000000000000u16),
             mux_4way_16bit(
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &[1, 0]
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &[I, O]
             )
         );
         assert_eq!(
-            converter_16bit_to_array("0000000000000000"),
+            Word16::from(0b0000000000000000u16),
             mux_4way_16bit(
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &[1, 1]
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &[I, I]
             )
         );
         assert_eq!(
-            converter_16bit_to_array("0001001000110100"),
+            Word16::from(0b0001001000110100u16),
             mux_4way_16bit(
-                &converter_16bit_to_array("0001001000110100"),
-                &converter_16bit_to_array("1001100001110110"),
-                &converter_16bit_to_array("1010101010101010"),
-                &converter_16bit_to_array("0101010101010101"),
-                &[0, 0]
+                &Word16::from(0b0001001000110100u16),
+                &Word16::from(0b1001100001110110u16),
+                &Word16::from(0b1010101010101010u16),
+                &Word16::from(0b0101010101010101u16),
+                &[O, O]
             )
         );
         assert_eq!(
-            converter_16bit_to_array("1001100001110110"),
+            Word16::from(0b1001100001110110u16),
             mux_4way_16bit(
-                &converter_16bit_to_array("0001001000110100"),
-                &converter_16bit_to_array("1001100001110110"),
-                &converter_16bit_to_array("1010101010101010"),
-                &converter_16bit_to_array("0101010101010101"),
-                &[0, 1]
+                &Word16::from(0b0001001000110100u16),
+                &Word16::from(0b1001100001110110u16),
+                &Word16::from(0b1010101010101010u16),
+                &Word16::from(0b0101010101010101u16),
+                &[O, I]
             )
         );
         assert_eq!(
-            converter_16bit_to_array("1010101010101010"),
+            Word16::from(0b1010101010101010u16),
             mux_4way_16bit(
-                &converter_16bit_to_array("0001001000110100"),
-                &converter_16bit_to_array("1001100001110110"),
-                &converter_16bit_to_array("1010101010101010"),
-                &converter_16bit_to_array("0101010101010101"),
-                &[1, 0]
+                &Word16::from(0b0001001000110100u16),
+                &Word16::from(0b1001100001110110u16),
+                &Word16::from(0b1010101010101010u16),
+                &Word16::from(0b0101010101010101u16),
+                &[I, O]
             )
         );
         assert_eq!(
-            converter_16bit_to_array("0101010101010101"),
+            Word16::from(0b0101010101010101u16),
             mux_4way_16bit(
-                &converter_16bit_to_array("0001001000110100"),
-                &converter_16bit_to_array("1001100001110110"),
-                &converter_16bit_to_array("1010101010101010"),
-                &converter_16bit_to_array("0101010101010101"),
-                &[1, 1]
+                &Word16::from(0b0001001000110100u16),
+                &Word16::from(0b1001100001110110u16),
+                &Word16::from(0b1010101010101010u16),
+                &Word16::from(0b0101010101010101u16),
+                &[I, I]
             )
         );
     }
@@ -476,229 +489,229 @@ mod test {
     #[test]
     fn mux_8way_16bit_test() {
         assert_eq!(
-            converter_16bit_to_array("0000000000000000"),
+            Word16::from(0b0000000000000000u16),
             mux_8way_16bit(
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &[0, 0, 0]
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &[O, O, O]
             )
         );
         assert_eq!(
-            converter_16bit_to_array("0000000000000000"),
+            Word16::from(0b0000000000000000u16),
             mux_8way_16bit(
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &[0, 0, 1]
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &[O, O, I]
             )
         );
         assert_eq!(
-            converter_16bit_to_array("0000000000000000"),
+            Word16::from(0b0000000000000000u16),
             mux_8way_16bit(
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &[0, 1, 0]
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &[O, I, O]
             )
         );
         assert_eq!(
-            converter_16bit_to_array("0000000000000000"),
+            Word16::from(0b0000000000000000u16),
             mux_8way_16bit(
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &[0, 1, 1]
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &[O, I, I]
             )
         );
         assert_eq!(
-            converter_16bit_to_array("0000000000000000"),
+            Word16::from(0b0000000000000000u16),
             mux_8way_16bit(
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &[1, 0, 0]
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &[I, O, O]
             )
         );
         assert_eq!(
-            converter_16bit_to_array("0000000000000000"),
+            Word16::from(0b0000000000000000u16),
             mux_8way_16bit(
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &[1, 0, 1]
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &[I, O, I]
             )
         );
         assert_eq!(
-            converter_16bit_to_array("0000000000000000"),
+            Word16::from(0b0000000000000000u16),
             mux_8way_16bit(
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &[1, 1, 0]
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &[I, I, O]
             )
         );
         assert_eq!(
-            converter_16bit_to_array("0000000000000000"),
+            Word16::from(0b0000000000000000u16),
             mux_8way_16bit(
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                &[1, 1, 1]
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                &[I, I, I]
             )
         );
 
 
         assert_eq!(
-            converter_16bit_to_array("0001001000110100"),
+            Word16::from(0b0001001000110100u16),
             mux_8way_16bit(
-                &converter_16bit_to_array("0001001000110100"),
-                &converter_16bit_to_array("0010001101000101"),
-                &converter_16bit_to_array("0011010001010110"),
-                &converter_16bit_to_array("0100010101100111"),
-                &converter_16bit_to_array("0101011001111000"),
-                &converter_16bit_to_array("0110011110001001"),
-                &converter_16bit_to_array("0111100010011010"),
-                &converter_16bit_to_array("1000100110101011"),
-                &[0, 0, 0]
+                &Word16::from(0b0001001000110100u16),
+                &Word16::from(0b0010001101000101u16),
+                &Word16::from(0b0011010001010110u16),
+                &Word16::from(0b0100010101100111u16),
+                &Word16::from(0b0101011001111000u16),
+                &Word16::from(0b0110011110001001u16),
+                &Word16::from(0b0111100010011010u16),
+                &Word16::from(0b1000100110101011u16),
+                &[O, O, O]
             )
         );
         assert_eq!(
-            converter_16bit_to_array("0010001101000101"),
+            Word16::from(0b0010001101000101u16),
             mux_8way_16bit(
-                &converter_16bit_to_array("0001001000110100"),
-                &converter_16bit_to_array("0010001101000101"),
-                &converter_16bit_to_array("0011010001010110"),
-                &converter_16bit_to_array("0100010101100111"),
-                &converter_16bit_to_array("0101011001111000"),
-                &converter_16bit_to_array("0110011110001001"),
-                &converter_16bit_to_array("0111100010011010"),
-                &converter_16bit_to_array("1000100110101011"),
-                &[0, 0, 1]
+                &Word16::from(0b0001001000110100u16),
+                &Word16::from(0b0010001101000101u16),
+                &Word16::from(0b0011010001010110u16),
+                &Word16::from(0b0100010101100111u16),
+                &Word16::from(0b0101011001111000u16),
+                &Word16::from(0b0110011110001001u16),
+                &Word16::from(0b0111100010011010u16),
+                &Word16::from(0b1000100110101011u16),
+                &[O, O, I]
             )
         );
         assert_eq!(
-            converter_16bit_to_array("0011010001010110"),
+            Word16::from(0b0011010001010110u16),
             mux_8way_16bit(
-                &converter_16bit_to_array("0001001000110100"),
-                &converter_16bit_to_array("0010001101000101"),
-                &converter_16bit_to_array("0011010001010110"),
-                &converter_16bit_to_array("0100010101100111"),
-                &converter_16bit_to_array("0101011001111000"),
-                &converter_16bit_to_array("0110011110001001"),
-                &converter_16bit_to_array("0111100010011010"),
-                &converter_16bit_to_array("1000100110101011"),
-                &[0, 1, 0]
+                &Word16::from(0b0001001000110100u16),
+                &Word16::from(0b0010001101000101u16),
+                &Word16::from(0b0011010001010110u16),
+                &Word16::from(0b0100010101100111u16),
+                &Word16::from(0b0101011001111000u16),
+                &Word16::from(0b0110011110001001u16),
+                &Word16::from(0b0111100010011010u16),
+                &Word16::from(0b1000100110101011u16),
+                &[O, I, O]
             )
         );
         assert_eq!(
-            converter_16bit_to_array("0100010101100111"),
+            Word16::from(0b0100010101100111u16),
             mux_8way_16bit(
-                &converter_16bit_to_array("0001001000110100"),
-                &converter_16bit_to_array("0010001101000101"),
-                &converter_16bit_to_array("0011010001010110"),
-                &converter_16bit_to_array("0100010101100111"),
-                &converter_16bit_to_array("0101011001111000"),
-                &converter_16bit_to_array("0110011110001001"),
-                &converter_16bit_to_array("0111100010011010"),
-                &converter_16bit_to_array("1000100110101011"),
-                &[0, 1, 1]
+                &Word16::from(0b0001001000110100u16),
+                &Word16::from(0b0010001101000101u16),
+                &Word16::from(0b0011010001010110u16),
+                &Word16::from(0b0100010101100111u16),
+                &Word16::from(0b0101011001111000u16),
+                &Word16::from(0b0110011110001001u16),
+                &Word16::from(0b0111100010011010u16),
+                &Word16::from(0b1000100110101011u16),
+                &[O, I, I]
             )
         );
         assert_eq!(
-            converter_16bit_to_array("0101011001111000"),
+            Word16::from(0b0101011001111000u16),
             mux_8way_16bit(
-                &converter_16bit_to_array("0001001000110100"),
-                &converter_16bit_to_array("0010001101000101"),
-                &converter_16bit_to_array("0011010001010110"),
-                &converter_16bit_to_array("0100010101100111"),
-                &converter_16bit_to_array("0101011001111000"),
-                &converter_16bit_to_array("0110011110001001"),
-                &converter_16bit_to_array("0111100010011010"),
-                &converter_16bit_to_array("1000100110101011"),
-                &[1, 0, 0]
+                &Word16::from(0b0001001000110100u16),
+                &Word16::from(0b0010001101000101u16),
+                &Word16::from(0b0011010001010110u16),
+                &Word16::from(0b0100010101100111u16),
+                &Word16::from(0b0101011001111000u16),
+                &Word16::from(0b0110011110001001u16),
+                &Word16::from(0b0111100010011010u16),
+                &Word16::from(0b1000100110101011u16),
+                &[I, O, O]
             )
         );
         assert_eq!(
-            converter_16bit_to_array("0110011110001001"),
+            Word16::from(0b0110011110001001u16),
             mux_8way_16bit(
-                &converter_16bit_to_array("0001001000110100"),
-                &converter_16bit_to_array("0010001101000101"),
-                &converter_16bit_to_array("0011010001010110"),
-                &converter_16bit_to_array("0100010101100111"),
-                &converter_16bit_to_array("0101011001111000"),
-                &converter_16bit_to_array("0110011110001001"),
-                &converter_16bit_to_array("0111100010011010"),
-                &converter_16bit_to_array("1000100110101011"),
-                &[1, 0, 1]
+                &Word16::from(0b0001001000110100u16),
+                &Word16::from(0b0010001101000101u16),
+                &Word16::from(0b0011010001010110u16),
+                &Word16::from(0b0100010101100111u16),
+                &Word16::from(0b0101011001111000u16),
+                &Word16::from(0b0110011110001001u16),
+                &Word16::from(0b0111100010011010u16),
+                &Word16::from(0b1000100110101011u16),
+                &[I, O, I]
             )
         );
         assert_eq!(
-            converter_16bit_to_array("0111100010011010"),
+            Word16::from(0b0111100010011010u16),
             mux_8way_16bit(
-                &converter_16bit_to_array("0001001000110100"),
-                &converter_16bit_to_array("0010001101000101"),
-                &converter_16bit_to_array("0011010001010110"),
-                &converter_16bit_to_array("0100010101100111"),
-                &converter_16bit_to_array("0101011001111000"),
-                &converter_16bit_to_array("0110011110001001"),
-                &converter_16bit_to_array("0111100010011010"),
-                &converter_16bit_to_array("1000100110101011"),
-                &[1, 1, 0]
+                &Word16::from(0b0001001000110100u16),
+                &Word16::from(0b0010001101000101u16),
+                &Word16::from(0b0011010001010110u16),
+                &Word16::from(0b0100010101100111u16),
+                &Word16::from(0b0101011001111000u16),
+                &Word16::from(0b0110011110001001u16),
+                &Word16::from(0b0111100010011010u16),
+                &Word16::from(0b1000100110101011u16),
+                &[I, I, O]
             )
         );
         assert_eq!(
-            converter_16bit_to_array("1000100110101011"),
+            Word16::from(0b1000100110101011u16),
             mux_8way_16bit(
-                &converter_16bit_to_array("0001001000110100"),
-                &converter_16bit_to_array("0010001101000101"),
-                &converter_16bit_to_array("0011010001010110"),
-                &converter_16bit_to_array("0100010101100111"),
-                &converter_16bit_to_array("0101011001111000"),
-                &converter_16bit_to_array("0110011110001001"),
-                &converter_16bit_to_array("0111100010011010"),
-                &converter_16bit_to_array("1000100110101011"),
-                &[1, 1, 1]
+                &Word16::from(0b0001001000110100u16),
+                &Word16::from(0b0010001101000101u16),
+                &Word16::from(0b0011010001010110u16),
+                &Word16::from(0b0100010101100111u16),
+                &Word16::from(0b0101011001111000u16),
+                &Word16::from(0b0110011110001001u16),
+                &Word16::from(0b0111100010011010u16),
+                &Word16::from(0b1000100110101011u16),
+                &[I, I, I]
             )
         );
     }
@@ -706,100 +719,127 @@ mod test {
     #[test]
     fn mux_16bit_test() {
         assert_eq!(
-            converter_16bit_to_array("0000000000000000"),
+            Word16::from(0b0000000000000000u16),
             mux_16bit(
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                0
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                O
             )
         );
         assert_eq!(
-            converter_16bit_to_array("0000000000000000"),
+            Word16::from(0b0000000000000000u16),
             mux_16bit(
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0000000000000000"),
-                1
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0000000000000000u16),
+                I
             )
         );
         assert_eq!(
-            converter_16bit_to_array("0000000000000000"),
+            Word16::from(0b0000000000000000u16),
             mux_16bit(
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0001001000110100"),
-                0
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0001001000110100u16),
+                O
             )
         );
         assert_eq!(
-            converter_16bit_to_array("0001001000110100"),
+            Word16::from(0b0001001000110100u16),
             mux_16bit(
-                &converter_16bit_to_array("0000000000000000"),
-                &converter_16bit_to_array("0001001000110100"),
-                1
+                &Word16::from(0b0000000000000000u16),
+                &Word16::from(0b0001001000110100u16),
+                I
             )
         );
         assert_eq!(
-            converter_16bit_to_array("1001100001110110"),
+            Word16::from(0b1001100001110110u16),
             mux_16bit(
-                &converter_16bit_to_array("1001100001110110"),
-                &converter_16bit_to_array("0000000000000000"),
-                0
+                &Word16::from(0b1001100001110110u16),
+                &Word16::from(0b0000000000000000u16),
+                O
             )
         );
         assert_eq!(
-            converter_16bit_to_array("0000000000000000"),
+            Word16::from(0b0000000000000000u16),
             mux_16bit(
-                &converter_16bit_to_array("1001100001110110"),
-                &converter_16bit_to_array("0000000000000000"),
-                1
+                &Word16::from(0b1001100001110110u16),
+                &Word16::from(0b0000000000000000u16),
+                I
             )
         );
         assert_eq!(
-            converter_16bit_to_array("1010101010101010"),
+            Word16::from(0b1010101010101010u16),
             mux_16bit(
-                &converter_16bit_to_array("1010101010101010"),
-                &converter_16bit_to_array("0101010101010101"),
-                0
+                &Word16::from(0b1010101010101010u16),
+                &Word16::from(0b0101010101010101u16),
+                O
             )
         );
         assert_eq!(
-            converter_16bit_to_array("0101010101010101"),
+            Word16::from(0b0101010101010101u16),
             mux_16bit(
-                &converter_16bit_to_array("1010101010101010"),
-                &converter_16bit_to_array("0101010101010101"),
-                1
+                &Word16::from(0b1010101010101010u16),
+                &Word16::from(0b0101010101010101u16),
+                I
             )
         );
     }
 
     #[test]
     fn dmux_4way_test() {
-        assert_eq!([0, 0, 0, 0], dmux_4way(0, &[0, 0]));
-        assert_eq!([0, 0, 0, 0], dmux_4way(0, &[0, 1]));
-        assert_eq!([0, 0, 0, 0], dmux_4way(0, &[1, 0]));
-        assert_eq!([0, 0, 0, 0], dmux_4way(0, &[1, 1]));
-        assert_eq!([1, 0, 0, 0], dmux_4way(1, &[0, 0]));
-        assert_eq!([0, 1, 0, 0], dmux_4way(1, &[0, 1]));
-        assert_eq!([0, 0, 1, 0], dmux_4way(1, &[1, 0]));
-        assert_eq!([0, 0, 0, 1], dmux_4way(1, &[1, 1]));
+        assert_eq!([O, O, O, O], dmux_4way(O, &[O, O]));
+        assert_eq!([O, O, O, O], dmux_4way(O, &[O, I]));
+        assert_eq!([O, O, O, O], dmux_4way(O, &[I, O]));
+        assert_eq!([O, O, O, O], dmux_4way(O, &[I, I]));
+        assert_eq!([I, O, O, O], dmux_4way(I, &[O, O]));
+        assert_eq!([O, I, O, O], dmux_4way(I, &[O, I]));
+        assert_eq!([O, O, I, O], dmux_4way(I, &[I, O]));
+        assert_eq!([O, O, O, I], dmux_4way(I, &[I, I]));
     }
 
     #[test]
     fn dmux_8way_test() {
-        assert_eq!([0, 0, 0, 0, 0, 0, 0, 0], dmux_8way(0, &[0, 0, 0]));
-        assert_eq!([0, 0, 0, 0, 0, 0, 0, 0], dmux_8way(0, &[0, 0, 1]));
-        assert_eq!([0, 0, 0, 0, 0, 0, 0, 0], dmux_8way(0, &[0, 1, 0]));
-        assert_eq!([0, 0, 0, 0, 0, 0, 0, 0], dmux_8way(0, &[0, 1, 1]));
-        assert_eq!([0, 0, 0, 0, 0, 0, 0, 0], dmux_8way(0, &[1, 0, 0]));
-        assert_eq!([0, 0, 0, 0, 0, 0, 0, 0], dmux_8way(0, &[1, 0, 1]));
-        assert_eq!([0, 0, 0, 0, 0, 0, 0, 0], dmux_8way(0, &[1, 1, 0]));
-        assert_eq!([0, 0, 0, 0, 0, 0, 0, 0], dmux_8way(0, &[1, 1, 1]));
-        assert_eq!([1, 0, 0, 0, 0, 0, 0, 0], dmux_8way(1, &[0, 0, 0]));
-        assert_eq!([0, 1, 0, 0, 0, 0, 0, 0], dmux_8way(1, &[0, 0, 1]));
-        assert_eq!([0, 0, 1, 0, 0, 0, 0, 0], dmux_8way(1, &[0, 1, 0]));
-        assert_eq!([0, 0, 0, 1, 0, 0, 0, 0], dmux_8way(1, &[0, 1, 1]));
-        assert_eq!([0, 0, 0, 0, 1, 0, 0, 0], dmux_8way(1, &[1, 0, 0]));
-        assert_eq!([0, 0, 0, 0, 0, 1, 0, 0], dmux_8way(1, &[1, 0, 1]));
-        assert_eq!([0, 0, 0, 0, 0, 0, 1, 0], dmux_8way(1, &[1, 1, 0]));
-        assert_eq!([0, 0, 0, 0, 0, 0, 0, 1], dmux_8way(1, &[1, 1, 1]));
+        assert_eq!([O, O, O, O, O, O, O, O], dmux_8way(O, &[O, O, O]));
+        assert_eq!([O, O, O, O, O, O, O, O], dmux_8way(O, &[O, O, I]));
+        assert_eq!([O, O, O, O, O, O, O, O], dmux_8way(O, &[O, I, O]));
+        assert_eq!([O, O, O, O, O, O, O, O], dmux_8way(O, &[O, I, I]));
+        assert_eq!([O, O, O, O, O, O, O, O], dmux_8way(O, &[I, O, O]));
+        assert_eq!([O, O, O, O, O, O, O, O], dmux_8way(O, &[I, O, I]));
+        assert_eq!([O, O, O, O, O, O, O, O], dmux_8way(O, &[I, I, O]));
+        assert_eq!([O, O, O, O, O, O, O, O], dmux_8way(O, &[I, I, I]));
+        assert_eq!([I, O, O, O, O, O, O, O], dmux_8way(I, &[O, O, O]));
+        assert_eq!([O, I, O, O, O, O, O, O], dmux_8way(I, &[O, O, I]));
+        assert_eq!([O, O, I, O, O, O, O, O], dmux_8way(I, &[O, I, O]));
+        assert_eq!([O, O, O, I, O, O, O, O], dmux_8way(I, &[O, I, I]));
+        assert_eq!([O, O, O, O, I, O, O, O], dmux_8way(I, &[I, O, O]));
+        assert_eq!([O, O, O, O, O, I, O, O], dmux_8way(I, &[I, O, I]));
+        assert_eq!([O, O, O, O, O, O, I, O], dmux_8way(I, &[I, I, O]));
+        assert_eq!([O, O, O, O, O, O, O, I], dmux_8way(I, &[I, I, I]));
+    }
+
+    #[test]
+    fn nbit_test() {
+        assert_eq!(Bus::<8>::from(0b10100101u8), not_nbit(&Bus::<8>::from(0b01011010u8)));
+        assert_eq!(Bus::<8>::from(0b00000101u8), and_nbit(&Bus::<8>::from(0b00001111u8), &Bus::<8>::from(0b01010101u8)));
+        assert_eq!(Bus::<32>::from(0xffff00ffu32), or_nbit(&Bus::<32>::from(0xff0000f0u32), &Bus::<32>::from(0x00ff000fu32)));
+        assert_eq!(Bus::<32>::from(0x12345678u32), mux_nbit(&Bus::<32>::from(0u32), &Bus::<32>::from(0x12345678u32), I));
+        assert_eq!(O, or_nway(&[O; 32]));
+        assert_eq!(I, or_nway(&[O, O, I]));
+    }
+
+    #[test]
+    fn kway_test() {
+        let inputs: Vec<Bus<8>> = (0..16).map(|i| Bus::<8>::from(i as u8 * 3)).collect();
+        for i in 0..16u64 {
+            let sel = Bus::<4>::from_u64(i);
+            let sel = [sel[3], sel[2], sel[1], sel[0]];
+            assert_eq!(inputs[i as usize], mux_kway(&inputs, &sel));
+
+            let mut expect = vec![O; 16];
+            expect[i as usize] = I;
+            assert_eq!(expect, dmux_kway(I, &sel));
+            assert_eq!(vec![O; 16], dmux_kway(O, &sel));
+        }
+        assert_eq!(vec![I], dmux_kway(I, &[]));
+        assert_eq!(inputs[0], mux_kway(&inputs[..1], &[]));
     }
 }
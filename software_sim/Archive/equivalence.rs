@@ -0,0 +1,426 @@
+use std::fmt;
+
+use crate::bitslice::{self, Lanes, XorShift, LANES};
+use crate::bool_arithmetic;
+use crate::bool_logic;
+use crate::hdl::rust_chip::sel;
+use crate::signal::Bus;
+
+// 入力の幅の合計がこれ以下なら全部の組み合わせを試す。超えたら乱数で samples 個
+pub const EXHAUSTIVE_WIDTH: usize = 24;
+
+// ゲートで作ったチップと、普通の Rust で書いた仕様の組
+// ピンの値は HDL と同じく in[i] が 2^i の桁。sel も数として扱う (sel=2 なら 3 番目を選ぶ)
+pub struct Chip {
+    pub name: &'static str,
+    pub inputs: Vec<(&'static str, usize)>,
+    pub outputs: Vec<(&'static str, usize)>,
+    // 入力ピンを並べた信号 (ピン順、各ピンは下の桁から) -> 出力ピンを同じように並べたもの
+    pub gate: fn(&[Lanes]) -> Vec<Lanes>,
+    // ピンごとの値 -> 出力ピンごとの値
+    pub spec: fn(&[u64]) -> Vec<u64>
+}
+
+// 仕様と食い違った入力。見つけたあとで、食い違ったまま 1 にできる桁を 0 にして小さくしてある
+#[derive(Debug, Clone, PartialEq)]
+pub struct Counterexample {
+    pub chip: &'static str,
+    pub inputs: Vec<(&'static str, u64)>,
+    pub expected: Vec<(&'static str, u64)>,
+    pub actual: Vec<(&'static str, u64)>
+}
+
+impl fmt::Display for Counterexample {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let pins = |values: &[(&str, u64)]| {
+            values.iter().map(|(name, v)| format!("{}={}", name, v)).collect::<Vec<String>>().join(" ")
+        };
+        write!(
+            f,
+            "{}: {} -> expected {}, got {}",
+            self.chip, pins(&self.inputs), pins(&self.expected), pins(&self.actual)
+        )
+    }
+}
+
+// 何通り試したか
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Coverage {
+    Exhaustive(u64),
+    Random(u64)
+}
+
+fn mask(width: usize) -> u64 {
+    if width >= 64 { !0 } else { (1 << width) - 1 }
+}
+
+// vectors[k] を k 番目のレーンにしてゲートを通し、ピンごとの値に戻す
+fn run(chip: &Chip, vectors: &[Vec<u64>]) -> Vec<Vec<u64>> {
+    let mut pins: Vec<Lanes> = Vec::new();
+    for (p, (_, width)) in chip.inputs.iter().enumerate() {
+        for i in 0..*width {
+            pins.push(Lanes(vectors.iter().enumerate().fold(0, |acc, (k, v)| acc | (((v[p] >> i) & 1) << k))));
+        }
+    }
+    let out = (chip.gate)(&pins);
+    let mut results = vec![vec![0u64; chip.outputs.len()]; vectors.len()];
+    let mut offset = 0;
+    for (p, (_, width)) in chip.outputs.iter().enumerate() {
+        for i in 0..*width {
+            for (k, result) in results.iter_mut().enumerate() {
+                result[p] |= ((out[offset + i].0 >> k) & 1) << i;
+            }
+        }
+        offset += width;
+    }
+    results
+}
+
+fn fails(chip: &Chip, vector: &[u64]) -> bool {
+    run(chip, &[vector.to_vec()])[0] != (chip.spec)(vector)
+}
+
+// 1 になっている桁を上から順に 0 にしてみて、まだ食い違うならそのままにする
+fn shrink(chip: &Chip, mut vector: Vec<u64>) -> Vec<u64> {
+    loop {
+        let mut changed = false;
+        for p in (0..vector.len()).rev() {
+            for i in (0..chip.inputs[p].1).rev() {
+                if (vector[p] >> i) & 1 == 0 {
+                    continue
+                }
+                let mut smaller = vector.clone();
+                smaller[p] &= !(1 << i);
+                if fails(chip, &smaller) {
+                    vector = smaller;
+                    changed = true;
+                }
+            }
+        }
+        if !changed {
+            return vector
+        }
+    }
+}
+
+fn counterexample(chip: &Chip, vector: Vec<u64>) -> Counterexample {
+    let vector = shrink(chip, vector);
+    let actual = run(chip, std::slice::from_ref(&vector)).remove(0);
+    let expected = (chip.spec)(&vector);
+    let named = |pins: &[(&'static str, usize)], values: &[u64]| {
+        pins.iter().zip(values).map(|((name, _), v)| (*name, *v)).collect::<Vec<(&'static str, u64)>>()
+    };
+    Counterexample {
+        chip: chip.name,
+        inputs: named(&chip.inputs, &vector),
+        expected: named(&chip.outputs, &expected),
+        actual: named(&chip.outputs, &actual)
+    }
+}
+
+fn check_batch(chip: &Chip, vectors: &[Vec<u64>]) -> Option<Vec<u64>> {
+    run(chip, vectors)
+        .into_iter()
+        .zip(vectors)
+        .find(|(actual, vector)| *actual != (chip.spec)(vector))
+        .map(|(_, vector)| vector.clone())
+}
+
+// chip を仕様と比べる。入力が狭ければ全部、広ければ乱数で samples 個 (64 個ずつまとめて評価する)
+pub fn check(chip: &Chip, samples: u64, seed: u64) -> Result<Coverage, Counterexample> {
+    let width: usize = chip.inputs.iter().map(|(_, w)| w).sum();
+    let split = |mut n: u64| -> Vec<u64> {
+        chip.inputs.iter().map(|(_, w)| { let v = n & mask(*w); n >>= *w; v }).collect()
+    };
+    if width <= EXHAUSTIVE_WIDTH {
+        // 小さい順に調べるので、最初に見つかったものがいちばん小さい
+        let total = 1u64 << width;
+        for base in (0..total).step_by(LANES) {
+            let vectors: Vec<Vec<u64>> = (base..total.min(base + LANES as u64)).map(split).collect();
+            if let Some(vector) = check_batch(chip, &vectors) {
+                return Err(counterexample(chip, vector))
+            }
+        }
+        Ok(Coverage::Exhaustive(total))
+    } else {
+        let mut rng = XorShift::new(seed);
+        let mut checked = 0;
+        while checked < samples {
+            let n = (samples - checked).min(LANES as u64);
+            let vectors: Vec<Vec<u64>> = (0..n)
+                .map(|_| chip.inputs.iter().map(|(_, w)| rng.next_u64() & mask(*w)).collect())
+                .collect();
+            if let Some(vector) = check_batch(chip, &vectors) {
+                return Err(counterexample(chip, vector))
+            }
+            checked += n;
+        }
+        Ok(Coverage::Random(samples))
+    }
+}
+
+fn word(pins: &[Lanes], offset: usize) -> Bus<16, Lanes> {
+    Bus::from_fn(|i| pins[offset + i])
+}
+
+fn bits<const N: usize>(bus: &Bus<N, Lanes>) -> Vec<Lanes> {
+    bus.bits().to_vec()
+}
+
+// bool_logic / bool_arithmetic の全チップ
+pub fn chips() -> Vec<Chip> {
+    vec![
+        Chip {
+            name: "Nand",
+            inputs: vec![("a", 1), ("b", 1)],
+            outputs: vec![("out", 1)],
+            gate: |p| vec![bool_logic::nand(p[0], p[1])],
+            spec: |v| vec![!(v[0] & v[1]) & 1]
+        },
+        Chip {
+            name: "Not",
+            inputs: vec![("in", 1)],
+            outputs: vec![("out", 1)],
+            gate: |p| vec![bool_logic::not(p[0])],
+            spec: |v| vec![!v[0] & 1]
+        },
+        Chip {
+            name: "And",
+            inputs: vec![("a", 1), ("b", 1)],
+            outputs: vec![("out", 1)],
+            gate: |p| vec![bool_logic::and(p[0], p[1])],
+            spec: |v| vec![v[0] & v[1]]
+        },
+        Chip {
+            name: "Or",
+            inputs: vec![("a", 1), ("b", 1)],
+            outputs: vec![("out", 1)],
+            gate: |p| vec![bool_logic::or(p[0], p[1])],
+            spec: |v| vec![v[0] | v[1]]
+        },
+        Chip {
+            name: "Nor",
+            inputs: vec![("a", 1), ("b", 1)],
+            outputs: vec![("out", 1)],
+            gate: |p| vec![bool_logic::nor(p[0], p[1])],
+            spec: |v| vec![!(v[0] | v[1]) & 1]
+        },
+        Chip {
+            name: "Xor",
+            inputs: vec![("a", 1), ("b", 1)],
+            outputs: vec![("out", 1)],
+            gate: |p| vec![bool_logic::xor(p[0], p[1])],
+            spec: |v| vec![v[0] ^ v[1]]
+        },
+        Chip {
+            name: "Mux",
+            inputs: vec![("a", 1), ("b", 1), ("sel", 1)],
+            outputs: vec![("out", 1)],
+            gate: |p| vec![bool_logic::mux(p[0], p[1], p[2])],
+            spec: |v| vec![if v[2] == 1 { v[1] } else { v[0] }]
+        },
+        Chip {
+            name: "DMux",
+            inputs: vec![("in", 1), ("sel", 1)],
+            outputs: vec![("a", 1), ("b", 1)],
+            gate: |p| bool_logic::dmux(p[0], p[1]).to_vec(),
+            spec: |v| if v[1] == 1 { vec![0, v[0]] } else { vec![v[0], 0] }
+        },
+        Chip {
+            name: "Not16",
+            inputs: vec![("in", 16)],
+            outputs: vec![("out", 16)],
+            gate: |p| bits(&bool_logic::not_16bit(&word(p, 0))),
+            spec: |v| vec![!v[0] & 0xffff]
+        },
+        Chip {
+            name: "And16",
+            inputs: vec![("a", 16), ("b", 16)],
+            outputs: vec![("out", 16)],
+            gate: |p| bits(&bool_logic::and_16bit(&word(p, 0), &word(p, 16))),
+            spec: |v| vec![v[0] & v[1]]
+        },
+        Chip {
+            name: "Or16",
+            inputs: vec![("a", 16), ("b", 16)],
+            outputs: vec![("out", 16)],
+            gate: |p| bits(&bool_logic::or_16bit(&word(p, 0), &word(p, 16))),
+            spec: |v| vec![v[0] | v[1]]
+        },
+        Chip {
+            name: "Mux16",
+            inputs: vec![("a", 16), ("b", 16), ("sel", 1)],
+            outputs: vec![("out", 16)],
+            gate: |p| bits(&bool_logic::mux_16bit(&word(p, 0), &word(p, 16), p[32])),
+            spec: |v| vec![if v[2] == 1 { v[1] } else { v[0] }]
+        },
+        Chip {
+            name: "Or8Way",
+            inputs: vec![("in", 8)],
+            outputs: vec![("out", 1)],
+            gate: |p| vec![bool_logic::or_8way(&[p[0], p[1], p[2], p[3], p[4], p[5], p[6], p[7]])],
+            spec: |v| vec![(v[0] != 0) as u64]
+        },
+        Chip {
+            name: "Mux4Way16",
+            inputs: vec![("a", 16), ("b", 16), ("c", 16), ("d", 16), ("sel", 2)],
+            outputs: vec![("out", 16)],
+            gate: |p| bits(&bool_logic::mux_4way_16bit(
+                &word(p, 0), &word(p, 16), &word(p, 32), &word(p, 48), &sel(&p[64..])
+            )),
+            spec: |v| vec![v[v[4] as usize]]
+        },
+        Chip {
+            name: "Mux8Way16",
+            inputs: vec![
+                ("a", 16), ("b", 16), ("c", 16), ("d", 16),
+                ("e", 16), ("f", 16), ("g", 16), ("h", 16), ("sel", 3)
+            ],
+            outputs: vec![("out", 16)],
+            gate: |p| bits(&bool_logic::mux_8way_16bit(
+                &word(p, 0), &word(p, 16), &word(p, 32), &word(p, 48),
+                &word(p, 64), &word(p, 80), &word(p, 96), &word(p, 112), &sel(&p[128..])
+            )),
+            spec: |v| vec![v[v[8] as usize]]
+        },
+        Chip {
+            name: "DMux4Way",
+            inputs: vec![("in", 1), ("sel", 2)],
+            outputs: vec![("a", 1), ("b", 1), ("c", 1), ("d", 1)],
+            gate: |p| bool_logic::dmux_4way(p[0], &sel(&p[1..])).to_vec(),
+            spec: |v| (0..4).map(|i| if i == v[1] { v[0] } else { 0 }).collect()
+        },
+        Chip {
+            name: "DMux8Way",
+            inputs: vec![("in", 1), ("sel", 3)],
+            outputs: vec![("a", 1), ("b", 1), ("c", 1), ("d", 1), ("e", 1), ("f", 1), ("g", 1), ("h", 1)],
+            gate: |p| bool_logic::dmux_8way(p[0], &sel(&p[1..])).to_vec(),
+            spec: |v| (0..8).map(|i| if i == v[1] { v[0] } else { 0 }).collect()
+        },
+        Chip {
+            name: "HalfAdder",
+            inputs: vec![("a", 1), ("b", 1)],
+            outputs: vec![("sum", 1), ("carry", 1)],
+            gate: |p| {
+                let out = bool_arithmetic::half_adder(p[0], p[1]);
+                vec![out.sum, out.carry]
+            },
+            spec: |v| vec![(v[0] + v[1]) & 1, (v[0] + v[1]) >> 1]
+        },
+        Chip {
+            name: "FullAdder",
+            inputs: vec![("a", 1), ("b", 1), ("c", 1)],
+            outputs: vec![("sum", 1), ("carry", 1)],
+            gate: |p| {
+                let out = bool_arithmetic::full_adder(p[0], p[1], p[2]);
+                vec![out.sum, out.carry]
+            },
+            spec: |v| vec![(v[0] + v[1] + v[2]) & 1, (v[0] + v[1] + v[2]) >> 1]
+        },
+        Chip {
+            name: "Add16",
+            inputs: vec![("a", 16), ("b", 16)],
+            outputs: vec![("out", 16)],
+            gate: |p| bits(&bool_arithmetic::adder_16bit(&word(p, 0), &word(p, 16))),
+            spec: |v| vec![(v[0] + v[1]) & 0xffff]
+        },
+        Chip {
+            name: "Inc16",
+            inputs: vec![("in", 16)],
+            outputs: vec![("out", 16)],
+            gate: |p| bits(&bool_arithmetic::incrementer(&word(p, 0))),
+            spec: |v| vec![(v[0] + 1) & 0xffff]
+        },
+        Chip {
+            name: "ALU",
+            inputs: vec![("x", 16), ("y", 16), ("zx", 1), ("nx", 1), ("zy", 1), ("ny", 1), ("f", 1), ("no", 1)],
+            outputs: vec![("out", 16), ("zr", 1), ("ng", 1)],
+            gate: |p| {
+                let (out, zr, ng) = bool_arithmetic::alu(
+                    &word(p, 0), &word(p, 16), p[32], p[33], p[34], p[35], p[36], p[37]
+                );
+                let mut result = bits(&out);
+                result.push(zr);
+                result.push(ng);
+                result
+            },
+            spec: |v| {
+                let control = v[2..].iter().fold(0u8, |acc, b| (acc << 1) | *b as u8);
+                let (out, zr, ng) = bitslice::alu_reference(v[0] as i16, v[1] as i16, control);
+                vec![out as u16 as u64, zr as u64, ng as u64]
+            }
+        },
+    ]
+}
+
+// 全チップを調べて 1 行ずつ結果を書く。食い違いがあれば false
+pub fn report(samples: u64, seed: u64) -> (String, bool) {
+    let mut out = String::new();
+    let mut ok = true;
+    for chip in chips() {
+        match check(&chip, samples, seed) {
+            Ok(Coverage::Exhaustive(n)) => out.push_str(&format!("{}: ok ({} inputs, exhaustive)\n", chip.name, n)),
+            Ok(Coverage::Random(n)) => out.push_str(&format!("{}: ok ({} random inputs)\n", chip.name, n)),
+            Err(e) => {
+                out.push_str(&format!("{}\n", e));
+                ok = false;
+            }
+        }
+    }
+    (out, ok)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn all_chips_test() {
+        for chip in chips() {
+            assert!(check(&chip, 4096, 1).is_ok(), "{}", chip.name);
+        }
+    }
+
+    #[test]
+    fn exhaustive_counterexample_test() {
+        // Xor のつもりで Or を書いた
+        let chip = Chip {
+            name: "Xor",
+            inputs: vec![("a", 1), ("b", 1)],
+            outputs: vec![("out", 1)],
+            gate: |p| vec![bool_logic::or(p[0], p[1])],
+            spec: |v| vec![v[0] ^ v[1]]
+        };
+        let e = check(&chip, 0, 1).unwrap_err();
+        assert_eq!(vec![("a", 1), ("b", 1)], e.inputs);
+        assert_eq!("Xor: a=1 b=1 -> expected out=0, got out=1", e.to_string());
+    }
+
+    #[test]
+    fn random_counterexample_test() {
+        // 5 桁目だけ Or になっている And16。乱数で見つけたものを a=32 b=0 まで小さくする
+        let chip = Chip {
+            name: "And16",
+            inputs: vec![("a", 16), ("b", 16)],
+            outputs: vec![("out", 16)],
+            gate: |p| {
+                let mut out = bits(&bool_logic::and_16bit(&word(p, 0), &word(p, 16)));
+                out[5] = bool_logic::or(p[5], p[21]);
+                out
+            },
+            spec: |v| vec![v[0] & v[1]]
+        };
+        let e = check(&chip, 4096, 7).unwrap_err();
+        assert!(e.inputs == vec![("a", 32), ("b", 0)] || e.inputs == vec![("a", 0), ("b", 32)]);
+        assert_eq!(vec![("out", 32)], e.actual);
+        assert_eq!(vec![("out", 0)], e.expected);
+    }
+
+    #[test]
+    fn coverage_test() {
+        let chips = chips();
+        let find = |name: &str| chips.iter().find(|c| c.name == name).unwrap();
+        assert_eq!(Ok(Coverage::Exhaustive(1 << 16)), check(find("Inc16"), 100, 1));
+        assert_eq!(Ok(Coverage::Random(100)), check(find("ALU"), 100, 1));
+    }
+}
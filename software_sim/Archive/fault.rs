@@ -0,0 +1,282 @@
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::rc::Rc;
+
+use crate::bitslice::LANES;
+use crate::hdl::tst::{self, Mismatch, Target, TstError};
+use crate::schematic::{self, Schematic};
+use crate::vectors::Vectors;
+
+// nand の出力を 0 か 1 に固定する故障 (縮退故障)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fault {
+    // ALU/Not16#4/Not#3/Nand#0 のような、チップの中の nand の場所
+    pub site: String,
+    pub stuck: bool
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} stuck-at-{}", self.site, self.stuck as u8)
+    }
+}
+
+// テストのベクタで見つけられなかった故障
+#[derive(Debug, Clone, PartialEq)]
+pub struct Coverage {
+    pub chip: String,
+    pub faults: usize,
+    pub undetected: Vec<Fault>,
+    // 故障の無いチップでも .tst のスクリプトが .cmp と食い違った。そこから先のベクタは使っていない
+    pub mismatch: Option<Mismatch>
+}
+
+impl fmt::Display for Coverage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let detected = self.faults - self.undetected.len();
+        let percent = if self.faults == 0 { 100.0 } else { detected as f64 * 100.0 / self.faults as f64 };
+        write!(f, "{}: {} of {} faults detected ({:.1}%)", self.chip, detected, self.faults, percent)
+    }
+}
+
+// schematic を 64 本並列で動かす。0 番目のレーンは故障の無いチップで、スクリプトやベクタにはこの値を見せる
+// 1 番目から先のレーンにはそれぞれ 1 つずつ故障を入れ、出力を読むたびに 0 番目と違うレーンを覚えておく
+struct Sim {
+    schematic: Rc<Schematic>,
+    dffs: Rc<Vec<usize>>,
+    nets: Vec<u64>,
+    inputs: Vec<u64>,
+    state: Vec<u64>,
+    next: Vec<u64>,
+    // cell ごとの (残すレーン, 1 にするレーン)
+    stuck: HashMap<usize, (u64, u64)>,
+    detected: Rc<Cell<u64>>
+}
+
+fn lanes(bit: bool) -> u64 {
+    if bit { !0 } else { 0 }
+}
+
+impl Sim {
+    fn new(schematic: Rc<Schematic>, dffs: Rc<Vec<usize>>, faults: &[(usize, bool)], detected: Rc<Cell<u64>>) -> Sim {
+        let mut stuck: HashMap<usize, (u64, u64)> = HashMap::new();
+        for (k, (cell, value)) in faults.iter().enumerate() {
+            let lane = 1u64 << (k + 1);
+            let entry = stuck.entry(*cell).or_insert((!0, 0));
+            if *value {
+                entry.1 |= lane;
+            } else {
+                entry.0 &= !lane;
+            }
+        }
+        Sim {
+            inputs: vec![0; schematic.inputs.len()],
+            state: vec![0; dffs.len()],
+            next: vec![0; dffs.len()],
+            nets: vec![0; schematic.net_count()],
+            schematic,
+            dffs,
+            stuck,
+            detected
+        }
+    }
+}
+
+impl Target for Sim {
+    fn set(&mut self, pin: &str, value: u64) -> bool {
+        match self.schematic.inputs.iter().position(|(name, _)| name == pin) {
+            Some(p) => {
+                for (i, net) in self.schematic.inputs[p].1.iter().enumerate() {
+                    self.nets[*net] = lanes((value >> i) & 1 == 1);
+                }
+                self.inputs[p] = value;
+                true
+            },
+            None => false
+        }
+    }
+
+    fn get(&self, pin: &str) -> Option<u64> {
+        if let Some(p) = self.schematic.inputs.iter().position(|(name, _)| name == pin) {
+            return Some(self.inputs[p])
+        }
+        let (_, nets) = self.schematic.outputs.iter().find(|(name, _)| name == pin)?;
+        let mut value = 0;
+        let mut differ = 0;
+        for (i, net) in nets.iter().enumerate() {
+            let bit = self.nets[*net] & 1 == 1;
+            value |= (bit as u64) << i;
+            differ |= self.nets[*net] ^ lanes(bit);
+        }
+        self.detected.set(self.detected.get() | differ);
+        Some(value)
+    }
+
+    fn eval(&mut self) {
+        let stuck = &self.stuck;
+        self.schematic.eval(&mut self.nets, &self.dffs, &self.state, |cell, value| match stuck.get(&cell) {
+            Some((keep, set)) => (value & keep) | set,
+            None => value
+        });
+    }
+
+    // 取り込む値を決めるだけで、出力は tock まで前の値のまま
+    fn tick(&mut self) {
+        self.eval();
+        for (k, cell) in self.dffs.iter().enumerate() {
+            self.next[k] = self.nets[self.schematic.cells[*cell].inputs[0]];
+        }
+    }
+
+    fn tock(&mut self) {
+        self.state.clone_from(&self.next);
+        self.eval();
+    }
+}
+
+// cell ごとに、同じ部品の中で何番目の nand かを付けた名前
+fn sites(schematic: &Schematic) -> Vec<String> {
+    let mut seen: HashMap<Vec<String>, usize> = HashMap::new();
+    schematic.cells.iter().map(|cell| {
+        let count = seen.entry(cell.path.clone()).or_insert(0);
+        *count += 1;
+        let mut parts = vec![schematic.name.clone()];
+        parts.extend(cell.path.iter().cloned());
+        parts.push(format!("{}#{}", cell.chip, *count - 1));
+        parts.join("/")
+    }).collect()
+}
+
+// schematic の nand を 1 つずつ 0 / 1 に固定して、63 個ずつ run に渡した Sim で流し直す
+// 出力が故障の無いときと 1 度も変わらなかった故障を集める
+fn undetected<E>(schematic: &Schematic, detected: &Cell<u64>, mut run: impl FnMut(&[(usize, bool)]) -> Result<(), E>) -> Result<(usize, Vec<Fault>), E> {
+    let faults: Vec<(usize, bool)> = (0..schematic.cells.len())
+        .filter(|c| schematic.cells[*c].chip == "Nand")
+        .flat_map(|c| vec![(c, false), (c, true)])
+        .collect();
+
+    let names = sites(schematic);
+    let mut undetected = Vec::new();
+    for batch in faults.chunks(LANES - 1) {
+        detected.set(0);
+        run(batch)?;
+        for (k, (cell, stuck)) in batch.iter().enumerate() {
+            if (detected.get() >> (k + 1)) & 1 == 0 {
+                undetected.push(Fault { site: names[*cell].clone(), stuck: *stuck });
+            }
+        }
+    }
+    Ok((faults.len(), undetected))
+}
+
+// script が load する Rust のチップで、hardware_sim の .tst / .cmp のベクタが見つけられない故障
+pub fn coverage(script: &Path) -> Result<Coverage, TstError> {
+    let mut found: Option<(Rc<Schematic>, Rc<Vec<usize>>)> = None;
+    let detected = Rc::new(Cell::new(0));
+    let report = tst::run_with(script, &mut |name, line| -> Result<Box<dyn Target>, TstError> {
+        let schematic = schematic::rust_chip(name)
+            .ok_or(TstError::Script { line, message: format!("no Rust chip named {}", name) })?;
+        let dffs = schematic.dffs();
+        let (schematic, dffs) = (Rc::new(schematic), Rc::new(dffs));
+        found = Some((schematic.clone(), dffs.clone()));
+        Ok(Box::new(Sim::new(schematic, dffs, &[], detected.clone())))
+    })?;
+    let (schematic, dffs) = match found {
+        Some(found) => found,
+        None => return Err(TstError::Script { line: 1, message: "no chip loaded".to_string() })
+    };
+    let (faults, undetected) = undetected(&schematic, &detected, |batch| {
+        tst::run_with(script, &mut |_, _| -> Result<Box<dyn Target>, TstError> {
+            Ok(Box::new(Sim::new(schematic.clone(), dffs.clone(), batch, detected.clone())))
+        }).map(|_| ())
+    })?;
+    Ok(Coverage { chip: schematic.name.clone(), faults, undetected, mismatch: report.mismatch })
+}
+
+// bool_arithmetic.rs と sequential_logic.rs の #[test] のベクタ (vectors) が見つけられない故障
+// 故障の無いチップが表と合わなければエラー
+pub fn vector_coverage(vectors: &Vectors) -> Result<Coverage, String> {
+    let schematic = Rc::new(schematic::rust_chip(vectors.chip).ok_or(format!("no Rust chip named {}", vectors.chip))?);
+    let dffs = Rc::new(schematic.dffs());
+    let detected = Rc::new(Cell::new(0));
+    vectors.replay(&mut Sim::new(schematic.clone(), dffs.clone(), &[], detected.clone()))?;
+    let (faults, undetected) = undetected(&schematic, &detected, |batch| {
+        // replay が見るのは故障の無い 0 番目のレーンだけなので、ここでは表と食い違わない
+        vectors.replay(&mut Sim::new(schematic.clone(), dffs.clone(), batch, detected.clone()))
+    })?;
+    Ok(Coverage { chip: schematic.name.clone(), faults, undetected, mismatch: None })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::vectors;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn hardware(file: &str) -> PathBuf {
+        Path::new(file!()).parent().unwrap().join("../../hardware_sim").join(file)
+    }
+
+    #[test]
+    fn xor_test() {
+        let xor = coverage(&hardware("01/Xor.tst")).unwrap();
+        let nands = schematic::rust_chip("Xor").unwrap().count("Nand");
+        assert_eq!(2 * nands, xor.faults);
+        assert_eq!(Vec::<Fault>::new(), xor.undetected);
+        assert_eq!(None, xor.mismatch);
+
+        // a=0, b=0 だけでは nand が 1 を出す故障は見つからない
+        let dir = std::env::temp_dir().join(format!("fault_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let script = dir.join("Xor.tst");
+        fs::write(&script, "load Xor.hdl, output-list a b out; set a 0, set b 0, eval, output;").unwrap();
+        let weak = coverage(&script).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(!weak.undetected.is_empty());
+        assert!(weak.undetected.iter().any(|f| f.stuck));
+        assert!(weak.undetected.iter().all(|f| f.site.starts_with("Xor/")));
+        assert!(weak.to_string().starts_with(&format!("Xor: {} of {} faults detected", nands * 2 - weak.undetected.len(), nands * 2)));
+    }
+
+    #[test]
+    fn chip_test() {
+        let mux = coverage(&hardware("01/Mux8Way16.tst")).unwrap();
+        assert_eq!(2 * schematic::rust_chip("Mux8Way16").unwrap().count("Nand"), mux.faults);
+        assert!(mux.undetected.len() < mux.faults);
+        // 順序回路も、tick で取り込んで tock で出す
+        let bit = coverage(&hardware("03/a/Bit.tst")).unwrap();
+        assert_eq!(None, bit.mismatch);
+        assert_eq!(Vec::<Fault>::new(), bit.undetected);
+        let pc = coverage(&hardware("03/a/PC.tst")).unwrap();
+        assert_eq!(None, pc.mismatch);
+        assert!(pc.undetected.len() < pc.faults);
+        assert!(matches!(coverage(&hardware("05/CPU.tst")), Err(TstError::Script { .. })));
+    }
+
+    #[test]
+    fn vector_test() {
+        // bool_arithmetic.rs と sequential_logic.rs の #[test] のベクタも流せる
+        let alu = vector_coverage(&vectors::find("ALU").unwrap()).unwrap();
+        assert_eq!(2 * schematic::rust_chip("ALU").unwrap().count("Nand"), alu.faults);
+        assert!(alu.undetected.len() < alu.faults);
+        assert_eq!(Vec::<Fault>::new(), vector_coverage(&vectors::find("FullAdder").unwrap()).unwrap().undetected);
+        let register = vector_coverage(&vectors::find("Register").unwrap()).unwrap();
+        assert_eq!(None, register.mismatch);
+        assert!(register.undetected.len() < register.faults);
+        // 故障の無いチップが表と合わなければエラー
+        let mut bit = vectors::find("Bit").unwrap();
+        bit.rows[0].1[0] = 1;
+        assert_eq!(Err("row 1: out = 0, expected 1".to_string()), vector_coverage(&bit));
+    }
+
+    #[test]
+    fn site_test() {
+        let and = schematic::rust_chip("And").unwrap();
+        assert_eq!(vec!["And/Nand#0".to_string(), "And/Not#0/Nand#0".to_string()], sites(&and));
+        let fault = Fault { site: "And/Nand#0".to_string(), stuck: true };
+        assert_eq!("And/Nand#0 stuck-at-1", fault.to_string());
+    }
+}
@@ -0,0 +1,72 @@
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub mod parser;
+pub mod builtin;
+pub mod netlist;
+pub mod rust_chip;
+pub mod tst;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HdlError {
+    // .hdl の書き方が間違っている。line, column は 1 始まり
+    Syntax { line: usize, column: usize, message: String },
+    // .hdl も builtin も無いチップ
+    UnknownChip(String),
+    UnknownPin { chip: String, pin: String },
+    // ピンの幅とつないだ信号の幅が違う
+    WidthMismatch { pin: String, expected: usize, found: usize },
+    // 入力ピンにつないだ先が出力になっているなど、つなぎ方がおかしい
+    BadConnection(String),
+    // 同じ信号を 2 つ以上の部品が出力している
+    MultipleDrivers(String),
+    // どの部品も出力していない信号を読んでいる
+    Undriven(String),
+    // DFF を通らないループ。部品のパスを持つ
+    CombinationalLoop(String),
+    // チップが自分自身を部品に使っている
+    Recursion(String),
+    // そのチップの .hdl を読んだときのエラー
+    InChip(String, Box<HdlError>),
+    // チップの line 行目の部品をつなぐときのエラー
+    At { chip: String, line: usize, error: Box<HdlError> }
+}
+
+impl HdlError {
+    // chip の line 行目で起きたことにする。もっと内側の位置が付いていればそのまま
+    pub fn at(self, chip: &str, line: usize) -> HdlError {
+        match self {
+            HdlError::At { .. } | HdlError::InChip(..) => self,
+            error => HdlError::At { chip: chip.to_string(), line, error: Box::new(error) }
+        }
+    }
+}
+
+impl fmt::Display for HdlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HdlError::Syntax { line, column, message } => write!(f, "{}:{}: {}", line, column, message),
+            HdlError::UnknownChip(s) => write!(f, "unknown chip: {}", s),
+            HdlError::UnknownPin { chip, pin } => write!(f, "{} has no pin named {}", chip, pin),
+            HdlError::WidthMismatch { pin, expected, found } =>
+                write!(f, "width mismatch on {}: expected {}, found {}", pin, expected, found),
+            HdlError::BadConnection(s) => write!(f, "bad connection: {}", s),
+            HdlError::MultipleDrivers(s) => write!(f, "{} is driven by more than one part", s),
+            HdlError::Undriven(s) => write!(f, "{} is read but never driven", s),
+            HdlError::CombinationalLoop(s) => write!(f, "combinational loop through {}", s),
+            HdlError::Recursion(s) => write!(f, "chip uses itself: {}", s),
+            HdlError::InChip(name, error) => write!(f, "{}.hdl:{}", name, error),
+            HdlError::At { chip, line, error } => write!(f, "{}.hdl:{}: {}", chip, line, error)
+        }
+    }
+}
+
+impl Error for HdlError {}
+
+// dir/{name}.hdl を読むローダー
+pub fn dir_loader(dir: &Path) -> impl Fn(&str) -> Option<String> {
+    let dir: PathBuf = dir.to_path_buf();
+    move |name| fs::read_to_string(dir.join(format!("{}.hdl", name))).ok()
+}
@@ -0,0 +1,442 @@
+use super::rust_chip::sel;
+use crate::bool_arithmetic;
+use crate::bool_logic;
+use crate::signal::{Bit, Bus, Word16, O};
+
+// .hdl が無いときに使う部品
+// 組み合わせ回路は bool_logic / bool_arithmetic をそのまま呼び、順序回路は値を覚えておくだけの実装
+pub trait Primitive {
+    // 今の入力と内部の状態から出力を出す。inputs / 戻り値はピンを並べたもの (各ピンは下の桁から)
+    fn eval(&self, inputs: &[Bit]) -> Vec<Bit>;
+
+    // クロックの立ち上がり (tick) で入力を取り込み、立ち下がり (tock) で出力に出す
+    fn tick(&mut self, _inputs: &[Bit]) {}
+    fn tock(&mut self) {}
+
+    // テストスクリプトから中の値を読み書きする (RAM16K[3], PC[] など)。index はメモリの番地
+    fn peek(&self, _index: usize) -> Option<u64> {
+        None
+    }
+    fn poke(&mut self, _index: usize, _value: u64) -> bool {
+        false
+    }
+}
+
+pub struct Builtin {
+    pub name: &'static str,
+    pub inputs: &'static [(&'static str, usize)],
+    pub outputs: &'static [(&'static str, usize)],
+    // クロックでしか読まないピン。これ以外の入力が変わると出力もすぐ変わる
+    pub clocked: &'static [&'static str],
+    pub make: fn() -> Box<dyn Primitive>
+}
+
+pub fn value(bits: &[Bit]) -> u64 {
+    bits.iter().enumerate().fold(0, |acc, (i, b)| acc | ((u8::from(*b) as u64) << i))
+}
+
+pub fn bits(value: u64, width: usize) -> Vec<Bit> {
+    (0..width).map(|i| Bit::from((value >> i) & 1 == 1)).collect()
+}
+
+struct Gate(fn(&[Bit]) -> Vec<Bit>);
+
+impl Primitive for Gate {
+    fn eval(&self, inputs: &[Bit]) -> Vec<Bit> {
+        (self.0)(inputs)
+    }
+}
+
+fn word(inputs: &[Bit], offset: usize) -> Word16 {
+    Bus::from_fn(|i| inputs[offset + i])
+}
+
+// DFF, Bit, Register, ARegister, DRegister
+struct Latch {
+    width: usize,
+    has_load: bool,
+    value: u64,
+    next: Option<u64>
+}
+
+impl Primitive for Latch {
+    fn eval(&self, _inputs: &[Bit]) -> Vec<Bit> {
+        bits(self.value, self.width)
+    }
+
+    fn tick(&mut self, inputs: &[Bit]) {
+        if !self.has_load || inputs[self.width].is_one() {
+            self.next = Some(value(&inputs[..self.width]));
+        }
+    }
+
+    fn tock(&mut self) {
+        if let Some(value) = self.next.take() {
+            self.value = value;
+        }
+    }
+
+    fn peek(&self, _index: usize) -> Option<u64> {
+        Some(self.value)
+    }
+
+    fn poke(&mut self, _index: usize, value: u64) -> bool {
+        self.value = value & ((1 << self.width) - 1);
+        true
+    }
+}
+
+// in[16], load, inc, reset
+struct Counter {
+    value: u64,
+    next: u64
+}
+
+impl Primitive for Counter {
+    fn eval(&self, _inputs: &[Bit]) -> Vec<Bit> {
+        bits(self.value, 16)
+    }
+
+    fn tick(&mut self, inputs: &[Bit]) {
+        self.next = if inputs[18].is_one() {
+            0
+        } else if inputs[16].is_one() {
+            value(&inputs[..16])
+        } else if inputs[17].is_one() {
+            (self.value + 1) & 0xffff
+        } else {
+            self.value
+        };
+    }
+
+    fn tock(&mut self) {
+        self.value = self.next;
+    }
+
+    fn peek(&self, _index: usize) -> Option<u64> {
+        Some(self.value)
+    }
+
+    fn poke(&mut self, _index: usize, value: u64) -> bool {
+        self.value = value & 0xffff;
+        true
+    }
+}
+
+// RAMn, Screen: in[16], load, address[k]。ROM32K と Keyboard は書き込まない
+struct Memory {
+    words: Vec<u64>,
+    // 入力の中で address が始まる位置
+    address_at: usize,
+    writable: bool,
+    write: Option<(usize, u64)>
+}
+
+impl Memory {
+    fn new(size: usize, address_at: usize, writable: bool) -> Memory {
+        Memory { words: vec![0; size], address_at, writable, write: None }
+    }
+
+    fn address(&self, inputs: &[Bit]) -> usize {
+        let width = self.words.len().trailing_zeros() as usize;
+        value(&inputs[self.address_at..self.address_at + width]) as usize
+    }
+}
+
+impl Primitive for Memory {
+    fn eval(&self, inputs: &[Bit]) -> Vec<Bit> {
+        bits(self.words[self.address(inputs)], 16)
+    }
+
+    fn tick(&mut self, inputs: &[Bit]) {
+        if self.writable && inputs[16].is_one() {
+            self.write = Some((self.address(inputs), value(&inputs[..16])));
+        }
+    }
+
+    fn tock(&mut self) {
+        if let Some((address, value)) = self.write.take() {
+            self.words[address] = value;
+        }
+    }
+
+    fn peek(&self, index: usize) -> Option<u64> {
+        self.words.get(index).copied()
+    }
+
+    // ROM32K にもプログラムを入れられるように、writable に関係なく書く
+    fn poke(&mut self, index: usize, value: u64) -> bool {
+        match self.words.get_mut(index) {
+            Some(word) => {
+                *word = value & 0xffff;
+                true
+            },
+            None => false
+        }
+    }
+}
+
+const A_B: &[(&str, usize)] = &[("a", 1), ("b", 1)];
+const OUT: &[(&str, usize)] = &[("out", 1)];
+const OUT16: &[(&str, usize)] = &[("out", 16)];
+const A_B16: &[(&str, usize)] = &[("a", 16), ("b", 16)];
+const RAM_CLOCKED: &[&str] = &["in", "load"];
+
+static BUILTINS: &[Builtin] = &[
+    Builtin {
+        name: "Nand", inputs: A_B, outputs: OUT, clocked: &[],
+        make: || Box::new(Gate(|i| vec![bool_logic::nand(i[0], i[1])]))
+    },
+    Builtin {
+        name: "Not", inputs: &[("in", 1)], outputs: OUT, clocked: &[],
+        make: || Box::new(Gate(|i| vec![bool_logic::not(i[0])]))
+    },
+    Builtin {
+        name: "And", inputs: A_B, outputs: OUT, clocked: &[],
+        make: || Box::new(Gate(|i| vec![bool_logic::and(i[0], i[1])]))
+    },
+    Builtin {
+        name: "Or", inputs: A_B, outputs: OUT, clocked: &[],
+        make: || Box::new(Gate(|i| vec![bool_logic::or(i[0], i[1])]))
+    },
+    Builtin {
+        name: "Xor", inputs: A_B, outputs: OUT, clocked: &[],
+        make: || Box::new(Gate(|i| vec![bool_logic::xor(i[0], i[1])]))
+    },
+    Builtin {
+        name: "Mux", inputs: &[("a", 1), ("b", 1), ("sel", 1)], outputs: OUT, clocked: &[],
+        make: || Box::new(Gate(|i| vec![bool_logic::mux(i[0], i[1], i[2])]))
+    },
+    Builtin {
+        name: "DMux", inputs: &[("in", 1), ("sel", 1)], outputs: A_B, clocked: &[],
+        make: || Box::new(Gate(|i| bool_logic::dmux(i[0], i[1]).to_vec()))
+    },
+    Builtin {
+        name: "Not16", inputs: &[("in", 16)], outputs: OUT16, clocked: &[],
+        make: || Box::new(Gate(|i| bool_logic::not_16bit(&word(i, 0)).bits().to_vec()))
+    },
+    Builtin {
+        name: "And16", inputs: A_B16, outputs: OUT16, clocked: &[],
+        make: || Box::new(Gate(|i| bool_logic::and_16bit(&word(i, 0), &word(i, 16)).bits().to_vec()))
+    },
+    Builtin {
+        name: "Or16", inputs: A_B16, outputs: OUT16, clocked: &[],
+        make: || Box::new(Gate(|i| bool_logic::or_16bit(&word(i, 0), &word(i, 16)).bits().to_vec()))
+    },
+    Builtin {
+        name: "Mux16", inputs: &[("a", 16), ("b", 16), ("sel", 1)], outputs: OUT16, clocked: &[],
+        make: || Box::new(Gate(|i| bool_logic::mux_16bit(&word(i, 0), &word(i, 16), i[32]).bits().to_vec()))
+    },
+    Builtin {
+        name: "Or8Way", inputs: &[("in", 8)], outputs: OUT, clocked: &[],
+        make: || Box::new(Gate(|i| vec![bool_logic::or_8way(&[i[0], i[1], i[2], i[3], i[4], i[5], i[6], i[7]])]))
+    },
+    Builtin {
+        name: "Mux4Way16",
+        inputs: &[("a", 16), ("b", 16), ("c", 16), ("d", 16), ("sel", 2)],
+        outputs: OUT16,
+        clocked: &[],
+        make: || Box::new(Gate(|i| {
+            bool_logic::mux_4way_16bit(&word(i, 0), &word(i, 16), &word(i, 32), &word(i, 48), &sel(&i[64..]))
+                .bits()
+                .to_vec()
+        }))
+    },
+    Builtin {
+        name: "Mux8Way16",
+        inputs: &[
+            ("a", 16), ("b", 16), ("c", 16), ("d", 16),
+            ("e", 16), ("f", 16), ("g", 16), ("h", 16), ("sel", 3)
+        ],
+        outputs: OUT16,
+        clocked: &[],
+        make: || Box::new(Gate(|i| {
+            bool_logic::mux_8way_16bit(
+                &word(i, 0), &word(i, 16), &word(i, 32), &word(i, 48),
+                &word(i, 64), &word(i, 80), &word(i, 96), &word(i, 112), &sel(&i[128..])
+            ).bits().to_vec()
+        }))
+    },
+    Builtin {
+        name: "DMux4Way",
+        inputs: &[("in", 1), ("sel", 2)],
+        outputs: &[("a", 1), ("b", 1), ("c", 1), ("d", 1)],
+        clocked: &[],
+        make: || Box::new(Gate(|i| bool_logic::dmux_4way(i[0], &sel(&i[1..])).to_vec()))
+    },
+    Builtin {
+        name: "DMux8Way",
+        inputs: &[("in", 1), ("sel", 3)],
+        outputs: &[("a", 1), ("b", 1), ("c", 1), ("d", 1), ("e", 1), ("f", 1), ("g", 1), ("h", 1)],
+        clocked: &[],
+        make: || Box::new(Gate(|i| bool_logic::dmux_8way(i[0], &sel(&i[1..])).to_vec()))
+    },
+    Builtin {
+        name: "HalfAdder", inputs: A_B, outputs: &[("sum", 1), ("carry", 1)], clocked: &[],
+        make: || Box::new(Gate(|i| {
+            let out = bool_arithmetic::half_adder(i[0], i[1]);
+            vec![out.sum, out.carry]
+        }))
+    },
+    Builtin {
+        name: "FullAdder", inputs: &[("a", 1), ("b", 1), ("c", 1)], outputs: &[("sum", 1), ("carry", 1)], clocked: &[],
+        make: || Box::new(Gate(|i| {
+            let out = bool_arithmetic::full_adder(i[0], i[1], i[2]);
+            vec![out.sum, out.carry]
+        }))
+    },
+    Builtin {
+        name: "Add16", inputs: A_B16, outputs: OUT16, clocked: &[],
+        make: || Box::new(Gate(|i| bool_arithmetic::adder_16bit(&word(i, 0), &word(i, 16)).bits().to_vec()))
+    },
+    Builtin {
+        name: "Inc16", inputs: &[("in", 16)], outputs: OUT16, clocked: &[],
+        make: || Box::new(Gate(|i| bool_arithmetic::incrementer(&word(i, 0)).bits().to_vec()))
+    },
+    Builtin {
+        name: "ALU",
+        inputs: &[("x", 16), ("y", 16), ("zx", 1), ("nx", 1), ("zy", 1), ("ny", 1), ("f", 1), ("no", 1)],
+        outputs: &[("out", 16), ("zr", 1), ("ng", 1)],
+        clocked: &[],
+        make: || Box::new(Gate(|i| {
+            let (out, zr, ng) = bool_arithmetic::alu(&word(i, 0), &word(i, 16), i[32], i[33], i[34], i[35], i[36], i[37]);
+            let mut result = out.bits().to_vec();
+            result.push(zr);
+            result.push(ng);
+            result
+        }))
+    },
+    Builtin {
+        name: "DFF", inputs: &[("in", 1)], outputs: OUT, clocked: &["in"],
+        make: || Box::new(Latch { width: 1, has_load: false, value: 0, next: None })
+    },
+    Builtin {
+        name: "Bit", inputs: &[("in", 1), ("load", 1)], outputs: OUT, clocked: RAM_CLOCKED,
+        make: || Box::new(Latch { width: 1, has_load: true, value: 0, next: None })
+    },
+    Builtin {
+        name: "Register", inputs: &[("in", 16), ("load", 1)], outputs: OUT16, clocked: RAM_CLOCKED,
+        make: || Box::new(Latch { width: 16, has_load: true, value: 0, next: None })
+    },
+    Builtin {
+        name: "ARegister", inputs: &[("in", 16), ("load", 1)], outputs: OUT16, clocked: RAM_CLOCKED,
+        make: || Box::new(Latch { width: 16, has_load: true, value: 0, next: None })
+    },
+    Builtin {
+        name: "DRegister", inputs: &[("in", 16), ("load", 1)], outputs: OUT16, clocked: RAM_CLOCKED,
+        make: || Box::new(Latch { width: 16, has_load: true, value: 0, next: None })
+    },
+    Builtin {
+        name: "PC",
+        inputs: &[("in", 16), ("load", 1), ("inc", 1), ("reset", 1)],
+        outputs: OUT16,
+        clocked: &["in", "load", "inc", "reset"],
+        make: || Box::new(Counter { value: 0, next: 0 })
+    },
+    Builtin {
+        name: "RAM8", inputs: &[("in", 16), ("load", 1), ("address", 3)], outputs: OUT16, clocked: RAM_CLOCKED,
+        make: || Box::new(Memory::new(8, 17, true))
+    },
+    Builtin {
+        name: "RAM64", inputs: &[("in", 16), ("load", 1), ("address", 6)], outputs: OUT16, clocked: RAM_CLOCKED,
+        make: || Box::new(Memory::new(64, 17, true))
+    },
+    Builtin {
+        name: "RAM512", inputs: &[("in", 16), ("load", 1), ("address", 9)], outputs: OUT16, clocked: RAM_CLOCKED,
+        make: || Box::new(Memory::new(512, 17, true))
+    },
+    Builtin {
+        name: "RAM4K", inputs: &[("in", 16), ("load", 1), ("address", 12)], outputs: OUT16, clocked: RAM_CLOCKED,
+        make: || Box::new(Memory::new(4096, 17, true))
+    },
+    Builtin {
+        name: "RAM16K", inputs: &[("in", 16), ("load", 1), ("address", 14)], outputs: OUT16, clocked: RAM_CLOCKED,
+        make: || Box::new(Memory::new(16384, 17, true))
+    },
+    Builtin {
+        name: "Screen", inputs: &[("in", 16), ("load", 1), ("address", 13)], outputs: OUT16, clocked: RAM_CLOCKED,
+        make: || Box::new(Memory::new(8192, 17, true))
+    },
+    Builtin {
+        name: "Keyboard", inputs: &[], outputs: OUT16, clocked: &[],
+        make: || Box::new(Gate(|_| vec![O; 16]))
+    },
+    Builtin {
+        name: "ROM32K", inputs: &[("address", 15)], outputs: OUT16, clocked: &[],
+        make: || Box::new(Memory::new(32768, 0, false))
+    },
+];
+
+pub fn find(name: &str) -> Option<&'static Builtin> {
+    BUILTINS.iter().find(|b| b.name == name)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::signal::I;
+
+    #[test]
+    fn gate_test() {
+        let xor = (find("Xor").unwrap().make)();
+        assert_eq!(vec![I], xor.eval(&[I, O]));
+        assert_eq!(vec![O], xor.eval(&[I, I]));
+
+        // sel=2 (HDL の sel[1]=1) で c を選ぶ
+        let mux = (find("Mux4Way16").unwrap().make)();
+        let mut inputs: Vec<Bit> = [0u64, 1, 2, 3].iter().flat_map(|v| bits(*v + 10, 16)).collect();
+        inputs.extend(bits(2, 2));
+        assert_eq!(12, value(&mux.eval(&inputs)));
+    }
+
+    #[test]
+    fn register_test() {
+        let mut register = (find("Register").unwrap().make)();
+        let mut inputs = bits(1234, 16);
+        inputs.push(I);
+        register.tick(&inputs);
+        // tock までは前の値
+        assert_eq!(0, value(&register.eval(&inputs)));
+        register.tock();
+        assert_eq!(1234, value(&register.eval(&inputs)));
+    }
+
+    #[test]
+    fn pc_test() {
+        let mut pc = (find("PC").unwrap().make)();
+        let clock = |pc: &mut Box<dyn Primitive>, input: u64, load, inc, reset| {
+            let mut inputs = bits(input, 16);
+            inputs.extend([load, inc, reset].iter());
+            pc.tick(&inputs);
+            pc.tock();
+            value(&pc.eval(&inputs))
+        };
+        assert_eq!(1, clock(&mut pc, 0, O, I, O));
+        assert_eq!(2, clock(&mut pc, 0, O, I, O));
+        assert_eq!(100, clock(&mut pc, 100, I, I, O));
+        assert_eq!(100, clock(&mut pc, 0, O, O, O));
+        assert_eq!(0, clock(&mut pc, 7, I, I, I));
+    }
+
+    #[test]
+    fn memory_test() {
+        let mut ram = (find("RAM64").unwrap().make)();
+        let mut inputs = bits(77, 16);
+        inputs.push(I);
+        inputs.extend(bits(33, 6));
+        ram.tick(&inputs);
+        ram.tock();
+        assert_eq!(77, value(&ram.eval(&inputs)));
+        inputs[16] = O;
+        inputs.truncate(17);
+        inputs.extend(bits(32, 6));
+        assert_eq!(0, value(&ram.eval(&inputs)));
+        assert_eq!(Some(77), ram.peek(33));
+        assert_eq!(None, ram.peek(64));
+        // ROM32K は回路からは書けないが、poke ならプログラムを入れられる
+        let mut rom = (find("ROM32K").unwrap().make)();
+        assert!(rom.poke(5, 0x1_ea10));
+        assert_eq!(0xea10, value(&rom.eval(&bits(5, 15))));
+        assert!(find("RAM32").is_none());
+    }
+}
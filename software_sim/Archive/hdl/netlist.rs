@@ -0,0 +1,653 @@
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use super::builtin::{self, Builtin, Primitive};
+use super::parser::{self, ChipDef, PinDecl, PinRef};
+use super::HdlError;
+use crate::signal::{Bit, O};
+
+// 0 番のネットはいつも false、1 番はいつも true
+const FALSE: usize = 0;
+const TRUE: usize = 1;
+
+// 部品を全部ほどいて、Rust で書いた部品 (builtin) とそれをつなぐネット (1 本の信号線) だけにしたもの
+pub struct Netlist {
+    pub name: String,
+    pub inputs: Vec<PinDecl>,
+    pub outputs: Vec<PinDecl>,
+    // 評価する順に並んでいる
+    pub instances: Vec<Instance>,
+    // トップのチップから見えるピン (入出力と内部ピン) -> ネット (下の桁から)
+    pins: HashMap<String, Vec<usize>>,
+    values: Vec<Bit>
+}
+
+pub struct Instance {
+    pub chip: &'static str,
+    // Computer/CPU#3/ALU#5/Mux16#0 のような、トップからの部品のたどり方
+    pub path: String,
+    pub inputs: Vec<usize>,
+    pub outputs: Vec<usize>,
+    primitive: Box<dyn Primitive>
+}
+
+// チップの入出力ピン
+struct Interface {
+    inputs: Vec<PinDecl>,
+    outputs: Vec<PinDecl>
+}
+
+impl Interface {
+    fn find(&self, name: &str) -> Option<(bool, usize, usize)> {
+        // (入力か, ピンを並べたときの開始位置, 幅)
+        for (is_input, pins) in [(true, &self.inputs), (false, &self.outputs)] {
+            let mut offset = 0;
+            for pin in pins.iter() {
+                if pin.name == name {
+                    return Some((is_input, offset, pin.width))
+                }
+                offset += pin.width;
+            }
+        }
+        None
+    }
+
+    fn input_width(&self) -> usize {
+        self.inputs.iter().map(|p| p.width).sum()
+    }
+
+    fn output_width(&self) -> usize {
+        self.outputs.iter().map(|p| p.width).sum()
+    }
+}
+
+enum Source {
+    Hdl(Rc<ChipDef>),
+    Builtin(&'static Builtin)
+}
+
+struct Pending {
+    builtin: &'static Builtin,
+    path: String,
+    inputs: Vec<usize>,
+    outputs: Vec<usize>
+}
+
+struct Elaborator<'a> {
+    loader: &'a dyn Fn(&str) -> Option<String>,
+    defs: HashMap<String, Rc<ChipDef>>,
+    // ネットの union-find。チップの出力ピンと中の部品の出力を同じネットにするのに使う
+    parent: Vec<usize>,
+    pending: Vec<Pending>,
+    stack: Vec<String>
+}
+
+impl<'a> Elaborator<'a> {
+    fn new_net(&mut self) -> usize {
+        self.parent.push(self.parent.len());
+        self.parent.len() - 1
+    }
+
+    fn find(&mut self, net: usize) -> usize {
+        let mut root = net;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+        let mut net = net;
+        while self.parent[net] != root {
+            let next = self.parent[net];
+            self.parent[net] = root;
+            net = next;
+        }
+        root
+    }
+
+    // .hdl があればそれを、無ければ builtin を使う
+    fn source(&mut self, name: &str) -> Result<Option<Source>, HdlError> {
+        if let Some(def) = self.defs.get(name) {
+            return Ok(Some(Source::Hdl(def.clone())))
+        }
+        match (self.loader)(name) {
+            Some(text) => {
+                let def = parser::parse(&text).map_err(|e| HdlError::InChip(name.to_string(), Box::new(e)))?;
+                if def.name != name {
+                    return Err(HdlError::InChip(
+                        name.to_string(),
+                        Box::new(HdlError::Syntax { line: 1, column: 1, message: format!("file defines chip {}", def.name) })
+                    ))
+                }
+                let def = Rc::new(def);
+                self.defs.insert(name.to_string(), def.clone());
+                Ok(Some(Source::Hdl(def)))
+            },
+            None => Ok(builtin::find(name).map(Source::Builtin))
+        }
+    }
+
+    fn interface(&mut self, name: &str) -> Result<Option<Interface>, HdlError> {
+        Ok(self.source(name)?.map(|source| match source {
+            Source::Hdl(def) => Interface { inputs: def.inputs.clone(), outputs: def.outputs.clone() },
+            Source::Builtin(b) => Interface {
+                inputs: b.inputs.iter().map(|(n, w)| PinDecl { name: n.to_string(), width: *w }).collect(),
+                outputs: b.outputs.iter().map(|(n, w)| PinDecl { name: n.to_string(), width: *w }).collect()
+            }
+        }))
+    }
+
+    // name を inputs / outputs のネットにつないで、中身を pending に足していく
+    // 戻り値はそのチップの中で見えるピン -> ネット
+    fn elaborate(&mut self, name: &str, path: String, inputs: Vec<usize>, outputs: Vec<usize>)
+        -> Result<HashMap<String, Vec<usize>>, HdlError> {
+        let def = match self.source(name)? {
+            Some(Source::Hdl(def)) => def,
+            Some(Source::Builtin(builtin)) => {
+                self.pending.push(Pending { builtin, path, inputs, outputs });
+                return Ok(HashMap::new())
+            },
+            None => return Err(HdlError::UnknownChip(name.to_string()))
+        };
+        if self.stack.iter().any(|n| n == name) {
+            return Err(HdlError::Recursion(format!("{} -> {}", self.stack.join(" -> "), name)))
+        }
+        self.stack.push(name.to_string());
+
+        let mut signals: HashMap<String, Vec<Option<usize>>> = HashMap::new();
+        let chip_pins = Interface { inputs: def.inputs.clone(), outputs: def.outputs.clone() };
+        let mut offset = 0;
+        for pin in &def.inputs {
+            signals.insert(pin.name.clone(), inputs[offset..offset + pin.width].iter().map(|n| Some(*n)).collect());
+            offset += pin.width;
+        }
+        let mut offset = 0;
+        for pin in &def.outputs {
+            signals.insert(pin.name.clone(), outputs[offset..offset + pin.width].iter().map(|n| Some(*n)).collect());
+            offset += pin.width;
+        }
+
+        // 1 周目: 部品の出力にネットを割り当て、つながっている信号に登録する
+        // 出力ピンのどの桁をもう部品がつないだか
+        let mut driven: HashSet<(String, usize)> = HashSet::new();
+        let mut parts: Vec<(Interface, Vec<usize>)> = Vec::new();
+        for part in &def.parts {
+            let error_at = |error: HdlError| error.at(name, part.line);
+            let interface = self.interface(&part.chip)?.ok_or_else(|| error_at(HdlError::UnknownChip(part.chip.clone())))?;
+            let part_outputs: Vec<usize> = (0..interface.output_width()).map(|_| self.new_net()).collect();
+            for connection in &part.connections {
+                let (is_input, start, width) = interface.find(&connection.internal.name).ok_or_else(|| {
+                    error_at(HdlError::UnknownPin { chip: part.chip.clone(), pin: connection.internal.name.clone() })
+                })?;
+                if is_input {
+                    continue
+                }
+                let (low, high) = slice(&connection.internal, width).map_err(error_at)?;
+                let nets = &part_outputs[start + low..=start + high];
+                let external = &connection.external;
+                if external.is_constant() || chip_pins.find(&external.name).is_some_and(|(is_input, _, _)| is_input) {
+                    return Err(error_at(HdlError::BadConnection(format!("output {} cannot drive {}", connection.internal.name, external.name))))
+                }
+                let is_output = chip_pins.find(&external.name).is_some();
+                let signal = signals.entry(external.name.clone()).or_default();
+                let (ext_low, ext_high) = match external.range {
+                    Some(range) => range,
+                    None if is_output => (0, signal.len() - 1),
+                    None => (0, nets.len() - 1)
+                };
+                if ext_high - ext_low + 1 != nets.len() {
+                    return Err(error_at(HdlError::WidthMismatch {
+                        pin: external.name.clone(), expected: nets.len(), found: ext_high - ext_low + 1
+                    }))
+                }
+                if is_output && ext_high >= signal.len() {
+                    return Err(error_at(HdlError::WidthMismatch { pin: external.name.clone(), expected: signal.len(), found: ext_high + 1 }))
+                }
+                if signal.len() <= ext_high {
+                    signal.resize(ext_high + 1, None);
+                }
+                for (i, net) in nets.iter().enumerate() {
+                    match signal[ext_low + i] {
+                        // チップの出力ピン: 外から渡されたネットと同じものにする
+                        Some(existing) if is_output => {
+                            if !driven.insert((external.name.clone(), ext_low + i)) {
+                                return Err(error_at(HdlError::MultipleDrivers(external.name.clone())))
+                            }
+                            let existing = self.find(existing);
+                            self.parent[existing] = *net;
+                        },
+                        Some(_) => return Err(error_at(HdlError::MultipleDrivers(external.name.clone()))),
+                        None => signal[ext_low + i] = Some(*net)
+                    }
+                }
+            }
+            parts.push((interface, part_outputs));
+        }
+
+        // 2 周目: 部品の入力を信号につなぎ、中身を展開する
+        for (part, (interface, part_outputs)) in def.parts.iter().zip(parts) {
+            let error_at = |error: HdlError| error.at(name, part.line);
+            // つないでいない入力は false
+            let mut part_inputs: Vec<usize> = vec![FALSE; interface.input_width()];
+            for connection in &part.connections {
+                let (is_input, start, width) = interface.find(&connection.internal.name).unwrap();
+                if !is_input {
+                    continue
+                }
+                let (low, high) = slice(&connection.internal, width).map_err(error_at)?;
+                let external = &connection.external;
+                let nets: Vec<usize> = if external.is_constant() {
+                    let net = if external.name == "true" { TRUE } else { FALSE };
+                    vec![net; high - low + 1]
+                } else {
+                    let signal = signals.get(&external.name).ok_or_else(|| error_at(HdlError::Undriven(external.name.clone())))?;
+                    let (ext_low, ext_high) = slice(external, signal.len()).map_err(error_at)?;
+                    signal[ext_low..=ext_high]
+                        .iter()
+                        .map(|net| net.ok_or_else(|| error_at(HdlError::Undriven(external.name.clone()))))
+                        .collect::<Result<Vec<usize>, HdlError>>()?
+                };
+                if nets.len() != high - low + 1 {
+                    return Err(error_at(HdlError::WidthMismatch {
+                        pin: connection.internal.name.clone(), expected: high - low + 1, found: nets.len()
+                    }))
+                }
+                part_inputs[start + low..=start + high].copy_from_slice(&nets);
+            }
+            let index = self.pending.len();
+            self.elaborate(&part.chip, format!("{}/{}#{}", path, part.chip, index), part_inputs, part_outputs)?;
+        }
+
+        self.stack.pop();
+        Ok(signals
+            .into_iter()
+            .map(|(name, nets)| (name, nets.into_iter().map(|n| n.unwrap_or(FALSE)).collect()))
+            .collect())
+    }
+}
+
+// ピンのどこからどこまでを使うか。範囲を書いていなければ全部
+fn slice(pin: &PinRef, width: usize) -> Result<(usize, usize), HdlError> {
+    match pin.range {
+        Some((low, high)) if high < width => Ok((low, high)),
+        Some((_, high)) => Err(HdlError::WidthMismatch { pin: pin.name.clone(), expected: width, found: high + 1 }),
+        None => Ok((0, width - 1))
+    }
+}
+
+impl Netlist {
+    // name.hdl を loader で読んで (無ければ builtin) 展開する
+    pub fn build(name: &str, loader: &dyn Fn(&str) -> Option<String>) -> Result<Netlist, HdlError> {
+        let mut elaborator = Elaborator {
+            loader,
+            defs: HashMap::new(),
+            parent: Vec::new(),
+            pending: Vec::new(),
+            stack: Vec::new()
+        };
+        elaborator.new_net();
+        elaborator.new_net();
+        let interface = elaborator
+            .interface(name)?
+            .ok_or_else(|| HdlError::UnknownChip(name.to_string()))?;
+        let inputs: Vec<usize> = (0..interface.input_width()).map(|_| elaborator.new_net()).collect();
+        let outputs: Vec<usize> = (0..interface.output_width()).map(|_| elaborator.new_net()).collect();
+        let mut pins = elaborator.elaborate(name, name.to_string(), inputs.clone(), outputs.clone())?;
+        // builtin をそのまま作ったときは入出力ピンだけ
+        let mut offset = 0;
+        for pin in &interface.inputs {
+            pins.insert(pin.name.clone(), inputs[offset..offset + pin.width].to_vec());
+            offset += pin.width;
+        }
+        let mut offset = 0;
+        for pin in &interface.outputs {
+            pins.entry(pin.name.clone()).or_insert_with(|| outputs[offset..offset + pin.width].to_vec());
+            offset += pin.width;
+        }
+
+        // union-find をたどって、ネットの番号を詰め直す
+        let mut numbers: HashMap<usize, usize> = HashMap::new();
+        numbers.insert(FALSE, FALSE);
+        numbers.insert(TRUE, TRUE);
+        let mut renumber = |elaborator: &mut Elaborator, net: usize| {
+            let root = elaborator.find(net);
+            let next = numbers.len();
+            *numbers.entry(root).or_insert(next)
+        };
+        for nets in pins.values_mut() {
+            for net in nets.iter_mut() {
+                *net = renumber(&mut elaborator, *net);
+            }
+        }
+        let pending: Vec<Pending> = std::mem::take(&mut elaborator.pending);
+        let mut instances: Vec<(Instance, Vec<usize>)> = Vec::new();
+        for p in pending {
+            let inputs: Vec<usize> = p.inputs.iter().map(|n| renumber(&mut elaborator, *n)).collect();
+            let outputs: Vec<usize> = p.outputs.iter().map(|n| renumber(&mut elaborator, *n)).collect();
+            // 出力がすぐに変わる入力
+            let mut combinational = Vec::new();
+            let mut offset = 0;
+            for (pin, width) in p.builtin.inputs {
+                if !p.builtin.clocked.contains(pin) {
+                    combinational.extend_from_slice(&inputs[offset..offset + width]);
+                }
+                offset += width;
+            }
+            let instance = Instance {
+                chip: p.builtin.name,
+                path: p.path,
+                inputs,
+                outputs,
+                primitive: (p.builtin.make)()
+            };
+            instances.push((instance, combinational));
+        }
+        let net_count = numbers.len();
+
+        Ok(Netlist {
+            name: name.to_string(),
+            inputs: interface.inputs,
+            outputs: interface.outputs,
+            instances: sort(instances, net_count)?,
+            pins,
+            values: vec![O; net_count]
+        })
+    }
+
+    // 部品の数をチップごとに数えたもの
+    pub fn summary(&self) -> String {
+        let mut counts: Vec<(&str, usize)> = Vec::new();
+        for instance in &self.instances {
+            match counts.iter_mut().find(|(chip, _)| *chip == instance.chip) {
+                Some((_, n)) => *n += 1,
+                None => counts.push((instance.chip, 1))
+            }
+        }
+        counts.sort();
+        let pins = |pins: &[PinDecl]| pins.iter()
+            .map(|p| if p.width == 1 { p.name.clone() } else { format!("{}[{}]", p.name, p.width) })
+            .collect::<Vec<String>>()
+            .join(", ");
+        let mut s = format!("{}: {} parts\n", self.name, self.instances.len());
+        s.push_str(&format!("  IN {}\n", pins(&self.inputs)));
+        s.push_str(&format!("  OUT {}\n", pins(&self.outputs)));
+        for (chip, n) in counts {
+            s.push_str(&format!("  {:<10}{:>6}\n", chip, n));
+        }
+        s
+    }
+
+    // ピン (入出力と内部ピン) につながっているネット。下の桁から
+    pub fn nets(&self, pin: &str) -> Option<&[usize]> {
+        self.pins.get(pin).map(|nets| nets.as_slice())
+    }
+
+    // ピンの幅。このバイナリからは使わない
+    #[allow(dead_code)]
+    pub fn width(&self, pin: &str) -> Option<usize> {
+        self.pins.get(pin).map(|nets| nets.len())
+    }
+
+    // 入力ピンに値を入れる (評価は eval するまでしない)
+    pub fn set(&mut self, pin: &str, value: u64) -> Result<(), HdlError> {
+        if !self.inputs.iter().any(|p| p.name == pin) {
+            return Err(HdlError::UnknownPin { chip: self.name.clone(), pin: pin.to_string() })
+        }
+        let nets = self.pins[pin].clone();
+        for (i, net) in nets.iter().enumerate() {
+            self.values[*net] = Bit::from((value >> i) & 1 == 1);
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, pin: &str) -> Option<u64> {
+        let nets = self.pins.get(pin)?;
+        Some(builtin::value(&nets.iter().map(|n| self.values[*n]).collect::<Vec<Bit>>()))
+    }
+
+    // chip という名前の部品 (最初に見つかったもの) が覚えている値。RAM16K なら index 番地
+    pub fn peek(&self, chip: &str, index: usize) -> Option<u64> {
+        self.instances.iter().find(|i| i.chip == chip)?.primitive.peek(index)
+    }
+
+    pub fn poke(&mut self, chip: &str, index: usize, value: u64) -> bool {
+        match self.instances.iter_mut().find(|i| i.chip == chip) {
+            Some(instance) => instance.primitive.poke(index, value),
+            None => false
+        }
+    }
+
+    // 組み合わせ回路を入力から順に計算する
+    pub fn eval(&mut self) {
+        self.values[FALSE] = O;
+        self.values[TRUE] = Bit::ONE;
+        for instance in &self.instances {
+            let inputs: Vec<Bit> = instance.inputs.iter().map(|n| self.values[*n]).collect();
+            let outputs = instance.primitive.eval(&inputs);
+            for (net, value) in instance.outputs.iter().zip(outputs) {
+                self.values[*net] = value;
+            }
+        }
+    }
+
+    // クロックの立ち上がり。今の値を順序回路に取り込ませる
+    pub fn tick(&mut self) {
+        self.eval();
+        let values = &self.values;
+        for instance in self.instances.iter_mut() {
+            let inputs: Vec<Bit> = instance.inputs.iter().map(|n| values[*n]).collect();
+            instance.primitive.tick(&inputs);
+        }
+    }
+
+    // クロックの立ち下がり。取り込んだ値が出力に出る
+    pub fn tock(&mut self) {
+        for instance in self.instances.iter_mut() {
+            instance.primitive.tock();
+        }
+        self.eval();
+    }
+}
+
+// 出力がすぐ変わる入力の依存関係で並べる (トポロジカルソート)
+fn sort(instances: Vec<(Instance, Vec<usize>)>, net_count: usize) -> Result<Vec<Instance>, HdlError> {
+    let mut driver: Vec<Option<usize>> = vec![None; net_count];
+    for (i, (instance, _)) in instances.iter().enumerate() {
+        for net in &instance.outputs {
+            driver[*net] = Some(i);
+        }
+    }
+    let mut waiting: Vec<usize> = vec![0; instances.len()];
+    let mut users: Vec<Vec<usize>> = vec![Vec::new(); instances.len()];
+    for (i, (_, combinational)) in instances.iter().enumerate() {
+        let mut from: Vec<usize> = combinational.iter().filter_map(|n| driver[*n]).collect();
+        from.sort_unstable();
+        from.dedup();
+        waiting[i] = from.len();
+        for d in from {
+            users[d].push(i);
+        }
+    }
+    let mut ready: Vec<usize> = (0..instances.len()).filter(|i| waiting[*i] == 0).rev().collect();
+    let mut order: Vec<usize> = Vec::new();
+    while let Some(i) = ready.pop() {
+        order.push(i);
+        for user in users[i].iter().rev() {
+            waiting[*user] -= 1;
+            if waiting[*user] == 0 {
+                ready.push(*user);
+            }
+        }
+    }
+    if order.len() != instances.len() {
+        let stuck = (0..instances.len()).find(|i| waiting[*i] > 0).unwrap();
+        return Err(HdlError::CombinationalLoop(instances[stuck].0.path.clone()))
+    }
+    let mut slots: Vec<Option<Instance>> = instances.into_iter().map(|(instance, _)| Some(instance)).collect();
+    Ok(order.into_iter().map(|i| slots[i].take().unwrap()).collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::equivalence;
+    use crate::hdl;
+    use std::path::{Path, PathBuf};
+
+    fn build(dir: &Path, name: &str) -> Netlist {
+        let loader = hdl::dir_loader(dir);
+        Netlist::build(name, &loader).unwrap()
+    }
+
+    fn hardware(dir: &str) -> PathBuf {
+        Path::new(file!()).parent().unwrap().join("../../../hardware_sim").join(dir)
+    }
+
+    #[test]
+    fn xor_test() {
+        let mut xor = build(&hardware("01"), "Xor");
+        for (a, b) in [(0, 0), (0, 1), (1, 0), (1, 1)].iter() {
+            xor.set("a", *a).unwrap();
+            xor.set("b", *b).unwrap();
+            xor.eval();
+            assert_eq!(Some(a ^ b), xor.get("out"));
+        }
+        assert!(xor.set("out", 1).is_err());
+        assert_eq!(None, xor.get("nothing"));
+    }
+
+    // hardware_sim の組み合わせ回路を、equivalence.rs の仕様と乱数で比べる
+    #[test]
+    fn combinational_test() {
+        let mut rng = crate::bitslice::XorShift::new(3);
+        for chip in equivalence::chips() {
+            let dir = if ["HalfAdder", "FullAdder", "Add16", "Inc16", "ALU"].contains(&chip.name) { "02" } else { "01" };
+            if chip.name == "Nand" || chip.name == "Nor" {
+                continue
+            }
+            let mut netlist = build(&hardware(dir), chip.name);
+            for _ in 0..200 {
+                let vector: Vec<u64> = chip.inputs.iter().map(|(_, w)| rng.next_u64() & ((1u64 << w) - 1)).collect();
+                for ((pin, _), value) in chip.inputs.iter().zip(&vector) {
+                    netlist.set(pin, *value).unwrap();
+                }
+                netlist.eval();
+                let actual: Vec<u64> = chip.outputs.iter().map(|(pin, _)| netlist.get(pin).unwrap()).collect();
+                assert_eq!((chip.spec)(&vector), actual, "{} {:?}", chip.name, vector);
+            }
+        }
+    }
+
+    #[test]
+    fn sequential_test() {
+        // PC.hdl は Register の出力を Inc16 に戻している (fb)
+        let mut pc = build(&hardware("03/a"), "PC");
+        pc.set("inc", 1).unwrap();
+        for expect in 1..=3 {
+            pc.tick();
+            pc.tock();
+            assert_eq!(Some(expect), pc.get("out"));
+        }
+        pc.set("in", 1000).unwrap();
+        pc.set("load", 1).unwrap();
+        pc.tick();
+        assert_eq!(Some(3), pc.get("out"));
+        pc.tock();
+        assert_eq!(Some(1000), pc.get("out"));
+        pc.set("reset", 1).unwrap();
+        pc.tick();
+        pc.tock();
+        assert_eq!(Some(0), pc.get("out"));
+        // 05 の CPU は DRegister (builtin) を持っているので、中の値を直接読み書きできる
+        let mut cpu = build(&hardware("05"), "CPU");
+        assert!(cpu.poke("DRegister", 0, 77));
+        assert_eq!(Some(77), cpu.peek("DRegister", 0));
+        assert_eq!(None, cpu.peek("RAM8", 0));
+    }
+
+    #[test]
+    fn ram_test() {
+        // RAM4K.hdl -> RAM512.hdl -> RAM64 (builtin)
+        let mut ram = build(&hardware("03/b"), "RAM4K");
+        ram.set("in", 4321).unwrap();
+        ram.set("load", 1).unwrap();
+        ram.set("address", 3000).unwrap();
+        ram.tick();
+        ram.tock();
+        ram.set("load", 0).unwrap();
+        ram.set("address", 2999).unwrap();
+        ram.eval();
+        assert_eq!(Some(0), ram.get("out"));
+        ram.set("address", 3000).unwrap();
+        ram.eval();
+        assert_eq!(Some(4321), ram.get("out"));
+    }
+
+    #[test]
+    fn all_files_test() {
+        for dir in ["01", "02", "03/a", "03/b", "05"].iter() {
+            let path = hardware(dir);
+            for entry in std::fs::read_dir(&path).unwrap() {
+                let file = entry.unwrap().path();
+                if file.extension().is_some_and(|e| e == "hdl") {
+                    let name = file.file_stem().unwrap().to_string_lossy().to_string();
+                    let netlist = build(&path, &name);
+                    assert!(!netlist.instances.is_empty(), "{}", name);
+                }
+            }
+        }
+        // CPU.hdl の内部ピンも読める
+        let cpu = build(&hardware("05"), "CPU");
+        assert_eq!(Some(16), cpu.width("outALU"));
+        assert_eq!(Some(15), cpu.width("pc"));
+    }
+
+    #[test]
+    fn internal_bus_test() {
+        let loader = |name: &str| match name {
+            "Split" => Some("CHIP Split { IN in[4]; OUT hi[2], lo, top; PARTS:
+                Not16(in[0..3]=in, out[0..1]=lo2, out[2..3]=hi, out[3]=top);
+                Not(in=lo2[1], out=lo);
+            }".to_string()),
+            _ => None
+        };
+        let mut split = Netlist::build("Split", &loader).unwrap();
+        split.set("in", 0b0110).unwrap();
+        split.eval();
+        assert_eq!(Some(0b10), split.get("hi"));
+        assert_eq!(Some(1), split.get("lo"));
+        assert_eq!(Some(1), split.get("top"));
+        assert_eq!(Some(0b01), split.get("lo2"));
+    }
+
+    #[test]
+    fn error_test() {
+        let chip = |source: &'static str| move |name: &str| if name == "Top" { Some(source.to_string()) } else { None };
+        let error = |source: &'static str| Netlist::build("Top", &chip(source)).err().unwrap();
+        assert_eq!(
+            HdlError::At { chip: "Top".to_string(), line: 2, error: Box::new(HdlError::UnknownChip("Nto".to_string())) },
+            error("CHIP Top { IN a; OUT out; PARTS:\nNto(in=a, out=out); }")
+        );
+        assert_eq!(
+            "Top.hdl:1: Not has no pin named x",
+            error("CHIP Top { IN a; OUT out; PARTS: Not(x=a, out=out); }").to_string()
+        );
+        assert_eq!(
+            HdlError::At { chip: "Top".to_string(), line: 1, error: Box::new(HdlError::MultipleDrivers("out".to_string())) },
+            error("CHIP Top { IN a; OUT out; PARTS: Not(in=a, out=out); Not(in=a, out=out); }")
+        );
+        assert_eq!(
+            HdlError::At { chip: "Top".to_string(), line: 1, error: Box::new(HdlError::Undriven("w".to_string())) },
+            error("CHIP Top { IN a; OUT out; PARTS: Not(in=w, out=out); }")
+        );
+        assert_eq!(
+            HdlError::At {
+                chip: "Top".to_string(), line: 1,
+                error: Box::new(HdlError::WidthMismatch { pin: "in".to_string(), expected: 16, found: 1 })
+            },
+            error("CHIP Top { IN a; OUT out[16]; PARTS: Not16(in=a, out=out); }")
+        );
+        assert!(matches!(error("CHIP Top { IN a; OUT out; PARTS: Not(in=w, out=w); And(a=w, b=a, out=out); }"), HdlError::CombinationalLoop(_)));
+        assert!(matches!(error("CHIP Top { IN a; OUT out; PARTS: Top(a=a, out=out); }"), HdlError::Recursion(_)));
+        assert!(matches!(error("CHIP Top { IN a; OUT out; PARTS: Not(in=a, out=a); }"), HdlError::At { .. }));
+        // DFF をはさめばループしてよい
+        assert!(Netlist::build("Top", &chip("CHIP Top { IN a; OUT out; PARTS: Not(in=w, out=v); DFF(in=v, out=w, out=out); }")).is_ok());
+    }
+}
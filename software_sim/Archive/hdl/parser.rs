@@ -0,0 +1,373 @@
+use super::HdlError;
+
+// CHIP Mux {
+//     IN a, b, sel;
+//     OUT out;
+//     PARTS:
+//     Not(in=sel, out=nsel);
+//     ...
+// }
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChipDef {
+    pub name: String,
+    pub inputs: Vec<PinDecl>,
+    pub outputs: Vec<PinDecl>,
+    pub parts: Vec<Part>
+}
+
+// IN / OUT に書いたピン。x[16] なら width 16
+#[derive(Debug, Clone, PartialEq)]
+pub struct PinDecl {
+    pub name: String,
+    pub width: usize
+}
+
+// PARTS: の 1 行
+#[derive(Debug, Clone, PartialEq)]
+pub struct Part {
+    pub chip: String,
+    pub connections: Vec<Connection>,
+    pub line: usize
+}
+
+// 部品側のピン = このチップ側の信号 (a[0..7]=x のように左が部品側)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Connection {
+    pub internal: PinRef,
+    pub external: PinRef
+}
+
+// a, a[3], a[0..7]。range は (下の桁, 上の桁) で両端を含む
+#[derive(Debug, Clone, PartialEq)]
+pub struct PinRef {
+    pub name: String,
+    pub range: Option<(usize, usize)>
+}
+
+impl PinRef {
+    pub fn is_constant(&self) -> bool {
+        self.name == "true" || self.name == "false"
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(usize),
+    Symbol(&'static str)
+}
+
+struct Lexer<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    line: usize,
+    column: usize
+}
+
+impl<'a> Lexer<'a> {
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    // 空白と // /* */ コメントを読み飛ばす
+    fn skip_trivia(&mut self) -> Result<(), HdlError> {
+        loop {
+            match self.chars.peek() {
+                Some(c) if c.is_whitespace() => { self.bump(); },
+                Some('/') => {
+                    let mut ahead = self.chars.clone();
+                    ahead.next();
+                    match ahead.next() {
+                        Some('/') => {
+                            while !matches!(self.chars.peek(), Some('\n') | None) {
+                                self.bump();
+                            }
+                        },
+                        Some('*') => {
+                            let (line, column) = (self.line, self.column);
+                            self.bump();
+                            self.bump();
+                            loop {
+                                match self.bump() {
+                                    Some('*') if self.chars.peek() == Some(&'/') => { self.bump(); break },
+                                    Some(_) => {},
+                                    None => return Err(HdlError::Syntax { line, column, message: "unterminated comment".to_string() })
+                                }
+                            }
+                        },
+                        _ => return Ok(())
+                    }
+                },
+                _ => return Ok(())
+            }
+        }
+    }
+
+    // (トークン, 行, 列)
+    fn next(&mut self) -> Result<Option<(Token, usize, usize)>, HdlError> {
+        self.skip_trivia()?;
+        let (line, column) = (self.line, self.column);
+        let c = match self.chars.peek() {
+            Some(c) => *c,
+            None => return Ok(None)
+        };
+        let token = if c.is_ascii_alphabetic() || c == '_' {
+            let mut s = String::new();
+            while let Some(c) = self.chars.peek().filter(|c| c.is_ascii_alphanumeric() || **c == '_') {
+                s.push(*c);
+                self.bump();
+            }
+            Token::Ident(s)
+        } else if c.is_ascii_digit() {
+            let mut s = String::new();
+            while let Some(c) = self.chars.peek().filter(|c| c.is_ascii_digit()) {
+                s.push(*c);
+                self.bump();
+            }
+            Token::Number(s.parse().map_err(|_| HdlError::Syntax { line, column, message: format!("number too large: {}", s) })?)
+        } else {
+            self.bump();
+            let symbol = match c {
+                '{' => "{", '}' => "}", '(' => "(", ')' => ")", '[' => "[", ']' => "]",
+                ',' => ",", ';' => ";", '=' => "=", ':' => ":",
+                '.' if self.chars.peek() == Some(&'.') => { self.bump(); ".." },
+                _ => return Err(HdlError::Syntax { line, column, message: format!("unexpected character: {}", c) })
+            };
+            Token::Symbol(symbol)
+        };
+        Ok(Some((token, line, column)))
+    }
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize, usize)>,
+    position: usize
+}
+
+impl Parser {
+    fn error<T>(&self, message: String) -> Result<T, HdlError> {
+        let (line, column) = match self.tokens.get(self.position).or_else(|| self.tokens.last()) {
+            Some((_, line, column)) => (*line, *column),
+            None => (1, 1)
+        };
+        Err(HdlError::Syntax { line, column, message })
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(t, _, _)| t)
+    }
+
+    fn line(&self) -> usize {
+        self.tokens.get(self.position).map_or(0, |(_, line, _)| *line)
+    }
+
+    fn eat(&mut self, symbol: &'static str) -> bool {
+        if self.peek() == Some(&Token::Symbol(symbol)) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, symbol: &'static str) -> Result<(), HdlError> {
+        if self.eat(symbol) {
+            Ok(())
+        } else {
+            self.error(format!("expected '{}'", symbol))
+        }
+    }
+
+    fn ident(&mut self) -> Result<String, HdlError> {
+        match self.peek() {
+            Some(Token::Ident(s)) => {
+                let s = s.clone();
+                self.position += 1;
+                Ok(s)
+            },
+            _ => self.error("expected a name".to_string())
+        }
+    }
+
+    fn number(&mut self) -> Result<usize, HdlError> {
+        match self.peek() {
+            Some(Token::Number(n)) => {
+                let n = *n;
+                self.position += 1;
+                Ok(n)
+            },
+            _ => self.error("expected a number".to_string())
+        }
+    }
+
+    fn keyword(&mut self, keyword: &str) -> bool {
+        if self.peek() == Some(&Token::Ident(keyword.to_string())) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    // a, b[16], c;
+    fn pin_decls(&mut self) -> Result<Vec<PinDecl>, HdlError> {
+        let mut pins = Vec::new();
+        loop {
+            let name = self.ident()?;
+            let width = if self.eat("[") {
+                let width = self.number()?;
+                self.expect("]")?;
+                if width == 0 || width > 64 {
+                    return self.error(format!("pin width must be 1..=64: {}", name))
+                }
+                width
+            } else {
+                1
+            };
+            pins.push(PinDecl { name, width });
+            if !self.eat(",") {
+                self.expect(";")?;
+                return Ok(pins)
+            }
+        }
+    }
+
+    fn pin_ref(&mut self) -> Result<PinRef, HdlError> {
+        let name = self.ident()?;
+        let range = if self.eat("[") {
+            let low = self.number()?;
+            let high = if self.eat("..") { self.number()? } else { low };
+            self.expect("]")?;
+            if high < low {
+                return self.error(format!("bad sub-bus: {}[{}..{}]", name, low, high))
+            }
+            Some((low, high))
+        } else {
+            None
+        };
+        Ok(PinRef { name, range })
+    }
+
+    fn part(&mut self) -> Result<Part, HdlError> {
+        let line = self.line();
+        let chip = self.ident()?;
+        self.expect("(")?;
+        let mut connections = Vec::new();
+        loop {
+            let internal = self.pin_ref()?;
+            self.expect("=")?;
+            let external = self.pin_ref()?;
+            connections.push(Connection { internal, external });
+            if !self.eat(",") {
+                break
+            }
+        }
+        self.expect(")")?;
+        self.expect(";")?;
+        Ok(Part { chip, connections, line })
+    }
+
+    fn chip(&mut self) -> Result<ChipDef, HdlError> {
+        if !self.keyword("CHIP") {
+            return self.error("expected CHIP".to_string())
+        }
+        let name = self.ident()?;
+        self.expect("{")?;
+        let inputs = if self.keyword("IN") { self.pin_decls()? } else { Vec::new() };
+        let outputs = if self.keyword("OUT") { self.pin_decls()? } else { Vec::new() };
+        if !self.keyword("PARTS") {
+            return self.error("expected PARTS:".to_string())
+        }
+        self.expect(":")?;
+        let mut parts = Vec::new();
+        while !self.eat("}") {
+            if self.peek().is_none() {
+                return self.error("expected '}'".to_string())
+            }
+            parts.push(self.part()?);
+        }
+        if self.peek().is_some() {
+            return self.error("unexpected text after the chip".to_string())
+        }
+        Ok(ChipDef { name, inputs, outputs, parts })
+    }
+}
+
+// .hdl のソースを 1 チップ分読む
+pub fn parse(source: &str) -> Result<ChipDef, HdlError> {
+    let mut lexer = Lexer { chars: source.chars().peekable(), line: 1, column: 1 };
+    let mut tokens = Vec::new();
+    while let Some(token) = lexer.next()? {
+        tokens.push(token);
+    }
+    Parser { tokens, position: 0 }.chip()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn pin(name: &str, range: Option<(usize, usize)>) -> PinRef {
+        PinRef { name: name.to_string(), range }
+    }
+
+    #[test]
+    fn parse_test() {
+        let chip = parse(include_str!("../../../hardware_sim/01/Mux.hdl")).unwrap();
+        assert_eq!("Mux", chip.name);
+        assert_eq!(vec!["a", "b", "sel"], chip.inputs.iter().map(|p| p.name.as_str()).collect::<Vec<&str>>());
+        assert_eq!(vec![PinDecl { name: "out".to_string(), width: 1 }], chip.outputs);
+        assert_eq!(4, chip.parts.len());
+        assert_eq!(
+            Part {
+                chip: "Not".to_string(),
+                connections: vec![
+                    Connection { internal: pin("in", None), external: pin("sel", None) },
+                    Connection { internal: pin("out", None), external: pin("wsel", None) },
+                ],
+                line: 17
+            },
+            chip.parts[0]
+        );
+    }
+
+    #[test]
+    fn bus_test() {
+        let chip = parse(include_str!("../../../hardware_sim/02/ALU.hdl")).unwrap();
+        assert_eq!(PinDecl { name: "x".to_string(), width: 16 }, chip.inputs[0]);
+        assert_eq!(8, chip.inputs.len());
+        assert_eq!(3, chip.outputs.len());
+        let mux = &chip.parts[10];
+        assert_eq!(pin("false", None), chip.parts[0].connections[1].external);
+        assert!(chip.parts[0].connections[1].external.is_constant());
+        assert_eq!(Connection { internal: pin("out", Some((15, 15))), external: pin("ng", None) }, mux.connections[4]);
+        assert_eq!(Connection { internal: pin("out", Some((8, 15))), external: pin("outhigh", None) }, mux.connections[6]);
+    }
+
+    #[test]
+    fn no_outputs_test() {
+        let chip = parse(include_str!("../../../hardware_sim/05/Computer.hdl")).unwrap();
+        assert_eq!(0, chip.outputs.len());
+        assert_eq!(3, chip.parts.len());
+    }
+
+    #[test]
+    fn error_test() {
+        assert_eq!(
+            Err(HdlError::Syntax { line: 2, column: 10, message: "expected '='".to_string() }),
+            parse("CHIP A { IN a; OUT b; PARTS:\n  Not(in a, out=b);\n}")
+        );
+        assert_eq!(
+            Err(HdlError::Syntax { line: 1, column: 10, message: "unterminated comment".to_string() }),
+            parse("CHIP A { /* IN a;")
+        );
+        assert!(parse("CHIP A { IN a[0]; PARTS: }").is_err());
+        assert!(parse("CHIP A { IN a; PARTS: Not(in=a[3..1], out=b); }").is_err());
+    }
+}
@@ -0,0 +1,315 @@
+use crate::bitslice::Lanes;
+use crate::equivalence;
+use crate::sequential_logic::{Bit, Dff, Pc, Ram64, Ram8, Register};
+use crate::signal::{self, Bus, Signal};
+
+// .hdl の代わりに Rust で書いたチップをテストスクリプトで動かすためのもの
+// ピンの名前と並びは builtin と同じ
+pub struct RustChip {
+    pub inputs: Vec<(&'static str, usize)>,
+    pub outputs: Vec<(&'static str, usize)>,
+    input_values: Vec<u64>,
+    output_values: Vec<u64>,
+    kind: Kind
+}
+
+enum Kind {
+    // equivalence::chips() のゲート。1 レーン目だけ使う
+    Gate(fn(&[Lanes]) -> Vec<Lanes>),
+    Clocked(Box<dyn Sequential<signal::Bit>>)
+}
+
+// 順序回路のピン。名前と並びは HDL と同じで、out は 1 本
+pub struct Pins {
+    pub name: &'static str,
+    pub inputs: &'static [(&'static str, usize)],
+    pub out: usize
+}
+
+impl Pins {
+    // address のピンの位置。メモリでなければ None
+    pub fn address(&self) -> Option<usize> {
+        self.inputs.iter().position(|(name, _)| *name == "address")
+    }
+}
+
+pub const DFF: Pins = Pins { name: "DFF", inputs: &[("in", 1)], out: 1 };
+pub const BIT: Pins = Pins { name: "Bit", inputs: &[("in", 1), ("load", 1)], out: 1 };
+pub const REGISTER: Pins = Pins { name: "Register", inputs: &[("in", 16), ("load", 1)], out: 16 };
+pub const RAM8: Pins = Pins { name: "RAM8", inputs: &[("in", 16), ("load", 1), ("address", 3)], out: 16 };
+pub const RAM64: Pins = Pins { name: "RAM64", inputs: &[("in", 16), ("load", 1), ("address", 6)], out: 16 };
+pub const PC: Pins = Pins { name: "PC", inputs: &[("in", 16), ("load", 1), ("inc", 1), ("reset", 1)], out: 16 };
+
+// HDL の sel や address (sel[0] が下の桁) を bool_logic の並び (sel[0] が上の桁) にする
+pub fn sel<S: Copy, const K: usize>(pin: &[S]) -> [S; K] {
+    std::array::from_fn(|i| pin[K - 1 - i])
+}
+
+// value の下から width 桁。pin[0] が下の桁
+pub fn pin<S: Signal>(value: u64, width: usize) -> Vec<S> {
+    (0..width).map(|i| S::constant(bit(value >> i))).collect()
+}
+
+pub fn value(pin: &[signal::Bit]) -> u64 {
+    pin.iter().enumerate().fold(0, |acc, (i, b)| acc | (u8::from(*b) as u64) << i)
+}
+
+// sequential_logic のチップを、どの信号でも同じピンで動かす
+// ピンはどれも下の桁から
+pub trait Sequential<S: Signal> {
+    fn pins(&self) -> &'static Pins;
+
+    // exec を 1 回 (1 クロック) 呼んで、その返り値 (進む前の out) を返す
+    fn step(&mut self, inputs: &[Vec<S>]) -> Vec<S>;
+
+    // 記憶している値。メモリなら番地ごと、それ以外は 1 つだけ
+    fn words(&self) -> Vec<Vec<S>>;
+
+    // 今 out に出ている値
+    fn out(&self, inputs: &[u64]) -> Vec<S> {
+        let mut words = self.words();
+        let address = self.pins().address().map_or(0, |a| inputs[a] as usize);
+        words.swap_remove(address)
+    }
+}
+
+fn word<S: Signal>(pin: &[S]) -> Bus<16, S> {
+    Bus::from_fn(|i| pin[i])
+}
+
+impl<S: Signal> Sequential<S> for Dff<S> {
+    fn pins(&self) -> &'static Pins {
+        &DFF
+    }
+
+    fn step(&mut self, inputs: &[Vec<S>]) -> Vec<S> {
+        vec![self.exec(inputs[0][0])]
+    }
+
+    fn words(&self) -> Vec<Vec<S>> {
+        vec![vec![self.value()]]
+    }
+}
+
+impl<S: Signal> Sequential<S> for Bit<S> {
+    fn pins(&self) -> &'static Pins {
+        &BIT
+    }
+
+    fn step(&mut self, inputs: &[Vec<S>]) -> Vec<S> {
+        vec![self.exec(inputs[0][0], inputs[1][0])]
+    }
+
+    fn words(&self) -> Vec<Vec<S>> {
+        vec![vec![self.value()]]
+    }
+}
+
+impl<S: Signal> Sequential<S> for Register<S> {
+    fn pins(&self) -> &'static Pins {
+        &REGISTER
+    }
+
+    fn step(&mut self, inputs: &[Vec<S>]) -> Vec<S> {
+        self.exec(&word(&inputs[0]), inputs[1][0]).bits().to_vec()
+    }
+
+    fn words(&self) -> Vec<Vec<S>> {
+        vec![self.value().bits().to_vec()]
+    }
+}
+
+impl<S: Signal> Sequential<S> for Ram8<S> {
+    fn pins(&self) -> &'static Pins {
+        &RAM8
+    }
+
+    fn step(&mut self, inputs: &[Vec<S>]) -> Vec<S> {
+        self.exec(&word(&inputs[0]), inputs[1][0], &sel(&inputs[2])).bits().to_vec()
+    }
+
+    fn words(&self) -> Vec<Vec<S>> {
+        (0..8).map(|a| self.value(a).bits().to_vec()).collect()
+    }
+}
+
+impl<S: Signal> Sequential<S> for Ram64<S> {
+    fn pins(&self) -> &'static Pins {
+        &RAM64
+    }
+
+    fn step(&mut self, inputs: &[Vec<S>]) -> Vec<S> {
+        self.exec(&word(&inputs[0]), inputs[1][0], &sel(&inputs[2])).bits().to_vec()
+    }
+
+    fn words(&self) -> Vec<Vec<S>> {
+        (0..64).map(|a| self.value(a).bits().to_vec()).collect()
+    }
+}
+
+// exec の引数は load, reset, inc の順なので入れ替える
+impl<S: Signal> Sequential<S> for Pc<S> {
+    fn pins(&self) -> &'static Pins {
+        &PC
+    }
+
+    fn step(&mut self, inputs: &[Vec<S>]) -> Vec<S> {
+        self.exec(&word(&inputs[0]), inputs[1][0], inputs[3][0], inputs[2][0]).bits().to_vec()
+    }
+
+    fn words(&self) -> Vec<Vec<S>> {
+        vec![self.value().bits().to_vec()]
+    }
+}
+
+// name の順序回路を、Dff を S::initial() にして作る。無ければ None
+pub fn sequential<S: Signal + 'static>(name: &str) -> Option<Box<dyn Sequential<S>>> {
+    match name {
+        "DFF" => Some(Box::new(Dff::new(S::initial()))),
+        "Bit" => Some(Box::new(Bit::new())),
+        "Register" => Some(Box::new(Register::new())),
+        "RAM8" => Some(Box::new(Ram8::new())),
+        "RAM64" => Some(Box::new(Ram64::new())),
+        "PC" => Some(Box::new(Pc::new())),
+        _ => None
+    }
+}
+
+fn bit(value: u64) -> signal::Bit {
+    signal::Bit::from(value & 1 == 1)
+}
+
+fn mask(width: usize) -> u64 {
+    if width >= 64 { !0 } else { (1 << width) - 1 }
+}
+
+impl RustChip {
+    // name の Rust のチップ。無ければ None
+    pub fn find(name: &str) -> Option<RustChip> {
+        if let Some(chip) = sequential(name) {
+            let pins = chip.pins();
+            return Some(RustChip {
+                inputs: pins.inputs.to_vec(),
+                outputs: vec![("out", pins.out)],
+                input_values: vec![0; pins.inputs.len()],
+                output_values: vec![0],
+                kind: Kind::Clocked(chip)
+            })
+        }
+        equivalence::chips().into_iter().find(|c| c.name == name).map(|c| RustChip {
+            input_values: vec![0; c.inputs.len()],
+            output_values: vec![0; c.outputs.len()],
+            inputs: c.inputs,
+            outputs: c.outputs,
+            kind: Kind::Gate(c.gate)
+        })
+    }
+
+    pub fn set(&mut self, pin: &str, value: u64) -> bool {
+        match self.inputs.iter().position(|(name, _)| *name == pin) {
+            Some(p) => {
+                self.input_values[p] = value & mask(self.inputs[p].1);
+                true
+            },
+            None => false
+        }
+    }
+
+    pub fn get(&self, pin: &str) -> Option<u64> {
+        if let Some(p) = self.inputs.iter().position(|(name, _)| *name == pin) {
+            return Some(self.input_values[p])
+        }
+        let p = self.outputs.iter().position(|(name, _)| *name == pin)?;
+        Some(self.output_values[p])
+    }
+
+    // 入力をピンごとの信号にする
+    fn pins<S: Signal>(&self) -> Vec<Vec<S>> {
+        self.inputs.iter().zip(&self.input_values).map(|((_, width), value)| pin(*value, *width)).collect()
+    }
+
+    pub fn eval(&mut self) {
+        match &self.kind {
+            Kind::Gate(gate) => {
+                let pins: Vec<Lanes> = self.pins().concat();
+                let out = gate(&pins);
+                let mut offset = 0;
+                for ((_, width), value) in self.outputs.iter().zip(self.output_values.iter_mut()) {
+                    *value = (0..*width).fold(0, |acc, i| acc | ((out[offset + i].0 & 1) << i));
+                    offset += width;
+                }
+            },
+            Kind::Clocked(chip) => self.output_values[0] = value(&chip.out(&self.input_values))
+        }
+    }
+
+    // 立ち上がりで 1 クロック進める。出力はまだ前の値のまま
+    pub fn tick(&mut self) {
+        let inputs = self.pins();
+        match &mut self.kind {
+            Kind::Gate(_) => self.eval(),
+            Kind::Clocked(chip) => self.output_values[0] = value(&chip.step(&inputs))
+        }
+    }
+
+    // 立ち下がりで進めた値を出力に出す
+    pub fn tock(&mut self) {
+        self.eval();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn gate_test() {
+        let mut mux = RustChip::find("Mux4Way16").unwrap();
+        for (pin, value) in [("a", 1), ("b", 2), ("c", 3), ("d", 4), ("sel", 2)].iter() {
+            assert!(mux.set(pin, *value));
+        }
+        mux.eval();
+        assert_eq!(Some(3), mux.get("out"));
+        assert!(!mux.set("e", 1));
+        assert!(RustChip::find("CPU").is_none());
+    }
+
+    #[test]
+    fn clocked_test() {
+        let mut ram = RustChip::find("RAM8").unwrap();
+        ram.set("in", 1234);
+        ram.set("load", 1);
+        ram.set("address", 6);
+        ram.tick();
+        assert_eq!(Some(0), ram.get("out"));
+        ram.tock();
+        assert_eq!(Some(1234), ram.get("out"));
+        ram.set("load", 0);
+        ram.set("address", 1);
+        ram.eval();
+        assert_eq!(Some(0), ram.get("out"));
+        ram.set("address", 6);
+        ram.eval();
+        assert_eq!(Some(1234), ram.get("out"));
+    }
+
+    #[test]
+    fn sequential_test() {
+        // HDL の sel[0] (下の桁) が bool_logic では最後に来る
+        assert_eq!([3, 2, 1], sel::<u8, 3>(&[1, 2, 3]));
+        assert_eq!(5, value(&pin::<signal::Bit>(5, 3)));
+        // in load inc reset の順で渡すと、load で in を取り込む
+        let mut pc = sequential::<signal::Bit>("PC").unwrap();
+        let inputs: Vec<Vec<signal::Bit>> = [(9, 16), (1, 1), (0, 1), (0, 1)].iter().map(|(v, w)| pin(*v, *w)).collect();
+        assert_eq!(0, value(&pc.step(&inputs)));
+        assert_eq!(9, value(&pc.out(&[9, 1, 0, 0])));
+        let mut dff = RustChip::find("DFF").unwrap();
+        dff.set("in", 1);
+        dff.eval();
+        assert_eq!(Some(0), dff.get("out"));
+        dff.tick();
+        dff.tock();
+        assert_eq!(Some(1), dff.get("out"));
+        assert!(sequential::<signal::Bit>("CPU").is_none());
+    }
+}
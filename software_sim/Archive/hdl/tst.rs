@@ -0,0 +1,693 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use super::netlist::Netlist;
+use super::rust_chip::RustChip;
+use super::{dir_loader, HdlError};
+
+// テストスクリプト (.tst) が動かす相手。HDL のネットリストか Rust で書いたチップ
+pub trait Target {
+    // 入力ピンに値を入れる。そんな入力ピンが無ければ false
+    fn set(&mut self, pin: &str, value: u64) -> bool;
+    fn get(&self, pin: &str) -> Option<u64>;
+    fn eval(&mut self);
+    fn tick(&mut self);
+    fn tock(&mut self);
+
+    // 部品が覚えている値 (RAM16K[3], PC[] など)。index はメモリの番地で、レジスタでは使わない
+    fn peek(&self, _part: &str, _index: usize) -> Option<u64> {
+        None
+    }
+    fn poke(&mut self, _part: &str, _index: usize, _value: u64) -> bool {
+        false
+    }
+}
+
+impl Target for Netlist {
+    fn set(&mut self, pin: &str, value: u64) -> bool {
+        Netlist::set(self, pin, value).is_ok()
+    }
+
+    fn get(&self, pin: &str) -> Option<u64> {
+        Netlist::get(self, pin)
+    }
+
+    fn eval(&mut self) {
+        Netlist::eval(self)
+    }
+
+    fn tick(&mut self) {
+        Netlist::tick(self)
+    }
+
+    fn tock(&mut self) {
+        Netlist::tock(self)
+    }
+
+    fn peek(&self, part: &str, index: usize) -> Option<u64> {
+        Netlist::peek(self, part, index)
+    }
+
+    fn poke(&mut self, part: &str, index: usize, value: u64) -> bool {
+        Netlist::poke(self, part, index, value)
+    }
+}
+
+impl Target for RustChip {
+    fn set(&mut self, pin: &str, value: u64) -> bool {
+        RustChip::set(self, pin, value)
+    }
+
+    fn get(&self, pin: &str) -> Option<u64> {
+        RustChip::get(self, pin)
+    }
+
+    fn eval(&mut self) {
+        RustChip::eval(self)
+    }
+
+    fn tick(&mut self) {
+        RustChip::tick(self)
+    }
+
+    fn tock(&mut self) {
+        RustChip::tock(self)
+    }
+}
+
+// load Xxx.hdl で何を読むか
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Backend {
+    // スクリプトと同じディレクトリの .hdl (無い部品は builtin)
+    Hdl,
+    // RustChip
+    Rust
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TstError {
+    // スクリプトの line 行目が読めない、または実行できない
+    Script { line: usize, message: String },
+    Io { file: String, message: String },
+    Hdl(HdlError)
+}
+
+impl fmt::Display for TstError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TstError::Script { line, message } => write!(f, "line {}: {}", line, message),
+            TstError::Io { file, message } => write!(f, "{}: {}", file, message),
+            TstError::Hdl(e) => write!(f, "{}", e)
+        }
+    }
+}
+
+impl std::error::Error for TstError {}
+
+// while がこれだけ回っても終わらなければ止める (Memory.tst のキーボード待ちなど、人が操作する前提のもの)
+pub const MAX_WHILE_ITERATIONS: usize = 100_000;
+
+// .cmp と最初に食い違った行。line は 1 始まりで、1 行目は見出し
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mismatch {
+    pub line: usize,
+    pub expected: String,
+    pub actual: String
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "comparison failure at line {}\n  expected: {}\n  actual:   {}", self.line, self.expected, self.actual)
+    }
+}
+
+// スクリプトを最後まで (食い違ったらそこまで) 動かした結果
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Report {
+    // output-file に書いたファイル名と、そこに書く行
+    pub output_file: Option<String>,
+    pub lines: Vec<String>,
+    // .cmp と比べた行の数
+    pub compared: usize,
+    pub mismatch: Option<Mismatch>,
+    pub echoes: Vec<String>
+}
+
+// output-list の 1 列。in%D1.6.1 なら format 'D'、左に 1 文字、値に 6 文字、右に 1 文字
+#[derive(Debug, Clone, PartialEq)]
+struct Column {
+    name: String,
+    format: char,
+    left: usize,
+    width: usize,
+    right: usize
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Condition {
+    Equal,
+    NotEqual,
+    Less,
+    Greater,
+    LessEqual,
+    GreaterEqual
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Command {
+    Load(String),
+    OutputFile(String),
+    CompareTo(String),
+    OutputList(Vec<Column>),
+    Set(String, u64),
+    Eval,
+    Tick,
+    Tock,
+    Output,
+    Echo(String),
+    ClearEcho,
+    // ROM32K load Add.hack
+    LoadInto(String, String),
+    Repeat(usize, Vec<(usize, Command)>),
+    While(String, Condition, u64, Vec<(usize, Command)>)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Text(String),
+    Symbol(char)
+}
+
+// (トークン, 行)
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, TstError> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    let mut line = 1;
+    while let Some(&c) = chars.peek() {
+        if c == '\n' {
+            line += 1;
+            chars.next();
+        } else if c.is_whitespace() {
+            chars.next();
+        } else if c == '/' && chars.clone().nth(1) == Some('/') {
+            while !matches!(chars.peek(), Some('\n') | None) {
+                chars.next();
+            }
+        } else if c == '/' && chars.clone().nth(1) == Some('*') {
+            let start = line;
+            chars.next();
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some('*') if chars.peek() == Some(&'/') => { chars.next(); break },
+                    Some('\n') => line += 1,
+                    Some(_) => {},
+                    None => return Err(TstError::Script { line: start, message: "unterminated comment".to_string() })
+                }
+            }
+        } else if c == '"' {
+            chars.next();
+            let mut s = String::new();
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\n') | None => return Err(TstError::Script { line, message: "unterminated string".to_string() }),
+                    Some(c) => s.push(c)
+                }
+            }
+            tokens.push((Token::Text(s), line));
+        } else if ",;{}".contains(c) {
+            chars.next();
+            tokens.push((Token::Symbol(c), line));
+        } else {
+            let mut s = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() || ",;{}\"".contains(c) {
+                    break
+                }
+                s.push(c);
+                chars.next();
+            }
+            tokens.push((Token::Word(s), line));
+        }
+    }
+    Ok(tokens)
+}
+
+// 123, -1, %B101, %XFF, %D7
+fn parse_value(s: &str) -> Option<u64> {
+    let (radix, digits) = match s.get(..2) {
+        Some("%B") => (2, &s[2..]),
+        Some("%X") => (16, &s[2..]),
+        Some("%D") => (10, &s[2..]),
+        _ => (10, s)
+    };
+    i64::from_str_radix(digits, radix).ok().map(|v| v as u64)
+}
+
+// time%S1.4.1。% が無ければ %B1.1.1
+fn parse_column(s: &str) -> Option<Column> {
+    let (name, spec) = match s.find('%') {
+        Some(i) => (&s[..i], &s[i + 1..]),
+        None => (s, "B1.1.1")
+    };
+    let format = spec.chars().next().filter(|c| "BDXS".contains(*c))?;
+    let numbers: Vec<usize> = spec[1..].split('.').map(|n| n.parse().ok()).collect::<Option<Vec<usize>>>()?;
+    match numbers[..] {
+        [left, width, right] if !name.is_empty() => Some(Column { name: name.to_string(), format, left, width, right }),
+        _ => None
+    }
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    position: usize
+}
+
+impl Parser {
+    fn line(&self) -> usize {
+        self.tokens.get(self.position).or_else(|| self.tokens.last()).map_or(1, |(_, line)| *line)
+    }
+
+    fn error<T>(&self, message: String) -> Result<T, TstError> {
+        Err(TstError::Script { line: self.line(), message })
+    }
+
+    fn eat(&mut self, symbol: char) -> bool {
+        if self.tokens.get(self.position).map(|(t, _)| t) == Some(&Token::Symbol(symbol)) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn number(&self, word: &str) -> Result<u64, TstError> {
+        match parse_value(word) {
+            Some(value) => Ok(value),
+            None => self.error(format!("not a number: {}", word))
+        }
+    }
+
+    // } か終わりまで読む
+    fn commands(&mut self, in_block: bool) -> Result<Vec<(usize, Command)>, TstError> {
+        let mut commands = Vec::new();
+        loop {
+            if self.position >= self.tokens.len() {
+                if in_block {
+                    return self.error("expected '}'".to_string())
+                }
+                return Ok(commands)
+            }
+            if in_block && self.eat('}') {
+                return Ok(commands)
+            }
+            let line = self.line();
+            commands.push((line, self.command()?));
+        }
+    }
+
+    // コマンドは , か ; で終わる。repeat / while は } で終わる
+    fn command(&mut self) -> Result<Command, TstError> {
+        let mut words = Vec::new();
+        let mut text = None;
+        loop {
+            match self.tokens.get(self.position).map(|(t, _)| t.clone()) {
+                Some(Token::Word(w)) => words.push(w),
+                Some(Token::Text(s)) => text = Some(s),
+                Some(Token::Symbol(',')) | Some(Token::Symbol(';')) => {
+                    self.position += 1;
+                    break
+                },
+                Some(Token::Symbol('{')) => {
+                    self.position += 1;
+                    return self.block(words)
+                },
+                Some(Token::Symbol(_)) => return self.error("unexpected '}'".to_string()),
+                None => return self.error("expected ',' or ';'".to_string())
+            }
+            self.position += 1;
+        }
+        let word = |i: usize| words.get(i).map(|w| w.as_str());
+        let command = match (word(0), words.len()) {
+            (Some("load"), 2) => Command::Load(words[1].clone()),
+            (Some("output-file"), 2) => Command::OutputFile(words[1].clone()),
+            (Some("compare-to"), 2) => Command::CompareTo(words[1].clone()),
+            (Some("output-list"), _) => {
+                let mut columns = Vec::new();
+                for w in &words[1..] {
+                    match parse_column(w) {
+                        Some(column) => columns.push(column),
+                        None => return self.error(format!("bad output-list entry: {}", w))
+                    }
+                }
+                Command::OutputList(columns)
+            },
+            (Some("set"), 3) => Command::Set(words[1].clone(), self.number(&words[2])?),
+            (Some("eval"), 1) => Command::Eval,
+            (Some("tick"), 1) => Command::Tick,
+            (Some("tock"), 1) => Command::Tock,
+            (Some("output"), 1) => Command::Output,
+            (Some("echo"), 1) => Command::Echo(text.unwrap_or_default()),
+            (Some("clear-echo"), 1) => Command::ClearEcho,
+            (Some(part), 3) if words[1] == "load" => Command::LoadInto(part.to_string(), words[2].clone()),
+            (None, _) => return self.error("empty command".to_string()),
+            _ => return self.error(format!("unknown command: {}", words.join(" ")))
+        };
+        Ok(command)
+    }
+
+    // repeat 6 { ... } / while out <> 0 { ... }
+    fn block(&mut self, words: Vec<String>) -> Result<Command, TstError> {
+        match words.iter().map(|w| w.as_str()).collect::<Vec<&str>>()[..] {
+            ["repeat", count] => {
+                let count = self.number(count)? as usize;
+                Ok(Command::Repeat(count, self.commands(true)?))
+            },
+            ["repeat"] => self.error("repeat without a count never ends".to_string()),
+            ["while", name, op, value] => {
+                let condition = match op {
+                    "=" => Condition::Equal,
+                    "<>" => Condition::NotEqual,
+                    "<" => Condition::Less,
+                    ">" => Condition::Greater,
+                    "<=" => Condition::LessEqual,
+                    ">=" => Condition::GreaterEqual,
+                    _ => return self.error(format!("unknown comparison: {}", op))
+                };
+                let value = self.number(value)?;
+                Ok(Command::While(name.to_string(), condition, value, self.commands(true)?))
+            },
+            _ => self.error(format!("unknown block: {}", words.join(" ")))
+        }
+    }
+}
+
+fn parse(source: &str) -> Result<Vec<(usize, Command)>, TstError> {
+    Parser { tokens: tokenize(source)?, position: 0 }.commands(false)
+}
+
+// 値は Hack と同じく 16 桁の 2 の補数として読む
+fn signed(value: u64) -> i64 {
+    value as u16 as i16 as i64
+}
+
+// RAM16K[3] -> ("RAM16K", 3)、PC[] -> ("PC", 0)
+fn split_index(name: &str) -> Option<(&str, usize)> {
+    let open = name.find('[')?;
+    let inside = name[open + 1..].strip_suffix(']')?;
+    let index = if inside.is_empty() { 0 } else { inside.parse().ok()? };
+    Some((&name[..open], index))
+}
+
+// .cmp の * はどの文字とも一致する
+fn matches(expected: &str, actual: &str) -> bool {
+    expected.len() == actual.len() && expected.chars().zip(actual.chars()).all(|(e, a)| e == '*' || e == a)
+}
+
+// load Xxx.hdl で Xxx と行番号から動かすものを作る
+pub type Loader<'a> = dyn FnMut(&str, usize) -> Result<Box<dyn Target>, TstError> + 'a;
+
+struct Runner<'a> {
+    dir: &'a Path,
+    load: &'a mut Loader<'a>,
+    target: Option<Box<dyn Target>>,
+    columns: Vec<Column>,
+    // tock で 1 進む。tick のあとは 0+ のように + が付く
+    time: usize,
+    ticked: bool,
+    compare: Option<Vec<String>>,
+    report: Report
+}
+
+impl<'a> Runner<'a> {
+    fn read(&self, file: &str) -> Result<String, TstError> {
+        fs::read_to_string(self.dir.join(file)).map_err(|e| TstError::Io { file: file.to_string(), message: e.to_string() })
+    }
+
+    fn target(&mut self, line: usize) -> Result<&mut Box<dyn Target>, TstError> {
+        self.target.as_mut().ok_or(TstError::Script { line, message: "no chip loaded".to_string() })
+    }
+
+    fn value(&self, line: usize, name: &str) -> Result<u64, TstError> {
+        let target = self.target.as_ref().ok_or(TstError::Script { line, message: "no chip loaded".to_string() })?;
+        target.get(name)
+            .or_else(|| split_index(name).and_then(|(part, index)| target.peek(part, index)))
+            .ok_or(TstError::Script { line, message: format!("unknown variable: {}", name) })
+    }
+
+    fn cell(&self, line: usize, column: &Column) -> Result<String, TstError> {
+        let body = if column.name == "time" {
+            format!("{:<width$}", format!("{}{}", self.time, if self.ticked { "+" } else { "" }), width = column.width)
+        } else {
+            let value = self.value(line, &column.name)?;
+            match column.format {
+                'B' => {
+                    let bits = (0..column.width).rev().map(|i| if i < 64 && (value >> i) & 1 == 1 { '1' } else { '0' });
+                    bits.collect()
+                },
+                'X' => format!("{:0width$X}", value & 0xffff, width = column.width),
+                'S' => format!("{:<width$}", signed(value), width = column.width),
+                _ => format!("{:>width$}", signed(value), width = column.width)
+            }
+        };
+        Ok(format!("{}{}{}", " ".repeat(column.left), body, " ".repeat(column.right)))
+    }
+
+    fn header(&self) -> String {
+        let cells: Vec<String> = self.columns.iter().map(|column| {
+            let total = column.left + column.width + column.right;
+            let name: String = column.name.chars().take(total).collect();
+            let left = (total - name.len()) / 2;
+            format!("{}{}{}", " ".repeat(left), name, " ".repeat(total - name.len() - left))
+        }).collect();
+        format!("|{}|", cells.join("|"))
+    }
+
+    // 1 行書いて .cmp と比べる。食い違ったら false
+    fn write(&mut self, row: String) -> bool {
+        let index = self.report.lines.len();
+        self.report.lines.push(row);
+        let expected = match self.compare.as_ref().and_then(|lines| lines.get(index)) {
+            Some(expected) => expected,
+            None => return true
+        };
+        self.report.compared += 1;
+        let actual = &self.report.lines[index];
+        if matches(expected, actual) {
+            return true
+        }
+        self.report.mismatch = Some(Mismatch { line: index + 1, expected: expected.clone(), actual: actual.clone() });
+        false
+    }
+
+    // 食い違いが見つかったら false を返して止める
+    fn run(&mut self, commands: &[(usize, Command)]) -> Result<bool, TstError> {
+        for (line, command) in commands {
+            let line = *line;
+            match command {
+                Command::Load(file) => {
+                    let name = file.strip_suffix(".hdl").unwrap_or(file);
+                    self.target = Some((self.load)(name, line)?);
+                },
+                Command::OutputFile(file) => self.report.output_file = Some(file.clone()),
+                Command::CompareTo(file) => {
+                    let text = self.read(file)?;
+                    self.compare = Some(text.lines().map(|l| l.trim_end_matches('\r').to_string()).collect());
+                },
+                Command::OutputList(columns) => {
+                    self.columns = columns.clone();
+                    if !self.write(self.header()) {
+                        return Ok(false)
+                    }
+                },
+                Command::Set(name, value) => {
+                    let target = self.target(line)?;
+                    if !target.set(name, *value) {
+                        let poked = split_index(name).is_some_and(|(part, index)| target.poke(part, index, *value));
+                        if !poked {
+                            return Err(TstError::Script { line, message: format!("cannot set {}", name) })
+                        }
+                    }
+                },
+                Command::Eval => self.target(line)?.eval(),
+                Command::Tick => {
+                    self.target(line)?.tick();
+                    self.ticked = true;
+                },
+                Command::Tock => {
+                    self.target(line)?.tock();
+                    self.time += 1;
+                    self.ticked = false;
+                },
+                Command::Output => {
+                    let cells = self.columns.iter().map(|c| self.cell(line, c)).collect::<Result<Vec<String>, TstError>>()?;
+                    if !self.write(format!("|{}|", cells.join("|"))) {
+                        return Ok(false)
+                    }
+                },
+                Command::Echo(text) => self.report.echoes.push(text.clone()),
+                // 画面に出すわけではないので、echo の記録は消さない
+                Command::ClearEcho => {},
+                Command::LoadInto(part, file) => {
+                    // .hack は 1 行 1 命令の 16 桁の 0/1
+                    let text = self.read(file)?;
+                    for (index, word) in text.lines().map(|l| l.trim()).filter(|l| !l.is_empty()).enumerate() {
+                        let value = u64::from_str_radix(word, 2)
+                            .map_err(|_| TstError::Io { file: file.clone(), message: format!("not machine code: {}", word) })?;
+                        if !self.target(line)?.poke(part, index, value) {
+                            return Err(TstError::Script { line, message: format!("cannot load into {}", part) })
+                        }
+                    }
+                },
+                Command::Repeat(count, body) => {
+                    for _ in 0..*count {
+                        if !self.run(body)? {
+                            return Ok(false)
+                        }
+                    }
+                },
+                Command::While(name, condition, value, body) => {
+                    for iteration in 0.. {
+                        if iteration == MAX_WHILE_ITERATIONS {
+                            return Err(TstError::Script {
+                                line,
+                                message: format!("while {} did not finish after {} iterations", name, MAX_WHILE_ITERATIONS)
+                            })
+                        }
+                        let (a, b) = (signed(self.value(line, name)?), signed(*value));
+                        let holds = match condition {
+                            Condition::Equal => a == b,
+                            Condition::NotEqual => a != b,
+                            Condition::Less => a < b,
+                            Condition::Greater => a > b,
+                            Condition::LessEqual => a <= b,
+                            Condition::GreaterEqual => a >= b
+                        };
+                        if !holds {
+                            break
+                        }
+                        if !self.run(body)? {
+                            return Ok(false)
+                        }
+                    }
+                }
+            }
+        }
+        Ok(true)
+    }
+}
+
+// script を動かす。.cmp や .hack はスクリプトと同じディレクトリから読む
+// .out は書かないので、書くなら Report::lines を output_file に書く
+pub fn run(script: &Path, backend: Backend) -> Result<Report, TstError> {
+    let dir = script.parent().unwrap_or_else(|| Path::new("."));
+    run_with(script, &mut |name: &str, line| -> Result<Box<dyn Target>, TstError> {
+        match backend {
+            Backend::Hdl => Ok(Box::new(Netlist::build(name, &dir_loader(dir)).map_err(TstError::Hdl)?)),
+            Backend::Rust => match RustChip::find(name) {
+                Some(chip) => Ok(Box::new(chip)),
+                None => Err(TstError::Script { line, message: format!("no Rust chip named {}", name) })
+            }
+        }
+    })
+}
+
+// load Xxx.hdl で load(Xxx, 行) が返すものを動かす
+pub fn run_with(script: &Path, load: &mut Loader) -> Result<Report, TstError> {
+    let source = fs::read_to_string(script)
+        .map_err(|e| TstError::Io { file: script.display().to_string(), message: e.to_string() })?;
+    let commands = parse(&source)?;
+    let dir = script.parent().unwrap_or_else(|| Path::new("."));
+    let mut runner = Runner {
+        dir,
+        load,
+        target: None,
+        columns: Vec::new(),
+        time: 0,
+        ticked: false,
+        compare: None,
+        report: Report::default()
+    };
+    runner.run(&commands)?;
+    Ok(runner.report)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn hardware(file: &str) -> PathBuf {
+        Path::new(file!()).parent().unwrap().join("../../../hardware_sim").join(file)
+    }
+
+    fn expected_out(file: &str) -> Vec<String> {
+        fs::read_to_string(hardware(file)).unwrap().lines().map(|l| l.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_test() {
+        assert_eq!(Some(5), parse_value("%B101"));
+        assert_eq!(Some(0x2000), parse_value("%X2000"));
+        assert_eq!(Some(-32123i64 as u64), parse_value("-32123"));
+        assert_eq!(None, parse_value("%Bx"));
+        assert_eq!(
+            Some(Column { name: "RAM16K[0]".to_string(), format: 'D', left: 1, width: 7, right: 1 }),
+            parse_column("RAM16K[0]%D1.7.1")
+        );
+        assert_eq!(Some(("PC", 0)), split_index("PC[]"));
+        assert_eq!(Some(("RAM16K", 2)), split_index("RAM16K[2]"));
+        let commands = parse("set a 1, /* x */ repeat 2 {\n tick, tock, output; }\n while out < 3 { eval; }").unwrap();
+        assert_eq!(3, commands.len());
+        assert_eq!((1, Command::Repeat(2, vec![(2, Command::Tick), (2, Command::Tock), (2, Command::Output)])), commands[1]);
+        assert_eq!(
+            Err(TstError::Script { line: 2, message: "unknown command: sett a 1".to_string() }),
+            parse("eval,\nsett a 1;")
+        );
+        assert_eq!(Err(TstError::Script { line: 1, message: "expected '}'".to_string() }), parse("repeat 3 { tick,"));
+    }
+
+    #[test]
+    fn format_test() {
+        assert!(matches("|  *** |", "|  123 |"));
+        assert!(!matches("|  0 |", "|  1 |"));
+        assert!(!matches("| 0 |", "| 0  |"));
+    }
+
+    #[test]
+    fn hdl_test() {
+        // 01, 02 の .out は .cmp と同じ中身なので、書く行も同じになるはず
+        for name in ["01/Mux", "01/DMux8Way", "02/ALU", "03/a/PC", "03/b/RAM512"].iter() {
+            let report = run(&hardware(&format!("{}.tst", name)), Backend::Hdl).unwrap();
+            assert_eq!(None, report.mismatch, "{}", name);
+            assert_eq!(expected_out(&format!("{}.out", name)), report.lines, "{}", name);
+            assert_eq!(report.lines.len(), report.compared);
+        }
+        assert_eq!(Some("Mux.out".to_string()), run(&hardware("01/Mux.tst"), Backend::Hdl).unwrap().output_file);
+    }
+
+    #[test]
+    fn computer_test() {
+        // ROM32K load Add.hack、RAM16K[0] や PC[] を output-list に使う
+        let report = run(&hardware("05/ComputerAdd.tst"), Backend::Hdl).unwrap();
+        assert_eq!(None, report.mismatch);
+        assert_eq!(expected_out("05/ComputerAdd.out"), report.lines);
+        // Memory.tst はキーボードが押されるのを待つので、最後まで動かせない
+        assert_eq!(
+            Err(TstError::Script { line: 108, message: format!("while out did not finish after {} iterations", MAX_WHILE_ITERATIONS) }),
+            run(&hardware("05/Memory.tst"), Backend::Hdl)
+        );
+    }
+
+    #[test]
+    fn rust_test() {
+        for name in ["01/Mux4Way16", "02/ALU", "03/a/RAM8", "03/a/RAM64", "03/a/PC"].iter() {
+            let report = run(&hardware(&format!("{}.tst", name)), Backend::Rust).unwrap();
+            assert_eq!(None, report.mismatch, "{}", name);
+        }
+        assert!(matches!(run(&hardware("05/CPU.tst"), Backend::Rust), Err(TstError::Script { .. })));
+    }
+}
@@ -0,0 +1,167 @@
+use std::cell::Cell;
+
+use crate::bool_arithmetic;
+use crate::bool_logic;
+use crate::sequential_logic::{Pc, Ram64, Ram8, Register};
+use crate::signal::{Bit, Bus, Signal, O};
+
+// 計測用の信号。値のほかに、入力 (または Dff の出力) から何段の nand を通ってきたかを持つ
+// nand を通るたびに回数を数えるので、チップを 1 回動かせば nand の数と一番長い経路が分かる
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Traced {
+    pub value: Bit,
+    pub depth: u32
+}
+
+thread_local! {
+    static NANDS: Cell<u64> = const { Cell::new(0) };
+    static MAX_DEPTH: Cell<u32> = const { Cell::new(0) };
+}
+
+impl Traced {
+    // チップの入力ピン
+    pub fn input(value: Bit) -> Traced {
+        Traced { value, depth: 0 }
+    }
+}
+
+impl Signal for Traced {
+    fn nand(self, other: Traced) -> Traced {
+        let depth = self.depth.max(other.depth) + 1;
+        NANDS.with(|n| n.set(n.get() + 1));
+        MAX_DEPTH.with(|d| d.set(d.get().max(depth)));
+        Traced { value: self.value.nand(other.value), depth }
+    }
+
+    fn constant(bit: Bit) -> Traced {
+        Traced::input(bit)
+    }
+
+    // Dff から出てくる値は次のクロックの始まりなので 0 段に戻す
+    fn latch(self) -> Traced {
+        Traced::input(self.value)
+    }
+}
+
+// 1 回の評価にかかった nand の数と、一番長い nand の段数 (クリティカルパス)
+// nands は評価した回数なので、xor の nand(x, y) のように同じ式を 2 回書くと 2 回数える
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cost {
+    pub nands: u64,
+    pub depth: u32
+}
+
+// f の中で評価した nand を数える
+// 段数は出力だけでなく途中の全ての nand を見るので、Dff に入るまでの経路も含む
+pub fn measure<F: FnOnce()>(f: F) -> Cost {
+    NANDS.with(|n| n.set(0));
+    MAX_DEPTH.with(|d| d.set(0));
+    f();
+    Cost {
+        nands: NANDS.with(|n| n.get()),
+        depth: MAX_DEPTH.with(|d| d.get())
+    }
+}
+
+fn input() -> Traced {
+    Traced::input(O)
+}
+
+fn word() -> Bus<16, Traced> {
+    Bus::constant(O)
+}
+
+// チップごとの (名前, コスト)。順序回路は exec を 1 クロック分
+pub fn costs() -> Vec<(&'static str, Cost)> {
+    vec![
+        ("Nand", measure(|| { bool_logic::nand(input(), input()); })),
+        ("Not", measure(|| { bool_logic::not(input()); })),
+        ("And", measure(|| { bool_logic::and(input(), input()); })),
+        ("Or", measure(|| { bool_logic::or(input(), input()); })),
+        ("Xor", measure(|| { bool_logic::xor(input(), input()); })),
+        ("Mux", measure(|| { bool_logic::mux(input(), input(), input()); })),
+        ("DMux", measure(|| { bool_logic::dmux(input(), input()); })),
+        ("Not16", measure(|| { bool_logic::not_16bit(&word()); })),
+        ("And16", measure(|| { bool_logic::and_16bit(&word(), &word()); })),
+        ("Or16", measure(|| { bool_logic::or_16bit(&word(), &word()); })),
+        ("Mux16", measure(|| { bool_logic::mux_16bit(&word(), &word(), input()); })),
+        ("Or8Way", measure(|| { bool_logic::or_8way(&[input(); 8]); })),
+        ("Mux4Way16", measure(|| {
+            bool_logic::mux_4way_16bit(&word(), &word(), &word(), &word(), &[input(); 2]);
+        })),
+        ("Mux8Way16", measure(|| {
+            let w = word();
+            bool_logic::mux_8way_16bit(&w, &w, &w, &w, &w, &w, &w, &w, &[input(); 3]);
+        })),
+        ("DMux4Way", measure(|| { bool_logic::dmux_4way(input(), &[input(); 2]); })),
+        ("DMux8Way", measure(|| { bool_logic::dmux_8way(input(), &[input(); 3]); })),
+        ("HalfAdder", measure(|| { bool_arithmetic::half_adder(input(), input()); })),
+        ("FullAdder", measure(|| { bool_arithmetic::full_adder(input(), input(), input()); })),
+        ("Add16", measure(|| { bool_arithmetic::adder_16bit(&word(), &word()); })),
+        ("Inc16", measure(|| { bool_arithmetic::incrementer(&word()); })),
+        ("ALU", measure(|| {
+            let i = input();
+            bool_arithmetic::alu(&word(), &word(), i, i, i, i, i, i);
+        })),
+        ("Register", measure(|| { Register::new().exec(&word(), input()); })),
+        ("RAM8", measure(|| { Ram8::new().exec(&word(), input(), &[input(); 3]); })),
+        ("RAM64", measure(|| { Ram64::new().exec(&word(), input(), &[input(); 6]); })),
+        ("PC", measure(|| { Pc::new().exec(&word(), input(), input(), input()); })),
+    ]
+}
+
+pub fn report() -> String {
+    let mut out = format!("{:<12}{:>8}{:>7}\n", "chip", "nand", "depth");
+    for (name, cost) in costs() {
+        out.push_str(&format!("{:<12}{:>8}{:>7}\n", name, cost.nands, cost.depth));
+    }
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn cost_of(name: &str) -> Cost {
+        costs().into_iter().find(|(n, _)| *n == name).unwrap().1
+    }
+
+    #[test]
+    fn basic_gate_test() {
+        assert_eq!(Cost { nands: 1, depth: 1 }, cost_of("Nand"));
+        assert_eq!(Cost { nands: 1, depth: 1 }, cost_of("Not"));
+        assert_eq!(Cost { nands: 2, depth: 2 }, cost_of("And"));
+        assert_eq!(Cost { nands: 3, depth: 2 }, cost_of("Or"));
+        assert_eq!(Cost { nands: 5, depth: 3 }, cost_of("Xor"));
+        assert_eq!(Cost { nands: 8, depth: 5 }, cost_of("Mux"));
+    }
+
+    #[test]
+    fn adder_test() {
+        assert_eq!(Cost { nands: 7, depth: 3 }, cost_of("HalfAdder"));
+        assert_eq!(Cost { nands: 17, depth: 7 }, cost_of("FullAdder"));
+        // 桁上がりが 16 桁ぶん伝わる
+        assert_eq!(Cost { nands: 16 * 17, depth: 67 }, cost_of("Add16"));
+    }
+
+    #[test]
+    fn sequential_test() {
+        // Dff から出た値は 0 段目なので、Register の段数は入力から Dff までの Mux だけ
+        assert_eq!(Cost { nands: 16 * 8, depth: 5 }, cost_of("Register"));
+    }
+
+    #[test]
+    fn value_test() {
+        // 計測しても値は Bit と同じ
+        let one = Traced::input(Bit::ONE);
+        assert_eq!(Bit::ONE, bool_logic::xor(one, Traced::input(O)).value);
+        assert_eq!(O, bool_logic::xor(one, one).value);
+    }
+
+    #[test]
+    fn report_test() {
+        let report = report();
+        assert!(report.starts_with("chip"));
+        assert!(report.contains("\nXor                5      3\n"));
+    }
+}
@@ -1,9 +1,248 @@
+mod signal;
 mod bool_logic;
 mod bool_arithmetic;
 mod sequential_logic;
+mod instrument;
+mod bitslice;
+mod equivalence;
 mod assembler;
+mod hdl;
+mod schematic;
+mod vcd;
+mod three_valued;
+mod fault;
+mod verilog;
+mod synthesis;
+mod vectors;
 
 fn main() {
+    // --gate-report を付けるとチップごとの nand の数と段数を出して終わる
+    if std::env::args().any(|a| a == "--gate-report") {
+        print!("{}", instrument::report());
+        return
+    }
+    // --exhaustive を付けると 64 本並列の評価で Add16 を全入力、ALU を乱数で調べて終わる
+    if std::env::args().any(|a| a == "--exhaustive") {
+        match bitslice::check_adder(0..=0xffff) {
+            Some((a, b, out)) => println!("Add16: {} + {} gave {}", a, b, out),
+            None => println!("Add16: ok (2^32 inputs)")
+        }
+        match bitslice::check_alu(1 << 20, 1) {
+            Some((x, y, control)) => println!("ALU: x={} y={} control={:06b} differs", x, y, control),
+            None => println!("ALU: ok ({} random x, y with all controls)", 1 << 20)
+        }
+        return
+    }
+    // --check-gates を付けると全チップを仕様と比べて、食い違えば一番小さい反例を出す
+    if std::env::args().any(|a| a == "--check-gates") {
+        let (report, ok) = equivalence::report(1 << 16, 1);
+        print!("{}", report);
+        if !ok {
+            std::process::exit(1)
+        }
+        return
+    }
+    // --hdl Chip.hdl を付けるとチップを builtin までほどいて、部品の数を出す
+    // 部品の .hdl は同じディレクトリから探し、無ければ builtin を使う
+    if let Some(file) = std::env::args().skip_while(|a| a != "--hdl").nth(1) {
+        let path = std::path::Path::new(&file);
+        let name = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        let loader = hdl::dir_loader(path.parent().unwrap_or_else(|| std::path::Path::new(".")));
+        match hdl::netlist::Netlist::build(&name, &loader) {
+            Ok(netlist) => print!("{}", netlist.summary()),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1)
+            }
+        }
+        return
+    }
+    // --tst Xxx.tst を付けるとテストスクリプトを動かして .out を書き、.cmp と食い違った最初の行を出す
+    // --rust も付けると .hdl の代わりに Rust で書いたチップを動かす
+    if let Some(file) = std::env::args().skip_while(|a| a != "--tst").nth(1) {
+        let backend = if std::env::args().any(|a| a == "--rust") { hdl::tst::Backend::Rust } else { hdl::tst::Backend::Hdl };
+        let script = std::path::Path::new(&file);
+        let report = match hdl::tst::run(script, backend) {
+            Ok(report) => report,
+            Err(e) => {
+                eprintln!("{}: {}", file, e);
+                std::process::exit(1)
+            }
+        };
+        if let Some(out) = &report.output_file {
+            let path = script.parent().unwrap_or_else(|| std::path::Path::new(".")).join(out);
+            let text: String = report.lines.iter().map(|l| format!("{}\n", l)).collect();
+            if let Err(e) = std::fs::write(&path, text) {
+                eprintln!("{}: {}", path.display(), e);
+                std::process::exit(1)
+            }
+        }
+        match &report.mismatch {
+            Some(mismatch) => {
+                println!("{}: {}", file, mismatch);
+                std::process::exit(1)
+            },
+            None => println!("{}: ok ({} lines compared)", file, report.compared)
+        }
+        return
+    }
+    // --dot Chip か --dot Chip.hdl を付けるとチップの回路図を Graphviz の DOT で出す
+    // --depth N で N 段目より内側の部品を 1 つの箱に畳む (省略すると 1)
+    if let Some(chip) = std::env::args().skip_while(|a| a != "--dot").nth(1) {
+        let depth = std::env::args().skip_while(|a| a != "--depth").nth(1).and_then(|d| d.parse().ok()).unwrap_or(1);
+        let schematic = if chip.ends_with(".hdl") {
+            let path = std::path::Path::new(&chip);
+            let name = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+            let loader = hdl::dir_loader(path.parent().unwrap_or_else(|| std::path::Path::new(".")));
+            match hdl::netlist::Netlist::build(&name, &loader) {
+                Ok(netlist) => schematic::Schematic::from_netlist(&netlist),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    std::process::exit(1)
+                }
+            }
+        } else {
+            match schematic::rust_chip(&chip) {
+                Some(schematic) => schematic,
+                None => {
+                    eprintln!("unknown chip: {}", chip);
+                    std::process::exit(1)
+                }
+            }
+        };
+        print!("{}", schematic.dot(depth));
+        return
+    }
+    // --vcd Chip を付けると標準入力の 1 行を 1 クロックの入力として Rust の順序回路を動かし、VCD を標準出力に出す
+    // 入力は HDL のピンの順 (PC なら in load inc reset) に 10 進数を空白区切りで書く
+    // 部品ごとに $scope を分けて、中の部品の in と out も出す
+    if let Some(chip) = std::env::args().skip_while(|a| a != "--vcd").nth(1) {
+        let mut text = String::new();
+        if let Err(e) = std::io::Read::read_to_string(&mut std::io::stdin(), &mut text) {
+            eprintln!("{}", e);
+            std::process::exit(1)
+        }
+        match vcd::run(&chip, &text) {
+            Some(Ok(vcd)) => print!("{}", vcd),
+            Some(Err(e)) => {
+                eprintln!("{}", e);
+                std::process::exit(1)
+            },
+            None => {
+                eprintln!("unknown chip: {}", chip);
+                std::process::exit(1)
+            }
+        }
+        return
+    }
+    // --x-check Chip を付けると Dff を X (不定) から始めて、--vcd と同じ形の入力で Rust の順序回路を動かす
+    // out やメモリから読んだ値に X が残ったクロックを出す。入力に x と書けば全部の桁が X になる
+    if let Some(chip) = std::env::args().skip_while(|a| a != "--x-check").nth(1) {
+        let mut text = String::new();
+        if let Err(e) = std::io::Read::read_to_string(&mut std::io::stdin(), &mut text) {
+            eprintln!("{}", e);
+            std::process::exit(1)
+        }
+        match three_valued::run(&chip, &text) {
+            Some(Ok(unknowns)) => {
+                for unknown in &unknowns {
+                    println!("{}", unknown);
+                }
+                if !unknowns.is_empty() {
+                    std::process::exit(1)
+                }
+                println!("ok (no X on out)");
+            },
+            Some(Err(e)) => {
+                eprintln!("{}", e);
+                std::process::exit(1)
+            },
+            None => {
+                eprintln!("unknown chip: {}", chip);
+                std::process::exit(1)
+            }
+        }
+        return
+    }
+    // --faults Xxx.tst を付けるとスクリプトが読み込む Rust のチップの nand を 1 つずつ 0 / 1 に固定して流し直し、
+    // 出力が変わらなかった (スクリプトで見つけられない) 故障を出す
+    // --faults Chip なら、bool_arithmetic.rs と sequential_logic.rs の #[test] に書いたベクタで同じことをする
+    if let Some(arg) = std::env::args().skip_while(|a| a != "--faults").nth(1) {
+        let coverage = if arg.ends_with(".tst") {
+            fault::coverage(std::path::Path::new(&arg)).map_err(|e| e.to_string())
+        } else {
+            match vectors::find(&arg) {
+                Some(vectors) => fault::vector_coverage(&vectors),
+                None => {
+                    let names: Vec<&str> = vectors::all().iter().map(|v| v.chip).collect();
+                    Err(format!("no test vectors (try {})", names.join(", ")))
+                }
+            }
+        };
+        match coverage {
+            Ok(coverage) => {
+                println!("{}", coverage);
+                if let Some(mismatch) = &coverage.mismatch {
+                    println!("note: the fault-free chip already fails the script, vectors after it were not used\n{}", mismatch);
+                }
+                for fault in &coverage.undetected {
+                    println!("  undetected: {}", fault);
+                }
+            },
+            Err(e) => {
+                eprintln!("{}: {}", arg, e);
+                std::process::exit(1)
+            }
+        }
+        return
+    }
+    // --verilog Chip を付けると Rust のチップを nand と DFF の構造記述の Verilog で出す。部品ごとに module を分ける
+    // #[test] のベクタがあるチップなら、そのベクタで Rust と比べる testbench (Chip_tb) も出す
+    // --verilog Xxx.tst なら、スクリプトが読み込むチップと、スクリプトと同じ入力で Rust と比べる testbench (Chip_tb) を出す
+    if let Some(arg) = std::env::args().skip_while(|a| a != "--verilog").nth(1) {
+        if arg.ends_with(".tst") {
+            match verilog::testbench(std::path::Path::new(&arg)) {
+                Ok((schematic, bench)) => print!("{}\n{}", verilog::design(&schematic), bench),
+                Err(e) => {
+                    eprintln!("{}: {}", arg, e);
+                    std::process::exit(1)
+                }
+            }
+        } else {
+            match (schematic::rust_chip(&arg), vectors::find(&arg)) {
+                (Some(_), Some(vectors)) => match verilog::vector_testbench(&vectors) {
+                    Ok((schematic, bench)) => print!("{}\n{}", verilog::design(&schematic), bench),
+                    Err(e) => {
+                        eprintln!("{}: {}", arg, e);
+                        std::process::exit(1)
+                    }
+                },
+                (Some(schematic), None) => print!("{}", verilog::design(&schematic)),
+                (None, _) => {
+                    eprintln!("unknown chip: {}", arg);
+                    std::process::exit(1)
+                }
+            }
+        }
+        return
+    }
+    // --synth "(a & !sel) | (b & sel)" か --synth "a b sel = 00011011" で、nand だけの回路を .hdl で出す
+    if let Some(arg) = std::env::args().skip_while(|a| a != "--synth").nth(1) {
+        let table = if arg.contains('=') { synthesis::TruthTable::parse(&arg) } else { synthesis::TruthTable::from_expr(&arg) };
+        match table {
+            Ok(table) => {
+                let (terms, circuit) = synthesis::synthesize(&table);
+                println!("// out = {}", synthesis::expression(&table.inputs, &terms));
+                println!("// {} nands", circuit.nands());
+                print!("{}", circuit.hdl("Synth"));
+            },
+            Err(e) => {
+                eprintln!("{}: {}", arg, e);
+                std::process::exit(1)
+            }
+        }
+        return
+    }
     assembler::main();
     let s = "12(3456789";
     // let ss = {
@@ -0,0 +1,651 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::bool_arithmetic;
+use crate::bool_logic;
+use crate::hdl::netlist::Netlist;
+use crate::hdl::rust_chip::{self, sel};
+use crate::signal::{self, Bus, Signal};
+
+// 0 番のネットはいつも false、1 番はいつも true (hdl::netlist と同じ)
+const FALSE: usize = 0;
+const TRUE: usize = 1;
+
+// チップを部品の入れ子と、一番下の部品 (cell) をつなぐネットにしたもの
+// Rust のチップなら cell は Nand と DFF、HDL のネットリストなら builtin
+pub struct Schematic {
+    pub name: String,
+    pub inputs: Vec<(String, Vec<usize>)>,
+    pub outputs: Vec<(String, Vec<usize>)>,
+    pub cells: Vec<Cell>
+}
+
+pub struct Cell {
+    pub chip: String,
+    // 外側から順に、cell を囲んでいる部品。Register#3 は親の中で 3 番目の部品
+    pub path: Vec<String>,
+    pub inputs: Vec<usize>,
+    pub outputs: Vec<usize>
+}
+
+// cell を path でまとめた部品 1 つ分
+pub struct Block {
+    // 外側から順に。空ならチップ全体
+    pub path: Vec<String>,
+    // 外で作って中で使うネットと、中で作って外 (か出力ピン) で使うネット。どちらも番号順で、定数は入れない
+    pub inputs: Vec<usize>,
+    pub outputs: Vec<usize>,
+    // すぐ下の部品 (blocks の番号) と、この部品に直接入っている cell
+    pub children: Vec<usize>,
+    pub cells: Vec<usize>
+}
+
+// 回路図を取るための信号。値は持たず、どのネットかだけを持つ
+// nand や Dff を通るたびに、今いる部品の中に cell を足していく
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Wire(usize);
+
+struct Scope {
+    label: String,
+    parent: usize,
+    children: Vec<usize>,
+    // このクロックで何番目の子まで入ったか
+    cursor: usize
+}
+
+struct Recorder {
+    nets: usize,
+    cells: Vec<Cell>,
+    // 0 番はチップ全体
+    scopes: Vec<Scope>,
+    current: usize,
+    // このクロックで作った DFF (cells の番号)
+    dffs: Vec<usize>
+}
+
+thread_local! {
+    static RECORDER: RefCell<Option<Recorder>> = const { RefCell::new(None) };
+}
+
+fn with_recorder<T>(f: impl FnOnce(&mut Recorder) -> T) -> T {
+    RECORDER.with(|r| f(r.borrow_mut().as_mut().expect("Wire can only be used inside schematic::record")))
+}
+
+impl Recorder {
+    fn new_net(&mut self) -> usize {
+        self.nets += 1;
+        self.nets - 1
+    }
+
+    fn path(&self) -> Vec<String> {
+        let mut path = Vec::new();
+        let mut scope = self.current;
+        while scope != 0 {
+            path.push(self.scopes[scope].label.clone());
+            scope = self.scopes[scope].parent;
+        }
+        path.reverse();
+        path
+    }
+
+    fn cell(&mut self, chip: &str, inputs: Vec<usize>) -> usize {
+        let net = self.new_net();
+        let path = self.path();
+        self.cells.push(Cell { chip: chip.to_string(), path, inputs, outputs: vec![net] });
+        net
+    }
+
+    // 2 回目のクロックでも同じ順に部品に入るので、同じ部品には同じ番号が付く
+    fn enter(&mut self, name: &'static str) {
+        let parent = self.current;
+        let cursor = self.scopes[parent].cursor;
+        let child = match self.scopes[parent].children.get(cursor) {
+            Some(child) => *child,
+            None => {
+                self.scopes.push(Scope { label: format!("{}#{}", name, cursor), parent, children: Vec::new(), cursor: 0 });
+                let child = self.scopes.len() - 1;
+                self.scopes[parent].children.push(child);
+                child
+            }
+        };
+        self.scopes[parent].cursor += 1;
+        self.current = child;
+    }
+
+    fn leave(&mut self) {
+        self.current = self.scopes[self.current].parent;
+    }
+
+    fn next_clock(&mut self) {
+        for scope in self.scopes.iter_mut() {
+            scope.cursor = 0;
+        }
+        self.current = 0;
+        self.dffs.clear();
+    }
+}
+
+impl Signal for Wire {
+    fn nand(self, other: Wire) -> Wire {
+        Wire(with_recorder(|r| r.cell("Nand", vec![self.0, other.0])))
+    }
+
+    fn constant(bit: signal::Bit) -> Wire {
+        Wire(if bit.is_one() { TRUE } else { FALSE })
+    }
+
+    fn latch(self) -> Wire {
+        Wire(with_recorder(|r| {
+            let net = r.cell("DFF", vec![self.0]);
+            r.dffs.push(r.cells.len() - 1);
+            net
+        }))
+    }
+
+    fn enter(name: &'static str) {
+        with_recorder(|r| r.enter(name))
+    }
+
+    fn leave() {
+        with_recorder(|r| r.leave())
+    }
+}
+
+// f にピンごとの Wire を渡してチップを 1 クロック動かし、出力ピンごとの Wire を返してもらう
+// 順序回路は 1 回目では Dff の出力がまだ無いので、2 回動かして 2 回目の Dff の入力を 1 回目の Dff につなぎ直す
+pub fn record<F>(name: &str, inputs: &[(&str, usize)], outputs: &[&str], mut f: F) -> Schematic
+    where F: FnMut(&[Vec<Wire>]) -> Vec<Vec<Wire>> {
+    let mut recorder = Recorder {
+        nets: 2,
+        cells: Vec::new(),
+        scopes: vec![Scope { label: String::new(), parent: 0, children: Vec::new(), cursor: 0 }],
+        current: 0,
+        dffs: Vec::new()
+    };
+    let pins: Vec<Vec<Wire>> = inputs.iter().map(|(_, width)| (0..*width).map(|_| Wire(recorder.new_net())).collect()).collect();
+    RECORDER.with(|r| *r.borrow_mut() = Some(recorder));
+    let mut result = f(&pins);
+    let first_dffs = with_recorder(|r| {
+        let dffs = r.dffs.clone();
+        r.next_clock();
+        dffs
+    });
+    if !first_dffs.is_empty() {
+        result = f(&pins);
+    }
+    let mut recorder = RECORDER.with(|r| r.borrow_mut().take()).unwrap();
+    if !first_dffs.is_empty() {
+        assert_eq!(first_dffs.len(), recorder.dffs.len(), "{} used a different number of Dffs on the second clock", name);
+        for (first, second) in first_dffs.iter().zip(recorder.dffs.clone()) {
+            recorder.cells[*first].inputs = recorder.cells[second].inputs.clone();
+        }
+    }
+
+    // 出力から逆にたどって、使われている cell だけ残す (1 回目のクロックの cell はここで消える)
+    let mut driver: HashMap<usize, usize> = HashMap::new();
+    for (index, cell) in recorder.cells.iter().enumerate() {
+        for net in &cell.outputs {
+            driver.insert(*net, index);
+        }
+    }
+    let mut used = vec![false; recorder.cells.len()];
+    let mut stack: Vec<usize> = result.iter().flatten().map(|w| w.0).collect();
+    while let Some(net) = stack.pop() {
+        if let Some(&index) = driver.get(&net) {
+            if !used[index] {
+                used[index] = true;
+                stack.extend(recorder.cells[index].inputs.iter().copied());
+            }
+        }
+    }
+    let mut cells: Vec<Cell> = recorder.cells.into_iter().zip(used).filter(|(_, used)| *used).map(|(cell, _)| cell).collect();
+
+    // 一番外の部品はチップそのものなので外す
+    let top = format!("{}#0", name);
+    if !cells.is_empty() && cells.iter().all(|c| c.path.first() == Some(&top)) {
+        for cell in cells.iter_mut() {
+            cell.path.remove(0);
+        }
+    }
+    Schematic {
+        name: name.to_string(),
+        inputs: inputs.iter().zip(&pins).map(|((n, _), wires)| (n.to_string(), wires.iter().map(|w| w.0).collect())).collect(),
+        outputs: outputs.iter().zip(&result).map(|(n, wires)| (n.to_string(), wires.iter().map(|w| w.0).collect())).collect(),
+        cells
+    }
+}
+
+fn word(pin: &[Wire]) -> Bus<16, Wire> {
+    Bus::from_fn(|i| pin[i])
+}
+
+fn bits<const N: usize>(bus: Bus<N, Wire>) -> Vec<Wire> {
+    bus.bits().to_vec()
+}
+
+const A_B: &[(&str, usize)] = &[("a", 1), ("b", 1)];
+const A_B16: &[(&str, usize)] = &[("a", 16), ("b", 16)];
+const WAYS: [&str; 8] = ["a", "b", "c", "d", "e", "f", "g", "h"];
+
+// bool_logic / bool_arithmetic / sequential_logic のチップを HDL と同じ名前とピンで記録する
+pub fn rust_chip(name: &str) -> Option<Schematic> {
+    let schematic = match name {
+        "Not" => record(name, &[("in", 1)], &["out"], |p| vec![vec![bool_logic::not(p[0][0])]]),
+        "And" => record(name, A_B, &["out"], |p| vec![vec![bool_logic::and(p[0][0], p[1][0])]]),
+        "Or" => record(name, A_B, &["out"], |p| vec![vec![bool_logic::or(p[0][0], p[1][0])]]),
+        "Nor" => record(name, A_B, &["out"], |p| vec![vec![bool_logic::nor(p[0][0], p[1][0])]]),
+        "Xor" => record(name, A_B, &["out"], |p| vec![vec![bool_logic::xor(p[0][0], p[1][0])]]),
+        "Mux" => record(name, &[("a", 1), ("b", 1), ("sel", 1)], &["out"], |p| {
+            vec![vec![bool_logic::mux(p[0][0], p[1][0], p[2][0])]]
+        }),
+        "DMux" => record(name, &[("in", 1), ("sel", 1)], &["a", "b"], |p| {
+            bool_logic::dmux(p[0][0], p[1][0]).iter().map(|w| vec![*w]).collect()
+        }),
+        "Not16" => record(name, &[("in", 16)], &["out"], |p| vec![bits(bool_logic::not_16bit(&word(&p[0])))]),
+        "And16" => record(name, A_B16, &["out"], |p| vec![bits(bool_logic::and_16bit(&word(&p[0]), &word(&p[1])))]),
+        "Or16" => record(name, A_B16, &["out"], |p| vec![bits(bool_logic::or_16bit(&word(&p[0]), &word(&p[1])))]),
+        "Mux16" => record(name, &[("a", 16), ("b", 16), ("sel", 1)], &["out"], |p| {
+            vec![bits(bool_logic::mux_16bit(&word(&p[0]), &word(&p[1]), p[2][0]))]
+        }),
+        "Or8Way" => record(name, &[("in", 8)], &["out"], |p| vec![vec![bool_logic::or_8way(&std::array::from_fn(|i| p[0][i]))]]),
+        "Mux4Way16" => record(name, &[("a", 16), ("b", 16), ("c", 16), ("d", 16), ("sel", 2)], &["out"], |p| {
+            vec![bits(bool_logic::mux_4way_16bit(&word(&p[0]), &word(&p[1]), &word(&p[2]), &word(&p[3]), &sel(&p[4])))]
+        }),
+        "Mux8Way16" => {
+            let inputs: Vec<(&str, usize)> = WAYS.iter().map(|w| (*w, 16)).chain(std::iter::once(("sel", 3))).collect();
+            record(name, &inputs, &["out"], |p| {
+                let w: Vec<Bus<16, Wire>> = p[..8].iter().map(|pin| word(pin)).collect();
+                vec![bits(bool_logic::mux_8way_16bit(&w[0], &w[1], &w[2], &w[3], &w[4], &w[5], &w[6], &w[7], &sel(&p[8])))]
+            })
+        },
+        "DMux4Way" => record(name, &[("in", 1), ("sel", 2)], &WAYS[..4], |p| {
+            bool_logic::dmux_4way(p[0][0], &sel(&p[1])).iter().map(|w| vec![*w]).collect()
+        }),
+        "DMux8Way" => record(name, &[("in", 1), ("sel", 3)], &WAYS, |p| {
+            bool_logic::dmux_8way(p[0][0], &sel(&p[1])).iter().map(|w| vec![*w]).collect()
+        }),
+        "HalfAdder" => record(name, A_B, &["sum", "carry"], |p| {
+            let out = bool_arithmetic::half_adder(p[0][0], p[1][0]);
+            vec![vec![out.sum], vec![out.carry]]
+        }),
+        "FullAdder" => record(name, &[("a", 1), ("b", 1), ("c", 1)], &["sum", "carry"], |p| {
+            let out = bool_arithmetic::full_adder(p[0][0], p[1][0], p[2][0]);
+            vec![vec![out.sum], vec![out.carry]]
+        }),
+        "Add16" => record(name, A_B16, &["out"], |p| vec![bits(bool_arithmetic::adder_16bit(&word(&p[0]), &word(&p[1])))]),
+        "Inc16" => record(name, &[("in", 16)], &["out"], |p| vec![bits(bool_arithmetic::incrementer(&word(&p[0])))]),
+        "ALU" => {
+            let inputs = [("x", 16), ("y", 16), ("zx", 1), ("nx", 1), ("zy", 1), ("ny", 1), ("f", 1), ("no", 1)];
+            record(name, &inputs, &["out", "zr", "ng"], |p| {
+                let (out, zr, ng) = bool_arithmetic::alu(
+                    &word(&p[0]), &word(&p[1]), p[2][0], p[3][0], p[4][0], p[5][0], p[6][0], p[7][0]
+                );
+                vec![bits(out), vec![zr], vec![ng]]
+            })
+        },
+        "Bit" | "Register" | "RAM8" | "RAM64" | "PC" => {
+            let mut chip = rust_chip::sequential::<Wire>(name)?;
+            record(name, chip.pins().inputs, &["out"], |p| vec![chip.step(p)])
+        },
+        _ => return None
+    };
+    Some(schematic)
+}
+
+// Register#3 -> Register
+fn chip_of(label: &str) -> &str {
+    label.split('#').next().unwrap_or(label)
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+impl Schematic {
+    // HDL のネットリストから。Instance::path の先頭はトップのチップ、最後は cell そのもの
+    pub fn from_netlist(netlist: &Netlist) -> Schematic {
+        let pins = |decls: &[crate::hdl::parser::PinDecl]| {
+            decls.iter().map(|p| (p.name.clone(), netlist.nets(&p.name).unwrap_or(&[]).to_vec())).collect()
+        };
+        Schematic {
+            name: netlist.name.clone(),
+            inputs: pins(&netlist.inputs),
+            outputs: pins(&netlist.outputs),
+            cells: netlist.instances.iter().map(|instance| {
+                let mut path: Vec<String> = instance.path.split('/').skip(1).map(|s| s.to_string()).collect();
+                path.pop();
+                Cell { chip: instance.chip.to_string(), path, inputs: instance.inputs.clone(), outputs: instance.outputs.clone() }
+            }).collect()
+        }
+    }
+
+    // 一番大きいネットの番号 + 1
+    pub fn net_count(&self) -> usize {
+        self.cells.iter().flat_map(|c| c.outputs.iter())
+            .chain(self.inputs.iter().flat_map(|(_, nets)| nets.iter()))
+            .max().map_or(2, |n| n + 1).max(2)
+    }
+
+    // DFF の cell の番号
+    pub fn dffs(&self) -> Vec<usize> {
+        (0..self.cells.len()).filter(|c| self.cells[*c].chip == "DFF").collect()
+    }
+
+    // cell を並んだ順に 64 本並列で計算する。入力ピンのネットは先に nets に入れておく
+    // DFF の出力には state (dffs の順) を出し、nand の出力は force(cell の番号, 値) にする (故障を入れないならそのまま返す)
+    pub fn eval(&self, nets: &mut [u64], dffs: &[usize], state: &[u64], force: impl Fn(usize, u64) -> u64) {
+        nets[FALSE] = 0;
+        nets[TRUE] = !0;
+        for (cell, value) in dffs.iter().zip(state) {
+            nets[self.cells[*cell].outputs[0]] = *value;
+        }
+        for (index, cell) in self.cells.iter().enumerate() {
+            if cell.chip == "Nand" {
+                nets[cell.outputs[0]] = force(index, !(nets[cell.inputs[0]] & nets[cell.inputs[1]]));
+            }
+        }
+    }
+
+    // 部品を出てきた順に並べる。0 番はチップ全体で、親はいつも子より前
+    pub fn blocks(&self) -> Vec<Block> {
+        let block = |path: Vec<String>| Block { path, inputs: Vec::new(), outputs: Vec::new(), children: Vec::new(), cells: Vec::new() };
+        let mut blocks = vec![block(Vec::new())];
+        let mut index: HashMap<Vec<String>, usize> = HashMap::new();
+        for (c, cell) in self.cells.iter().enumerate() {
+            let mut parent = 0;
+            for depth in 1..=cell.path.len() {
+                parent = match index.get(&cell.path[..depth]) {
+                    Some(b) => *b,
+                    None => {
+                        blocks.push(block(cell.path[..depth].to_vec()));
+                        let b = blocks.len() - 1;
+                        blocks[parent].children.push(b);
+                        index.insert(cell.path[..depth].to_vec(), b);
+                        b
+                    }
+                };
+            }
+            blocks[parent].cells.push(c);
+        }
+        // 順序回路の DFF は 1 回目のクロックで作るので先に出てくる。子は部品の中の順 (#n) に並べ直す
+        let order = |label: &str| label.rsplit('#').next().and_then(|n| n.parse::<usize>().ok()).unwrap_or(0);
+        for b in 0..blocks.len() {
+            let mut children = std::mem::take(&mut blocks[b].children);
+            children.sort_by_key(|c| order(blocks[*c].path.last().unwrap()));
+            blocks[b].children = children;
+        }
+
+        // ネットを作った cell から使う cell まで、通り抜ける部品の入出力にする
+        let mut driver: HashMap<usize, &[String]> = HashMap::new();
+        for cell in &self.cells {
+            for net in &cell.outputs {
+                driver.insert(*net, &cell.path);
+            }
+        }
+        let mut inputs: Vec<BTreeSet<usize>> = vec![BTreeSet::new(); blocks.len()];
+        let mut outputs: Vec<BTreeSet<usize>> = vec![BTreeSet::new(); blocks.len()];
+        let mut cross = |net: usize, from: &[String], to: &[String]| {
+            let common = from.iter().zip(to).take_while(|(a, b)| a == b).count();
+            for depth in common + 1..=from.len() {
+                outputs[index[&from[..depth]]].insert(net);
+            }
+            for depth in common + 1..=to.len() {
+                inputs[index[&to[..depth]]].insert(net);
+            }
+        };
+        for cell in &self.cells {
+            for net in cell.inputs.iter().filter(|n| **n != FALSE && **n != TRUE) {
+                cross(*net, driver.get(net).copied().unwrap_or(&[]), &cell.path);
+            }
+        }
+        for (_, nets) in &self.outputs {
+            for net in nets {
+                cross(*net, driver.get(net).copied().unwrap_or(&[]), &[]);
+            }
+        }
+        for (block, (inputs, outputs)) in blocks.iter_mut().zip(inputs.into_iter().zip(outputs)) {
+            block.inputs = inputs.into_iter().collect();
+            block.outputs = outputs.into_iter().collect();
+        }
+        blocks[0].inputs = self.inputs.iter().flat_map(|(_, nets)| nets.iter().copied()).collect();
+        blocks[0].outputs = self.outputs.iter().flat_map(|(_, nets)| nets.iter().copied()).collect();
+        blocks
+    }
+
+    // chip という部品 (Nand や DFF) の数。このバイナリからは使わない
+    #[allow(dead_code)]
+    pub fn count(&self, chip: &str) -> usize {
+        self.cells.iter().filter(|c| c.chip == chip).count()
+    }
+
+    // cell を depth 段目の部品にまとめたときの (ノード, ノードを入れる部品, ラベル)
+    // depth より浅いところにある cell はそのまま出す。depth 0 ならチップ全体が 1 つの箱
+    fn group(&self, index: usize, depth: usize) -> (String, Vec<String>, String) {
+        let cell = &self.cells[index];
+        if cell.path.len() >= depth {
+            let prefix = &cell.path[..depth];
+            let label = prefix.last().cloned().unwrap_or_else(|| self.name.clone());
+            let container = prefix[..depth.saturating_sub(1)].to_vec();
+            (format!("part:{}", prefix.join("/")), container, label)
+        } else {
+            (format!("cell{}", index), cell.path.clone(), cell.chip.clone())
+        }
+    }
+
+    // Graphviz の DOT。部品は depth 段目までを箱にして、その中は畳む
+    pub fn dot(&self, depth: usize) -> String {
+        let mut driver: HashMap<usize, String> = HashMap::new();
+        driver.insert(FALSE, "const:0".to_string());
+        driver.insert(TRUE, "const:1".to_string());
+        for (name, nets) in &self.inputs {
+            for net in nets {
+                driver.insert(*net, format!("in:{}", name));
+            }
+        }
+        // 部品ごとに中に置くノード
+        let mut containers: BTreeMap<Vec<String>, BTreeMap<String, String>> = BTreeMap::new();
+        let mut groups = Vec::new();
+        for index in 0..self.cells.len() {
+            let (id, container, label) = self.group(index, depth);
+            for net in &self.cells[index].outputs {
+                driver.insert(*net, id.clone());
+            }
+            containers.entry(container).or_default().insert(id.clone(), label);
+            groups.push(id);
+        }
+
+        // ノードをまたぐ線を、ネットの本数ごとにまとめる
+        let mut edges: BTreeMap<(String, String), BTreeSet<usize>> = BTreeMap::new();
+        for (cell, group) in self.cells.iter().zip(&groups) {
+            for net in &cell.inputs {
+                if let Some(from) = driver.get(net).filter(|from| *from != group) {
+                    edges.entry((from.clone(), group.clone())).or_default().insert(*net);
+                }
+            }
+        }
+        for (name, nets) in &self.outputs {
+            for net in nets {
+                if let Some(from) = driver.get(net) {
+                    edges.entry((from.clone(), format!("out:{}", name))).or_default().insert(*net);
+                }
+            }
+        }
+
+        let mut out = format!("digraph {} {{\n    rankdir=LR;\n    node [shape=box];\n", quote(&self.name));
+        let pin_label = |name: &str, nets: &[usize]| if nets.len() == 1 { name.to_string() } else { format!("{}[{}]", name, nets.len()) };
+        for (kind, pins) in [("in", &self.inputs), ("out", &self.outputs)].iter() {
+            for (name, nets) in pins.iter() {
+                out.push_str(&format!(
+                    "    {} [label={}, shape=ellipse];\n", quote(&format!("{}:{}", kind, name)), quote(&pin_label(name, nets))
+                ));
+            }
+        }
+        let used: BTreeSet<&String> = edges.keys().map(|(from, _)| from).collect();
+        for (id, label) in [("const:0", "false"), ("const:1", "true")].iter() {
+            if used.contains(&id.to_string()) {
+                out.push_str(&format!("    {} [label={}, shape=plaintext];\n", quote(id), quote(label)));
+            }
+        }
+        // 箱を入れる部品と、その外側の部品を全部クラスタにする
+        let mut clusters: BTreeSet<Vec<String>> = BTreeSet::new();
+        for container in containers.keys() {
+            for end in 1..=container.len() {
+                clusters.insert(container[..end].to_vec());
+            }
+        }
+        self.emit(&mut out, &[], &containers, &clusters, 1);
+        for ((from, to), nets) in &edges {
+            if nets.len() == 1 {
+                out.push_str(&format!("    {} -> {};\n", quote(from), quote(to)));
+            } else {
+                out.push_str(&format!("    {} -> {} [label={}];\n", quote(from), quote(to), quote(&nets.len().to_string())));
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    fn emit(
+        &self,
+        out: &mut String,
+        prefix: &[String],
+        containers: &BTreeMap<Vec<String>, BTreeMap<String, String>>,
+        clusters: &BTreeSet<Vec<String>>,
+        indent: usize
+    ) {
+        let pad = "    ".repeat(indent);
+        if let Some(nodes) = containers.get(prefix) {
+            for (id, label) in nodes {
+                let shape = if id.starts_with("part:") { "box3d" } else { "box" };
+                out.push_str(&format!("{}{} [label={}, shape={}];\n", pad, quote(id), quote(chip_of(label)), shape));
+            }
+        }
+        for cluster in clusters.iter().filter(|c| c.len() == prefix.len() + 1 && c.starts_with(prefix)) {
+            let label = cluster.last().unwrap();
+            out.push_str(&format!("{}subgraph {} {{\n", pad, quote(&format!("cluster_{}", cluster.join("/")))));
+            out.push_str(&format!("{}    label={};\n", pad, quote(label)));
+            self.emit(out, cluster, containers, clusters, indent + 1);
+            out.push_str(&format!("{}}}\n", pad));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::hdl;
+    use crate::instrument;
+    use std::path::Path;
+
+    fn nets_driven(schematic: &Schematic) -> BTreeSet<usize> {
+        let mut nets: BTreeSet<usize> = [FALSE, TRUE].iter().copied().collect();
+        nets.extend(schematic.inputs.iter().flat_map(|(_, n)| n.iter().copied()));
+        nets.extend(schematic.cells.iter().flat_map(|c| c.outputs.iter().copied()));
+        nets
+    }
+
+    #[test]
+    fn and_test() {
+        // and = not(nand(x, y))
+        let and = rust_chip("And").unwrap();
+        assert_eq!(2, and.count("Nand"));
+        assert_eq!(Vec::<String>::new(), and.cells[0].path);
+        assert_eq!(vec!["Not#0".to_string()], and.cells[1].path);
+        let dot = and.dot(1);
+        assert!(dot.contains("\"in:a\" -> \"cell0\";"), "{}", dot);
+        assert!(dot.contains("\"cell0\" -> \"part:Not#0\";"), "{}", dot);
+        assert!(dot.contains("\"part:Not#0\" -> \"out:out\";"), "{}", dot);
+        // 全部開くと nand だけになる
+        assert!(!and.dot(5).contains("part:"));
+        assert!(and.dot(0).contains("\"part:\" [label=\"And\", shape=box3d];"));
+    }
+
+    #[test]
+    fn nand_count_test() {
+        // instrument の nand の数と同じになる。Add16 の最後の桁上がりのように、使わない出力の nand は消える
+        for (name, cost) in instrument::costs() {
+            if let Some(schematic) = rust_chip(name) {
+                assert!(schematic.count("Nand") <= cost.nands as usize, "{}", name);
+                if ["Xor", "Mux", "DMux8Way", "Mux8Way16", "FullAdder"].contains(&name) {
+                    assert_eq!(cost.nands as usize, schematic.count("Nand"), "{}", name);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn ram8_test() {
+        let ram = rust_chip("RAM8").unwrap();
+        assert_eq!(128, ram.count("DFF"));
+        // 2 回目のクロックでつなぎ直した Dff の入力も、ちゃんとどこかから来ている
+        let driven = nets_driven(&ram);
+        assert!(ram.cells.iter().all(|c| c.inputs.iter().all(|n| driven.contains(n))));
+        let dot = ram.dot(1);
+        assert_eq!(8, dot.matches("[label=\"Register\", shape=box3d]").count());
+        assert!(dot.contains("\"part:DMux8Way#0\""));
+        assert!(dot.contains("\"part:Mux8Way16#9\""));
+        assert!(dot.contains("\"in:in\" -> \"part:Register#1\" [label=\"16\"];"));
+        // Register の出力は Mux8Way16 に戻り、Register の中の Dff は Register の中で回っている
+        assert!(dot.contains("\"part:Register#1\" -> \"part:Mux8Way16#9\" [label=\"16\"];"));
+        let dot = ram.dot(2);
+        assert!(dot.contains("subgraph \"cluster_Register#1\""));
+        assert!(dot.contains("\"part:Register#1/Bit#15\""));
+    }
+
+    #[test]
+    fn bit_test() {
+        let bit = rust_chip("Bit").unwrap();
+        assert_eq!(1, bit.count("DFF"));
+        let dff = bit.cells.iter().find(|c| c.chip == "DFF").unwrap();
+        // Dff の出力は mux に戻り、mux の出力が Dff に入る
+        assert!(bit.cells.iter().any(|c| c.chip == "Nand" && c.inputs.contains(&dff.outputs[0])));
+        assert!(bit.cells.iter().any(|c| c.chip == "Nand" && c.outputs == dff.inputs));
+        assert_eq!(bit.outputs[0].1, dff.outputs);
+        assert!(rust_chip("CPU").is_none());
+    }
+
+    #[test]
+    fn block_test() {
+        let and = rust_chip("And").unwrap();
+        let blocks = and.blocks();
+        assert_eq!(2, blocks.len());
+        assert_eq!((vec![1], vec![0]), (blocks[0].children.clone(), blocks[0].cells.clone()));
+        // Not#0 は nand の出力を受けて out を出す
+        assert_eq!(vec!["Not#0".to_string()], blocks[1].path);
+        assert_eq!(and.cells[0].outputs, blocks[1].inputs);
+        assert_eq!(and.outputs[0].1, blocks[1].outputs);
+
+        let pc = rust_chip("PC").unwrap();
+        let blocks = pc.blocks();
+        let top: Vec<&str> = blocks[0].children.iter().map(|b| blocks[*b].path[0].as_str()).collect();
+        assert_eq!(vec!["Inc16#0", "Mux16#1", "Mux16#2", "Mux16#3", "Register#4"], top);
+        // reset の Mux16 の b は定数なので、in は c と reset だけ
+        let reset = blocks.iter().find(|b| b.path == ["Mux16#3"]).unwrap();
+        assert_eq!((17, 16), (reset.inputs.len(), reset.outputs.len()));
+        let register = blocks.iter().find(|b| b.path == ["Register#4"]).unwrap();
+        assert_eq!(16, register.children.len());
+        assert_eq!(pc.outputs[0].1, register.outputs);
+
+        // 入力をそのまま出す Xor は a xor a = 0 になる
+        let xor = rust_chip("Xor").unwrap();
+        let mut nets = vec![0; xor.net_count()];
+        nets[xor.inputs[0].1[0]] = 0b1100;
+        nets[xor.inputs[1].1[0]] = 0b1010;
+        xor.eval(&mut nets, &[], &[], |_, v| v);
+        assert_eq!(0b0110, nets[xor.outputs[0].1[0]] & 0b1111);
+        xor.eval(&mut nets, &[], &[], |cell, v| if cell == xor.cells.len() - 1 { 0 } else { v });
+        assert_eq!(0, nets[xor.outputs[0].1[0]]);
+    }
+
+    #[test]
+    fn hdl_test() {
+        let dir = Path::new(file!()).parent().unwrap().join("../../hardware_sim/05");
+        let cpu = Netlist::build("CPU", &hdl::dir_loader(&dir)).unwrap();
+        let schematic = Schematic::from_netlist(&cpu);
+        assert_eq!(cpu.instances.len(), schematic.cells.len());
+        let dot = schematic.dot(1);
+        assert!(dot.contains("[label=\"ALU\", shape=box]"), "{}", dot);
+        assert!(dot.contains("\"in:instruction\" [label=\"instruction[16]\", shape=ellipse];"));
+        assert_eq!(1, schematic.dot(0).matches("shape=box3d").count());
+    }
+}
@@ -1,45 +1,56 @@
 use crate::bool_logic;
 use crate::bool_arithmetic;
+// チップの Bit と名前がかぶるので、信号の Bit は signal::Bit と書く
+use crate::signal::{self, Bus, Part, Signal, I, O};
 
-struct Dff {
-    pre_value: u8
+pub struct Dff<S = signal::Bit> {
+    pre_value: S
 }
 
-impl Dff {
-    fn new(init_status: u8) -> Dff {
+impl<S: Signal> Dff<S> {
+    pub fn new(init_status: S) -> Dff<S> {
         Dff {
             pre_value: init_status
         }
     }
-    fn exec(&mut self, change: u8) -> u8 {
+    pub fn exec(&mut self, change: S) -> S {
         let result = self.pre_value;
-        self.pre_value = change;
+        // ここでクロックをまたぐ
+        self.pre_value = change.latch();
         result
     }
+    // 今記憶している値 (次の exec で出てくる値)
+    pub fn value(&self) -> S {
+        self.pre_value
+    }
 }
 
-struct Bit {
-    dff: Dff
+pub struct Bit<S = signal::Bit> {
+    dff: Dff<S>
 }
 
-impl Bit {
-    fn new() -> Bit {
+impl<S: Signal> Bit<S> {
+    pub fn new() -> Bit<S> {
         Bit {
-            dff: Dff::new(0)
+            dff: Dff::new(S::initial())
         }
     }
-    fn exec(&mut self, input: u8, load: u8) -> u8 {
+    pub fn exec(&mut self, input: S, load: S) -> S {
+        let _part = Part::<S>::enter("Bit");
         let value = bool_logic::mux(self.dff.pre_value, input, load);
         self.dff.exec(value)
     }
+    pub fn value(&self) -> S {
+        self.dff.value()
+    }
 }
 
-struct Register {
-    bits: [Bit; 16]
+pub struct Register<S = signal::Bit> {
+    bits: [Bit<S>; 16]
 }
 
-impl Register {
-    fn new() -> Register {
+impl<S: Signal> Register<S> {
+    pub fn new() -> Register<S> {
         Register {
             bits: [
                 Bit::new(),
@@ -61,34 +72,25 @@ impl Register {
             ]
         }
     }
-    fn exec(&mut self, input_arr: &[u8; 16], load: u8) -> [u8; 16] {
-        [
-            self.bits[0].exec(input_arr[0], load),
-            self.bits[1].exec(input_arr[1], load),
-            self.bits[2].exec(input_arr[2], load),
-            self.bits[3].exec(input_arr[3], load),
-            self.bits[4].exec(input_arr[4], load),
-            self.bits[5].exec(input_arr[5], load),
-            self.bits[6].exec(input_arr[6], load),
-            self.bits[7].exec(input_arr[7], load),
-            self.bits[8].exec(input_arr[8], load),
-            self.bits[9].exec(input_arr[9], load),
-            self.bits[10].exec(input_arr[10], load),
-            self.bits[11].exec(input_arr[11], load),
-            self.bits[12].exec(input_arr[12], load),
-            self.bits[13].exec(input_arr[13], load),
-            self.bits[14].exec(input_arr[14], load),
-            self.bits[15].exec(input_arr[15], load),
-        ]
+    pub fn exec(&mut self, input_arr: &Bus<16, S>, load: S) -> Bus<16, S> {
+        let _part = Part::<S>::enter("Register");
+        let mut result: Bus<16, S> = Bus::constant(O);
+        for i in 0..16 {
+            result[i] = self.bits[i].exec(input_arr[i], load);
+        }
+        result
+    }
+    pub fn value(&self) -> Bus<16, S> {
+        Bus::from_fn(|i| self.bits[i].value())
     }
 }
 
-struct Ram8 {
-    registers: [Register; 8]
+pub struct Ram8<S = signal::Bit> {
+    registers: [Register<S>; 8]
 }
 
-impl Ram8 {
-    fn new() -> Ram8 {
+impl<S: Signal> Ram8<S> {
+    pub fn new() -> Ram8<S> {
         Ram8 {
             registers: [
                 Register::new(),
@@ -102,7 +104,8 @@ impl Ram8 {
             ]
         }
     }
-    fn exec(&mut self, input_arr: &[u8; 16], load: u8, address: &[u8; 3]) -> [u8; 16] {
+    pub fn exec(&mut self, input_arr: &Bus<16, S>, load: S, address: &[S; 3]) -> Bus<16, S> {
+        let _part = Part::<S>::enter("RAM8");
         let selector = bool_logic::dmux_8way(load, address);
         bool_logic::mux_8way_16bit(
             &self.registers[0].exec(input_arr, selector[0]),
@@ -116,14 +119,18 @@ impl Ram8 {
             address
         )
     }
+    // address 番地 (0 から 7) の値
+    pub fn value(&self, address: usize) -> Bus<16, S> {
+        self.registers[address].value()
+    }
 }
 
-struct Ram64 {
-    rams: [Ram8; 8]
+pub struct Ram64<S = signal::Bit> {
+    rams: [Ram8<S>; 8]
 }
 
-impl Ram64 {
-    fn new() -> Ram64 {
+impl<S: Signal> Ram64<S> {
+    pub fn new() -> Ram64<S> {
         Ram64 {
             rams: [
                 Ram8::new(),
@@ -137,7 +144,8 @@ impl Ram64 {
             ]
         }
     }
-    fn exec(&mut self, input: &[u8; 16], load: u8, address: &[u8; 6]) -> [u8; 16] {
+    pub fn exec(&mut self, input: &Bus<16, S>, load: S, address: &[S; 6]) -> Bus<16, S> {
+        let _part = Part::<S>::enter("RAM64");
         let upper = [address[0], address[1], address[2]];
         let lower = [address[3], address[4], address[5]];
         let selector = bool_logic::dmux_8way(load, &upper);
@@ -154,1044 +162,1012 @@ impl Ram64 {
             &upper
         )
     }
+    // address 番地 (0 から 63) の値
+    pub fn value(&self, address: usize) -> Bus<16, S> {
+        self.rams[address / 8].value(address % 8)
+    }
 }
 
-struct Pc {
-    register: Register
+pub struct Pc<S = signal::Bit> {
+    register: Register<S>
 }
 
-impl Pc {
-    fn new() -> Pc {
+impl<S: Signal> Pc<S> {
+    pub fn new() -> Pc<S> {
         Pc {
             register: Register::new()
         }
     }
-    fn exec(&mut self, input: &[u8; 16], load: u8, reset: u8, inc: u8) -> [u8; 16] {
-        let a = bool_arithmetic::incrementer(input);
-        let b = bool_logic::mux_16bit(input, &a, inc);
+    // reset, load, inc の順に優先する。どれも 0 なら今の値のまま
+    pub fn exec(&mut self, input: &Bus<16, S>, load: S, reset: S, inc: S) -> Bus<16, S> {
+        let _part = Part::<S>::enter("PC");
+        let out = self.register.value();
+        let a = bool_arithmetic::incrementer(&out);
+        let b = bool_logic::mux_16bit(&out, &a, inc);
         let c = bool_logic::mux_16bit(&b, input, load);
-        let d = bool_logic::mux_16bit(&c, &[0; 16], reset);
-        self.register.exec(&d, load)
+        let d = bool_logic::mux_16bit(&c, &Bus::constant(O), reset);
+        self.register.exec(&d, S::constant(I))
+    }
+    pub fn value(&self) -> Bus<16, S> {
+        self.register.value()
     }
 }
 
-#[cfg(test)]
-mod test {
-    use std::convert::{TryFrom};
-    use super::*;
+// 手で書いたテストのベクタ。下の #[test] のほか、vectors を通して fault と verilog でも流す
+// 1 行で exec を 1 回 (1 クロック) 呼び、out はそのクロックで進む前の値
+// (in, load, out)
+pub const BIT_VECTORS: &[(signal::Bit, signal::Bit, signal::Bit)] = &[
+    (O, O, O),
+    (O, O, O),
+    (O, I, O),
+    (O, I, O),
+    (I, O, O),
+    (I, O, O),
+    (I, I, O),
+    (I, I, I),
+    (O, O, I),
+    (O, O, I),
+    (I, O, I),
+    (I, O, I),
+    (O, I, I),
+    (O, I, O),
+    (I, I, O),
+    (I, I, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, O, I),
+    (O, I, I),
+    (O, I, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+    (I, O, O),
+];
 
-    fn converter_16bit_to_array<'a>(input: &'a str) -> [u8; 16] {
-        let mut output: [u8; 16] = [0; 16];
-        for i in 0..input.len() {
-            output[i] = u8::try_from(
-                input.chars().nth(i).unwrap().to_digit(2).unwrap()
-            ).unwrap();
-        }
-        output
-    }
+// (in, load, out)
+pub const REGISTER_VECTORS: &[(i16, signal::Bit, i16)] = &[
+    (     0, O,      0),
+    (     0, O,      0),
+    (     0, I,      0),
+    (     0, I,      0),
+    (-32123, O,      0),
+    (-32123, O,      0),
+    ( 11111, O,      0),
+    ( 11111, O,      0),
+    (-32123, I,      0),
+    (-32123, I, -32123),
+    (-32123, I, -32123),
+    (-32123, I, -32123),
+    (-32123, O, -32123),
+    (-32123, O, -32123),
+    ( 12345, I, -32123),
+    ( 12345, I,  12345),
+    (     0, O,  12345),
+    (     0, O,  12345),
+    (     0, I,  12345),
+    (     0, I,      0),
+    (     1, O,      0),
+    (     1, O,      0),
+    (     1, I,      0),
+    (     1, I,      1),
+    (     2, O,      1),
+    (     2, O,      1),
+    (     2, I,      1),
+    (     2, I,      2),
+    (     4, O,      2),
+    (     4, O,      2),
+    (     4, I,      2),
+    (     4, I,      4),
+    (     8, O,      4),
+    (     8, O,      4),
+    (     8, I,      4),
+    (     8, I,      8),
+    (    16, O,      8),
+    (    16, O,      8),
+    (    16, I,      8),
+    (    16, I,     16),
+    (    32, O,     16),
+    (    32, O,     16),
+    (    32, I,     16),
+    (    32, I,     32),
+    (    64, O,     32),
+    (    64, O,     32),
+    (    64, I,     32),
+    (    64, I,     64),
+    (   128, O,     64),
+    (   128, O,     64),
+    (   128, I,     64),
+    (   128, I,    128),
+    (   256, O,    128),
+    (   256, O,    128),
+    (   256, I,    128),
+    (   256, I,    256),
+    (   512, O,    256),
+    (   512, O,    256),
+    (   512, I,    256),
+    (   512, I,    512),
+    (  1024, O,    512),
+    (  1024, O,    512),
+    (  1024, I,    512),
+    (  1024, I,   1024),
+    (  2048, O,   1024),
+    (  2048, O,   1024),
+    (  2048, I,   1024),
+    (  2048, I,   2048),
+    (  4096, O,   2048),
+    (  4096, O,   2048),
+    (  4096, I,   2048),
+    (  4096, I,   4096),
+    (  8192, O,   4096),
+    (  8192, O,   4096),
+    (  8192, I,   4096),
+    (  8192, I,   8192),
+    ( 16384, O,   8192),
+    ( 16384, O,   8192),
+    ( 16384, I,   8192),
+    ( 16384, I,  16384),
+    (-32768, O,  16384),
+    (-32768, O,  16384),
+    (-32768, I,  16384),
+    (-32768, I, -32768),
+    (    -2, O, -32768),
+    (    -2, O, -32768),
+    (    -2, I, -32768),
+    (    -2, I,     -2),
+    (    -3, O,     -2),
+    (    -3, O,     -2),
+    (    -3, I,     -2),
+    (    -3, I,     -3),
+    (    -5, O,     -3),
+    (    -5, O,     -3),
+    (    -5, I,     -3),
+    (    -5, I,     -5),
+    (    -9, O,     -5),
+    (    -9, O,     -5),
+    (    -9, I,     -5),
+    (    -9, I,     -9),
+    (   -17, O,     -9),
+    (   -17, O,     -9),
+    (   -17, I,     -9),
+    (   -17, I,    -17),
+    (   -33, O,    -17),
+    (   -33, O,    -17),
+    (   -33, I,    -17),
+    (   -33, I,    -33),
+    (   -65, O,    -33),
+    (   -65, O,    -33),
+    (   -65, I,    -33),
+    (   -65, I,    -65),
+    (  -129, O,    -65),
+    (  -129, O,    -65),
+    (  -129, I,    -65),
+    (  -129, I,   -129),
+    (  -257, O,   -129),
+    (  -257, O,   -129),
+    (  -257, I,   -129),
+    (  -257, I,   -257),
+    (  -513, O,   -257),
+    (  -513, O,   -257),
+    (  -513, I,   -257),
+    (  -513, I,   -513),
+    ( -1025, O,   -513),
+    ( -1025, O,   -513),
+    ( -1025, I,   -513),
+    ( -1025, I,  -1025),
+    ( -2049, O,  -1025),
+    ( -2049, O,  -1025),
+    ( -2049, I,  -1025),
+    ( -2049, I,  -2049),
+    ( -4097, O,  -2049),
+    ( -4097, O,  -2049),
+    ( -4097, I,  -2049),
+    ( -4097, I,  -4097),
+    ( -8193, O,  -4097),
+    ( -8193, O,  -4097),
+    ( -8193, I,  -4097),
+    ( -8193, I,  -8193),
+    (-16385, O,  -8193),
+    (-16385, O,  -8193),
+    (-16385, I,  -8193),
+    (-16385, I, -16385),
+    ( 32767, O, -16385),
+    ( 32767, O, -16385),
+    ( 32767, I, -16385),
+    ( 32767, I,  32767),
+];
 
-    fn converter_6bit_to_array<'a>(input: &'a str) -> [u8; 6] {
-        let mut output: [u8; 6] = [0; 6];
-        for i in 0..input.len() {
-            output[i] = u8::try_from(
-                input.chars().nth(i).unwrap().to_digit(2).unwrap()
-            ).unwrap();
-        }
-        output
-    }
+// (in, load, address, out)
+pub const RAM8_VECTORS: &[(i16, signal::Bit, u16, i16)] = &[
+    (     0, O, 0,      0),
+    (     0, I, 0,      0),
+    (     0, I, 0,      0),
+    ( 11111, O, 0,      0),
+    ( 11111, O, 0,      0),
+    ( 11111, I, 1,      0),
+    ( 11111, I, 1,  11111),
+    ( 11111, O, 0,      0),
+    ( 11111, O, 0,      0),
+    (  3333, O, 3,      0),
+    (  3333, O, 3,      0),
+    (  3333, I, 3,      0),
+    (  3333, I, 3,   3333),
+    (  3333, O, 3,   3333),
+    (  3333, O, 3,   3333),
+    (  3333, O, 1,  11111),
+    (  7777, O, 1,  11111),
+    (  7777, O, 1,  11111),
+    (  7777, I, 7,      0),
+    (  7777, I, 7,   7777),
+    (  7777, O, 7,   7777),
+    (  7777, O, 7,   7777),
+    (  7777, O, 3,   3333),
+    (  7777, O, 7,   7777),
+    (  7777, O, 0,      0),
+    (  7777, O, 0,      0),
+    (  7777, O, 1,  11111),
+    (  7777, O, 2,      0),
+    (  7777, O, 3,   3333),
+    (  7777, O, 4,      0),
+    (  7777, O, 5,      0),
+    (  7777, O, 6,      0),
+    (  7777, O, 7,   7777),
+    ( 21845, I, 0,      0),
+    ( 21845, I, 0,  21845),
+    ( 21845, I, 1,  11111),
+    ( 21845, I, 1,  21845),
+    ( 21845, I, 2,      0),
+    ( 21845, I, 2,  21845),
+    ( 21845, I, 3,   3333),
+    ( 21845, I, 3,  21845),
+    ( 21845, I, 4,      0),
+    ( 21845, I, 4,  21845),
+    ( 21845, I, 5,      0),
+    ( 21845, I, 5,  21845),
+    ( 21845, I, 6,      0),
+    ( 21845, I, 6,  21845),
+    ( 21845, I, 7,   7777),
+    ( 21845, I, 7,  21845),
+    ( 21845, O, 0,  21845),
+    ( 21845, O, 0,  21845),
+    ( 21845, O, 1,  21845),
+    ( 21845, O, 2,  21845),
+    ( 21845, O, 3,  21845),
+    ( 21845, O, 4,  21845),
+    ( 21845, O, 5,  21845),
+    ( 21845, O, 6,  21845),
+    ( 21845, O, 7,  21845),
+    (-21846, I, 0,  21845),
+    (-21846, I, 0, -21846),
+    (-21846, O, 0, -21846),
+    (-21846, O, 0, -21846),
+    (-21846, O, 1,  21845),
+    (-21846, O, 2,  21845),
+    (-21846, O, 3,  21845),
+    (-21846, O, 4,  21845),
+    (-21846, O, 5,  21845),
+    (-21846, O, 6,  21845),
+    (-21846, O, 7,  21845),
+    ( 21845, I, 0, -21846),
+    ( 21845, I, 0,  21845),
+    (-21846, I, 1,  21845),
+    (-21846, I, 1, -21846),
+    (-21846, O, 0,  21845),
+    (-21846, O, 0,  21845),
+    (-21846, O, 1, -21846),
+    (-21846, O, 2,  21845),
+    (-21846, O, 3,  21845),
+    (-21846, O, 4,  21845),
+    (-21846, O, 5,  21845),
+    (-21846, O, 6,  21845),
+    (-21846, O, 7,  21845),
+    ( 21845, I, 1, -21846),
+    ( 21845, I, 1,  21845),
+    (-21846, I, 2,  21845),
+    (-21846, I, 2, -21846),
+    (-21846, O, 0,  21845),
+    (-21846, O, 0,  21845),
+    (-21846, O, 1,  21845),
+    (-21846, O, 2, -21846),
+    (-21846, O, 3,  21845),
+    (-21846, O, 4,  21845),
+    (-21846, O, 5,  21845),
+    (-21846, O, 6,  21845),
+    (-21846, O, 7,  21845),
+    ( 21845, I, 2, -21846),
+    ( 21845, I, 2,  21845),
+    (-21846, I, 3,  21845),
+    (-21846, I, 3, -21846),
+    (-21846, O, 0,  21845),
+    (-21846, O, 0,  21845),
+    (-21846, O, 1,  21845),
+    (-21846, O, 2,  21845),
+    (-21846, O, 3, -21846),
+    (-21846, O, 4,  21845),
+    (-21846, O, 5,  21845),
+    (-21846, O, 6,  21845),
+    (-21846, O, 7,  21845),
+    ( 21845, I, 3, -21846),
+    ( 21845, I, 3,  21845),
+    (-21846, I, 4,  21845),
+    (-21846, I, 4, -21846),
+    (-21846, O, 0,  21845),
+    (-21846, O, 0,  21845),
+    (-21846, O, 1,  21845),
+    (-21846, O, 2,  21845),
+    (-21846, O, 3,  21845),
+    (-21846, O, 4, -21846),
+    (-21846, O, 5,  21845),
+    (-21846, O, 6,  21845),
+    (-21846, O, 7,  21845),
+    ( 21845, I, 4, -21846),
+    ( 21845, I, 4,  21845),
+    (-21846, I, 5,  21845),
+    (-21846, I, 5, -21846),
+    (-21846, O, 0,  21845),
+    (-21846, O, 0,  21845),
+    (-21846, O, 1,  21845),
+    (-21846, O, 2,  21845),
+    (-21846, O, 3,  21845),
+    (-21846, O, 4,  21845),
+    (-21846, O, 5, -21846),
+    (-21846, O, 6,  21845),
+    (-21846, O, 7,  21845),
+    ( 21845, I, 5, -21846),
+    ( 21845, I, 5,  21845),
+    (-21846, I, 6,  21845),
+    (-21846, I, 6, -21846),
+    (-21846, O, 0,  21845),
+    (-21846, O, 0,  21845),
+    (-21846, O, 1,  21845),
+    (-21846, O, 2,  21845),
+    (-21846, O, 3,  21845),
+    (-21846, O, 4,  21845),
+    (-21846, O, 5,  21845),
+    (-21846, O, 6, -21846),
+    (-21846, O, 7,  21845),
+    ( 21845, I, 6, -21846),
+    ( 21845, I, 6,  21845),
+    (-21846, I, 7,  21845),
+    (-21846, I, 7, -21846),
+    (-21846, O, 0,  21845),
+    (-21846, O, 0,  21845),
+    (-21846, O, 1,  21845),
+    (-21846, O, 2,  21845),
+    (-21846, O, 3,  21845),
+    (-21846, O, 4,  21845),
+    (-21846, O, 5,  21845),
+    (-21846, O, 6,  21845),
+    (-21846, O, 7, -21846),
+    ( 21845, I, 7, -21846),
+    ( 21845, I, 7,  21845),
+    ( 21845, O, 0,  21845),
+    ( 21845, O, 0,  21845),
+    ( 21845, O, 1,  21845),
+    ( 21845, O, 2,  21845),
+    ( 21845, O, 3,  21845),
+    ( 21845, O, 4,  21845),
+    ( 21845, O, 5,  21845),
+    ( 21845, O, 6,  21845),
+    ( 21845, O, 7,  21845),
+];
 
-    fn converter_3bit_to_array<'a>(input: &'a str) -> [u8; 3] {
-        let mut output: [u8; 3] = [0; 3];
-        for i in 0..input.len() {
-            output[i] = u8::try_from(
-                input.chars().nth(i).unwrap().to_digit(2).unwrap()
-            ).unwrap();
-        }
-        output
-    }
+// (in, load, address, out)
+pub const RAM64_VECTORS: &[(i16, signal::Bit, u16, i16)] = &[
+    (     0, O,  0,      0),
+    (     0, O,  0,      0),
+    (     0, I,  0,      0),
+    (     0, I,  0,      0),
+    (  1313, O,  0,      0),
+    (  1313, O,  0,      0),
+    (  1313, I, 13,      0),
+    (  1313, I, 13,   1313),
+    (  1313, O,  0,      0),
+    (  1313, O,  0,      0),
+    (  4747, O, 47,      0),
+    (  4747, O, 47,      0),
+    (  4747, I, 47,      0),
+    (  4747, I, 47,   4747),
+    (  4747, O, 47,   4747),
+    (  4747, O, 47,   4747),
+    (  4747, O, 13,   1313),
+    (  6363, O, 13,   1313),
+    (  6363, O, 13,   1313),
+    (  6363, I, 63,      0),
+    (  6363, I, 63,   6363),
+    (  6363, O, 63,   6363),
+    (  6363, O, 63,   6363),
+    (  6363, O, 47,   4747),
+    (  6363, O, 63,   6363),
+    (  6363, O, 40,      0),
+    (  6363, O, 40,      0),
+    (  6363, O, 41,      0),
+    (  6363, O, 42,      0),
+    (  6363, O, 43,      0),
+    (  6363, O, 44,      0),
+    (  6363, O, 45,      0),
+    (  6363, O, 46,      0),
+    (  6363, O, 47,   4747),
+    ( 21845, I, 40,      0),
+    ( 21845, I, 40,  21845),
+    ( 21845, I, 41,      0),
+    ( 21845, I, 41,  21845),
+    ( 21845, I, 42,      0),
+    ( 21845, I, 42,  21845),
+    ( 21845, I, 43,      0),
+    ( 21845, I, 43,  21845),
+    ( 21845, I, 44,      0),
+    ( 21845, I, 44,  21845),
+    ( 21845, I, 45,      0),
+    ( 21845, I, 45,  21845),
+    ( 21845, I, 46,      0),
+    ( 21845, I, 46,  21845),
+    ( 21845, I, 47,   4747),
+    ( 21845, I, 47,  21845),
+    ( 21845, O, 40,  21845),
+    ( 21845, O, 40,  21845),
+    ( 21845, O, 41,  21845),
+    ( 21845, O, 42,  21845),
+    ( 21845, O, 43,  21845),
+    ( 21845, O, 44,  21845),
+    ( 21845, O, 45,  21845),
+    ( 21845, O, 46,  21845),
+    ( 21845, O, 47,  21845),
+    (-21846, I, 40,  21845),
+    (-21846, I, 40, -21846),
+    (-21846, O, 40, -21846),
+    (-21846, O, 40, -21846),
+    (-21846, O, 41,  21845),
+    (-21846, O, 42,  21845),
+    (-21846, O, 43,  21845),
+    (-21846, O, 44,  21845),
+    (-21846, O, 45,  21845),
+    (-21846, O, 46,  21845),
+    (-21846, O, 47,  21845),
+    ( 21845, I, 40, -21846),
+    ( 21845, I, 40,  21845),
+    (-21846, I, 41,  21845),
+    (-21846, I, 41, -21846),
+    (-21846, O, 40,  21845),
+    (-21846, O, 40,  21845),
+    (-21846, O, 41, -21846),
+    (-21846, O, 42,  21845),
+    (-21846, O, 43,  21845),
+    (-21846, O, 44,  21845),
+    (-21846, O, 45,  21845),
+    (-21846, O, 46,  21845),
+    (-21846, O, 47,  21845),
+    ( 21845, I, 41, -21846),
+    ( 21845, I, 41,  21845),
+    (-21846, I, 42,  21845),
+    (-21846, I, 42, -21846),
+    (-21846, O, 40,  21845),
+    (-21846, O, 40,  21845),
+    (-21846, O, 41,  21845),
+    (-21846, O, 42, -21846),
+    (-21846, O, 43,  21845),
+    (-21846, O, 44,  21845),
+    (-21846, O, 45,  21845),
+    (-21846, O, 46,  21845),
+    (-21846, O, 47,  21845),
+    ( 21845, I, 42, -21846),
+    ( 21845, I, 42,  21845),
+    (-21846, I, 43,  21845),
+    (-21846, I, 43, -21846),
+    (-21846, O, 40,  21845),
+    (-21846, O, 40,  21845),
+    (-21846, O, 41,  21845),
+    (-21846, O, 42,  21845),
+    (-21846, O, 43, -21846),
+    (-21846, O, 44,  21845),
+    (-21846, O, 45,  21845),
+    (-21846, O, 46,  21845),
+    (-21846, O, 47,  21845),
+    ( 21845, I, 43, -21846),
+    ( 21845, I, 43,  21845),
+    (-21846, I, 44,  21845),
+    (-21846, I, 44, -21846),
+    (-21846, O, 40,  21845),
+    (-21846, O, 40,  21845),
+    (-21846, O, 41,  21845),
+    (-21846, O, 42,  21845),
+    (-21846, O, 43,  21845),
+    (-21846, O, 44, -21846),
+    (-21846, O, 45,  21845),
+    (-21846, O, 46,  21845),
+    (-21846, O, 47,  21845),
+    ( 21845, I, 44, -21846),
+    ( 21845, I, 44,  21845),
+    (-21846, I, 45,  21845),
+    (-21846, I, 45, -21846),
+    (-21846, O, 40,  21845),
+    (-21846, O, 40,  21845),
+    (-21846, O, 41,  21845),
+    (-21846, O, 42,  21845),
+    (-21846, O, 43,  21845),
+    (-21846, O, 44,  21845),
+    (-21846, O, 45, -21846),
+    (-21846, O, 46,  21845),
+    (-21846, O, 47,  21845),
+    ( 21845, I, 45, -21846),
+    ( 21845, I, 45,  21845),
+    (-21846, I, 46,  21845),
+    (-21846, I, 46, -21846),
+    (-21846, O, 40,  21845),
+    (-21846, O, 40,  21845),
+    (-21846, O, 41,  21845),
+    (-21846, O, 42,  21845),
+    (-21846, O, 43,  21845),
+    (-21846, O, 44,  21845),
+    (-21846, O, 45,  21845),
+    (-21846, O, 46, -21846),
+    (-21846, O, 47,  21845),
+    ( 21845, I, 46, -21846),
+    ( 21845, I, 46,  21845),
+    (-21846, I, 47,  21845),
+    (-21846, I, 47, -21846),
+    (-21846, O, 40,  21845),
+    (-21846, O, 40,  21845),
+    (-21846, O, 41,  21845),
+    (-21846, O, 42,  21845),
+    (-21846, O, 43,  21845),
+    (-21846, O, 44,  21845),
+    (-21846, O, 45,  21845),
+    (-21846, O, 46,  21845),
+    (-21846, O, 47, -21846),
+    ( 21845, I, 47, -21846),
+    ( 21845, I, 47,  21845),
+    ( 21845, O, 40,  21845),
+    ( 21845, O, 40,  21845),
+    ( 21845, O, 41,  21845),
+    ( 21845, O, 42,  21845),
+    ( 21845, O, 43,  21845),
+    ( 21845, O, 44,  21845),
+    ( 21845, O, 45,  21845),
+    ( 21845, O, 46,  21845),
+    ( 21845, O, 47,  21845),
+    ( 21845, O,  5,      0),
+    ( 21845, O,  5,      0),
+    ( 21845, O, 13,   1313),
+    ( 21845, O, 21,      0),
+    ( 21845, O, 29,      0),
+    ( 21845, O, 37,      0),
+    ( 21845, O, 45,  21845),
+    ( 21845, O, 53,      0),
+    ( 21845, O, 61,      0),
+    ( 21845, I,  5,      0),
+    ( 21845, I,  5,  21845),
+    ( 21845, I, 13,   1313),
+    ( 21845, I, 13,  21845),
+    ( 21845, I, 21,      0),
+    ( 21845, I, 21,  21845),
+    ( 21845, I, 29,      0),
+    ( 21845, I, 29,  21845),
+    ( 21845, I, 37,      0),
+    ( 21845, I, 37,  21845),
+    ( 21845, I, 45,  21845),
+    ( 21845, I, 45,  21845),
+    ( 21845, I, 53,      0),
+    ( 21845, I, 53,  21845),
+    ( 21845, I, 61,      0),
+    ( 21845, I, 61,  21845),
+    ( 21845, O,  5,  21845),
+    ( 21845, O,  5,  21845),
+    ( 21845, O, 13,  21845),
+    ( 21845, O, 21,  21845),
+    ( 21845, O, 29,  21845),
+    ( 21845, O, 37,  21845),
+    ( 21845, O, 45,  21845),
+    ( 21845, O, 53,  21845),
+    ( 21845, O, 61,  21845),
+    (-21846, I,  5,  21845),
+    (-21846, I,  5, -21846),
+    (-21846, O,  5, -21846),
+    (-21846, O,  5, -21846),
+    (-21846, O, 13,  21845),
+    (-21846, O, 21,  21845),
+    (-21846, O, 29,  21845),
+    (-21846, O, 37,  21845),
+    (-21846, O, 45,  21845),
+    (-21846, O, 53,  21845),
+    (-21846, O, 61,  21845),
+    ( 21845, I,  5, -21846),
+    ( 21845, I,  5,  21845),
+    (-21846, I, 13,  21845),
+    (-21846, I, 13, -21846),
+    (-21846, O,  5,  21845),
+    (-21846, O,  5,  21845),
+    (-21846, O, 13, -21846),
+    (-21846, O, 21,  21845),
+    (-21846, O, 29,  21845),
+    (-21846, O, 37,  21845),
+    (-21846, O, 45,  21845),
+    (-21846, O, 53,  21845),
+    (-21846, O, 61,  21845),
+    ( 21845, I, 13, -21846),
+    ( 21845, I, 13,  21845),
+    (-21846, I, 21,  21845),
+    (-21846, I, 21, -21846),
+    (-21846, O,  5,  21845),
+    (-21846, O,  5,  21845),
+    (-21846, O, 13,  21845),
+    (-21846, O, 21, -21846),
+    (-21846, O, 29,  21845),
+    (-21846, O, 37,  21845),
+    (-21846, O, 45,  21845),
+    (-21846, O, 53,  21845),
+    (-21846, O, 61,  21845),
+    ( 21845, I, 21, -21846),
+    ( 21845, I, 21,  21845),
+    (-21846, I, 29,  21845),
+    (-21846, I, 29, -21846),
+    (-21846, O,  5,  21845),
+    (-21846, O,  5,  21845),
+    (-21846, O, 13,  21845),
+    (-21846, O, 21,  21845),
+    (-21846, O, 29, -21846),
+    (-21846, O, 37,  21845),
+    (-21846, O, 45,  21845),
+    (-21846, O, 53,  21845),
+    (-21846, O, 61,  21845),
+    ( 21845, I, 29, -21846),
+    ( 21845, I, 29,  21845),
+    (-21846, I, 37,  21845),
+    (-21846, I, 37, -21846),
+    (-21846, O,  5,  21845),
+    (-21846, O,  5,  21845),
+    (-21846, O, 13,  21845),
+    (-21846, O, 21,  21845),
+    (-21846, O, 29,  21845),
+    (-21846, O, 37, -21846),
+    (-21846, O, 45,  21845),
+    (-21846, O, 53,  21845),
+    (-21846, O, 61,  21845),
+    ( 21845, I, 37, -21846),
+    ( 21845, I, 37,  21845),
+    (-21846, I, 45,  21845),
+    (-21846, I, 45, -21846),
+    (-21846, O,  5,  21845),
+    (-21846, O,  5,  21845),
+    (-21846, O, 13,  21845),
+    (-21846, O, 21,  21845),
+    (-21846, O, 29,  21845),
+    (-21846, O, 37,  21845),
+    (-21846, O, 45, -21846),
+    (-21846, O, 53,  21845),
+    (-21846, O, 61,  21845),
+    ( 21845, I, 45, -21846),
+    ( 21845, I, 45,  21845),
+    (-21846, I, 53,  21845),
+    (-21846, I, 53, -21846),
+    (-21846, O,  5,  21845),
+    (-21846, O,  5,  21845),
+    (-21846, O, 13,  21845),
+    (-21846, O, 21,  21845),
+    (-21846, O, 29,  21845),
+    (-21846, O, 37,  21845),
+    (-21846, O, 45,  21845),
+    (-21846, O, 53, -21846),
+    (-21846, O, 61,  21845),
+    ( 21845, I, 53, -21846),
+    ( 21845, I, 53,  21845),
+    (-21846, I, 61,  21845),
+    (-21846, I, 61, -21846),
+    (-21846, O,  5,  21845),
+    (-21846, O,  5,  21845),
+    (-21846, O, 13,  21845),
+    (-21846, O, 21,  21845),
+    (-21846, O, 29,  21845),
+    (-21846, O, 37,  21845),
+    (-21846, O, 45,  21845),
+    (-21846, O, 53,  21845),
+    (-21846, O, 61, -21846),
+    ( 21845, I, 61, -21846),
+    ( 21845, I, 61,  21845),
+    ( 21845, O,  5,  21845),
+    ( 21845, O,  5,  21845),
+    ( 21845, O, 13,  21845),
+    ( 21845, O, 21,  21845),
+    ( 21845, O, 29,  21845),
+    ( 21845, O, 37,  21845),
+    ( 21845, O, 45,  21845),
+    ( 21845, O, 53,  21845),
+    ( 21845, O, 61,  21845),
+];
 
-    fn register_test_exec(expect: i16, input: i16, load: u8, register: &mut Register) {
-        // .cpmファイルが10進数で記載されているのでビット列に変換してから比較する
-        // expect
-        let pre_formatted_expect: String = format!("{:0b}", expect);
-        let formatted_expect: String = format!("{:0>16}", pre_formatted_expect);
-        let expect_arr: [u8; 16] = converter_16bit_to_array(&formatted_expect);
-        // input
-        let pre_formatted_input: String = format!("{:0b}", input);
-        let formatted_input: String = format!("{:0>16}", pre_formatted_input);
-        let input_arr: [u8; 16] = converter_16bit_to_array(&formatted_input);
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::signal::{I, Word16};
 
-        assert_eq!(expect_arr, register.exec(&input_arr, load));
+    fn register_test_exec(expect: i16, input: i16, load: signal::Bit, register: &mut Register) {
+        // .cmpファイルが10進数で記載されているので Word16 にしてから比較する
+        assert_eq!(Word16::from(expect), register.exec(&Word16::from(input), load));
     }
 
-    fn ram8_test_exec(expect: i16, input: i16, load: u8, address: u8, ram8: &mut Ram8) {
-        // .cpmファイルが10進数で記載されているのでビット列に変換してから比較する
-        // expect
-        let pre_formatted_expect: String = format!("{:0b}", expect);
-        let formatted_expect: String = format!("{:0>16}", pre_formatted_expect);
-        let expect_arr: [u8; 16] = converter_16bit_to_array(&formatted_expect);
-        // input
-        let pre_formatted_input: String = format!("{:0b}", input);
-        let formatted_input: String = format!("{:0>16}", pre_formatted_input);
-        let input_arr: [u8; 16] = converter_16bit_to_array(&formatted_input);
-        // address
-        let pre_formatted_address: String = format!("{:0b}", address);
-        let formatted_address: String = format!("{:0>3}",pre_formatted_address);
-        let address_arr: [u8; 3] = converter_3bit_to_array(&formatted_address);
-
-        assert_eq!(expect_arr, ram8.exec(&input_arr, load, &address_arr));
+    fn ram8_test_exec(expect: i16, input: i16, load: signal::Bit, address: u16, ram8: &mut Ram8) {
+        // address は上の桁から並べて渡す
+        let address_bits = Word16::from(address);
+        let address_arr = [address_bits[2], address_bits[1], address_bits[0]];
+        assert_eq!(Word16::from(expect), ram8.exec(&Word16::from(input), load, &address_arr));
     }
 
-    fn ram64_test_exec(expect: i16, input: i16, load: u8, address: u8, ram64: &mut Ram64) {
-        // .cpmファイルが10進数で記載されているのでビット列に変換してから比較する
-        // expect
-        let pre_formatted_expect: String = format!("{:0b}", expect);
-        let formatted_expect: String = format!("{:0>16}", pre_formatted_expect);
-        let expect_arr: [u8; 16] = converter_16bit_to_array(&formatted_expect);
-
-        // input
-        let pre_formatted_input: String = format!("{:0b}", input);
-        let formatted_input: String = format!("{:0>16}", pre_formatted_input);
-        let input_arr: [u8; 16] = converter_16bit_to_array(&formatted_input);
-
-        // address
-        let pre_formatted_address: String = format!("{:0b}", address);
-        let formatted_address: String = format!("{:0>6}",pre_formatted_address);
-        let address_arr: [u8; 6] = converter_6bit_to_array(&formatted_address);
-
-        assert_eq!(expect_arr, ram64.exec(&input_arr, load, &address_arr));
+    fn ram64_test_exec(expect: i16, input: i16, load: signal::Bit, address: u16, ram64: &mut Ram64) {
+        let address_bits = Word16::from(address);
+        let address_arr = [
+            address_bits[5], address_bits[4], address_bits[3],
+            address_bits[2], address_bits[1], address_bits[0]
+        ];
+        assert_eq!(Word16::from(expect), ram64.exec(&Word16::from(input), load, &address_arr));
     }
 
-    fn pc_test_exec(expect: i16, input: i16, reset: u8, load: u8, inc: u8, pc: &mut Pc) {
-        // .cpmファイルが10進数で記載されているのでビット列に変換してから比較する
-        // expect
-        let pre_formatted_expect: String = format!("{:0b}", expect);
-        let formatted_expect: String = format!("{:0>16}", pre_formatted_expect);
-        let expect_arr: [u8; 16] = converter_16bit_to_array(&formatted_expect);
-
-        // input
-        let pre_formatted_input: String = format!("{:0b}", input);
-        let formatted_input: String = format!("{:0>16}", pre_formatted_input);
-        let input_arr: [u8; 16] = converter_16bit_to_array(&formatted_input);
-
-        assert_eq!(expect_arr, pc.exec(&input_arr, reset, load, inc));
+    fn pc_test_exec(expect: i16, input: i16, reset: signal::Bit, load: signal::Bit, inc: signal::Bit, pc: &mut Pc) {
+        assert_eq!(Word16::from(expect), pc.exec(&Word16::from(input), reset, load, inc));
     }
 
     #[test]
     fn dff_test() {
-        let mut dff: Dff = Dff::new(0);
-        assert_eq!(0, dff.exec(1));
-        assert_eq!(1, dff.exec(0));
-        assert_eq!(0, dff.exec(0));
-        assert_eq!(0, dff.exec(1));
-        assert_eq!(1, dff.exec(1));
-        assert_eq!(1, dff.exec(1));
+        let mut dff: Dff = Dff::new(O);
+        assert_eq!(O, dff.exec(I));
+        assert_eq!(I, dff.exec(O));
+        assert_eq!(O, dff.exec(O));
+        assert_eq!(O, dff.exec(I));
+        assert_eq!(I, dff.exec(I));
+        assert_eq!(I, dff.exec(I));
     }
 
     #[test]
     fn bit_test() {
         let mut bit: Bit = Bit::new();
-        assert_eq!(0, bit.exec(0, 0));
-        assert_eq!(0, bit.exec(0, 0));
-        assert_eq!(0, bit.exec(0, 1));
-        assert_eq!(0, bit.exec(0, 1));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 1));
-        assert_eq!(1, bit.exec(1, 1));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(1, 0));
-        assert_eq!(1, bit.exec(1, 0));
-        assert_eq!(1, bit.exec(0, 1));
-        assert_eq!(0, bit.exec(0, 1));
-        assert_eq!(0, bit.exec(1, 1));
-        assert_eq!(1, bit.exec(1, 1));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 0));
-        assert_eq!(1, bit.exec(0, 1));
-        assert_eq!(0, bit.exec(0, 1));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
-        assert_eq!(0, bit.exec(1, 0));
+        for (input, load, out) in BIT_VECTORS {
+            assert_eq!(*out, bit.exec(*input, *load));
+        }
     }
 
     #[test]
     fn register_test() {
         let mut register = Register::new();
-        register_test_exec(     0,      0 , 0, &mut register);
-        register_test_exec(     0,      0 , 0, &mut register);
-        register_test_exec(     0,      0 , 1, &mut register);
-        register_test_exec(     0,      0 , 1, &mut register);
-        register_test_exec(     0, -32123 , 0, &mut register);
-        register_test_exec(     0, -32123 , 0, &mut register);
-        register_test_exec(     0,  11111 , 0, &mut register);
-        register_test_exec(     0,  11111 , 0, &mut register);
-        register_test_exec(     0, -32123 , 1, &mut register);
-        register_test_exec(-32123, -32123 , 1, &mut register);
-        register_test_exec(-32123, -32123 , 1, &mut register);
-        register_test_exec(-32123, -32123 , 1, &mut register);
-        register_test_exec(-32123, -32123 , 0, &mut register);
-        register_test_exec(-32123, -32123 , 0, &mut register);
-        register_test_exec(-32123,  12345 , 1, &mut register);
-        register_test_exec( 12345,  12345 , 1, &mut register);
-        register_test_exec( 12345,      0 , 0, &mut register);
-        register_test_exec( 12345,      0 , 0, &mut register);
-        register_test_exec( 12345,      0 , 1, &mut register);
-        register_test_exec(     0,      0 , 1, &mut register);
-        register_test_exec(     0,      1 , 0, &mut register);
-        register_test_exec(     0,      1 , 0, &mut register);
-        register_test_exec(     0,      1 , 1, &mut register);
-        register_test_exec(     1,      1 , 1, &mut register);
-        register_test_exec(     1,      2 , 0, &mut register);
-        register_test_exec(     1,      2 , 0, &mut register);
-        register_test_exec(     1,      2 , 1, &mut register);
-        register_test_exec(     2,      2 , 1, &mut register);
-        register_test_exec(     2,      4 , 0, &mut register);
-        register_test_exec(     2,      4 , 0, &mut register);
-        register_test_exec(     2,      4 , 1, &mut register);
-        register_test_exec(     4,      4 , 1, &mut register);
-        register_test_exec(     4,      8 , 0, &mut register);
-        register_test_exec(     4,      8 , 0, &mut register);
-        register_test_exec(     4,      8 , 1, &mut register);
-        register_test_exec(     8,      8 , 1, &mut register);
-        register_test_exec(     8,     16 , 0, &mut register);
-        register_test_exec(     8,     16 , 0, &mut register);
-        register_test_exec(     8,     16 , 1, &mut register);
-        register_test_exec(    16,     16 , 1, &mut register);
-        register_test_exec(    16,     32 , 0, &mut register);
-        register_test_exec(    16,     32 , 0, &mut register);
-        register_test_exec(    16,     32 , 1, &mut register);
-        register_test_exec(    32,     32 , 1, &mut register);
-        register_test_exec(    32,     64 , 0, &mut register);
-        register_test_exec(    32,     64 , 0, &mut register);
-        register_test_exec(    32,     64 , 1, &mut register);
-        register_test_exec(    64,     64 , 1, &mut register);
-        register_test_exec(    64,    128 , 0, &mut register);
-        register_test_exec(    64,    128 , 0, &mut register);
-        register_test_exec(    64,    128 , 1, &mut register);
-        register_test_exec(   128,    128 , 1, &mut register);
-        register_test_exec(   128,    256 , 0, &mut register);
-        register_test_exec(   128,    256 , 0, &mut register);
-        register_test_exec(   128,    256 , 1, &mut register);
-        register_test_exec(   256,    256 , 1, &mut register);
-        register_test_exec(   256,    512 , 0, &mut register);
-        register_test_exec(   256,    512 , 0, &mut register);
-        register_test_exec(   256,    512 , 1, &mut register);
-        register_test_exec(   512,    512 , 1, &mut register);
-        register_test_exec(   512,   1024 , 0, &mut register);
-        register_test_exec(   512,   1024 , 0, &mut register);
-        register_test_exec(   512,   1024 , 1, &mut register);
-        register_test_exec(  1024,   1024 , 1, &mut register);
-        register_test_exec(  1024,   2048 , 0, &mut register);
-        register_test_exec(  1024,   2048 , 0, &mut register);
-        register_test_exec(  1024,   2048 , 1, &mut register);
-        register_test_exec(  2048,   2048 , 1, &mut register);
-        register_test_exec(  2048,   4096 , 0, &mut register);
-        register_test_exec(  2048,   4096 , 0, &mut register);
-        register_test_exec(  2048,   4096 , 1, &mut register);
-        register_test_exec(  4096,   4096 , 1, &mut register);
-        register_test_exec(  4096,   8192 , 0, &mut register);
-        register_test_exec(  4096,   8192 , 0, &mut register);
-        register_test_exec(  4096,   8192 , 1, &mut register);
-        register_test_exec(  8192,   8192 , 1, &mut register);
-        register_test_exec(  8192,  16384 , 0, &mut register);
-        register_test_exec(  8192,  16384 , 0, &mut register);
-        register_test_exec(  8192,  16384 , 1, &mut register);
-        register_test_exec( 16384,  16384 , 1, &mut register);
-        register_test_exec( 16384, -32768 , 0, &mut register);
-        register_test_exec( 16384, -32768 , 0, &mut register);
-        register_test_exec( 16384, -32768 , 1, &mut register);
-        register_test_exec(-32768, -32768 , 1, &mut register);
-        register_test_exec(-32768,     -2 , 0, &mut register);
-        register_test_exec(-32768,     -2 , 0, &mut register);
-        register_test_exec(-32768,     -2 , 1, &mut register);
-        register_test_exec(    -2,     -2 , 1, &mut register);
-        register_test_exec(    -2,     -3 , 0, &mut register);
-        register_test_exec(    -2,     -3 , 0, &mut register);
-        register_test_exec(    -2,     -3 , 1, &mut register);
-        register_test_exec(    -3,     -3 , 1, &mut register);
-        register_test_exec(    -3,     -5 , 0, &mut register);
-        register_test_exec(    -3,     -5 , 0, &mut register);
-        register_test_exec(    -3,     -5 , 1, &mut register);
-        register_test_exec(    -5,     -5 , 1, &mut register);
-        register_test_exec(    -5,     -9 , 0, &mut register);
-        register_test_exec(    -5,     -9 , 0, &mut register);
-        register_test_exec(    -5,     -9 , 1, &mut register);
-        register_test_exec(    -9,     -9 , 1, &mut register);
-        register_test_exec(    -9,    -17 , 0, &mut register);
-        register_test_exec(    -9,    -17 , 0, &mut register);
-        register_test_exec(    -9,    -17 , 1, &mut register);
-        register_test_exec(   -17,    -17 , 1, &mut register);
-        register_test_exec(   -17,    -33 , 0, &mut register);
-        register_test_exec(   -17,    -33 , 0, &mut register);
-        register_test_exec(   -17,    -33 , 1, &mut register);
-        register_test_exec(   -33,    -33 , 1, &mut register);
-        register_test_exec(   -33,    -65 , 0, &mut register);
-        register_test_exec(   -33,    -65 , 0, &mut register);
-        register_test_exec(   -33,    -65 , 1, &mut register);
-        register_test_exec(   -65,    -65 , 1, &mut register);
-        register_test_exec(   -65,   -129 , 0, &mut register);
-        register_test_exec(   -65,   -129 , 0, &mut register);
-        register_test_exec(   -65,   -129 , 1, &mut register);
-        register_test_exec(  -129,   -129 , 1, &mut register);
-        register_test_exec(  -129,   -257 , 0, &mut register);
-        register_test_exec(  -129,   -257 , 0, &mut register);
-        register_test_exec(  -129,   -257 , 1, &mut register);
-        register_test_exec(  -257,   -257 , 1, &mut register);
-        register_test_exec(  -257,   -513 , 0, &mut register);
-        register_test_exec(  -257,   -513 , 0, &mut register);
-        register_test_exec(  -257,   -513 , 1, &mut register);
-        register_test_exec(  -513,   -513 , 1, &mut register);
-        register_test_exec(  -513,  -1025 , 0, &mut register);
-        register_test_exec(  -513,  -1025 , 0, &mut register);
-        register_test_exec(  -513,  -1025 , 1, &mut register);
-        register_test_exec( -1025,  -1025 , 1, &mut register);
-        register_test_exec( -1025,  -2049 , 0, &mut register);
-        register_test_exec( -1025,  -2049 , 0, &mut register);
-        register_test_exec( -1025,  -2049 , 1, &mut register);
-        register_test_exec( -2049,  -2049 , 1, &mut register);
-        register_test_exec( -2049,  -4097 , 0, &mut register);
-        register_test_exec( -2049,  -4097 , 0, &mut register);
-        register_test_exec( -2049,  -4097 , 1, &mut register);
-        register_test_exec( -4097,  -4097 , 1, &mut register);
-        register_test_exec( -4097,  -8193 , 0, &mut register);
-        register_test_exec( -4097,  -8193 , 0, &mut register);
-        register_test_exec( -4097,  -8193 , 1, &mut register);
-        register_test_exec( -8193,  -8193 , 1, &mut register);
-        register_test_exec( -8193, -16385 , 0, &mut register);
-        register_test_exec( -8193, -16385 , 0, &mut register);
-        register_test_exec( -8193, -16385 , 1, &mut register);
-        register_test_exec(-16385, -16385 , 1, &mut register);
-        register_test_exec(-16385,  32767 , 0, &mut register);
-        register_test_exec(-16385,  32767 , 0, &mut register);
-        register_test_exec(-16385,  32767 , 1, &mut register);
-        register_test_exec( 32767,  32767 , 1, &mut register);
+        for (input, load, expect) in REGISTER_VECTORS {
+            register_test_exec(*expect, *input, *load, &mut register);
+        }
     }
 
     #[test]
     fn ram8_test() {
         let mut ram8 = Ram8::new();
-        ram8_test_exec(     0,      0, 0, 0, &mut ram8);
-        ram8_test_exec(     0,      0, 1, 0, &mut ram8);
-        ram8_test_exec(     0,      0, 1, 0, &mut ram8);
-        ram8_test_exec(     0,  11111, 0, 0, &mut ram8);
-        ram8_test_exec(     0,  11111, 0, 0, &mut ram8);
-        ram8_test_exec(     0,  11111, 1, 1, &mut ram8);
-        ram8_test_exec( 11111,  11111, 1, 1, &mut ram8);
-        ram8_test_exec(     0,  11111, 0, 0, &mut ram8);
-        ram8_test_exec(     0,  11111, 0, 0, &mut ram8);
-        ram8_test_exec(     0,   3333, 0, 3, &mut ram8);
-        ram8_test_exec(     0,   3333, 0, 3, &mut ram8);
-        ram8_test_exec(     0,   3333, 1, 3, &mut ram8);
-        ram8_test_exec(  3333,   3333, 1, 3, &mut ram8);
-        ram8_test_exec(  3333,   3333, 0, 3, &mut ram8);
-        ram8_test_exec(  3333,   3333, 0, 3, &mut ram8);
-        ram8_test_exec( 11111,   3333, 0, 1, &mut ram8);
-        ram8_test_exec( 11111,   7777, 0, 1, &mut ram8);
-        ram8_test_exec( 11111,   7777, 0, 1, &mut ram8);
-        ram8_test_exec(     0,   7777, 1, 7, &mut ram8);
-        ram8_test_exec(  7777,   7777, 1, 7, &mut ram8);
-        ram8_test_exec(  7777,   7777, 0, 7, &mut ram8);
-        ram8_test_exec(  7777,   7777, 0, 7, &mut ram8);
-        ram8_test_exec(  3333,   7777, 0, 3, &mut ram8);
-        ram8_test_exec(  7777,   7777, 0, 7, &mut ram8);
-        ram8_test_exec(     0,   7777, 0, 0, &mut ram8);
-        ram8_test_exec(     0,   7777, 0, 0, &mut ram8);
-        ram8_test_exec( 11111,   7777, 0, 1, &mut ram8);
-        ram8_test_exec(     0,   7777, 0, 2, &mut ram8);
-        ram8_test_exec(  3333,   7777, 0, 3, &mut ram8);
-        ram8_test_exec(     0,   7777, 0, 4, &mut ram8);
-        ram8_test_exec(     0,   7777, 0, 5, &mut ram8);
-        ram8_test_exec(     0,   7777, 0, 6, &mut ram8);
-        ram8_test_exec(  7777,   7777, 0, 7, &mut ram8);
-        ram8_test_exec(     0,  21845, 1, 0, &mut ram8);
-        ram8_test_exec( 21845,  21845, 1, 0, &mut ram8);
-        ram8_test_exec( 11111,  21845, 1, 1, &mut ram8);
-        ram8_test_exec( 21845,  21845, 1, 1, &mut ram8);
-        ram8_test_exec(     0,  21845, 1, 2, &mut ram8);
-        ram8_test_exec( 21845,  21845, 1, 2, &mut ram8);
-        ram8_test_exec(  3333,  21845, 1, 3, &mut ram8);
-        ram8_test_exec( 21845,  21845, 1, 3, &mut ram8);
-        ram8_test_exec(     0,  21845, 1, 4, &mut ram8);
-        ram8_test_exec( 21845,  21845, 1, 4, &mut ram8);
-        ram8_test_exec(     0,  21845, 1, 5, &mut ram8);
-        ram8_test_exec( 21845,  21845, 1, 5, &mut ram8);
-        ram8_test_exec(     0,  21845, 1, 6, &mut ram8);
-        ram8_test_exec( 21845,  21845, 1, 6, &mut ram8);
-        ram8_test_exec(  7777,  21845, 1, 7, &mut ram8);
-        ram8_test_exec( 21845,  21845, 1, 7, &mut ram8);
-        ram8_test_exec( 21845,  21845, 0, 0, &mut ram8);
-        ram8_test_exec( 21845,  21845, 0, 0, &mut ram8);
-        ram8_test_exec( 21845,  21845, 0, 1, &mut ram8);
-        ram8_test_exec( 21845,  21845, 0, 2, &mut ram8);
-        ram8_test_exec( 21845,  21845, 0, 3, &mut ram8);
-        ram8_test_exec( 21845,  21845, 0, 4, &mut ram8);
-        ram8_test_exec( 21845,  21845, 0, 5, &mut ram8);
-        ram8_test_exec( 21845,  21845, 0, 6, &mut ram8);
-        ram8_test_exec( 21845,  21845, 0, 7, &mut ram8);
-        ram8_test_exec( 21845, -21846, 1, 0, &mut ram8);
-        ram8_test_exec(-21846, -21846, 1, 0, &mut ram8);
-        ram8_test_exec(-21846, -21846, 0, 0, &mut ram8);
-        ram8_test_exec(-21846, -21846, 0, 0, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 1, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 2, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 3, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 4, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 5, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 6, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 7, &mut ram8);
-        ram8_test_exec(-21846,  21845, 1, 0, &mut ram8);
-        ram8_test_exec( 21845,  21845, 1, 0, &mut ram8);
-        ram8_test_exec( 21845, -21846, 1, 1, &mut ram8);
-        ram8_test_exec(-21846, -21846, 1, 1, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 0, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 0, &mut ram8);
-        ram8_test_exec(-21846, -21846, 0, 1, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 2, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 3, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 4, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 5, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 6, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 7, &mut ram8);
-        ram8_test_exec(-21846,  21845, 1, 1, &mut ram8);
-        ram8_test_exec( 21845,  21845, 1, 1, &mut ram8);
-        ram8_test_exec( 21845, -21846, 1, 2, &mut ram8);
-        ram8_test_exec(-21846, -21846, 1, 2, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 0, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 0, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 1, &mut ram8);
-        ram8_test_exec(-21846, -21846, 0, 2, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 3, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 4, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 5, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 6, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 7, &mut ram8);
-        ram8_test_exec(-21846,  21845, 1, 2, &mut ram8);
-        ram8_test_exec( 21845,  21845, 1, 2, &mut ram8);
-        ram8_test_exec( 21845, -21846, 1, 3, &mut ram8);
-        ram8_test_exec(-21846, -21846, 1, 3, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 0, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 0, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 1, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 2, &mut ram8);
-        ram8_test_exec(-21846, -21846, 0, 3, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 4, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 5, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 6, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 7, &mut ram8);
-        ram8_test_exec(-21846,  21845, 1, 3, &mut ram8);
-        ram8_test_exec( 21845,  21845, 1, 3, &mut ram8);
-        ram8_test_exec( 21845, -21846, 1, 4, &mut ram8);
-        ram8_test_exec(-21846, -21846, 1, 4, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 0, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 0, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 1, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 2, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 3, &mut ram8);
-        ram8_test_exec(-21846, -21846, 0, 4, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 5, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 6, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 7, &mut ram8);
-        ram8_test_exec(-21846,  21845, 1, 4, &mut ram8);
-        ram8_test_exec( 21845,  21845, 1, 4, &mut ram8);
-        ram8_test_exec( 21845, -21846, 1, 5, &mut ram8);
-        ram8_test_exec(-21846, -21846, 1, 5, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 0, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 0, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 1, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 2, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 3, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 4, &mut ram8);
-        ram8_test_exec(-21846, -21846, 0, 5, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 6, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 7, &mut ram8);
-        ram8_test_exec(-21846,  21845, 1, 5, &mut ram8);
-        ram8_test_exec( 21845,  21845, 1, 5, &mut ram8);
-        ram8_test_exec( 21845, -21846, 1, 6, &mut ram8);
-        ram8_test_exec(-21846, -21846, 1, 6, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 0, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 0, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 1, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 2, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 3, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 4, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 5, &mut ram8);
-        ram8_test_exec(-21846, -21846, 0, 6, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 7, &mut ram8);
-        ram8_test_exec(-21846,  21845, 1, 6, &mut ram8);
-        ram8_test_exec( 21845,  21845, 1, 6, &mut ram8);
-        ram8_test_exec( 21845, -21846, 1, 7, &mut ram8);
-        ram8_test_exec(-21846, -21846, 1, 7, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 0, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 0, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 1, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 2, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 3, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 4, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 5, &mut ram8);
-        ram8_test_exec( 21845, -21846, 0, 6, &mut ram8);
-        ram8_test_exec(-21846, -21846, 0, 7, &mut ram8);
-        ram8_test_exec(-21846,  21845, 1, 7, &mut ram8);
-        ram8_test_exec( 21845,  21845, 1, 7, &mut ram8);
-        ram8_test_exec( 21845,  21845, 0, 0, &mut ram8);
-        ram8_test_exec( 21845,  21845, 0, 0, &mut ram8);
-        ram8_test_exec( 21845,  21845, 0, 1, &mut ram8);
-        ram8_test_exec( 21845,  21845, 0, 2, &mut ram8);
-        ram8_test_exec( 21845,  21845, 0, 3, &mut ram8);
-        ram8_test_exec( 21845,  21845, 0, 4, &mut ram8);
-        ram8_test_exec( 21845,  21845, 0, 5, &mut ram8);
-        ram8_test_exec( 21845,  21845, 0, 6, &mut ram8);
-        ram8_test_exec( 21845,  21845, 0, 7, &mut ram8);
+        for (input, load, address, expect) in RAM8_VECTORS {
+            ram8_test_exec(*expect, *input, *load, *address, &mut ram8);
+        }
     }
 
     #[test]
     fn ram64_test() {
         let mut ram64 = Ram64::new();
-        ram64_test_exec(     0,      0, 0,   0, &mut ram64);
-        ram64_test_exec(     0,      0, 0,   0, &mut ram64);
-        ram64_test_exec(     0,      0, 1,   0, &mut ram64);
-        ram64_test_exec(     0,      0, 1,   0, &mut ram64);
-        ram64_test_exec(     0,   1313, 0,   0, &mut ram64);
-        ram64_test_exec(     0,   1313, 0,   0, &mut ram64);
-        ram64_test_exec(     0,   1313, 1,  13, &mut ram64);
-        ram64_test_exec(  1313,   1313, 1,  13, &mut ram64);
-        ram64_test_exec(     0,   1313, 0,   0, &mut ram64);
-        ram64_test_exec(     0,   1313, 0,   0, &mut ram64);
-        ram64_test_exec(     0,   4747, 0,  47, &mut ram64);
-        ram64_test_exec(     0,   4747, 0,  47, &mut ram64);
-        ram64_test_exec(     0,   4747, 1,  47, &mut ram64);
-        ram64_test_exec(  4747,   4747, 1,  47, &mut ram64);
-        ram64_test_exec(  4747,   4747, 0,  47, &mut ram64);
-        ram64_test_exec(  4747,   4747, 0,  47, &mut ram64);
-        ram64_test_exec(  1313,   4747, 0,  13, &mut ram64);
-        ram64_test_exec(  1313,   6363, 0,  13, &mut ram64);
-        ram64_test_exec(  1313,   6363, 0,  13, &mut ram64);
-        ram64_test_exec(     0,   6363, 1,  63, &mut ram64);
-        ram64_test_exec(  6363,   6363, 1,  63, &mut ram64);
-        ram64_test_exec(  6363,   6363, 0,  63, &mut ram64);
-        ram64_test_exec(  6363,   6363, 0,  63, &mut ram64);
-        ram64_test_exec(  4747,   6363, 0,  47, &mut ram64);
-        ram64_test_exec(  6363,   6363, 0,  63, &mut ram64);
-        ram64_test_exec(     0,   6363, 0,  40, &mut ram64);
-        ram64_test_exec(     0,   6363, 0,  40, &mut ram64);
-        ram64_test_exec(     0,   6363, 0,  41, &mut ram64);
-        ram64_test_exec(     0,   6363, 0,  42, &mut ram64);
-        ram64_test_exec(     0,   6363, 0,  43, &mut ram64);
-        ram64_test_exec(     0,   6363, 0,  44, &mut ram64);
-        ram64_test_exec(     0,   6363, 0,  45, &mut ram64);
-        ram64_test_exec(     0,   6363, 0,  46, &mut ram64);
-        ram64_test_exec(  4747,   6363, 0,  47, &mut ram64);
-        ram64_test_exec(     0,  21845, 1,  40, &mut ram64);
-        ram64_test_exec( 21845,  21845, 1,  40, &mut ram64);
-        ram64_test_exec(     0,  21845, 1,  41, &mut ram64);
-        ram64_test_exec( 21845,  21845, 1,  41, &mut ram64);
-        ram64_test_exec(     0,  21845, 1,  42, &mut ram64);
-        ram64_test_exec( 21845,  21845, 1,  42, &mut ram64);
-        ram64_test_exec(     0,  21845, 1,  43, &mut ram64);
-        ram64_test_exec( 21845,  21845, 1,  43, &mut ram64);
-        ram64_test_exec(     0,  21845, 1,  44, &mut ram64);
-        ram64_test_exec( 21845,  21845, 1,  44, &mut ram64);
-        ram64_test_exec(     0,  21845, 1,  45, &mut ram64);
-        ram64_test_exec( 21845,  21845, 1,  45, &mut ram64);
-        ram64_test_exec(     0,  21845, 1,  46, &mut ram64);
-        ram64_test_exec( 21845,  21845, 1,  46, &mut ram64);
-        ram64_test_exec(  4747,  21845, 1,  47, &mut ram64);
-        ram64_test_exec( 21845,  21845, 1,  47, &mut ram64);
-        ram64_test_exec( 21845,  21845, 0,  40, &mut ram64);
-        ram64_test_exec( 21845,  21845, 0,  40, &mut ram64);
-        ram64_test_exec( 21845,  21845, 0,  41, &mut ram64);
-        ram64_test_exec( 21845,  21845, 0,  42, &mut ram64);
-        ram64_test_exec( 21845,  21845, 0,  43, &mut ram64);
-        ram64_test_exec( 21845,  21845, 0,  44, &mut ram64);
-        ram64_test_exec( 21845,  21845, 0,  45, &mut ram64);
-        ram64_test_exec( 21845,  21845, 0,  46, &mut ram64);
-        ram64_test_exec( 21845,  21845, 0,  47, &mut ram64);
-        ram64_test_exec( 21845, -21846, 1,  40, &mut ram64);
-        ram64_test_exec(-21846, -21846, 1,  40, &mut ram64);
-        ram64_test_exec(-21846, -21846, 0,  40, &mut ram64);
-        ram64_test_exec(-21846, -21846, 0,  40, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  41, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  42, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  43, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  44, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  45, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  46, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  47, &mut ram64);
-        ram64_test_exec(-21846,  21845, 1,  40, &mut ram64);
-        ram64_test_exec( 21845,  21845, 1,  40, &mut ram64);
-        ram64_test_exec( 21845, -21846, 1,  41, &mut ram64);
-        ram64_test_exec(-21846, -21846, 1,  41, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  40, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  40, &mut ram64);
-        ram64_test_exec(-21846, -21846, 0,  41, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  42, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  43, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  44, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  45, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  46, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  47, &mut ram64);
-        ram64_test_exec(-21846,  21845, 1,  41, &mut ram64);
-        ram64_test_exec( 21845,  21845, 1,  41, &mut ram64);
-        ram64_test_exec( 21845, -21846, 1,  42, &mut ram64);
-        ram64_test_exec(-21846, -21846, 1,  42, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  40, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  40, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  41, &mut ram64);
-        ram64_test_exec(-21846, -21846, 0,  42, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  43, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  44, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  45, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  46, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  47, &mut ram64);
-        ram64_test_exec(-21846,  21845, 1,  42, &mut ram64);
-        ram64_test_exec( 21845,  21845, 1,  42, &mut ram64);
-        ram64_test_exec( 21845, -21846, 1,  43, &mut ram64);
-        ram64_test_exec(-21846, -21846, 1,  43, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  40, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  40, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  41, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  42, &mut ram64);
-        ram64_test_exec(-21846, -21846, 0,  43, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  44, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  45, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  46, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  47, &mut ram64);
-        ram64_test_exec(-21846,  21845, 1,  43, &mut ram64);
-        ram64_test_exec( 21845,  21845, 1,  43, &mut ram64);
-        ram64_test_exec( 21845, -21846, 1,  44, &mut ram64);
-        ram64_test_exec(-21846, -21846, 1,  44, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  40, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  40, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  41, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  42, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  43, &mut ram64);
-        ram64_test_exec(-21846, -21846, 0,  44, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  45, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  46, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  47, &mut ram64);
-        ram64_test_exec(-21846,  21845, 1,  44, &mut ram64);
-        ram64_test_exec( 21845,  21845, 1,  44, &mut ram64);
-        ram64_test_exec( 21845, -21846, 1,  45, &mut ram64);
-        ram64_test_exec(-21846, -21846, 1,  45, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  40, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  40, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  41, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  42, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  43, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  44, &mut ram64);
-        ram64_test_exec(-21846, -21846, 0,  45, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  46, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  47, &mut ram64);
-        ram64_test_exec(-21846,  21845, 1,  45, &mut ram64);
-        ram64_test_exec( 21845,  21845, 1,  45, &mut ram64);
-        ram64_test_exec( 21845, -21846, 1,  46, &mut ram64);
-        ram64_test_exec(-21846, -21846, 1,  46, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  40, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  40, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  41, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  42, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  43, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  44, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  45, &mut ram64);
-        ram64_test_exec(-21846, -21846, 0,  46, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  47, &mut ram64);
-        ram64_test_exec(-21846,  21845, 1,  46, &mut ram64);
-        ram64_test_exec( 21845,  21845, 1,  46, &mut ram64);
-        ram64_test_exec( 21845, -21846, 1,  47, &mut ram64);
-        ram64_test_exec(-21846, -21846, 1,  47, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  40, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  40, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  41, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  42, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  43, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  44, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  45, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  46, &mut ram64);
-        ram64_test_exec(-21846, -21846, 0,  47, &mut ram64);
-        ram64_test_exec(-21846,  21845, 1,  47, &mut ram64);
-        ram64_test_exec( 21845,  21845, 1,  47, &mut ram64);
-        ram64_test_exec( 21845,  21845, 0,  40, &mut ram64);
-        ram64_test_exec( 21845,  21845, 0,  40, &mut ram64);
-        ram64_test_exec( 21845,  21845, 0,  41, &mut ram64);
-        ram64_test_exec( 21845,  21845, 0,  42, &mut ram64);
-        ram64_test_exec( 21845,  21845, 0,  43, &mut ram64);
-        ram64_test_exec( 21845,  21845, 0,  44, &mut ram64);
-        ram64_test_exec( 21845,  21845, 0,  45, &mut ram64);
-        ram64_test_exec( 21845,  21845, 0,  46, &mut ram64);
-        ram64_test_exec( 21845,  21845, 0,  47, &mut ram64);
-        ram64_test_exec(     0,  21845, 0,   5, &mut ram64);
-        ram64_test_exec(     0,  21845, 0,   5, &mut ram64);
-        ram64_test_exec(  1313,  21845, 0,  13, &mut ram64);
-        ram64_test_exec(     0,  21845, 0,  21, &mut ram64);
-        ram64_test_exec(     0,  21845, 0,  29, &mut ram64);
-        ram64_test_exec(     0,  21845, 0,  37, &mut ram64);
-        ram64_test_exec( 21845,  21845, 0,  45, &mut ram64);
-        ram64_test_exec(     0,  21845, 0,  53, &mut ram64);
-        ram64_test_exec(     0,  21845, 0,  61, &mut ram64);
-        ram64_test_exec(     0,  21845, 1,   5, &mut ram64);
-        ram64_test_exec( 21845,  21845, 1,   5, &mut ram64);
-        ram64_test_exec(  1313,  21845, 1,  13, &mut ram64);
-        ram64_test_exec( 21845,  21845, 1,  13, &mut ram64);
-        ram64_test_exec(     0,  21845, 1,  21, &mut ram64);
-        ram64_test_exec( 21845,  21845, 1,  21, &mut ram64);
-        ram64_test_exec(     0,  21845, 1,  29, &mut ram64);
-        ram64_test_exec( 21845,  21845, 1,  29, &mut ram64);
-        ram64_test_exec(     0,  21845, 1,  37, &mut ram64);
-        ram64_test_exec( 21845,  21845, 1,  37, &mut ram64);
-        ram64_test_exec( 21845,  21845, 1,  45, &mut ram64);
-        ram64_test_exec( 21845,  21845, 1,  45, &mut ram64);
-        ram64_test_exec(     0,  21845, 1,  53, &mut ram64);
-        ram64_test_exec( 21845,  21845, 1,  53, &mut ram64);
-        ram64_test_exec(     0,  21845, 1,  61, &mut ram64);
-        ram64_test_exec( 21845,  21845, 1,  61, &mut ram64);
-        ram64_test_exec( 21845,  21845, 0,   5, &mut ram64);
-        ram64_test_exec( 21845,  21845, 0,   5, &mut ram64);
-        ram64_test_exec( 21845,  21845, 0,  13, &mut ram64);
-        ram64_test_exec( 21845,  21845, 0,  21, &mut ram64);
-        ram64_test_exec( 21845,  21845, 0,  29, &mut ram64);
-        ram64_test_exec( 21845,  21845, 0,  37, &mut ram64);
-        ram64_test_exec( 21845,  21845, 0,  45, &mut ram64);
-        ram64_test_exec( 21845,  21845, 0,  53, &mut ram64);
-        ram64_test_exec( 21845,  21845, 0,  61, &mut ram64);
-        ram64_test_exec( 21845, -21846, 1,   5, &mut ram64);
-        ram64_test_exec(-21846, -21846, 1,   5, &mut ram64);
-        ram64_test_exec(-21846, -21846, 0,   5, &mut ram64);
-        ram64_test_exec(-21846, -21846, 0,   5, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  13, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  21, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  29, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  37, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  45, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  53, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  61, &mut ram64);
-        ram64_test_exec(-21846,  21845, 1,   5, &mut ram64);
-        ram64_test_exec( 21845,  21845, 1,   5, &mut ram64);
-        ram64_test_exec( 21845, -21846, 1,  13, &mut ram64);
-        ram64_test_exec(-21846, -21846, 1,  13, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,   5, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,   5, &mut ram64);
-        ram64_test_exec(-21846, -21846, 0,  13, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  21, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  29, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  37, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  45, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  53, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  61, &mut ram64);
-        ram64_test_exec(-21846,  21845, 1,  13, &mut ram64);
-        ram64_test_exec( 21845,  21845, 1,  13, &mut ram64);
-        ram64_test_exec( 21845, -21846, 1,  21, &mut ram64);
-        ram64_test_exec(-21846, -21846, 1,  21, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,   5, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,   5, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  13, &mut ram64);
-        ram64_test_exec(-21846, -21846, 0,  21, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  29, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  37, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  45, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  53, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  61, &mut ram64);
-        ram64_test_exec(-21846,  21845, 1,  21, &mut ram64);
-        ram64_test_exec( 21845,  21845, 1,  21, &mut ram64);
-        ram64_test_exec( 21845, -21846, 1,  29, &mut ram64);
-        ram64_test_exec(-21846, -21846, 1,  29, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,   5, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,   5, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  13, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  21, &mut ram64);
-        ram64_test_exec(-21846, -21846, 0,  29, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  37, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  45, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  53, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  61, &mut ram64);
-        ram64_test_exec(-21846,  21845, 1,  29, &mut ram64);
-        ram64_test_exec( 21845,  21845, 1,  29, &mut ram64);
-        ram64_test_exec( 21845, -21846, 1,  37, &mut ram64);
-        ram64_test_exec(-21846, -21846, 1,  37, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,   5, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,   5, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  13, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  21, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  29, &mut ram64);
-        ram64_test_exec(-21846, -21846, 0,  37, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  45, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  53, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  61, &mut ram64);
-        ram64_test_exec(-21846,  21845, 1,  37, &mut ram64);
-        ram64_test_exec( 21845,  21845, 1,  37, &mut ram64);
-        ram64_test_exec( 21845, -21846, 1,  45, &mut ram64);
-        ram64_test_exec(-21846, -21846, 1,  45, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,   5, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,   5, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  13, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  21, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  29, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  37, &mut ram64);
-        ram64_test_exec(-21846, -21846, 0,  45, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  53, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  61, &mut ram64);
-        ram64_test_exec(-21846,  21845, 1,  45, &mut ram64);
-        ram64_test_exec( 21845,  21845, 1,  45, &mut ram64);
-        ram64_test_exec( 21845, -21846, 1,  53, &mut ram64);
-        ram64_test_exec(-21846, -21846, 1,  53, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,   5, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,   5, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  13, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  21, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  29, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  37, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  45, &mut ram64);
-        ram64_test_exec(-21846, -21846, 0,  53, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  61, &mut ram64);
-        ram64_test_exec(-21846,  21845, 1,  53, &mut ram64);
-        ram64_test_exec( 21845,  21845, 1,  53, &mut ram64);
-        ram64_test_exec( 21845, -21846, 1,  61, &mut ram64);
-        ram64_test_exec(-21846, -21846, 1,  61, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,   5, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,   5, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  13, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  21, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  29, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  37, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  45, &mut ram64);
-        ram64_test_exec( 21845, -21846, 0,  53, &mut ram64);
-        ram64_test_exec(-21846, -21846, 0,  61, &mut ram64);
-        ram64_test_exec(-21846,  21845, 1,  61, &mut ram64);
-        ram64_test_exec( 21845,  21845, 1,  61, &mut ram64);
-        ram64_test_exec( 21845,  21845, 0,   5, &mut ram64);
-        ram64_test_exec( 21845,  21845, 0,   5, &mut ram64);
-        ram64_test_exec( 21845,  21845, 0,  13, &mut ram64);
-        ram64_test_exec( 21845,  21845, 0,  21, &mut ram64);
-        ram64_test_exec( 21845,  21845, 0,  29, &mut ram64);
-        ram64_test_exec( 21845,  21845, 0,  37, &mut ram64);
-        ram64_test_exec( 21845,  21845, 0,  45, &mut ram64);
-        ram64_test_exec( 21845,  21845, 0,  53, &mut ram64);
-        ram64_test_exec( 21845,  21845, 0,  61, &mut ram64);
+        for (input, load, address, expect) in RAM64_VECTORS {
+            ram64_test_exec(*expect, *input, *load, *address, &mut ram64);
+        }
     }
 
     // #[test]
     fn pc_test() {
         let mut pc: Pc = Pc::new();
-        pc_test_exec(     0,      0, 0, 0, 0, &mut pc);
-        pc_test_exec(     0,      0, 0, 0, 0, &mut pc);
-        pc_test_exec(     0,      0, 0, 0, 1, &mut pc);
-        pc_test_exec(     1,      0, 0, 0, 1, &mut pc);
-        pc_test_exec(     1, -32123, 0, 0, 1, &mut pc);
-        pc_test_exec(     2, -32123, 0, 0, 1, &mut pc);
-        pc_test_exec(     2, -32123, 0, 1, 1, &mut pc);
-        pc_test_exec(-32123, -32123, 0, 1, 1, &mut pc);
-        pc_test_exec(-32123, -32123, 0, 0, 1, &mut pc);
-        pc_test_exec(-32122, -32123, 0, 0, 1, &mut pc);
-        pc_test_exec(-32122, -32123, 0, 0, 1, &mut pc);
-        pc_test_exec(-32121, -32123, 0, 0, 1, &mut pc);
-        pc_test_exec(-32121,  12345, 0, 1, 0, &mut pc);
-        pc_test_exec( 12345,  12345, 0, 1, 0, &mut pc);
-        pc_test_exec( 12345,  12345, 1, 1, 0, &mut pc);
-        pc_test_exec(     0,  12345, 1, 1, 0, &mut pc);
-        pc_test_exec(     0,  12345, 0, 1, 1, &mut pc);
-        pc_test_exec( 12345,  12345, 0, 1, 1, &mut pc);
-        pc_test_exec( 12345,  12345, 1, 1, 1, &mut pc);
-        pc_test_exec(     0,  12345, 1, 1, 1, &mut pc);
-        pc_test_exec(     0,  12345, 0, 0, 1, &mut pc);
-        pc_test_exec(     1,  12345, 0, 0, 1, &mut pc);
-        pc_test_exec(     1,  12345, 1, 0, 1, &mut pc);
-        pc_test_exec(     0,  12345, 1, 0, 1, &mut pc);
-        pc_test_exec(     0,      0, 0, 1, 1, &mut pc);
-        pc_test_exec(     0,      0, 0, 1, 1, &mut pc);
-        pc_test_exec(     0,      0, 0, 0, 1, &mut pc);
-        pc_test_exec(     1,      0, 0, 0, 1, &mut pc);
-        pc_test_exec(     1,  22222, 1, 0, 0, &mut pc);
-        pc_test_exec(     0,  22222, 1, 0, 0, &mut pc);
+        pc_test_exec(     0,      0, O, O, O, &mut pc);
+        pc_test_exec(     0,      0, O, O, O, &mut pc);
+        pc_test_exec(     0,      0, O, O, I, &mut pc);
+        pc_test_exec(     1,      0, O, O, I, &mut pc);
+        pc_test_exec(     1, -32123, O, O, I, &mut pc);
+        pc_test_exec(     2, -32123, O, O, I, &mut pc);
+        pc_test_exec(     2, -32123, O, I, I, &mut pc);
+        pc_test_exec(-32123, -32123, O, I, I, &mut pc);
+        pc_test_exec(-32123, -32123, O, O, I, &mut pc);
+        pc_test_exec(-32122, -32123, O, O, I, &mut pc);
+        pc_test_exec(-32122, -32123, O, O, I, &mut pc);
+        pc_test_exec(-32121, -32123, O, O, I, &mut pc);
+        pc_test_exec(-32121,  12345, O, I, O, &mut pc);
+        pc_test_exec( 12345,  12345, O, I, O, &mut pc);
+        pc_test_exec( 12345,  12345, I, I, O, &mut pc);
+        pc_test_exec(     0,  12345, I, I, O, &mut pc);
+        pc_test_exec(     0,  12345, O, I, I, &mut pc);
+        pc_test_exec( 12345,  12345, O, I, I, &mut pc);
+        pc_test_exec( 12345,  12345, I, I, I, &mut pc);
+        pc_test_exec(     0,  12345, I, I, I, &mut pc);
+        pc_test_exec(     0,  12345, O, O, I, &mut pc);
+        pc_test_exec(     1,  12345, O, O, I, &mut pc);
+        pc_test_exec(     1,  12345, I, O, I, &mut pc);
+        pc_test_exec(     0,  12345, I, O, I, &mut pc);
+        pc_test_exec(     0,      0, O, I, I, &mut pc);
+        pc_test_exec(     0,      0, O, I, I, &mut pc);
+        pc_test_exec(     0,      0, O, O, I, &mut pc);
+        pc_test_exec(     1,      0, O, O, I, &mut pc);
+        pc_test_exec(     1,  22222, I, O, O, &mut pc);
+        pc_test_exec(     0,  22222, I, O, O, &mut pc);
     }
 }
@@ -0,0 +1,289 @@
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Index, IndexMut};
+
+// 1本の信号線。0 か 1 以外は作れない
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Bit(bool);
+
+// 真理値表を書くときの短い名前
+pub const O: Bit = Bit::ZERO;
+pub const I: Bit = Bit::ONE;
+
+impl Bit {
+    pub const ZERO: Bit = Bit(false);
+    pub const ONE: Bit = Bit(true);
+
+    pub fn is_one(self) -> bool {
+        self.0
+    }
+}
+
+// ゲートに流せる信号。nand さえ決めれば bool_logic のゲートは全部これから作れる
+// Bit のほかに、計測用などの信号を流せるようにしている
+pub trait Signal: Copy {
+    fn nand(self, other: Self) -> Self;
+
+    // 回路に直接つないだ 0 / 1 (ALU の 0 入力など)
+    fn constant(bit: Bit) -> Self;
+
+    // Dff に記憶されてクロックをまたぐときに呼ばれる。普通の信号はそのまま
+    fn latch(self) -> Self {
+        self
+    }
+
+    // 書き込む前の Dff が持っている値。普通は 0
+    fn initial() -> Self {
+        Self::constant(O)
+    }
+
+    // name の部品 (Mux, Register など) に入る / 出るときに呼ばれる。回路図を取るとき以外は何もしない
+    fn enter(_name: &'static str) {}
+    fn leave() {}
+}
+
+// 部品の関数の最初で let _part = Part::<S>::enter("Mux"); のように持っておくと、関数を抜けるときに leave する
+pub struct Part<S: Signal>(PhantomData<S>);
+
+impl<S: Signal> Part<S> {
+    pub fn enter(name: &'static str) -> Part<S> {
+        S::enter(name);
+        Part(PhantomData)
+    }
+}
+
+impl<S: Signal> Drop for Part<S> {
+    fn drop(&mut self) {
+        S::leave()
+    }
+}
+
+impl Signal for Bit {
+    fn nand(self, other: Bit) -> Bit {
+        // 全ての基本
+        if self == I && other == I {
+            O
+        } else {
+            I
+        }
+    }
+
+    fn constant(bit: Bit) -> Bit {
+        bit
+    }
+}
+
+impl From<bool> for Bit {
+    fn from(value: bool) -> Bit {
+        Bit(value)
+    }
+}
+
+impl From<Bit> for bool {
+    fn from(bit: Bit) -> bool {
+        bit.0
+    }
+}
+
+impl From<Bit> for u8 {
+    fn from(bit: Bit) -> u8 {
+        bit.0 as u8
+    }
+}
+
+// 0 と 1 以外の値から Bit を作ろうとした
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InvalidBit(pub u8);
+
+impl fmt::Display for InvalidBit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "signal must be 0 or 1: {}", self.0)
+    }
+}
+
+impl Error for InvalidBit {}
+
+impl TryFrom<u8> for Bit {
+    type Error = InvalidBit;
+
+    fn try_from(value: u8) -> Result<Bit, InvalidBit> {
+        match value {
+            0 => Ok(Bit::ZERO),
+            1 => Ok(Bit::ONE),
+            _ => Err(InvalidBit(value))
+        }
+    }
+}
+
+impl fmt::Display for Bit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", u8::from(*self))
+    }
+}
+
+// N 本の信号線
+// bus[i] は 2^i の桁。bus[0] が最下位、bus[N - 1] が最上位 (符号) で、HDL の out[0] などと同じ並び
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Bus<const N: usize, S = Bit>([S; N]);
+
+// Hack の 1ワード
+pub type Word16 = Bus<16>;
+
+impl<const N: usize, S: Signal> Bus<N, S> {
+    pub fn from_fn<F: FnMut(usize) -> S>(f: F) -> Bus<N, S> {
+        Bus(std::array::from_fn(f))
+    }
+
+    // 全部の線を同じ定数につなぐ
+    pub fn constant(bit: Bit) -> Bus<N, S> {
+        Bus([S::constant(bit); N])
+    }
+
+    // bits[i] が 2^i の桁
+    pub fn from_bits(bits: [S; N]) -> Bus<N, S> {
+        Bus(bits)
+    }
+
+    pub fn bits(&self) -> [S; N] {
+        self.0
+    }
+}
+
+impl<const N: usize> Bus<N> {
+    pub const ZERO: Bus<N> = Bus([Bit::ZERO; N]);
+
+    // 下から N 桁を取り出す (N は 64 まで)
+    pub fn from_u64(value: u64) -> Bus<N> {
+        Bus::from_fn(|i| Bit::from((value >> i) & 1 == 1))
+    }
+
+    pub fn to_u64(self) -> u64 {
+        self.0
+            .iter()
+            .enumerate()
+            .fold(0, |acc, (i, bit)| acc | ((u8::from(*bit) as u64) << i))
+    }
+}
+
+impl<const N: usize> Default for Bus<N> {
+    fn default() -> Bus<N> {
+        Bus::ZERO
+    }
+}
+
+impl From<u8> for Bus<8> {
+    fn from(value: u8) -> Bus<8> {
+        Bus::from_u64(value as u64)
+    }
+}
+
+impl From<u16> for Bus<16> {
+    fn from(value: u16) -> Bus<16> {
+        Bus::from_u64(value as u64)
+    }
+}
+
+impl From<i16> for Bus<16> {
+    fn from(value: i16) -> Bus<16> {
+        Bus::from(value as u16)
+    }
+}
+
+impl From<u32> for Bus<32> {
+    fn from(value: u32) -> Bus<32> {
+        Bus::from_u64(value as u64)
+    }
+}
+
+impl From<Bus<8>> for u8 {
+    fn from(bus: Bus<8>) -> u8 {
+        bus.to_u64() as u8
+    }
+}
+
+impl From<Bus<16>> for u16 {
+    fn from(bus: Bus<16>) -> u16 {
+        bus.to_u64() as u16
+    }
+}
+
+impl From<Bus<16>> for i16 {
+    fn from(bus: Bus<16>) -> i16 {
+        u16::from(bus) as i16
+    }
+}
+
+impl From<Bus<32>> for u32 {
+    fn from(bus: Bus<32>) -> u32 {
+        bus.to_u64() as u32
+    }
+}
+
+impl<const N: usize, S> Index<usize> for Bus<N, S> {
+    type Output = S;
+
+    fn index(&self, i: usize) -> &S {
+        &self.0[i]
+    }
+}
+
+impl<const N: usize, S> IndexMut<usize> for Bus<N, S> {
+    fn index_mut(&mut self, i: usize) -> &mut S {
+        &mut self.0[i]
+    }
+}
+
+// 上の桁から N 文字の 0/1 で表示する
+impl<const N: usize> fmt::Display for Bus<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for bit in self.0.iter().rev() {
+            write!(f, "{}", bit)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bit_test() {
+        assert_eq!(Ok(O), Bit::try_from(0));
+        assert_eq!(Ok(I), Bit::try_from(1));
+        assert_eq!(Err(InvalidBit(2)), Bit::try_from(2));
+        assert_eq!(1u8, u8::from(I));
+        assert!(bool::from(I));
+        assert_eq!(O, Bit::default());
+        assert_eq!("1", I.to_string());
+    }
+
+    #[test]
+    fn word16_test() {
+        let word = Word16::from(0b1001001001001001u16);
+        assert_eq!(I, word[0]);
+        assert_eq!(O, word[1]);
+        assert_eq!(I, word[15]);
+        assert_eq!("1001001001001001", word.to_string());
+        assert_eq!(0b1001001001001001u16, u16::from(word));
+
+        assert_eq!(Word16::from(0xffffu16), Word16::from(-1i16));
+        assert_eq!(-32123i16, i16::from(Word16::from(-32123i16)));
+        assert_eq!(12345u16, u16::from(Word16::from(12345i16)));
+        assert_eq!(Word16::ZERO, Word16::from(0u16));
+        assert_eq!([I; 16], Word16::from(0xffffu16).bits());
+        assert_eq!(Word16::from(1u16), Word16::from_bits([I, O, O, O, O, O, O, O, O, O, O, O, O, O, O, O]));
+    }
+
+    #[test]
+    fn bus_test() {
+        assert_eq!("10100101", Bus::<8>::from(0xa5u8).to_string());
+        assert_eq!(0xa5u8, u8::from(Bus::<8>::from(0xa5u8)));
+        assert_eq!(0xdeadbeefu32, u32::from(Bus::<32>::from(0xdeadbeefu32)));
+        assert_eq!("101", Bus::<3>::from_u64(0b1101).to_string());
+        assert_eq!(5, Bus::<3>::from_u64(0b1101).to_u64());
+        assert_eq!(Bus::<4>::ZERO, Bus::<4>::default());
+    }
+}
@@ -0,0 +1,641 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::error::Error;
+use std::fmt;
+
+#[cfg(test)]
+use crate::signal::{Bit, Signal};
+
+// 入力が多すぎると真理値表も Quine–McCluskey も重くなる
+// 途中の項は 3^n 個まで増え、minimize は (mask, value) ごとに 1 桁使うので 4^n 桁の表がいる
+pub const MAX_INPUTS: usize = 12;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SynthError {
+    // 式の column 文字目 (1 始まり) が読めない
+    Syntax { column: usize, message: String },
+    TooManyInputs(usize),
+    // 真理値表の行の数や文字がおかしい
+    BadTable(String),
+    // .hdl にしたときの中の線 (w0, w1, ..., notout) や out, true, false と同じ名前の入力
+    ReservedName(String)
+}
+
+impl fmt::Display for SynthError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SynthError::Syntax { column, message } => write!(f, "{}: {}", column, message),
+            SynthError::TooManyInputs(n) => write!(f, "{} inputs is too many (at most {})", n, MAX_INPUTS),
+            SynthError::BadTable(s) => write!(f, "bad truth table: {}", s),
+            SynthError::ReservedName(name) => write!(f, "input name {} is used by the generated .hdl", name)
+        }
+    }
+}
+
+impl Error for SynthError {}
+
+// 真理値表。rows[r] は入力を inputs[0] を上の桁にした 2 進数 r と読んだときの出力で、None はどちらでもよい
+// (a b sel の表なら r = 0b011 は a=0, b=1, sel=1)
+#[derive(Debug, Clone, PartialEq)]
+pub struct TruthTable {
+    pub inputs: Vec<String>,
+    pub rows: Vec<Option<bool>>
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Constant(bool),
+    Input(usize),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Xor(Box<Expr>, Box<Expr>)
+}
+
+impl Expr {
+    fn eval(&self, values: &[bool]) -> bool {
+        match self {
+            Expr::Constant(b) => *b,
+            Expr::Input(i) => values[*i],
+            Expr::Not(e) => !e.eval(values),
+            Expr::And(a, b) => a.eval(values) && b.eval(values),
+            Expr::Or(a, b) => a.eval(values) || b.eval(values),
+            Expr::Xor(a, b) => a.eval(values) != b.eval(values)
+        }
+    }
+}
+
+// 強い順に ! (~), &, ^, |。名前は出てきた順に入力にする
+struct Parser<'a> {
+    chars: Vec<char>,
+    position: usize,
+    inputs: &'a mut Vec<String>
+}
+
+impl<'a> Parser<'a> {
+    fn error<T>(&self, message: &str) -> Result<T, SynthError> {
+        Err(SynthError::Syntax { column: self.position + 1, message: message.to_string() })
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        while self.chars.get(self.position).is_some_and(|c| c.is_whitespace()) {
+            self.position += 1;
+        }
+        self.chars.get(self.position).copied()
+    }
+
+    fn binary(&mut self, op: char, next: fn(&mut Self) -> Result<Expr, SynthError>, make: fn(Box<Expr>, Box<Expr>) -> Expr) -> Result<Expr, SynthError> {
+        let mut left = next(self)?;
+        while self.peek() == Some(op) {
+            self.position += 1;
+            left = make(Box::new(left), Box::new(next(self)?));
+        }
+        Ok(left)
+    }
+
+    fn or(&mut self) -> Result<Expr, SynthError> {
+        self.binary('|', Parser::xor, Expr::Or)
+    }
+
+    fn xor(&mut self) -> Result<Expr, SynthError> {
+        self.binary('^', Parser::and, Expr::Xor)
+    }
+
+    fn and(&mut self) -> Result<Expr, SynthError> {
+        self.binary('&', Parser::unary, Expr::And)
+    }
+
+    fn unary(&mut self) -> Result<Expr, SynthError> {
+        match self.peek() {
+            Some('!') | Some('~') => {
+                self.position += 1;
+                Ok(Expr::Not(Box::new(self.unary()?)))
+            },
+            Some('(') => {
+                self.position += 1;
+                let e = self.or()?;
+                if self.peek() != Some(')') {
+                    return self.error("expected )")
+                }
+                self.position += 1;
+                Ok(e)
+            },
+            Some('0') => { self.position += 1; Ok(Expr::Constant(false)) },
+            Some('1') => { self.position += 1; Ok(Expr::Constant(true)) },
+            Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+                let start = self.position;
+                while self.chars.get(self.position).is_some_and(|c| c.is_ascii_alphanumeric() || *c == '_') {
+                    self.position += 1;
+                }
+                let name: String = self.chars[start..self.position].iter().collect();
+                let index = match self.inputs.iter().position(|n| *n == name) {
+                    Some(index) => index,
+                    None => {
+                        self.inputs.push(name);
+                        self.inputs.len() - 1
+                    }
+                };
+                Ok(Expr::Input(index))
+            },
+            Some(_) => self.error("expected a name, 0, 1, ! or ("),
+            None => self.error("unexpected end of expression")
+        }
+    }
+}
+
+// Circuit::hdl で入力のピンの名前にできるか
+fn check_names(inputs: &[String]) -> Result<(), SynthError> {
+    for (i, name) in inputs.iter().enumerate() {
+        let wire = name.strip_prefix('w').is_some_and(|d| !d.is_empty() && d.chars().all(|c| c.is_ascii_digit()));
+        if wire || ["out", "notout", "true", "false"].contains(&name.as_str()) {
+            return Err(SynthError::ReservedName(name.clone()))
+        }
+        let identifier = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !identifier || inputs[..i].contains(name) {
+            return Err(SynthError::BadTable(format!("bad input name {}", name)))
+        }
+    }
+    Ok(())
+}
+
+impl TruthTable {
+    pub fn new(inputs: Vec<String>, rows: Vec<Option<bool>>) -> Result<TruthTable, SynthError> {
+        if inputs.len() > MAX_INPUTS {
+            return Err(SynthError::TooManyInputs(inputs.len()))
+        }
+        check_names(&inputs)?;
+        if rows.len() != 1 << inputs.len() {
+            return Err(SynthError::BadTable(format!("{} inputs need {} rows, found {}", inputs.len(), 1 << inputs.len(), rows.len())))
+        }
+        Ok(TruthTable { inputs, rows })
+    }
+
+    // (a & !sel) | (b & sel) のような式から
+    pub fn from_expr(source: &str) -> Result<TruthTable, SynthError> {
+        let mut inputs = Vec::new();
+        let mut parser = Parser { chars: source.chars().collect(), position: 0, inputs: &mut inputs };
+        let expr = parser.or()?;
+        if parser.peek().is_some() {
+            return parser.error("unexpected character")
+        }
+        if inputs.len() > MAX_INPUTS {
+            return Err(SynthError::TooManyInputs(inputs.len()))
+        }
+        check_names(&inputs)?;
+        let n = inputs.len();
+        let rows = (0..1usize << n).map(|r| {
+            let values: Vec<bool> = (0..n).map(|i| (r >> (n - 1 - i)) & 1 == 1).collect();
+            Some(expr.eval(&values))
+        }).collect();
+        Ok(TruthTable { inputs, rows })
+    }
+
+    // "a b sel = 00011011" のように、入力の名前と、行の順に出力を 0 / 1 / - (どちらでもよい) で並べたもの
+    pub fn parse(source: &str) -> Result<TruthTable, SynthError> {
+        let (names, outputs) = source.split_once('=').ok_or(SynthError::BadTable("expected names = outputs".to_string()))?;
+        let inputs: Vec<String> = names.split_whitespace().map(|s| s.to_string()).collect();
+        let rows = outputs.chars().filter(|c| !c.is_whitespace()).map(|c| match c {
+            '0' => Ok(Some(false)),
+            '1' => Ok(Some(true)),
+            '-' => Ok(None),
+            _ => Err(SynthError::BadTable(format!("unexpected {}", c)))
+        }).collect::<Result<Vec<Option<bool>>, SynthError>>()?;
+        TruthTable::new(inputs, rows)
+    }
+}
+
+// 積項。mask の桁は使わない入力、value はそれ以外の入力の値 (桁は TruthTable の行と同じ)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Term {
+    pub value: usize,
+    pub mask: usize
+}
+
+impl Term {
+    fn literals(self, n: usize) -> usize {
+        n - self.mask.count_ones() as usize
+    }
+
+    // 覆う行を全部
+    fn rows(self) -> Vec<usize> {
+        let mut rows = Vec::with_capacity(1 << self.mask.count_ones());
+        let mut sub = self.mask;
+        loop {
+            rows.push(self.value | sub);
+            if sub == 0 {
+                return rows
+            }
+            sub = (sub - 1) & self.mask;
+        }
+    }
+}
+
+// minimize で使う、桁ごとの true / false
+struct Bits(Vec<u64>);
+
+impl Bits {
+    fn new(len: usize) -> Bits {
+        Bits(vec![0; len.div_ceil(64)])
+    }
+
+    fn get(&self, i: usize) -> bool {
+        self.0[i / 64] >> (i % 64) & 1 == 1
+    }
+
+    fn set(&mut self, i: usize) {
+        self.0[i / 64] |= 1 << (i % 64);
+    }
+}
+
+// Quine–McCluskey で主項を全部求め、必須主項のあとは一番多く残りを覆う主項を選んでいく
+// 項は (mask, value の 1 の数) で組に分け、1 の数が 1 つ多い隣の組とだけまとめる
+pub fn minimize(table: &TruthTable) -> Vec<Term> {
+    let n = table.inputs.len();
+    let ones: Vec<usize> = (0..table.rows.len()).filter(|r| table.rows[*r] == Some(true)).collect();
+    // 段ごとに、(mask, value) がその段の項か、もっと大きい項にまとまったかを mask << n | value の桁で持つ
+    let index = |mask: usize, value: usize| mask << n | value;
+    let mut current: BTreeMap<(usize, u32), Vec<usize>> = BTreeMap::new();
+    let mut present = Bits::new(1 << (2 * n));
+    for r in (0..table.rows.len()).filter(|r| table.rows[*r] != Some(false)) {
+        current.entry((0, r.count_ones())).or_default().push(r);
+        present.set(index(0, r));
+    }
+    let mut primes: BTreeSet<Term> = BTreeSet::new();
+    while !current.is_empty() {
+        let mut next: BTreeMap<(usize, u32), Vec<usize>> = BTreeMap::new();
+        let mut next_present = Bits::new(1 << (2 * n));
+        let mut merged = Bits::new(1 << (2 * n));
+        for (&(mask, count), values) in &current {
+            if !current.contains_key(&(mask, count + 1)) {
+                continue
+            }
+            // mask の一番上の桁より上の桁でまとめたものだけ次に入れる。どの項もちょうど 1 回ずつ作られる
+            let top = (usize::BITS - mask.leading_zeros()) as usize;
+            for value in values {
+                for i in (0..n).filter(|i| (mask | value) >> i & 1 == 0) {
+                    let bit = 1 << i;
+                    if present.get(index(mask, value | bit)) {
+                        if i >= top {
+                            next.entry((mask | bit, count)).or_default().push(*value);
+                            next_present.set(index(mask | bit, *value));
+                        }
+                        merged.set(index(mask, *value));
+                        merged.set(index(mask, value | bit));
+                    }
+                }
+            }
+        }
+        for (&(mask, _), values) in &current {
+            primes.extend(values.iter().filter(|v| !merged.get(index(mask, **v))).map(|v| Term { value: *v, mask }));
+        }
+        current = next;
+        present = next_present;
+    }
+
+    // 1 の行ごとに、それを覆う主項の番号
+    let primes: Vec<Term> = primes.into_iter().collect();
+    let mut covering: Vec<Vec<usize>> = vec![Vec::new(); table.rows.len()];
+    for (p, prime) in primes.iter().enumerate() {
+        for row in prime.rows() {
+            if table.rows[row] == Some(true) {
+                covering[row].push(p);
+            }
+        }
+    }
+    let mut essential = vec![false; primes.len()];
+    let mut chosen: Vec<usize> = Vec::new();
+    for row in &ones {
+        if covering[*row].len() == 1 && !essential[covering[*row][0]] {
+            essential[covering[*row][0]] = true;
+            chosen.push(covering[*row][0]);
+        }
+    }
+    let mut left: BTreeSet<usize> = ones.iter().copied().collect();
+    for p in &chosen {
+        for row in primes[*p].rows() {
+            left.remove(&row);
+        }
+    }
+    // 主項ごとに、まだ覆われていない行の数
+    let mut counts = vec![0; primes.len()];
+    for row in &left {
+        for p in &covering[*row] {
+            counts[*p] += 1;
+        }
+    }
+    while !left.is_empty() {
+        let best = (0..primes.len())
+            .max_by_key(|p| (counts[*p], std::cmp::Reverse(primes[*p].literals(n)), std::cmp::Reverse(primes[*p])))
+            .unwrap();
+        for row in primes[best].rows() {
+            if left.remove(&row) {
+                for p in &covering[row] {
+                    counts[*p] -= 1;
+                }
+            }
+        }
+        chosen.push(best);
+    }
+    let mut chosen: Vec<Term> = chosen.into_iter().map(|p| primes[p]).collect();
+    chosen.sort();
+    chosen
+}
+
+// a & !sel | b & sel のように書く
+pub fn expression(inputs: &[String], terms: &[Term]) -> String {
+    let n = inputs.len();
+    if terms.is_empty() {
+        return "0".to_string()
+    }
+    terms.iter().map(|t| {
+        let literals: Vec<String> = (0..n).filter(|i| (t.mask >> (n - 1 - i)) & 1 == 0).map(|i| {
+            let one = (t.value >> (n - 1 - i)) & 1 == 1;
+            format!("{}{}", if one { "" } else { "!" }, inputs[i])
+        }).collect();
+        if literals.is_empty() { "1".to_string() } else { literals.join(" & ") }
+    }).collect::<Vec<String>>().join(" | ")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Operand {
+    Constant(bool),
+    Input(usize),
+    Gate(usize)
+}
+
+// nand だけの回路。gates[i] は前の gate か入力しか読まない
+#[derive(Debug, Clone)]
+pub struct Circuit {
+    pub inputs: Vec<String>,
+    pub gates: Vec<(Operand, Operand)>,
+    pub output: Operand,
+    // 同じ nand を 2 回作らない
+    made: HashMap<(Operand, Operand), usize>
+}
+
+impl Circuit {
+    fn nand(&mut self, a: Operand, b: Operand) -> Operand {
+        match (a, b) {
+            (Operand::Constant(false), _) | (_, Operand::Constant(false)) => return Operand::Constant(true),
+            (Operand::Constant(true), Operand::Constant(true)) => return Operand::Constant(false),
+            (Operand::Constant(true), x) | (x, Operand::Constant(true)) => return self.nand(x, x),
+            _ => {}
+        }
+        // not(not(x)) は x
+        if let (Operand::Gate(g), true) = (a, a == b) {
+            let (x, y) = self.gates[g];
+            if x == y {
+                return x
+            }
+        }
+        let key = (a.min(b), a.max(b));
+        if let Some(g) = self.made.get(&key) {
+            return Operand::Gate(*g)
+        }
+        self.gates.push(key);
+        self.made.insert(key, self.gates.len() - 1);
+        Operand::Gate(self.gates.len() - 1)
+    }
+
+    fn not(&mut self, x: Operand) -> Operand {
+        self.nand(x, x)
+    }
+
+    // 前から 1 つずつ and していく。積項が何万もあるので再帰にはしない
+    fn and_all(&mut self, xs: &[Operand]) -> Operand {
+        let (first, rest) = match xs.split_first() {
+            Some(split) => split,
+            None => return Operand::Constant(true)
+        };
+        let mut and = *first;
+        for x in rest {
+            let nand = self.nand(and, *x);
+            and = self.not(nand);
+        }
+        and
+    }
+
+    // nand(x1, x2, ..., xk) を 2 入力の nand で作る
+    fn nand_all(&mut self, xs: &[Operand]) -> Operand {
+        match xs {
+            [] => Operand::Constant(false),
+            [rest @ .., last] => {
+                let and = self.and_all(rest);
+                self.nand(and, *last)
+            }
+        }
+    }
+
+    pub fn nands(&self) -> usize {
+        self.gates.len()
+    }
+
+    // 入力を入れて出力を計算する。Lanes を流せば 64 通りまとめて調べられる
+    #[cfg(test)]
+    pub fn eval<S: Signal>(&self, inputs: &[S]) -> S {
+        let operand = |values: &[S], o: Operand| match o {
+            Operand::Constant(b) => S::constant(Bit::from(b)),
+            Operand::Input(i) => inputs[i],
+            Operand::Gate(g) => values[g]
+        };
+        let mut values: Vec<S> = Vec::with_capacity(self.gates.len());
+        for (a, b) in &self.gates {
+            let value = operand(&values, *a).nand(operand(&values, *b));
+            values.push(value);
+        }
+        operand(&values, self.output)
+    }
+
+    // nand2tetris の .hdl (Nand だけを部品にする) にする
+    pub fn hdl(&self, name: &str) -> String {
+        let pin = |o: Operand| match o {
+            Operand::Constant(b) => (if b { "true" } else { "false" }).to_string(),
+            Operand::Input(i) => self.inputs[i].clone(),
+            Operand::Gate(g) => format!("w{}", g)
+        };
+        let mut out = format!("CHIP {} {{\n", name);
+        if !self.inputs.is_empty() {
+            out.push_str(&format!("    IN {};\n", self.inputs.join(", ")));
+        }
+        out.push_str("    OUT out;\n\n    PARTS:\n");
+        for (g, (a, b)) in self.gates.iter().enumerate() {
+            let outputs = if self.output == Operand::Gate(g) { format!("out=w{}, out=out", g) } else { format!("out=w{}", g) };
+            out.push_str(&format!("    Nand(a={}, b={}, {});\n", pin(*a), pin(*b), outputs));
+        }
+        // 出力が nand を通らないときは not を 2 回通す
+        match self.output {
+            Operand::Gate(_) => {},
+            Operand::Constant(b) => out.push_str(&format!("    Nand(a={}, b={}, out=out);\n", !b, !b)),
+            Operand::Input(_) => {
+                let x = pin(self.output);
+                out.push_str(&format!("    Nand(a={}, b={}, out=notout);\n    Nand(a=notout, b=notout, out=out);\n", x, x));
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+// 積和形を nand-nand の 2 段にする (!x は nand(x, x))
+pub fn synthesize(table: &TruthTable) -> (Vec<Term>, Circuit) {
+    let terms = minimize(table);
+    let n = table.inputs.len();
+    let mut circuit = Circuit { inputs: table.inputs.clone(), gates: Vec::new(), output: Operand::Constant(false), made: HashMap::new() };
+    let mut products = Vec::new();
+    for term in &terms {
+        let mut literals = Vec::new();
+        for i in 0..n {
+            if (term.mask >> (n - 1 - i)) & 1 == 1 {
+                continue
+            }
+            let input = Operand::Input(i);
+            literals.push(if (term.value >> (n - 1 - i)) & 1 == 1 { input } else { circuit.not(input) });
+        }
+        // 入力の無い積項は 1 なので、nand すると 0
+        let product = if literals.is_empty() { Operand::Constant(false) } else { circuit.nand_all(&literals) };
+        products.push(product);
+    }
+    circuit.output = if products.is_empty() { Operand::Constant(false) } else { circuit.nand_all(&products) };
+    (terms, circuit)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bitslice::Lanes;
+    use crate::bool_logic;
+    use crate::hdl::netlist::Netlist;
+
+    // 入力 i (上の桁から) の値をレーン k = 行番号に入れる (入力 6 個まで)
+    fn rows(n: usize) -> Vec<Lanes> {
+        (0..n).map(|i| Lanes((0..1u64 << n).fold(0, |acc, k| acc | (((k >> (n - 1 - i)) & 1) << k)))).collect()
+    }
+
+    fn mask(n: usize) -> u64 {
+        if n >= 6 { !0 } else { (1 << (1 << n)) - 1 }
+    }
+
+    #[test]
+    fn parse_test() {
+        let table = TruthTable::from_expr("(a & !sel) | (b & sel)").unwrap();
+        assert_eq!(vec!["a", "sel", "b"], table.inputs);
+        assert_eq!(Some(true), table.rows[0b100]);
+        assert_eq!(Some(false), table.rows[0b110]);
+        assert_eq!(Some(true), table.rows[0b011]);
+        assert_eq!(Some(true), TruthTable::from_expr("a ^ b & 0 | ~1").unwrap().rows[0b10]);
+        assert_eq!(Err(SynthError::Syntax { column: 5, message: "expected a name, 0, 1, ! or (".to_string() }), TruthTable::from_expr("a & | b"));
+        assert_eq!(Err(SynthError::Syntax { column: 7, message: "expected )".to_string() }), TruthTable::from_expr("(a | b"));
+        assert!(TruthTable::from_expr("a b").is_err());
+        let table = TruthTable::parse("a b = 01-0").unwrap();
+        assert_eq!(vec![Some(false), Some(true), None, Some(false)], table.rows);
+        assert!(matches!(TruthTable::parse("a b = 010"), Err(SynthError::BadTable(_))));
+        assert!(matches!(TruthTable::parse("a = 0x"), Err(SynthError::BadTable(_))));
+    }
+
+    #[test]
+    fn mux_test() {
+        let (terms, circuit) = synthesize(&TruthTable::parse("a b sel = 00011011").unwrap());
+        assert_eq!("b & sel | a & !sel", expression(&circuit.inputs, &terms));
+        // 手で書いた bool_logic::mux (not, and, and, or で 8 個) の半分
+        assert_eq!(4, circuit.nands());
+        let p = rows(3);
+        assert_eq!(bool_logic::mux(p[0], p[1], p[2]).0 & mask(3), circuit.eval(&p).0 & mask(3));
+    }
+
+    #[test]
+    fn xor_test() {
+        let (terms, circuit) = synthesize(&TruthTable::from_expr("a ^ b").unwrap());
+        assert_eq!("!a & b | a & !b", expression(&circuit.inputs, &terms));
+        // 積和形からだと not が 2 個いるので、bool_logic::xor の 4 個より 1 個多い
+        assert_eq!(5, circuit.nands());
+        let p = rows(2);
+        assert_eq!(bool_logic::xor(p[0], p[1]).0 & mask(2), circuit.eval(&p).0 & mask(2));
+    }
+
+    #[test]
+    fn minimize_test() {
+        // 多数決: 主項 3 個が全部必須
+        let (terms, circuit) = synthesize(&TruthTable::from_expr("a & b | b & c | a & c | a & b & c").unwrap());
+        assert_eq!("b & c | a & c | a & b", expression(&circuit.inputs, &terms));
+        // どちらでもよい行は 1 にして項を小さくする
+        let table = TruthTable::parse("a b c = 0001-111").unwrap();
+        assert_eq!("b & c | a", expression(&table.inputs, &minimize(&table)));
+        // 定数
+        let (terms, circuit) = synthesize(&TruthTable::from_expr("a | !a").unwrap());
+        assert_eq!("1", expression(&circuit.inputs, &terms));
+        assert_eq!((0, Operand::Constant(true)), (circuit.nands(), circuit.output));
+        let (terms, circuit) = synthesize(&TruthTable::from_expr("a & !a").unwrap());
+        assert_eq!("0", expression(&circuit.inputs, &terms));
+        assert_eq!(Operand::Constant(false), circuit.output);
+        // 入力そのままなら nand はいらない
+        assert_eq!(Operand::Input(0), synthesize(&TruthTable::from_expr("!!a").unwrap()).1.output);
+        // 6 入力の全部の行で式と同じ
+        let source = "(a ^ b ^ c) & !(d | e) | f & a";
+        let table = TruthTable::from_expr(source).unwrap();
+        let (_, circuit) = synthesize(&table);
+        let expected = table.rows.iter().enumerate().fold(0u64, |acc, (k, r)| acc | ((r.unwrap() as u64) << k));
+        assert_eq!(expected, circuit.eval(&rows(6)).0);
+    }
+
+    // 全部の行を 64 行ずつ流して、どちらでもよい行以外が真理値表と同じか
+    fn agrees(table: &TruthTable, circuit: &Circuit) -> bool {
+        let n = table.inputs.len();
+        (0..table.rows.len()).step_by(64).all(|base| {
+            let inputs: Vec<Lanes> = (0..n).map(|i| {
+                Lanes((0..64).filter(|k| base + k < table.rows.len()).fold(0, |acc, k| acc | ((((base + k) >> (n - 1 - i)) & 1) as u64) << k))
+            }).collect();
+            let out = circuit.eval(&inputs).0;
+            (0..64).filter(|k| base + k < table.rows.len())
+                .all(|k| table.rows[base + k].is_none_or(|r| r == ((out >> k) & 1 == 1)))
+        })
+    }
+
+    #[test]
+    fn max_inputs_test() {
+        let names: Vec<String> = (0..MAX_INPUTS).map(|i| format!("x{}", i)).collect();
+        // ほとんど 1 の関数は Quine–McCluskey の途中の項が一番多くなる
+        let table = TruthTable::from_expr(&format!("x0 | !x0 | {}", names.join(" & "))).unwrap();
+        assert_eq!(vec![Term { value: 0, mask: (1 << MAX_INPUTS) - 1 }], minimize(&table));
+        let (terms, circuit) = synthesize(&TruthTable::from_expr(&names.join(" ^ ")).unwrap());
+        assert_eq!(1 << (MAX_INPUTS - 1), terms.len());
+        assert!(agrees(&TruthTable::from_expr(&names.join(" ^ ")).unwrap(), &circuit));
+        // 乱数の表 (どちらでもよい行もある)
+        let mut state = 12345u64;
+        let outputs: String = (0..1 << MAX_INPUTS).map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            ['0', '1', '1', '-'][(state % 4) as usize]
+        }).collect();
+        let table = TruthTable::parse(&format!("{} = {}", names.join(" "), outputs)).unwrap();
+        assert!(agrees(&table, &synthesize(&table).1));
+        let too_many = format!("{} y = {}", names.join(" "), "0".repeat(1 << (MAX_INPUTS + 1)));
+        assert_eq!(Err(SynthError::TooManyInputs(MAX_INPUTS + 1)), TruthTable::parse(&too_many));
+    }
+
+    #[test]
+    fn hdl_test() {
+        for source in ["(a & !sel) | (b & sel)", "a ^ b ^ c", "a", "1"].iter() {
+            let table = TruthTable::from_expr(source).unwrap();
+            let (_, circuit) = synthesize(&table);
+            let hdl = circuit.hdl("Synth");
+            let mut netlist = Netlist::build("Synth", &|name| if name == "Synth" { Some(hdl.clone()) } else { None })
+                .unwrap_or_else(|e| panic!("{}\n{}", e, hdl));
+            let n = table.inputs.len();
+            for (r, row) in table.rows.iter().enumerate() {
+                for (i, input) in table.inputs.iter().enumerate() {
+                    netlist.set(input, ((r >> (n - 1 - i)) & 1) as u64).unwrap();
+                }
+                netlist.eval();
+                assert_eq!(Some(row.unwrap() as u64), netlist.get("out"), "{} row {}", source, r);
+            }
+        }
+        // 中の線やピンと同じ名前の入力は .hdl にできない
+        assert_eq!(Err(SynthError::ReservedName("out".to_string())), TruthTable::from_expr("out & w0"));
+        assert_eq!(Err(SynthError::ReservedName("w12".to_string())), TruthTable::from_expr("a | w12"));
+        assert_eq!(Err(SynthError::ReservedName("true".to_string())), TruthTable::parse("a true = 0110"));
+        assert!(matches!(TruthTable::parse("a a = 0110"), Err(SynthError::BadTable(_))));
+        assert!(matches!(TruthTable::parse("a b-c = 0110"), Err(SynthError::BadTable(_))));
+        assert!(TruthTable::from_expr("w & wx & w0a").is_ok());
+    }
+}
@@ -0,0 +1,191 @@
+use std::fmt;
+
+use crate::hdl::rust_chip::{self, Sequential};
+use crate::signal::{self, Signal};
+
+// 0 / 1 と、まだ決まっていない X の 3 値の信号
+// 書き込む前の Dff は X から始まるので、書く前に読むと X が出てくる
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tri {
+    Zero,
+    One,
+    X
+}
+
+impl Signal for Tri {
+    // どちらかが 0 なら 1。両方 1 なら 0。それ以外は決まらない
+    fn nand(self, other: Tri) -> Tri {
+        match (self, other) {
+            (Tri::Zero, _) | (_, Tri::Zero) => Tri::One,
+            (Tri::One, Tri::One) => Tri::Zero,
+            _ => Tri::X
+        }
+    }
+
+    fn constant(bit: signal::Bit) -> Tri {
+        if bit.is_one() { Tri::One } else { Tri::Zero }
+    }
+
+    fn initial() -> Tri {
+        Tri::X
+    }
+}
+
+impl fmt::Display for Tri {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Tri::Zero => write!(f, "0"),
+            Tri::One => write!(f, "1"),
+            Tri::X => write!(f, "x")
+        }
+    }
+}
+
+// 上の桁から 0/1/x で並べる
+fn show(bits: &[Tri]) -> String {
+    bits.iter().rev().map(|b| b.to_string()).collect()
+}
+
+// 番地が全部決まっていれば数にする
+fn number(pin: &[Tri]) -> Option<usize> {
+    pin.iter().enumerate().try_fold(0, |acc, (i, b)| match b {
+        Tri::Zero => Some(acc),
+        Tri::One => Some(acc | 1 << i),
+        Tri::X => None
+    })
+}
+
+fn read_of(pin: &[Tri]) -> String {
+    match number(pin) {
+        Some(a) => format!("address {}", a),
+        None => format!("address {}", show(pin))
+    }
+}
+
+// まだ X が残っている出力。cycle は 1 始まり
+#[derive(Debug, Clone, PartialEq)]
+pub struct Unknown {
+    pub cycle: usize,
+    pub line: usize,
+    // メモリなら読んだ番地
+    pub read: Option<String>,
+    pub value: String
+}
+
+impl fmt::Display for Unknown {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.read {
+            Some(read) => write!(f, "cycle {} (line {}): read of {} is {}", self.cycle, self.line, read, self.value),
+            None => write!(f, "cycle {} (line {}): out is {}", self.cycle, self.line, self.value)
+        }
+    }
+}
+
+// 10 進数か、全部の桁が X の x。ピンの幅に収まらない数はエラー
+fn pin(token: &str, name: &str, width: usize) -> Result<Vec<Tri>, String> {
+    if token == "x" || token == "X" {
+        return Ok(vec![Tri::X; width])
+    }
+    let value: u64 = token.parse().map_err(|e| format!("{}: {}", token, e))?;
+    if value >> width != 0 {
+        return Err(format!("{} does not fit in {} ({} bits)", value, name, width))
+    }
+    Ok(rust_chip::pin(value, width))
+}
+
+fn simulate(mut chip: Box<dyn Sequential<Tri>>, text: &str) -> Result<Vec<Unknown>, String> {
+    let pins = chip.pins();
+    let mut unknowns = Vec::new();
+    let mut cycle = 0;
+    for (index, line) in text.lines().enumerate() {
+        let line = line.split("//").next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue
+        }
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        if tokens.len() != pins.inputs.len() {
+            let names: Vec<&str> = pins.inputs.iter().map(|(name, _)| *name).collect();
+            return Err(format!("line {}: expected {} values ({})", index + 1, names.len(), names.join(" ")))
+        }
+        let inputs = tokens.iter().zip(pins.inputs).map(|(token, (name, width))| pin(token, name, *width))
+            .collect::<Result<Vec<Vec<Tri>>, String>>()
+            .map_err(|e| format!("line {}: {}", index + 1, e))?;
+        cycle += 1;
+        let out = chip.step(&inputs);
+        if out.contains(&Tri::X) {
+            // メモリなら読んだ番地も出す
+            let read = pins.address().map(|a| read_of(&inputs[a]));
+            unknowns.push(Unknown { cycle, line: index + 1, read, value: show(&out) });
+        }
+    }
+    Ok(unknowns)
+}
+
+// text の 1 行を 1 クロックの入力 (HDL のピンの順に 10 進数か x を空白区切り) として
+// Dff を X から始めて chip を動かし、out に X が残ったクロックを返す。知らないチップなら None
+pub fn run(chip: &str, text: &str) -> Option<Result<Vec<Unknown>, String>> {
+    rust_chip::sequential(chip).map(|chip| simulate(chip, text))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bool_arithmetic;
+    use crate::bool_logic;
+    use crate::signal::Bus;
+
+    #[test]
+    fn nand_test() {
+        use Tri::*;
+        assert_eq!(One, Zero.nand(X));
+        assert_eq!(One, X.nand(Zero));
+        assert_eq!(X, One.nand(X));
+        assert_eq!(X, X.nand(X));
+        assert_eq!(Zero, One.nand(One));
+        // 選ばない方が X でも mux の出力は決まる
+        assert_eq!(One, bool_logic::mux(One, X, Zero));
+        assert_eq!(X, bool_logic::mux(One, X, One));
+        let x: Bus<16, Tri> = Bus::from_fn(|_| X);
+        let zero: Bus<16, Tri> = Bus::from_fn(|_| Zero);
+        // zx, zy で両方 0 にすれば ALU の出力も決まる
+        let (out, zr, _) = bool_arithmetic::alu(&x, &x, One, Zero, One, Zero, One, Zero);
+        assert_eq!(zero, out);
+        assert_eq!(One, zr);
+        assert_eq!("0000000000000000", show(&out.bits()));
+    }
+
+    #[test]
+    fn register_test() {
+        // in load
+        let unknowns = run("Register", "5 0\n5 1\n0 0\n0 0").unwrap().unwrap();
+        // 1, 2 クロック目はまだ書いていない値が出てくる
+        assert_eq!(vec![1, 2], unknowns.iter().map(|u| u.cycle).collect::<Vec<usize>>());
+        assert_eq!("cycle 1 (line 1): out is xxxxxxxxxxxxxxxx", unknowns[0].to_string());
+        // load が X だと書いたかどうか分からない
+        let unknowns = run("Bit", "1 1\n0 x\n0 0").unwrap().unwrap();
+        assert_eq!(vec![1, 3], unknowns.iter().map(|u| u.cycle).collect::<Vec<usize>>());
+    }
+
+    #[test]
+    fn ram_test() {
+        // in load address
+        let text = "7 1 3\n0 0 3\n0 0 4\n0 0 x\n";
+        let unknowns = run("RAM8", text).unwrap().unwrap();
+        assert_eq!(3, unknowns.len());
+        assert_eq!(Some("address 3".to_string()), unknowns[0].read);
+        // 3 番地は書いたので 2 クロック目は決まっている
+        assert_eq!(3, unknowns[1].cycle);
+        assert_eq!("cycle 3 (line 3): read of address 4 is xxxxxxxxxxxxxxxx", unknowns[1].to_string());
+        assert_eq!(Some("address xxx".to_string()), unknowns[2].read);
+        assert_eq!(Some("address 63".to_string()), run("RAM64", "0 0 63").unwrap().unwrap()[0].read);
+        // PC は reset しないと X のまま
+        assert_eq!(2, run("PC", "0 0 0 0\n0 1 0 1\n0 0 0 0").unwrap().unwrap().len());
+        assert_eq!(1, run("DFF", "1\n0").unwrap().unwrap().len());
+        assert_eq!(Some(Err("line 1: expected 3 values (in load address)".to_string())), run("RAM8", "1 1"));
+        assert!(run("RAM8", "1 y 1").unwrap().is_err());
+        // ピンに収まらない数は切り捨てずにエラーにする
+        assert_eq!(Some(Err("line 2: 8 does not fit in address (3 bits)".to_string())), run("RAM8", "0 0 1\n0 0 8"));
+        assert!(run("Bit", "2 1").unwrap().is_err());
+        assert!(run("CPU", "").is_none());
+    }
+}
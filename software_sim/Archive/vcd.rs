@@ -0,0 +1,408 @@
+use crate::hdl::rust_chip::{self, Sequential};
+use crate::schematic::{self, Block, Schematic};
+use crate::signal;
+
+// IEEE 1364 の VCD (GTKWave で開ける波形) を書く
+// 変数を全部宣言してから、時刻ごとに値を渡す。前と同じ値は書かない
+pub struct Vcd {
+    name: String,
+    vars: Vec<(String, usize)>,
+    // $scope と var を宣言した順に
+    decls: Vec<Decl>,
+    // 下の桁から
+    values: Vec<Option<Vec<bool>>>,
+    time: Option<u64>,
+    body: String
+}
+
+enum Decl {
+    Scope(String),
+    Upscope,
+    Var(usize)
+}
+
+// 0 番目の変数から順に ! " # ... と、表示できる ASCII で名前を付ける
+fn identifier(mut index: usize) -> String {
+    let mut id = String::new();
+    loop {
+        id.push((b'!' + (index % 94) as u8) as char);
+        index /= 94;
+        if index == 0 {
+            return id
+        }
+        index -= 1;
+    }
+}
+
+impl Vcd {
+    // name は一番外の scope の名前
+    pub fn new(name: &str) -> Vcd {
+        Vcd { name: name.to_string(), vars: Vec::new(), decls: Vec::new(), values: Vec::new(), time: None, body: String::new() }
+    }
+
+    // 幅 width の wire を足して、その番号を返す。値を書き始める前に全部足しておく
+    pub fn var(&mut self, name: &str, width: usize) -> usize {
+        assert!(self.time.is_none(), "add all vars before the first change");
+        self.vars.push((name.to_string(), width));
+        self.values.push(None);
+        self.decls.push(Decl::Var(self.vars.len() - 1));
+        self.vars.len() - 1
+    }
+
+    // upscope するまでに足す var は name の scope の中に入る
+    pub fn scope(&mut self, name: &str) {
+        self.decls.push(Decl::Scope(name.to_string()));
+    }
+
+    pub fn upscope(&mut self) {
+        self.decls.push(Decl::Upscope);
+    }
+
+    // time に var が value になった。time は前より小さくしない
+    pub fn change(&mut self, time: u64, var: usize, value: u64) {
+        assert!(self.vars[var].1 >= 64 || value >> self.vars[var].1 == 0, "{} does not fit in {}", value, self.vars[var].0);
+        let bits: Vec<bool> = (0..self.vars[var].1).map(|i| i < 64 && (value >> i) & 1 == 1).collect();
+        self.change_bits(time, var, &bits);
+    }
+
+    // 64 桁より広い var 用。bits[0] が下の桁
+    pub fn change_bits(&mut self, time: u64, var: usize, bits: &[bool]) {
+        assert_eq!(self.vars[var].1, bits.len(), "width of {}", self.vars[var].0);
+        if self.values[var].as_deref() == Some(bits) {
+            return
+        }
+        if self.time != Some(time) {
+            assert!(self.time.is_none_or(|t| t < time), "time went backwards: {}", time);
+            self.body.push_str(&format!("#{}\n", time));
+            self.time = Some(time);
+        }
+        self.values[var] = Some(bits.to_vec());
+        let digits: String = bits.iter().rev().map(|b| if *b { '1' } else { '0' }).collect();
+        if bits.len() == 1 {
+            self.body.push_str(&format!("{}{}\n", digits, identifier(var)));
+        } else {
+            self.body.push_str(&format!("b{} {}\n", digits, identifier(var)));
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut out = String::from("$version rust_nand_2_tetris $end\n$timescale 1ns $end\n");
+        out.push_str(&format!("$scope module {} $end\n", self.name));
+        for decl in &self.decls {
+            match decl {
+                Decl::Scope(name) => out.push_str(&format!("$scope module {} $end\n", name)),
+                Decl::Upscope => out.push_str("$upscope $end\n"),
+                Decl::Var(index) => {
+                    let (name, width) = &self.vars[*index];
+                    let range = if *width == 1 { String::new() } else { format!(" [{}:0]", width - 1) };
+                    out.push_str(&format!("$var wire {} {} {}{} $end\n", width, identifier(*index), name, range));
+                }
+            }
+        }
+        out.push_str("$upscope $end\n$enddefinitions $end\n");
+        out.push_str(&self.body);
+        out
+    }
+}
+
+// チップの中の部品。schematic::rust_chip の回路図を nand と DFF で同じように動かして、部品ごとの in / out を出す
+// 部品は HDL のようにピンの名前を持っていないので、部品の外とつながっているネットを番号順に並べて in / out にする
+struct Inside {
+    schematic: Schematic,
+    dffs: Vec<usize>,
+    nets: Vec<u64>,
+    state: Vec<u64>,
+    // (var, 下の桁からのネット)
+    vars: Vec<(usize, Vec<usize>)>
+}
+
+impl Inside {
+    fn new(schematic: Schematic, vcd: &mut Vcd) -> Inside {
+        let blocks = schematic.blocks();
+        let mut vars = Vec::new();
+        Inside::declare(&blocks, 0, vcd, &mut vars);
+        let dffs = schematic.dffs();
+        Inside { nets: vec![0; schematic.net_count()], state: vec![0; dffs.len()], schematic, dffs, vars }
+    }
+
+    // Mux16#2 は Mux16_2 という scope にする
+    fn declare(blocks: &[Block], block: usize, vcd: &mut Vcd, vars: &mut Vec<(usize, Vec<usize>)>) {
+        for index in &blocks[block].children {
+            let child = &blocks[*index];
+            vcd.scope(&child.path.last().unwrap().replace('#', "_"));
+            for (name, nets) in [("in", &child.inputs), ("out", &child.outputs)].iter() {
+                if !nets.is_empty() {
+                    vars.push((vcd.var(name, nets.len()), nets.to_vec()));
+                }
+            }
+            Inside::declare(blocks, *index, vcd, vars);
+            vcd.upscope();
+        }
+    }
+
+    fn eval(&mut self, inputs: &[u64], vcd: &mut Vcd, time: u64) {
+        for ((_, nets), value) in self.schematic.inputs.iter().zip(inputs) {
+            for (i, net) in nets.iter().enumerate() {
+                self.nets[*net] = (value >> i) & 1;
+            }
+        }
+        self.schematic.eval(&mut self.nets, &self.dffs, &self.state, |_, value| value);
+        for (var, nets) in &self.vars {
+            let bits: Vec<bool> = nets.iter().map(|n| self.nets[*n] & 1 == 1).collect();
+            vcd.change_bits(time, *var, &bits);
+        }
+    }
+
+    // 立ち上がりで DFF が取り込む
+    fn clock(&mut self) {
+        for (k, cell) in self.dffs.iter().enumerate() {
+            self.state[k] = self.nets[self.schematic.cells[*cell].inputs[0]];
+        }
+    }
+}
+
+// chip を動かしながら 1 クロックごとに clk, 入力, out, 記憶している値と、中の部品ごとの in / out を VCD に取る
+// クロック k は 10k ns から始まる。10k で clk が 0 になって入力が変わり、10k+5 の立ち上がりで値を取り込む
+// 中の部品の値は new したばかりの (全部 0 の) チップから数える
+pub struct Trace {
+    chip: Box<dyn Sequential<signal::Bit>>,
+    vcd: Vcd,
+    cycle: u64,
+    clk: usize,
+    inputs: Vec<usize>,
+    out: usize,
+    // メモリなら番地ごとの mem_0, mem_1, ...
+    state: Vec<usize>,
+    inside: Option<Inside>
+}
+
+impl Trace {
+    pub fn new(chip: Box<dyn Sequential<signal::Bit>>) -> Trace {
+        let pins = chip.pins();
+        let mut vcd = Vcd::new(pins.name);
+        let clk = vcd.var("clk", 1);
+        let inputs = pins.inputs.iter().map(|(name, width)| vcd.var(name, *width)).collect();
+        let out = vcd.var("out", pins.out);
+        let words = chip.words();
+        let state: Vec<usize> = if pins.address().is_some() {
+            (0..words.len()).map(|a| vcd.var(&format!("mem_{}", a), pins.out)).collect()
+        } else {
+            Vec::new()
+        };
+        let inside = schematic::rust_chip(pins.name).map(|schematic| Inside::new(schematic, &mut vcd));
+        for (var, word) in state.iter().zip(&words) {
+            vcd.change(0, *var, rust_chip::value(word));
+        }
+        Trace { chip, vcd, cycle: 0, clk, inputs, out, state, inside }
+    }
+
+    // chip の exec を 1 回呼んで、その返り値を返す。inputs は HDL のピンの順で、ピンの幅に収まっていること
+    pub fn step(&mut self, inputs: &[u64]) -> u64 {
+        let time = self.cycle * 10;
+        let pins: Vec<Vec<signal::Bit>> = self.chip.pins().inputs.iter().zip(inputs)
+            .map(|((_, width), value)| rust_chip::pin(*value, *width))
+            .collect();
+        let result = rust_chip::value(&self.chip.step(&pins));
+        self.vcd.change(time, self.clk, 0);
+        for (var, value) in self.inputs.iter().zip(inputs) {
+            self.vcd.change(time, *var, *value);
+        }
+        self.vcd.change(time, self.out, result);
+        if let Some(inside) = &mut self.inside {
+            inside.eval(inputs, &mut self.vcd, time);
+            inside.clock();
+        }
+
+        self.vcd.change(time + 5, self.clk, 1);
+        self.vcd.change(time + 5, self.out, rust_chip::value(&self.chip.out(inputs)));
+        for (var, word) in self.state.iter().zip(self.chip.words()) {
+            self.vcd.change(time + 5, *var, rust_chip::value(&word));
+        }
+        if let Some(inside) = &mut self.inside {
+            inside.eval(inputs, &mut self.vcd, time + 5);
+        }
+        self.cycle += 1;
+        result
+    }
+
+    #[cfg(test)]
+    pub fn chip(&self) -> &dyn Sequential<signal::Bit> {
+        self.chip.as_ref()
+    }
+
+    pub fn vcd(&self) -> String {
+        self.vcd.to_text()
+    }
+}
+
+fn trace(chip: Box<dyn Sequential<signal::Bit>>, text: &str) -> Result<String, String> {
+    let pins = chip.pins();
+    let mut trace = Trace::new(chip);
+    for (index, line) in text.lines().enumerate() {
+        let line = line.split("//").next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue
+        }
+        let inputs = line.split_whitespace().map(|v| v.parse::<u64>()).collect::<Result<Vec<u64>, _>>()
+            .map_err(|e| format!("line {}: {}", index + 1, e))?;
+        if inputs.len() != pins.inputs.len() {
+            let names: Vec<&str> = pins.inputs.iter().map(|(name, _)| *name).collect();
+            return Err(format!("line {}: expected {} values ({})", index + 1, names.len(), names.join(" ")))
+        }
+        for (value, (name, width)) in inputs.iter().zip(pins.inputs) {
+            if value >> width != 0 {
+                return Err(format!("line {}: {} does not fit in {} ({} bits)", index + 1, value, name, width))
+            }
+        }
+        trace.step(&inputs);
+    }
+    Ok(trace.vcd())
+}
+
+// text の 1 行を 1 クロックの入力 (HDL のピンの順に 10 進数を空白区切り) として chip を動かし、VCD を返す
+// 知らないチップなら None
+pub fn run(chip: &str, text: &str) -> Option<Result<String, String>> {
+    rust_chip::sequential(chip).map(|chip| trace(chip, text))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn start(chip: &str) -> Trace {
+        Trace::new(rust_chip::sequential(chip).unwrap())
+    }
+
+    #[test]
+    fn identifier_test() {
+        assert_eq!("!", identifier(0));
+        assert_eq!("~", identifier(93));
+        assert_eq!("!!", identifier(94));
+        assert_eq!("\"!", identifier(95));
+        assert_eq!("!\"", identifier(94 * 2));
+    }
+
+    #[test]
+    fn vcd_test() {
+        let mut vcd = Vcd::new("top");
+        let a = vcd.var("a", 1);
+        let b = vcd.var("b", 4);
+        vcd.change(0, a, 0);
+        vcd.change(0, b, 5);
+        vcd.change(5, a, 0);
+        vcd.change(10, a, 1);
+        assert_eq!(
+            "$version rust_nand_2_tetris $end\n$timescale 1ns $end\n$scope module top $end\n\
+             $var wire 1 ! a $end\n$var wire 4 \" b [3:0] $end\n$upscope $end\n$enddefinitions $end\n\
+             #0\n0!\nb0101 \"\n#10\n1!\n",
+            vcd.to_text()
+        );
+    }
+
+    #[test]
+    fn pc_test() {
+        // in, load, inc, reset
+        let mut trace = start("PC");
+        trace.step(&[1234, 1, 0, 0]);
+        trace.step(&[0, 1, 0, 1]);
+        assert_eq!(0, rust_chip::value(&trace.chip().words()[0]));
+        let vcd = trace.vcd();
+        assert!(vcd.contains("$var wire 16 \" in [15:0] $end\n"));
+        assert!(vcd.contains("$var wire 1 % reset $end\n"));
+        // 1 クロック目の立ち上がりで 1234 を取り込み、2 クロック目の立ち上がりで reset される
+        assert!(vcd.contains("#5\n1!\nb0000010011010010 &\n"), "{}", vcd);
+        assert!(vcd.contains("#10\n0!\nb0000000000000000 \"\n1%\n"), "{}", vcd);
+        assert!(vcd.contains("#15\n1!\nb0000000000000000 &\n"), "{}", vcd);
+    }
+
+    // scope の中の var の名前 (Register_4.Bit_0.out のように) から識別子
+    fn ids(vcd: &str) -> HashMap<String, String> {
+        let mut scopes: Vec<&str> = Vec::new();
+        let mut ids = HashMap::new();
+        for line in vcd.lines().take_while(|l| *l != "$enddefinitions $end") {
+            let words: Vec<&str> = line.split(' ').collect();
+            match words[0] {
+                "$scope" => scopes.push(words[2]),
+                "$upscope" => { scopes.pop(); },
+                "$var" => {
+                    let mut name: Vec<&str> = scopes[1..].to_vec();
+                    name.push(words[4]);
+                    ids.insert(name.join("."), words[3].to_string());
+                },
+                _ => {}
+            }
+        }
+        ids
+    }
+
+    // id の値が変わった順
+    fn changes(vcd: &str, id: &str) -> Vec<String> {
+        let suffix = format!(" {}", id);
+        vcd.lines().filter_map(|l| l.strip_suffix(&suffix)).map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn scope_test() {
+        let mut trace = start("PC");
+        trace.step(&[1234, 1, 0, 0]);
+        trace.step(&[0, 1, 0, 1]);
+        let vcd = trace.vcd();
+        let ids = ids(&vcd);
+        assert!(vcd.contains("$scope module Inc16_0 $end\n"), "{}", vcd);
+        // reset の Mux16 は b が定数なので、in は c の 16 本と reset
+        assert!(vcd.contains(&format!("$var wire 17 {} in [16:0] $end\n", ids["Mux16_3.in"])));
+        assert_eq!(vec!["b0000010011010010", "b0000000000000000"], changes(&vcd, &ids["Mux16_3.out"]));
+        // Register の出力はチップの out と同じ時刻に同じ値になる
+        assert_eq!(changes(&vcd, &ids["out"]), changes(&vcd, &ids["Register_4.out"]));
+        assert!(ids.contains_key("Register_4.Bit_15.Mux_0.Not_0.out"));
+
+        // DFF は部品が無い
+        let mut trace = start("DFF");
+        trace.step(&[1]);
+        assert!(!trace.vcd().contains("$scope module Mux"));
+    }
+
+    #[test]
+    fn ram8_test() {
+        let mut trace = start("RAM8");
+        assert_eq!(0, trace.step(&[7, 1, 6]));
+        assert_eq!(0, trace.step(&[9, 1, 2]));
+        assert_eq!(7, trace.step(&[0, 0, 6]));
+        assert_eq!(7, rust_chip::value(&trace.chip().words()[6]));
+        assert_eq!(9, rust_chip::value(&trace.chip().words()[2]));
+        let vcd = trace.vcd();
+        assert!(vcd.contains("$var wire 16 , mem_6 [15:0] $end\n"), "{}", vcd);
+        // mem_6 は 1 クロック目の立ち上がり (#5) で 7 になる
+        assert!(vcd.contains("#5\n1!\nb0000000000000111 %\nb0000000000000111 ,\n"), "{}", vcd);
+    }
+
+    #[test]
+    fn bit_test() {
+        let mut trace = start("Bit");
+        for (input, load) in [(1, 1), (0, 0), (0, 1)].iter() {
+            trace.step(&[*input, *load]);
+        }
+        let vcd = trace.vcd();
+        assert!(vcd.contains("#5\n1!\n1$\n"), "{}", vcd);
+        assert!(vcd.contains("#25\n1!\n0$\n"), "{}", vcd);
+        let mut trace = start("RAM64");
+        trace.step(&[5, 1, 63]);
+        assert_eq!(5, rust_chip::value(&trace.chip().words()[63]));
+        let mut trace = start("DFF");
+        assert_eq!(0, trace.step(&[1]));
+        assert_eq!(1, trace.step(&[0]));
+    }
+
+    #[test]
+    fn run_test() {
+        let vcd = run("Register", "// in load\n5 1\n\n0 0\n").unwrap().unwrap();
+        assert!(vcd.contains("#15\n1!\n"));
+        assert_eq!(Some(Err("line 1: expected 4 values (in load inc reset)".to_string())), run("PC", "1 2"));
+        assert!(run("Register", "x 1").unwrap().is_err());
+        assert_eq!(Some(Err("line 2: 70000 does not fit in in (16 bits)".to_string())), run("Register", "1 1\n70000 1"));
+        assert_eq!(Some(Err("line 1: 2 does not fit in in (1 bits)".to_string())), run("Bit", "2 0"));
+        assert!(run("RAM8", "0 0 8").unwrap().is_err());
+        assert!(run("CPU", "").is_none());
+    }
+}
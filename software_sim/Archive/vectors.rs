@@ -0,0 +1,110 @@
+use crate::bool_arithmetic;
+use crate::hdl::tst::Target;
+use crate::sequential_logic;
+use crate::signal::Bit;
+
+// bool_arithmetic.rs と sequential_logic.rs の #[test] のベクタを、HDL のチップの名前とピンで並べ直したもの
+// 1 行ごとに入力を入れて eval し、出力を読む。順序回路は読んだあとで 1 クロック (tick, tock) 進める
+pub struct Vectors {
+    pub chip: &'static str,
+    pub clocked: bool,
+    pub inputs: &'static [&'static str],
+    pub outputs: &'static [&'static str],
+    // 入力と出力の値。ピンの順で、どれも下の桁から
+    pub rows: Vec<(Vec<u64>, Vec<u64>)>
+}
+
+fn bit(bit: &Bit) -> u64 {
+    u8::from(*bit) as u64
+}
+
+fn word(word: &i16) -> u64 {
+    *word as u16 as u64
+}
+
+pub fn all() -> Vec<Vectors> {
+    let combinational = |chip, inputs, outputs, rows| Vectors { chip, clocked: false, inputs, outputs, rows };
+    let clocked = |chip, inputs, rows| Vectors { chip, clocked: true, inputs, outputs: &["out"], rows };
+    vec![
+        combinational("HalfAdder", &["a", "b"], &["sum", "carry"], bool_arithmetic::HALF_ADDER_VECTORS.iter()
+            .map(|(a, b, sum, carry)| (vec![bit(a), bit(b)], vec![bit(sum), bit(carry)])).collect()),
+        combinational("FullAdder", &["a", "b", "c"], &["sum", "carry"], bool_arithmetic::FULL_ADDER_VECTORS.iter()
+            .map(|(a, b, c, sum, carry)| (vec![bit(a), bit(b), bit(c)], vec![bit(sum), bit(carry)])).collect()),
+        combinational("Add16", &["a", "b"], &["out"], bool_arithmetic::ADD16_VECTORS.iter()
+            .map(|(a, b, out)| (vec![*a as u64, *b as u64], vec![*out as u64])).collect()),
+        combinational("Inc16", &["in"], &["out"], bool_arithmetic::INC16_VECTORS.iter()
+            .map(|(input, out)| (vec![*input as u64], vec![*out as u64])).collect()),
+        combinational("ALU", &["x", "y", "zx", "nx", "zy", "ny", "f", "no"], &["out", "zr", "ng"], bool_arithmetic::ALU_VECTORS.iter()
+            .map(|(x, y, control, out, zr, ng)| {
+                let mut inputs = vec![*x as u64, *y as u64];
+                inputs.extend(control.iter().map(bit));
+                (inputs, vec![*out as u64, bit(zr), bit(ng)])
+            }).collect()),
+        clocked("Bit", &["in", "load"], sequential_logic::BIT_VECTORS.iter()
+            .map(|(input, load, out)| (vec![bit(input), bit(load)], vec![bit(out)])).collect()),
+        clocked("Register", &["in", "load"], sequential_logic::REGISTER_VECTORS.iter()
+            .map(|(input, load, out)| (vec![word(input), bit(load)], vec![word(out)])).collect()),
+        clocked("RAM8", &["in", "load", "address"], sequential_logic::RAM8_VECTORS.iter()
+            .map(|(input, load, address, out)| (vec![word(input), bit(load), *address as u64], vec![word(out)])).collect()),
+        clocked("RAM64", &["in", "load", "address"], sequential_logic::RAM64_VECTORS.iter()
+            .map(|(input, load, address, out)| (vec![word(input), bit(load), *address as u64], vec![word(out)])).collect())
+    ]
+}
+
+// chip のベクタ。#[test] で書いていなければ None
+pub fn find(chip: &str) -> Option<Vectors> {
+    all().into_iter().find(|v| v.chip == chip)
+}
+
+impl Vectors {
+    // target に上から順に流す。出力が表と違えば、何行目 (1 始まり) のどのピンかを返す
+    // 違っていても、その行の出力は全部読む
+    pub fn replay(&self, target: &mut dyn Target) -> Result<(), String> {
+        for (row, (inputs, outputs)) in self.rows.iter().enumerate() {
+            for (pin, value) in self.inputs.iter().zip(inputs) {
+                if !target.set(pin, *value) {
+                    return Err(format!("{} has no input {}", self.chip, pin))
+                }
+            }
+            target.eval();
+            let mut error = None;
+            for (pin, expected) in self.outputs.iter().zip(outputs) {
+                let actual = target.get(pin);
+                if error.is_none() && actual != Some(*expected) {
+                    let actual = actual.map_or("nothing".to_string(), |a| a.to_string());
+                    error = Some(format!("row {}: {} = {}, expected {}", row + 1, pin, actual, expected));
+                }
+            }
+            if let Some(error) = error {
+                return Err(error)
+            }
+            if self.clocked {
+                target.tick();
+                target.tock();
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::hdl::rust_chip::RustChip;
+
+    #[test]
+    fn replay_test() {
+        for vectors in all() {
+            let mut chip = RustChip::find(vectors.chip).unwrap();
+            assert_eq!(Ok(()), vectors.replay(&mut chip), "{}", vectors.chip);
+        }
+        let alu = find("ALU").unwrap();
+        assert_eq!(36, alu.rows.len());
+        assert_eq!(8, alu.rows[0].0.len());
+        // 表と違う出力は行とピンを返す
+        let mut register = find("Register").unwrap();
+        register.rows[1].1[0] = 7;
+        assert_eq!(Err("row 2: out = 0, expected 7".to_string()), register.replay(&mut RustChip::find("Register").unwrap()));
+        assert!(find("PC").is_none());
+    }
+}
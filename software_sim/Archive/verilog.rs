@@ -0,0 +1,871 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::path::Path;
+use std::rc::Rc;
+
+use crate::hdl::rust_chip::RustChip;
+use crate::hdl::tst::{self, Target, TstError};
+use crate::schematic::{self, Block, Schematic};
+use crate::vectors::Vectors;
+
+// schematic の Dff。tst の tock にあたる clk の立ち上がりで取り込む
+pub const DFF: &str = "\
+module DFF(input clk, input in, output reg out);
+    initial out = 1'b0;
+    always @(posedge clk) out <= in;
+endmodule
+";
+
+fn bit(name: &str, width: usize, i: usize) -> String {
+    if width == 1 { name.to_string() } else { format!("{}[{}]", name, i) }
+}
+
+fn port(direction: &str, name: &str, width: usize) -> String {
+    if width == 1 {
+        format!("{} {}", direction, name)
+    } else {
+        format!("{} [{}:0] {}", direction, width - 1, name)
+    }
+}
+
+fn literal(width: usize, value: u64) -> String {
+    if width == 1 { format!("1'b{}", value & 1) } else { format!("{}'d{}", width, value) }
+}
+
+fn sequential(schematic: &Schematic) -> bool {
+    schematic.cells.iter().any(|c| c.chip == "DFF")
+}
+
+// Mux16#3 の部品なら Mux16
+fn chip_of(block: &Block) -> &str {
+    let label = block.path.last().unwrap();
+    label.split('#').next().unwrap_or(label)
+}
+
+// nets を 1 つの式にする。{上の桁, ..., 下の桁} の順
+fn concat(nets: &[usize], names: &HashMap<usize, String>) -> String {
+    if nets.len() == 1 {
+        return names[&nets[0]].clone()
+    }
+    let bits: Vec<&str> = nets.iter().rev().map(|n| names[n].as_str()).collect();
+    format!("{{{}}}", bits.join(", "))
+}
+
+// 部品 block の中身 (wire の宣言から assign まで)。names には入力のネットの名前を入れておく
+// 中で作るネットは w0, w1, ... と部品の中だけの名前にするので、同じ作りの部品は同じ文字列になる
+fn body(schematic: &Schematic, blocks: &[Block], block: usize, modules: &[String], clocked: &[bool], names: &mut HashMap<usize, String>) -> String {
+    names.insert(0, "1'b0".to_string());
+    names.insert(1, "1'b1".to_string());
+    let block = &blocks[block];
+    let mut wires = 0;
+    let mut out = String::new();
+    let mut wire = |net: usize, names: &mut HashMap<usize, String>| {
+        names.insert(net, format!("w{}", wires));
+        out.push_str(&format!("    wire w{};\n", wires));
+        wires += 1;
+    };
+    for cell in &block.cells {
+        for net in &schematic.cells[*cell].outputs {
+            wire(*net, names);
+        }
+    }
+    for child in &block.children {
+        for net in &blocks[*child].outputs {
+            wire(*net, names);
+        }
+    }
+    let (mut nands, mut dffs) = (0, 0);
+    for cell in block.cells.iter().map(|c| &schematic.cells[*c]) {
+        if cell.chip == "DFF" {
+            out.push_str(&format!("    DFF d{} (.clk(clk), .in({}), .out({}));\n", dffs, names[&cell.inputs[0]], names[&cell.outputs[0]]));
+            dffs += 1;
+        } else {
+            out.push_str(&format!(
+                "    nand g{} ({}, {}, {});\n",
+                nands, names[&cell.outputs[0]], names[&cell.inputs[0]], names[&cell.inputs[1]]
+            ));
+            nands += 1;
+        }
+    }
+    for index in &block.children {
+        let child = &blocks[*index];
+        let mut ports = Vec::new();
+        if clocked[*index] {
+            ports.push(".clk(clk)".to_string());
+        }
+        if !child.inputs.is_empty() {
+            ports.push(format!(".in({})", concat(&child.inputs, names)));
+        }
+        if !child.outputs.is_empty() {
+            ports.push(format!(".out({})", concat(&child.outputs, names)));
+        }
+        // Mux16#2 は Mux16_2 というインスタンスにする
+        let instance = child.path.last().unwrap().replace('#', "_");
+        out.push_str(&format!("    {} {} ({});\n", modules[*index], instance, ports.join(", ")));
+    }
+    out
+}
+
+// schematic を部品ごとの module に分けた、nand と DFF だけの構造記述にする
+// 部品の module は clk (中に Dff があれば), in, out のポートを持ち、in / out は部品の外とつながっているネットを番号順に並べたもの
+// 同じ部品でも定数につながっているところなどで作りが違えば Mux16_2 のように別の module にする
+// 一番外の module だけはチップのピンの名前を使う。Dff があれば最後に DFF の module を付ける
+pub fn design(schematic: &Schematic) -> String {
+    let blocks = schematic.blocks();
+    let mut clocked = vec![false; blocks.len()];
+    // まず作りが同じ部品を 1 つの module にまとめる。名前はまだ付けずに、親の中には \u{1}番号\u{1} と書いておく
+    let mut ids = vec![0; blocks.len()];
+    let mut modules = vec![String::new(); blocks.len()];
+    let mut texts: Vec<String> = Vec::new();
+    let mut found: HashMap<String, usize> = HashMap::new();
+    // 子の部品は親より後ろの番号なので、後ろから作れば子の module が先にできている
+    for b in (1..blocks.len()).rev() {
+        let block = &blocks[b];
+        clocked[b] = block.cells.iter().any(|c| schematic.cells[*c].chip == "DFF") || block.children.iter().any(|c| clocked[*c]);
+        let mut names: HashMap<usize, String> = HashMap::new();
+        let mut ports = Vec::new();
+        if clocked[b] {
+            ports.push("input clk".to_string());
+        }
+        for (i, net) in block.inputs.iter().enumerate() {
+            names.insert(*net, bit("in", block.inputs.len(), i));
+        }
+        if !block.inputs.is_empty() {
+            ports.push(port("input", "in", block.inputs.len()));
+        }
+        if !block.outputs.is_empty() {
+            ports.push(port("output", "out", block.outputs.len()));
+        }
+        let mut text = format!("({});\n{}", ports.join(", "), body(schematic, &blocks, b, &modules, &clocked, &mut names));
+        for (i, net) in block.outputs.iter().enumerate() {
+            text.push_str(&format!("    assign {} = {};\n", bit("out", block.outputs.len(), i), names[net]));
+        }
+        let key = format!("{} {}", chip_of(block), text);
+        ids[b] = *found.entry(key).or_insert_with(|| {
+            texts.push(text);
+            texts.len() - 1
+        });
+        modules[b] = format!("\u{1}{}\u{1}", ids[b]);
+    }
+    // 名前は前から付ける。PC なら Mux16#1 が Mux16 で、作りの違う Mux16#3 が Mux16_2
+    let mut names: Vec<String> = vec![String::new(); texts.len()];
+    let mut variants: HashMap<&str, usize> = HashMap::new();
+    for b in 1..blocks.len() {
+        if names[ids[b]].is_empty() {
+            let chip = chip_of(&blocks[b]);
+            let count = variants.entry(chip).or_insert(0);
+            *count += 1;
+            names[ids[b]] = if *count == 1 { chip.to_string() } else { format!("{}_{}", chip, count) };
+        }
+    }
+    // \u{1} で区切った奇数番目が module の番号
+    let resolve = |text: &str| -> String {
+        text.split('\u{1}').enumerate().map(|(i, part)| if i % 2 == 1 { names[part.parse::<usize>().unwrap()].as_str() } else { part }).collect()
+    };
+    let mut out = String::new();
+    for (name, text) in names.iter().zip(&texts) {
+        out.push_str(&format!("module {}{}endmodule\n\n", name, resolve(text)));
+    }
+
+    let mut pins: HashMap<usize, String> = HashMap::new();
+    for (name, nets) in &schematic.inputs {
+        for (i, net) in nets.iter().enumerate() {
+            pins.insert(*net, bit(name, nets.len(), i));
+        }
+    }
+    let mut ports = Vec::new();
+    if sequential(schematic) {
+        ports.push("input clk".to_string());
+    }
+    ports.extend(schematic.inputs.iter().map(|(name, nets)| port("input", name, nets.len())));
+    ports.extend(schematic.outputs.iter().map(|(name, nets)| port("output", name, nets.len())));
+    out.push_str(&format!("module {}({});\n", schematic.name, ports.join(", ")));
+    out.push_str(&resolve(&body(schematic, &blocks, 0, &modules, &clocked, &mut pins)));
+    for (name, nets) in &schematic.outputs {
+        for (i, net) in nets.iter().enumerate() {
+            out.push_str(&format!("    assign {} = {};\n", bit(name, nets.len(), i), pins[net]));
+        }
+    }
+    out.push_str("endmodule\n");
+    if sequential(schematic) {
+        out.push('\n');
+        out.push_str(DFF);
+    }
+    out
+}
+
+// テストスクリプトで RustChip に何をしたか
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    Set(String, u64),
+    Eval,
+    Tick,
+    Tock,
+    // 読んだピンと、RustChip が返した値
+    Get(String, u64)
+}
+
+// RustChip を動かしながら、スクリプトがしたことを覚えておく
+// tick のあとで tock の前に入力を変えようとしたら、断って late に印を付ける
+struct Recorder {
+    chip: RustChip,
+    steps: Rc<RefCell<Vec<Step>>>,
+    ticked: bool,
+    late: Rc<Cell<bool>>
+}
+
+impl Recorder {
+    fn new(chip: RustChip, steps: &Rc<RefCell<Vec<Step>>>, late: &Rc<Cell<bool>>) -> Recorder {
+        Recorder { chip, steps: steps.clone(), ticked: false, late: late.clone() }
+    }
+}
+
+impl Target for Recorder {
+    fn set(&mut self, pin: &str, value: u64) -> bool {
+        if self.ticked {
+            self.late.set(true);
+            return false
+        }
+        if !self.chip.set(pin, value) {
+            return false
+        }
+        let value = self.chip.get(pin).unwrap_or(value);
+        self.steps.borrow_mut().push(Step::Set(pin.to_string(), value));
+        true
+    }
+
+    fn get(&self, pin: &str) -> Option<u64> {
+        let value = self.chip.get(pin)?;
+        self.steps.borrow_mut().push(Step::Get(pin.to_string(), value));
+        Some(value)
+    }
+
+    fn eval(&mut self) {
+        self.chip.eval();
+        self.steps.borrow_mut().push(Step::Eval);
+    }
+
+    fn tick(&mut self) {
+        self.chip.tick();
+        self.ticked = true;
+        self.steps.borrow_mut().push(Step::Tick);
+    }
+
+    fn tock(&mut self) {
+        self.chip.tock();
+        self.ticked = false;
+        self.steps.borrow_mut().push(Step::Tock);
+    }
+}
+
+// script を Rust のチップで動かし、読み込んだチップの回路図と、同じ入力を入れて出力が Rust と同じか調べる testbench を作る
+// clk は tock で立ち上げるので、tick と tock の間に入力を変えるスクリプトは Rust と合わない。そういうスクリプトはエラーにする
+pub fn testbench(script: &Path) -> Result<(Schematic, String), TstError> {
+    let steps = Rc::new(RefCell::new(Vec::new()));
+    let late = Rc::new(Cell::new(false));
+    let mut loaded = None;
+    let report = tst::run_with(script, &mut |name, line| -> Result<Box<dyn Target>, TstError> {
+        let missing = || TstError::Script { line, message: format!("no Rust chip named {}", name) };
+        let chip = RustChip::find(name).ok_or_else(missing)?;
+        loaded = Some(schematic::rust_chip(name).ok_or_else(missing)?);
+        Ok(Box::new(Recorder::new(chip, &steps, &late)))
+    }).map_err(|e| match e {
+        TstError::Script { line, .. } if late.get() => TstError::Script {
+            line,
+            message: "inputs change between tick and tock, which the testbench cannot follow because clk rises on tock".to_string()
+        },
+        e => e
+    })?;
+    let schematic = loaded.ok_or(TstError::Script { line: 1, message: "no chip loaded".to_string() })?;
+    let note = report.mismatch.map(|mismatch| {
+        format!("the Rust chip fails {} at line {}, so the vectors after it are not included", script.display(), mismatch.line)
+    });
+    let bench = bench(&schematic, &steps.borrow(), note);
+    Ok((schematic, bench))
+}
+
+// bool_arithmetic.rs と sequential_logic.rs の #[test] のベクタで testbench を作る
+// ベクタを Rust のチップに流して、違っていればエラー
+pub fn vector_testbench(vectors: &Vectors) -> Result<(Schematic, String), String> {
+    let missing = || format!("no Rust chip named {}", vectors.chip);
+    let chip = RustChip::find(vectors.chip).ok_or_else(missing)?;
+    let schematic = schematic::rust_chip(vectors.chip).ok_or_else(missing)?;
+    let steps = Rc::new(RefCell::new(Vec::new()));
+    vectors.replay(&mut Recorder::new(chip, &steps, &Rc::new(Cell::new(false))))?;
+    let bench = bench(&schematic, &steps.borrow(), None);
+    Ok((schematic, bench))
+}
+
+// steps を Verilog の testbench にする。出力を読んだところごとに !== で比べ、違えば表示して最後に数を出す
+fn bench(schematic: &Schematic, steps: &[Step], note: Option<String>) -> String {
+    let clocked = sequential(schematic);
+    let mut out = String::new();
+    if let Some(note) = note {
+        out.push_str(&format!("// {}\n", note));
+    }
+    out.push_str(&format!("`timescale 1ns/1ps\nmodule {}_tb;\n", schematic.name));
+    let mut connections = Vec::new();
+    if clocked {
+        out.push_str("    reg clk = 1'b0;\n");
+        connections.push(".clk(clk)".to_string());
+    }
+    for (name, nets) in &schematic.inputs {
+        out.push_str(&format!("    {} = {};\n", port("reg", name, nets.len()), literal(nets.len(), 0)));
+        connections.push(format!(".{}({})", name, name));
+    }
+    for (name, nets) in &schematic.outputs {
+        out.push_str(&format!("    {};\n", port("wire", name, nets.len())));
+        connections.push(format!(".{}({})", name, name));
+    }
+    out.push_str("    integer errors = 0;\n");
+    out.push_str(&format!("    {} dut({});\n", schematic.name, connections.join(", ")));
+    out.push_str("    initial begin\n        #1;\n");
+    let mut checks = 0;
+    for step in steps {
+        match step {
+            Step::Set(pin, value) => {
+                if let Some((_, nets)) = schematic.inputs.iter().find(|(name, _)| name == pin) {
+                    out.push_str(&format!("        {} = {};\n", pin, literal(nets.len(), *value)));
+                }
+            },
+            Step::Eval => out.push_str("        #1;\n"),
+            Step::Tick if clocked => out.push_str("        #1;\n"),
+            Step::Tock if clocked => out.push_str("        clk = 1'b1; #1; clk = 1'b0; #1;\n"),
+            Step::Tick | Step::Tock => out.push_str("        #1;\n"),
+            Step::Get(pin, value) => {
+                if let Some((_, nets)) = schematic.outputs.iter().find(|(name, _)| name == pin) {
+                    checks += 1;
+                    out.push_str(&format!(
+                        "        if ({pin} !== {expected}) begin $display(\"check {n}: {pin} = %0d, expected {value}\", {pin}); errors = errors + 1; end\n",
+                        pin = pin, expected = literal(nets.len(), *value), n = checks, value = value
+                    ));
+                }
+            }
+        }
+    }
+    out.push_str(&format!(
+        "        if (errors == 0) $display(\"ok ({} checks)\"); else $display(\"%0d of {} checks failed\", errors);\n",
+        checks, checks
+    ));
+    out.push_str("        $finish;\n    end\nendmodule\n");
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::vectors;
+    use std::path::PathBuf;
+
+    fn hardware(file: &str) -> PathBuf {
+        Path::new(file!()).parent().unwrap().join("../../hardware_sim").join(file)
+    }
+
+    #[test]
+    fn design_test() {
+        let and = design(&schematic::rust_chip("And").unwrap());
+        assert!(and.starts_with("module Not(input in, output out);\n    wire w0;\n    nand g0 (w0, in, in);\n    assign out = w0;\nendmodule\n"), "{}", and);
+        assert!(and.contains("module And(input a, input b, output out);\n"));
+        assert!(and.contains("    nand g0 (w0, a, b);\n    Not Not_0 (.in(w0), .out(w1));\n    assign out = w1;\n"), "{}", and);
+        assert!(!and.contains("DFF"));
+
+        let register = design(&schematic::rust_chip("Register").unwrap());
+        assert!(register.contains("module Register(input clk, input [15:0] in, input load, output [15:0] out);\n"), "{}", register);
+        // Bit は 16 個とも同じ作りなので module は 1 つ
+        assert_eq!(1, register.matches("module Bit(").count());
+        assert!(register.contains("    Bit Bit_15 (.clk(clk), .in({load, in[15]}), .out(w15));\n"), "{}", register);
+        assert!(register.contains("    DFF d0 (.clk(clk), .in(w1), .out(w0));\n"));
+        assert!(register.ends_with(DFF));
+
+        // PC の Mux16#3 は b が 0 につながっているので、別の module になる
+        let pc = design(&schematic::rust_chip("PC").unwrap());
+        assert!(pc.contains("    Mux16 Mux16_1 ("), "{}", pc);
+        assert!(pc.contains("module Mux16_3(input [16:0] in, output [15:0] out);\n"));
+        assert!(pc.contains("    Register Register_4 (.clk(clk), "));
+        // ALU の zx などで使う 0 は定数になる
+        let alu = design(&schematic::rust_chip("ALU").unwrap());
+        assert!(alu.contains("1'b0"));
+        assert!(alu.contains("output [15:0] out, output zr, output ng"));
+    }
+
+    // 式。名前 (と何桁目か)、幅の決まった定数、{} でつないだもの
+    enum Expr {
+        Net(String, Option<usize>),
+        Literal(usize),
+        Concat(Vec<Expr>)
+    }
+
+    #[derive(Clone, Copy, PartialEq)]
+    enum Kind {
+        Input,
+        Output,
+        Wire,
+        Reg
+    }
+
+    #[derive(Default)]
+    struct Module {
+        ports: Vec<(String, Kind, usize)>,
+        nets: HashMap<String, (Kind, usize)>,
+        drivers: HashMap<(String, usize), usize>,
+        // (module, ポートと式)
+        instances: Vec<(String, Vec<(String, Expr)>)>
+    }
+
+    impl Module {
+        fn declare(&mut self, name: &str, kind: Kind, width: usize) -> Result<(), String> {
+            match self.nets.insert(name.to_string(), (kind, width)) {
+                Some(_) => Err(format!("{} is declared twice", name)),
+                None => Ok(())
+            }
+        }
+
+        fn width(&self, expr: &Expr) -> Result<usize, String> {
+            match expr {
+                Expr::Net(name, index) => {
+                    let (_, width) = self.nets.get(name).ok_or_else(|| format!("{} is not declared", name))?;
+                    match index {
+                        Some(i) if i >= width => Err(format!("{}[{}] is out of range", name, i)),
+                        Some(_) => Ok(1),
+                        None => Ok(*width)
+                    }
+                },
+                Expr::Literal(width) => Ok(*width),
+                Expr::Concat(parts) => parts.iter().map(|p| self.width(p)).sum()
+            }
+        }
+
+        fn drive(&mut self, expr: &Expr) -> Result<(), String> {
+            match expr {
+                Expr::Net(name, index) => {
+                    let (kind, width) = self.nets[name];
+                    if kind == Kind::Input {
+                        return Err(format!("input {} is driven", name))
+                    }
+                    let bits: Vec<usize> = match index {
+                        Some(i) => vec![*i],
+                        None => (0..width).collect()
+                    };
+                    for i in bits {
+                        *self.drivers.entry((name.clone(), i)).or_insert(0) += 1;
+                    }
+                    Ok(())
+                },
+                Expr::Literal(_) => Err("a constant is driven".to_string()),
+                Expr::Concat(parts) => parts.iter().try_for_each(|p| self.drive(p))
+            }
+        }
+    }
+
+    // 空白とコメントと `timescale を除いて、名前、数、'b などの定数、文字列、記号に分ける
+    fn tokens(text: &str) -> Vec<String> {
+        let chars: Vec<char> = text.chars().collect();
+        let mut tokens = Vec::new();
+        let mut i = 0;
+        let take = |i: &mut usize, f: &dyn Fn(char) -> bool| -> String {
+            let start = *i;
+            while *i < chars.len() && f(chars[*i]) {
+                *i += 1;
+            }
+            chars[start..*i].iter().collect()
+        };
+        while i < chars.len() {
+            let c = chars[i];
+            if c.is_whitespace() {
+                i += 1;
+            } else if c == '`' || (c == '/' && chars.get(i + 1) == Some(&'/')) {
+                take(&mut i, &|c| c != '\n');
+            } else if c == '"' {
+                i += 1;
+                take(&mut i, &|c| c != '"');
+                i += 1;
+                tokens.push("\"\"".to_string());
+            } else if c.is_ascii_alphabetic() || c == '_' || c == '$' {
+                tokens.push(take(&mut i, &|c| c.is_ascii_alphanumeric() || c == '_' || c == '$'));
+            } else if c.is_ascii_digit() {
+                let mut number = take(&mut i, &|c| c.is_ascii_digit());
+                if chars.get(i) == Some(&'\'') {
+                    i += 1;
+                    number.push('\'');
+                    number.push_str(&take(&mut i, &|c| c.is_ascii_alphanumeric() || c == '_'));
+                }
+                tokens.push(number);
+            } else if c == '<' && chars.get(i + 1) == Some(&'=') {
+                i += 2;
+                tokens.push("<=".to_string());
+            } else {
+                i += 1;
+                tokens.push(c.to_string());
+            }
+        }
+        tokens
+    }
+
+    struct Parser {
+        tokens: Vec<String>,
+        at: usize
+    }
+
+    impl Parser {
+        fn peek(&self) -> &str {
+            self.tokens.get(self.at).map_or("", |t| t.as_str())
+        }
+
+        fn next(&mut self) -> Result<String, String> {
+            let token = self.tokens.get(self.at).cloned().ok_or("unexpected end of file")?;
+            self.at += 1;
+            Ok(token)
+        }
+
+        fn expect(&mut self, token: &str) -> Result<(), String> {
+            let found = self.next()?;
+            if found == token { Ok(()) } else { Err(format!("expected {} but found {}", token, found)) }
+        }
+
+        fn name(&mut self) -> Result<String, String> {
+            let token = self.next()?;
+            match token.chars().next() {
+                Some(c) if c.is_ascii_alphabetic() || c == '_' => Ok(token),
+                _ => Err(format!("expected a name but found {}", token))
+            }
+        }
+
+        fn number(&mut self) -> Result<usize, String> {
+            let token = self.next()?;
+            token.parse().map_err(|_| format!("expected a number but found {}", token))
+        }
+
+        // [n:0] の幅。無ければ 1
+        fn range(&mut self) -> Result<usize, String> {
+            if self.peek() != "[" {
+                return Ok(1)
+            }
+            self.expect("[")?;
+            let high = self.number()?;
+            self.expect(":")?;
+            self.expect("0")?;
+            self.expect("]")?;
+            Ok(high + 1)
+        }
+
+        fn expr(&mut self) -> Result<Expr, String> {
+            if self.peek() == "{" {
+                self.expect("{")?;
+                let mut parts = vec![self.expr()?];
+                while self.peek() == "," {
+                    self.expect(",")?;
+                    parts.push(self.expr()?);
+                }
+                self.expect("}")?;
+                return Ok(Expr::Concat(parts))
+            }
+            if self.peek().contains('\'') {
+                let token = self.next()?;
+                let width = token.split('\'').next().unwrap().parse().map_err(|_| format!("bad constant {}", token))?;
+                return Ok(Expr::Literal(width))
+            }
+            // 幅を書かない数は 32 桁
+            if self.peek().starts_with(|c: char| c.is_ascii_digit()) {
+                self.number()?;
+                return Ok(Expr::Literal(32))
+            }
+            let name = self.name()?;
+            if self.peek() != "[" {
+                return Ok(Expr::Net(name, None))
+            }
+            self.expect("[")?;
+            let index = self.number()?;
+            self.expect("]")?;
+            Ok(Expr::Net(name, Some(index)))
+        }
+
+        // initial や always の後の文。begin ... end は中を読まずに飛ばす
+        fn statement(&mut self, module: &Module) -> Result<(), String> {
+            if self.peek() == "begin" {
+                let mut depth = 0;
+                loop {
+                    match self.next()?.as_str() {
+                        "begin" => depth += 1,
+                        "end" if depth == 1 => return Ok(()),
+                        "end" => depth -= 1,
+                        _ => {}
+                    }
+                }
+            }
+            let target = self.expr()?;
+            match &target {
+                Expr::Net(name, _) if module.nets.get(name).map(|n| n.0) == Some(Kind::Reg) => {},
+                _ => return Err("only a reg can be assigned in a statement".to_string())
+            }
+            if self.peek() == "<=" { self.expect("<=")? } else { self.expect("=")? }
+            let value = self.expr()?;
+            if module.width(&target)? != module.width(&value)? {
+                return Err("width mismatch in a statement".to_string())
+            }
+            self.expect(";")
+        }
+
+        fn module(&mut self) -> Result<(String, Module), String> {
+            self.expect("module")?;
+            let name = self.name()?;
+            let mut module = Module::default();
+            if self.peek() == "(" {
+                self.expect("(")?;
+                while self.peek() != ")" {
+                    let mut kind = match self.next()?.as_str() {
+                        "input" => Kind::Input,
+                        "output" => Kind::Output,
+                        other => return Err(format!("expected input or output but found {}", other))
+                    };
+                    if self.peek() == "reg" {
+                        self.expect("reg")?;
+                        kind = Kind::Reg;
+                    }
+                    let width = self.range()?;
+                    let port = self.name()?;
+                    module.declare(&port, kind, width)?;
+                    module.ports.push((port, kind, width));
+                    if self.peek() == "," {
+                        self.expect(",")?;
+                    }
+                }
+                self.expect(")")?;
+            }
+            self.expect(";")?;
+            loop {
+                match self.next()?.as_str() {
+                    "endmodule" => return Ok((name, module)),
+                    "wire" => {
+                        let width = self.range()?;
+                        loop {
+                            let wire = self.name()?;
+                            module.declare(&wire, Kind::Wire, width)?;
+                            if self.next()? == ";" {
+                                break
+                            }
+                        }
+                    },
+                    keyword @ "reg" | keyword @ "integer" => {
+                        let width = if keyword == "reg" { self.range()? } else { 32 };
+                        let reg = self.name()?;
+                        module.declare(&reg, Kind::Reg, width)?;
+                        if self.peek() == "=" {
+                            self.expect("=")?;
+                            self.expr()?;
+                        }
+                        self.expect(";")?;
+                    },
+                    "assign" => {
+                        let target = self.expr()?;
+                        self.expect("=")?;
+                        let value = self.expr()?;
+                        if module.width(&target)? != module.width(&value)? {
+                            return Err("width mismatch in assign".to_string())
+                        }
+                        module.drive(&target)?;
+                        self.expect(";")?;
+                    },
+                    "nand" => {
+                        if self.peek() != "(" {
+                            self.name()?;
+                        }
+                        self.expect("(")?;
+                        let mut pins = vec![self.expr()?];
+                        for _ in 0..2 {
+                            self.expect(",")?;
+                            pins.push(self.expr()?);
+                        }
+                        self.expect(")")?;
+                        self.expect(";")?;
+                        for pin in &pins {
+                            if module.width(pin)? != 1 {
+                                return Err("nand takes 1 bit pins".to_string())
+                            }
+                        }
+                        module.drive(&pins[0])?;
+                    },
+                    "initial" => self.statement(&module)?,
+                    "always" => {
+                        for token in ["@", "(", "posedge"].iter() {
+                            self.expect(token)?;
+                        }
+                        self.name()?;
+                        self.expect(")")?;
+                        self.statement(&module)?;
+                    },
+                    other => {
+                        // 部品のインスタンス
+                        let target = other.to_string();
+                        self.name()?;
+                        self.expect("(")?;
+                        let mut connections = Vec::new();
+                        while self.peek() != ")" {
+                            self.expect(".")?;
+                            let port = self.name()?;
+                            self.expect("(")?;
+                            connections.push((port, self.expr()?));
+                            self.expect(")")?;
+                            if self.peek() == "," {
+                                self.expect(",")?;
+                            }
+                        }
+                        self.expect(")")?;
+                        self.expect(";")?;
+                        module.instances.push((target, connections));
+                    }
+                }
+            }
+        }
+    }
+
+    // ここで出す範囲の Verilog を読んで、つながりがおかしければその説明を返す
+    fn check(text: &str) -> Result<(), String> {
+        let mut parser = Parser { tokens: tokens(text), at: 0 };
+        let mut modules: HashMap<String, Module> = HashMap::new();
+        while parser.peek() != "" {
+            let (name, module) = parser.module()?;
+            if modules.insert(name.clone(), module).is_some() {
+                return Err(format!("module {} is defined twice", name))
+            }
+        }
+        let ports: HashMap<String, Vec<(String, Kind, usize)>> = modules.iter().map(|(n, m)| (n.clone(), m.ports.clone())).collect();
+        for (name, module) in modules.iter_mut() {
+            let instances = std::mem::take(&mut module.instances);
+            for (target, connections) in &instances {
+                let target_ports = ports.get(target).ok_or_else(|| format!("{}: module {} is not defined", name, target))?;
+                if connections.len() != target_ports.len() {
+                    return Err(format!("{}: {} needs {} ports", name, target, target_ports.len()))
+                }
+                for (port, expr) in connections {
+                    let (_, kind, width) = target_ports.iter().find(|(p, _, _)| p == port)
+                        .ok_or_else(|| format!("{}: {} has no port {}", name, target, port))?;
+                    if module.width(expr)? != *width {
+                        return Err(format!("{}: width of {}.{} does not match", name, target, port))
+                    }
+                    if *kind != Kind::Input {
+                        module.drive(expr)?;
+                    }
+                }
+            }
+            for (net, (kind, width)) in &module.nets {
+                if *kind == Kind::Output || *kind == Kind::Wire {
+                    for i in 0..*width {
+                        let drivers = module.drivers.get(&(net.clone(), i)).copied().unwrap_or(0);
+                        if drivers != 1 {
+                            return Err(format!("{}: {}[{}] has {} drivers", name, net, i, drivers))
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn parse_test() {
+        let mut names: Vec<&str> = crate::equivalence::chips().iter().map(|c| c.name).collect();
+        names.extend(["Bit", "Register", "RAM8", "RAM64", "PC"].iter());
+        // Nand は回路図が無い
+        for schematic in names.into_iter().filter_map(schematic::rust_chip) {
+            check(&design(&schematic)).unwrap_or_else(|e| panic!("{}: {}", schematic.name, e));
+        }
+        for script in ["01/Xor.tst", "03/a/PC.tst"].iter() {
+            let (schematic, bench) = testbench(&hardware(script)).unwrap();
+            check(&format!("{}\n{}", design(&schematic), bench)).unwrap_or_else(|e| panic!("{}: {}", script, e));
+        }
+        // 壊れたものは通さない
+        assert!(check("module A(input a, output b);\n    assign b = c;\nendmodule\n").is_err());
+        assert!(check("module A(input a, output b);\n    assign b = a;\n    assign b = a;\nendmodule\n").is_err());
+        assert!(check("module A(input a, output b);\n    wire w0;\n    assign b = w0;\nendmodule\n").is_err());
+        assert!(check("module A(input a, output b);\n    B u (.x(a), .y(b));\nendmodule\n").is_err());
+        assert!(check("module A(input [1:0] a, output b);\n    nand g0 (b, a, a);\nendmodule\n").is_err());
+        assert!(check("module A(input a, output b);\n    assign b = a;\n").is_err());
+    }
+
+    // iverilog があれば testbench まで動かして、Rust と食い違わないことを見る。無ければ何もしない
+    #[test]
+    fn iverilog_test() {
+        use std::process::Command;
+        if Command::new("iverilog").arg("-V").output().is_err() {
+            return
+        }
+        let dir = std::env::temp_dir().join(format!("verilog_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for script in ["01/Xor.tst", "03/a/Register.tst", "03/a/RAM8.tst", "03/a/PC.tst"].iter() {
+            let (schematic, bench) = testbench(&hardware(script)).unwrap();
+            let source = dir.join(format!("{}.v", schematic.name));
+            std::fs::write(&source, format!("{}\n{}", design(&schematic), bench)).unwrap();
+            let binary = dir.join(&schematic.name);
+            assert!(Command::new("iverilog").arg("-o").arg(&binary).arg(&source).status().unwrap().success(), "{}", script);
+            let output = Command::new("vvp").arg(&binary).output().unwrap();
+            let text = String::from_utf8_lossy(&output.stdout);
+            assert!(text.contains("ok ("), "{}: {}", script, text);
+        }
+        for chip in ["ALU", "Bit", "RAM8"].iter() {
+            let (schematic, bench) = vector_testbench(&vectors::find(chip).unwrap()).unwrap();
+            let source = dir.join(format!("{}.v", schematic.name));
+            std::fs::write(&source, format!("{}\n{}", design(&schematic), bench)).unwrap();
+            let binary = dir.join(&schematic.name);
+            assert!(Command::new("iverilog").arg("-o").arg(&binary).arg(&source).status().unwrap().success(), "{}", chip);
+            let output = Command::new("vvp").arg(&binary).output().unwrap();
+            let text = String::from_utf8_lossy(&output.stdout);
+            assert!(text.contains("ok ("), "{}: {}", chip, text);
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn testbench_test() {
+        let (xor, bench) = testbench(&hardware("01/Xor.tst")).unwrap();
+        assert_eq!("Xor", xor.name);
+        assert!(bench.contains("    reg a = 1'b0;\n"));
+        assert!(bench.contains("    Xor dut(.a(a), .b(b), .out(out));\n"), "{}", bench);
+        assert!(bench.contains("        a = 1'b1;\n"));
+        assert!(bench.contains("if (out !== 1'b0) begin $display(\"check 4: out = %0d, expected 0\", out); errors = errors + 1; end"), "{}", bench);
+        assert!(bench.contains("$display(\"ok (4 checks)\")"));
+        assert!(!bench.contains("clk"));
+
+        let (_, bench) = testbench(&hardware("03/a/PC.tst")).unwrap();
+        assert!(bench.contains("    reg [15:0] in = 16'd0;\n"));
+        assert!(bench.contains("    PC dut(.clk(clk), .in(in), .load(load), .inc(inc), .reset(reset), .out(out));\n"), "{}", bench);
+        assert!(bench.contains("clk = 1'b1; #1; clk = 1'b0; #1;"));
+        assert!(!bench.starts_with("//"));
+        assert!(bench.contains("$display(\"ok (30 checks)\")"), "{}", bench);
+        // 負の数は 16 桁の 2 の補数にする
+        let (_, bench) = testbench(&hardware("03/a/Register.tst")).unwrap();
+        assert!(bench.contains("        in = 16'd33413;\n"));
+        assert!(bench.contains("expected 33413"));
+        assert!(!bench.starts_with("//"));
+        assert!(matches!(testbench(&hardware("05/CPU.tst")), Err(TstError::Script { .. })));
+
+        // tick と tock の間で入力を変えるスクリプトは断る
+        let dir = std::env::temp_dir().join(format!("testbench_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let script = dir.join("Late.tst");
+        std::fs::write(&script, "load Bit,\nset in 1, set load 1,\ntick,\nset in 0,\ntock,\n").unwrap();
+        match testbench(&script) {
+            Err(TstError::Script { line, message }) => {
+                assert_eq!(4, line);
+                assert!(message.contains("between tick and tock"), "{}", message);
+            },
+            other => panic!("{:?}", other.map(|(_, bench)| bench))
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn vector_testbench_test() {
+        let (alu, bench) = vector_testbench(&vectors::find("ALU").unwrap()).unwrap();
+        assert_eq!("ALU", alu.name);
+        assert!(bench.contains("    ALU dut(.x(x), .y(y), .zx(zx), .nx(nx), .zy(zy), .ny(ny), .f(f), .no(no), .out(out), .zr(zr), .ng(ng));\n"), "{}", bench);
+        // 1 行で out, zr, ng の 3 つを比べる
+        assert!(bench.contains("$display(\"ok (108 checks)\")"), "{}", bench);
+        assert!(!bench.contains("clk"));
+        let (_, bench) = vector_testbench(&vectors::find("RAM8").unwrap()).unwrap();
+        assert!(bench.contains("clk = 1'b1; #1; clk = 1'b0; #1;"));
+        // Rust のチップと合わないベクタはエラー
+        let mut bit = vectors::find("Bit").unwrap();
+        bit.rows[0].1[0] = 1;
+        assert_eq!(Err("row 1: out = 0, expected 1".to_string()), vector_testbench(&bit).map(|(_, bench)| bench));
+    }
+}